@@ -0,0 +1,109 @@
+//! Peak memory of a write query binding 100k small documents, through an
+//! intermediate `serde_json::Value` with `try_bind` or serialized straight to
+//! JSON text with `insert_serialize_seq`.
+//!
+//! ```sh
+//! cargo bench --bench bind_seq
+//! ```
+//!
+//! The peak of each path is printed before the timings. The `Value` tree of a
+//! small document takes a few times the size of its JSON text, so the
+//! serialized path is expected to peak well below the other.
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use serde::Serialize;
+
+use arangors::AqlQuery;
+
+const DOCUMENTS: usize = 100_000;
+
+const QUERY: &str = "FOR doc IN @docs INSERT doc INTO readings";
+
+/// Tracks the bytes allocated by the whole process, and their peak.
+struct PeakAlloc;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAlloc = PeakAlloc;
+
+#[derive(Debug, Serialize)]
+struct Reading {
+    sensor: &'static str,
+    timestamp: u64,
+    value: f64,
+}
+
+fn readings() -> impl Iterator<Item = Reading> {
+    (0..DOCUMENTS).map(|i| Reading {
+        sensor: "sensor",
+        timestamp: 1_600_000_000 + i as u64,
+        value: i as f64 / 2.0,
+    })
+}
+
+/// The body of the request, as sent by `Database::aql_query`.
+fn bind_via_value() -> String {
+    let docs: Vec<Reading> = readings().collect();
+    let aql = AqlQuery::builder()
+        .query(QUERY)
+        .try_bind("docs", docs)
+        .unwrap()
+        .build();
+    serde_json::to_string(&aql).unwrap()
+}
+
+fn bind_serialized() -> String {
+    let aql = AqlQuery::builder()
+        .query(QUERY)
+        .insert_serialize_seq("docs", readings())
+        .unwrap()
+        .build();
+    serde_json::to_string(&aql).unwrap()
+}
+
+/// Bytes allocated at the peak of `run`, above what was allocated before.
+fn peak_bytes(run: fn() -> String) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    black_box(run());
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+fn bind_seq(c: &mut Criterion) {
+    assert_eq!(bind_via_value(), bind_serialized());
+    let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    println!(
+        "peak memory: bind via value {:.1}MiB, bind serialized {:.1}MiB",
+        mib(peak_bytes(bind_via_value)),
+        mib(peak_bytes(bind_serialized)),
+    );
+
+    let mut group = c.benchmark_group("bind_seq");
+    group.throughput(Throughput::Elements(DOCUMENTS as u64));
+    group.sample_size(10);
+    group.bench_function("bind_via_value", |b| b.iter(bind_via_value));
+    group.bench_function("bind_serialized", |b| b.iter(bind_serialized));
+    group.finish();
+}
+
+criterion_group!(benches, bind_seq);
+criterion_main!(benches);
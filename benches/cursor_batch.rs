@@ -0,0 +1,122 @@
+//! Parsing of a single cursor batch of about 50MB against the mock client.
+//!
+//! Run it once with the default `serde_json` parser and once with
+//! `simd-json` to compare:
+//!
+//! ```sh
+//! cargo bench --bench cursor_batch --features test-support
+//! cargo bench --bench cursor_batch --features "test-support simd-json"
+//! ```
+//!
+//! The orders deserialize straight into structs, where `simd-json` has
+//! little to gain, so which parser is faster depends on the CPU, and on
+//! `-C target-cpu=native`: measure on the machine serving the application.
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use http::Method;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use arangors::{
+    test_support::{fixtures, MockClient, MockResponse},
+    Database,
+};
+
+const BATCH_BYTES: usize = 50 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Order {
+    #[serde(rename = "_key")]
+    key: String,
+    customer: String,
+    note: String,
+    total: f64,
+    placed_at: u64,
+    shipping: Address,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Address {
+    street: String,
+    city: String,
+    zip: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct Item {
+    sku: String,
+    quantity: u32,
+    price: f64,
+}
+
+fn order(i: usize) -> Value {
+    json!({
+        "_key": i.to_string(),
+        "_id": format!("orders/{}", i),
+        "_rev": "_bHj2k3a---",
+        "customer": format!("customer-{}", i % 1000),
+        "note": "leave at the door \"front\" – merci\n",
+        "total": 129.95 + i as f64,
+        "placed_at": 1_600_000_000_000u64 + i as u64,
+        "shipping": { "street": "Hauptstraße 1", "city": "Köln", "zip": "50667" },
+        "items": [
+            { "sku": "A-100", "quantity": 2, "price": 19.99 },
+            { "sku": "B-200", "quantity": 1, "price": 89.97 },
+        ],
+    })
+}
+
+/// The batch, and its length in bytes.
+fn batch() -> (MockResponse, u64) {
+    let mut orders = Vec::new();
+    let mut len = 0;
+    while len < BATCH_BYTES {
+        let order = order(orders.len());
+        len += order.to_string().len() + 1;
+        orders.push(order);
+    }
+    let body = fixtures::cursor_batches("batch", vec![orders]).remove(0);
+    let len = body.to_string().len() as u64;
+    (MockResponse::json(201, body), len)
+}
+
+fn database() -> (Database<MockClient>, u64) {
+    let mock = MockClient::default();
+    let (batch, len) = batch();
+    mock.on(Method::POST, "/_db/bench/_api/cursor", batch);
+    (mock.database("bench"), len)
+}
+
+#[maybe_async::maybe_async]
+async fn query(db: &Database<MockClient>) -> usize {
+    let orders: Vec<Order> = db.aql_str("FOR o IN orders RETURN o").await.unwrap();
+    orders.len()
+}
+
+fn cursor_batch(c: &mut Criterion) {
+    let (db, len) = database();
+    #[cfg(not(feature = "blocking"))]
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let parser = if cfg!(feature = "simd-json") {
+        "simd-json"
+    } else {
+        "serde_json"
+    };
+    let mut group = c.benchmark_group("cursor_batch");
+    group.throughput(Throughput::Bytes(len));
+    group.sample_size(10);
+    group.bench_function(parser, |b| {
+        #[cfg(not(feature = "blocking"))]
+        b.iter(|| runtime.block_on(query(&db)));
+        #[cfg(feature = "blocking")]
+        b.iter(|| query(&db));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, cursor_batch);
+criterion_main!(benches);
@@ -0,0 +1,136 @@
+//! Single document reads against the mock client, typed, as a
+//! `serde_json::Value`, and raw with `Collection::read_document_raw` for
+//! callers forwarding the documents untouched.
+//!
+//! ```sh
+//! cargo bench --bench raw_read --features test-support
+//! ```
+//!
+//! Raw reads skip the parsing of the document, leaving the transport of the
+//! mock and the parsing of the header, so the gap grows with the size of the
+//! order below.
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use http::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use arangors::{
+    test_support::{fixtures, MockClient, MockResponse},
+    Collection, Document,
+};
+
+const READS: u64 = 1_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
+struct Order {
+    customer: String,
+    note: String,
+    total: f64,
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(dead_code)]
+struct Item {
+    sku: String,
+    quantity: u32,
+    price: f64,
+}
+
+fn order() -> Value {
+    let items: Vec<Value> = (0..50)
+        .map(|i| json!({ "sku": format!("A-{}", i), "quantity": i, "price": 19.99 }))
+        .collect();
+    json!({
+        "_key": "o1",
+        "_id": "orders/o1",
+        "_rev": "_bHj2k3a---",
+        "customer": "customer-1",
+        "note": "leave at the door \"front\" – merci\n",
+        "total": 129.95,
+        "items": items,
+    })
+}
+
+/// The mock with the order registered, and the length of the order in bytes.
+fn mock() -> (MockClient, u64) {
+    let mock = MockClient::default();
+    let order = order();
+    let len = order.to_string().len() as u64;
+    mock.on(
+        Method::GET,
+        "/_db/bench/_api/collection/orders",
+        MockResponse::json(200, fixtures::collection_info("orders", "1")),
+    );
+    mock.on(
+        Method::GET,
+        "/_db/bench/_api/document/orders/o1",
+        MockResponse::json(200, order),
+    );
+    (mock, len)
+}
+
+#[maybe_async::maybe_async]
+async fn read_typed(mock: &MockClient, coll: &Collection<MockClient>) {
+    for _ in 0..READS {
+        let doc: Document<Order> = coll.document("o1").await.unwrap();
+        black_box(doc);
+    }
+    mock.clear_requests();
+}
+
+#[maybe_async::maybe_async]
+async fn read_value(mock: &MockClient, coll: &Collection<MockClient>) {
+    for _ in 0..READS {
+        let doc: Document<Value> = coll.document("o1").await.unwrap();
+        black_box(doc);
+    }
+    mock.clear_requests();
+}
+
+#[maybe_async::maybe_async]
+async fn read_raw(mock: &MockClient, coll: &Collection<MockClient>) {
+    for _ in 0..READS {
+        black_box(coll.read_document_raw("o1").await.unwrap());
+    }
+    mock.clear_requests();
+}
+
+fn raw_read(c: &mut Criterion) {
+    let (mock, len) = mock();
+    #[cfg(not(feature = "blocking"))]
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    #[cfg(not(feature = "blocking"))]
+    let coll = runtime
+        .block_on(mock.database("bench").collection("orders"))
+        .unwrap();
+    #[cfg(feature = "blocking")]
+    let coll = mock.database("bench").collection("orders").unwrap();
+
+    let mut group = c.benchmark_group("raw_read");
+    group.throughput(Throughput::Bytes(len * READS));
+    group.sample_size(10);
+    #[cfg(not(feature = "blocking"))]
+    {
+        group.bench_function("typed", |b| {
+            b.iter(|| runtime.block_on(read_typed(&mock, &coll)))
+        });
+        group.bench_function("value", |b| {
+            b.iter(|| runtime.block_on(read_value(&mock, &coll)))
+        });
+        group.bench_function("raw", |b| {
+            b.iter(|| runtime.block_on(read_raw(&mock, &coll)))
+        });
+    }
+    #[cfg(feature = "blocking")]
+    {
+        group.bench_function("typed", |b| b.iter(|| read_typed(&mock, &coll)));
+        group.bench_function("value", |b| b.iter(|| read_value(&mock, &coll)));
+        group.bench_function("raw", |b| b.iter(|| read_raw(&mock, &coll)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, raw_read);
+criterion_main!(benches);
@@ -0,0 +1,141 @@
+//! Throughput and allocations of 10k single document inserts against the
+//! mock client, compared to serializing each document through an
+//! intermediate `serde_json::Value` as insertions used to.
+//!
+//! ```sh
+//! cargo bench --bench single_insert --features test-support
+//! ```
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use http::Method;
+use serde::{Deserialize, Serialize};
+
+use arangors::{
+    test_support::{fixtures, MockClient, MockResponse},
+    Collection,
+};
+
+const INSERTS: usize = 10_000;
+
+/// Counts the allocations of the whole process.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Reading {
+    sensor: String,
+    timestamp: u64,
+    values: Vec<f64>,
+}
+
+fn reading(i: usize) -> Reading {
+    Reading {
+        sensor: format!("sensor-{}", i % 100),
+        timestamp: 1_600_000_000 + i as u64,
+        values: vec![1.5, 2.5, 3.5, 4.5],
+    }
+}
+
+fn serialize_via_value() {
+    for i in 0..INSERTS {
+        let value = serde_json::to_value(&reading(i)).unwrap();
+        black_box(serde_json::to_string(&value).unwrap());
+    }
+}
+
+fn serialize_directly() {
+    for i in 0..INSERTS {
+        black_box(serde_json::to_string(&reading(i)).unwrap());
+    }
+}
+
+fn mock() -> MockClient {
+    let mock = MockClient::default();
+    mock.on(
+        Method::GET,
+        "/_db/bench/_api/collection/readings",
+        MockResponse::json(200, fixtures::collection_info("readings", "1")),
+    );
+    mock.on(
+        Method::POST,
+        "/_db/bench/_api/document/readings/",
+        MockResponse::json(202, fixtures::DocumentFixture::new("readings", "k").build()),
+    );
+    mock
+}
+
+#[maybe_async::maybe_async]
+async fn insert_all(mock: &MockClient, coll: &Collection<MockClient>) {
+    for i in 0..INSERTS {
+        coll.create_document(reading(i), Default::default())
+            .await
+            .unwrap();
+        if i % 1000 == 0 {
+            mock.clear_requests();
+        }
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+fn create_documents() -> impl FnMut() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mock = mock();
+    let coll = runtime
+        .block_on(mock.database("bench").collection("readings"))
+        .unwrap();
+    move || runtime.block_on(insert_all(&mock, &coll))
+}
+
+#[cfg(feature = "blocking")]
+fn create_documents() -> impl FnMut() {
+    let mock = mock();
+    let coll = mock.database("bench").collection("readings").unwrap();
+    move || insert_all(&mock, &coll)
+}
+
+fn allocations_per_insert(mut run: impl FnMut()) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    run();
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / INSERTS as f64
+}
+
+fn single_insert(c: &mut Criterion) {
+    println!(
+        "allocations per insert: serialize via value {:.1}, serialize directly {:.1}, \
+         create_document {:.1}",
+        allocations_per_insert(serialize_via_value),
+        allocations_per_insert(serialize_directly),
+        allocations_per_insert(create_documents()),
+    );
+
+    let mut group = c.benchmark_group("single_insert");
+    group.throughput(Throughput::Elements(INSERTS as u64));
+    group.sample_size(10);
+    group.bench_function("serialize_via_value", |b| b.iter(serialize_via_value));
+    group.bench_function("serialize_directly", |b| b.iter(serialize_directly));
+    let mut create = create_documents();
+    group.bench_function("create_document", |b| b.iter(&mut create));
+    group.finish();
+}
+
+criterion_group!(benches, single_insert);
+criterion_main!(benches);
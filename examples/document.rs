@@ -106,7 +106,7 @@ async fn main() -> Result<(), Error> {
                 .return_new(true)
                 .return_old(true)
                 .build(),
-            Some(_rev.to_string()),
+            _rev.clone(),
         )
         .await
         .unwrap();
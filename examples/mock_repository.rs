@@ -0,0 +1,85 @@
+#![allow(unused_imports)]
+#![allow(unused_parens)]
+
+//! Test a repository layer built on arangors without a running ArangoDB.
+//!
+//! Write the repository generic over the HTTP client, use the real client in
+//! production, and `MockClient` from the `test-support` feature in tests:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! arangors = { version = "0.5", features = ["test-support"] }
+//! ```
+use anyhow::Error;
+use http::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use arangors::{
+    test_support::{fixtures, MockClient, MockResponse},
+    uclient::ClientExt,
+    AqlQuery, Database,
+};
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct User {
+    username: String,
+    age: u32,
+}
+
+/// The repository under test.
+struct UserRepository<C: ClientExt> {
+    db: Database<C>,
+}
+
+impl<C: ClientExt> UserRepository<C> {
+    #[maybe_async::maybe_async]
+    async fn adults(&self) -> Result<Vec<User>, Error> {
+        let aql = AqlQuery::builder()
+            .query("FOR u IN users FILTER u.age >= @age RETURN u")
+            .bind_var("age", 18)
+            .build();
+        Ok(self.db.aql_query(aql).await?)
+    }
+}
+
+#[cfg_attr(feature = "blocking", maybe_async::must_be_sync)]
+#[cfg_attr(not(feature = "blocking"), tokio::main)]
+async fn main() -> Result<(), Error> {
+    let mock = MockClient::default();
+    // two batches, as if the query was run with a small batch size
+    mock.expect_cursor(
+        "test_db",
+        vec![
+            vec![json!({ "username": "alice", "age": 30 })],
+            vec![json!({ "username": "bob", "age": 42 })],
+        ],
+    );
+
+    let repository = UserRepository {
+        db: mock.database("test_db"),
+    };
+    let users = repository.adults().await?;
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[1].username, "bob");
+
+    // the first request created the cursor with the expected bind variable
+    let requests = mock.requests();
+    assert_eq!(requests[0].json_body().unwrap()["bindVars"]["age"], 18);
+    mock.assert_requests(&[
+        (Method::POST, "/_db/test_db/_api/cursor"),
+        (Method::PUT, "/_db/test_db/_api/cursor/mock_cursor"),
+    ]);
+    mock.assert_no_pending();
+
+    // errors from the server are mocked likewise
+    mock.once(
+        Method::POST,
+        "/_db/test_db/_api/cursor",
+        MockResponse::json(404, fixtures::error(404, 1203, "collection not found")),
+    );
+    assert!(repository.adults().await.is_err());
+
+    println!("repository behaves as expected");
+    Ok(())
+}
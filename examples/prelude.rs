@@ -0,0 +1,75 @@
+#![allow(unused_imports)]
+#![allow(unused_parens)]
+
+//! A tour of the common operations with the prelude as the only import of
+//! arangors.
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use arangors::prelude::*;
+
+const URL: &str = "http://localhost:8529";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct User {
+    username: String,
+    age: u32,
+}
+
+#[cfg_attr(feature = "reqwest_async", tokio::main)]
+#[cfg_attr(feature = "surf_async", async_std::main)]
+#[cfg_attr(feature = "reqwest_blocking", maybe_async::must_be_sync)]
+async fn main() -> Result<(), Error> {
+    let conn = Connection::establish_jwt(URL, "username", "password").await?;
+    let db: Database<_> = conn.db("test_db").await?;
+
+    let collection: Collection<_> = db.create_collection("test_collection_prelude").await?;
+
+    let created: DocumentResponse<User> = collection
+        .create_document(
+            User {
+                username: "alice".to_owned(),
+                age: 30,
+            },
+            InsertOptions::builder().return_new(true).build(),
+        )
+        .await?;
+    let header: &DocumentHeader = created.header().unwrap();
+    let key = header._key.clone();
+
+    let aql = AqlQuery::builder()
+        .query("FOR u IN @@collection FILTER u.age >= @age RETURN u")
+        .bind_var("@collection", collection.name())
+        .bind_var("age", 18)
+        .build();
+    let adults: Vec<Document<User>> = db.aql_query(aql).await?;
+    println!("adults: {:?}", adults);
+
+    collection
+        .update_document(
+            &key,
+            json!({ "age": 31 }),
+            UpdateOptions::builder().return_new(true).build(),
+        )
+        .await?;
+    let updated: Document<User> = collection
+        .document_with_options(&key, DocumentReadOptions::default())
+        .await?;
+    println!(
+        "{} is now {}",
+        updated.document.username, updated.document.age
+    );
+
+    let removed: Result<DocumentResponse<User>, ClientError> = collection
+        .remove_document(
+            &key,
+            RemoveOptions::builder().return_old(true).build(),
+            None,
+        )
+        .await;
+    println!("removed: {:?}", removed?.old_doc());
+
+    db.drop_collection("test_collection_prelude").await?;
+    Ok(())
+}
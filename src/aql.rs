@@ -7,19 +7,30 @@
 /// 1. (optional) construct a AqlQuery object.
 ///     - (optional) construct AqlOption.
 /// 1. perform AQL query via `database.aql_query`.
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 
-use serde::{Deserialize, Serialize};
-use serde_json::value::Value;
+use log::{debug, warn};
+
+use serde::{ser::Serializer, Deserialize, Serialize};
+use serde_json::value::{RawValue, Value};
 use typed_builder::TypedBuilder;
 
+use crate::{
+    query::{fnv1a, merge_defaults, sort_keys, FNV_OFFSET},
+    response::checked_id,
+    ClientError,
+};
+
 #[derive(Debug, Serialize, TypedBuilder)]
 #[builder(
     doc,
     builder_method_doc = r#"Create a builder for building `AqlQuery`.
 
 On the builder, call `.query(...)`, `.bind_vars(...)(optional)`, `.bind_var(...)(optional)`,
-`.try_bind(...)(optional)`, `.count(...)(optional)`, `.batch_size(...)(optional)`,
+`.try_bind(...)(optional)`, `.insert_serialize_seq(...)(optional)`, `.count(...)(optional)`, `.batch_size(...)(optional)`,
 `.cache(...)(optional)`, `.memory_limit(...)(optional)`, `.ttl(...)(optional)`,
 `.options(...)(optional)` to set the values of the fields (they accept Into values).
 
@@ -34,9 +45,9 @@ pub struct AqlQuery<'a> {
     query: &'a str,
 
     /// bind parameters to substitute in query string
-    #[serde(skip_serializing_if = "HashMap::is_empty")]
-    #[builder(default)]
-    bind_vars: HashMap<&'a str, Value>,
+    #[serde(skip_serializing_if = "BindVars::is_empty")]
+    #[builder(default, setter(into))]
+    bind_vars: BindVars<'a>,
 
     /// Indicates whether the number of documents in the result set should be
     /// returned in the "count" attribute of the result.
@@ -123,7 +134,7 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
         'a,
         (
             __query,
-            (HashMap<&'a str, Value>,),
+            (BindVars<'a>,),
             __count,
             __batch_size,
             __cache,
@@ -136,8 +147,61 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
         K: Into<&'a str>,
         V: Into<Value>,
     {
-        let mut bind_vars = HashMap::new();
+        let mut bind_vars = BindVars::default();
         bind_vars.insert(key.into(), value.into());
+        self.with_bind_vars(bind_vars)
+    }
+
+    /// Bind `key` to the array of `items`, serialized one after another
+    /// straight to JSON text, see [`BindVars::insert_serialize_seq`].
+    #[allow(clippy::type_complexity)]
+    pub fn insert_serialize_seq<K, I>(
+        self,
+        key: K,
+        items: I,
+    ) -> Result<
+        AqlQueryBuilder<
+            'a,
+            (
+                __query,
+                (BindVars<'a>,),
+                __count,
+                __batch_size,
+                __cache,
+                __memory_limit,
+                __ttl,
+                __options,
+            ),
+        >,
+        serde_json::Error,
+    >
+    where
+        K: Into<&'a str>,
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        let mut bind_vars = BindVars::default();
+        bind_vars.insert_serialize_seq(key.into(), items)?;
+        Ok(self.with_bind_vars(bind_vars))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn with_bind_vars(
+        self,
+        bind_vars: BindVars<'a>,
+    ) -> AqlQueryBuilder<
+        'a,
+        (
+            __query,
+            (BindVars<'a>,),
+            __count,
+            __batch_size,
+            __cache,
+            __memory_limit,
+            __ttl,
+            __options,
+        ),
+    > {
         let (query, _, count, batch_size, cache, memory_limit, ttl, options) = self.fields;
         AqlQueryBuilder {
             fields: (
@@ -164,7 +228,7 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
             'a,
             (
                 __query,
-                (HashMap<&'a str, Value>,),
+                (BindVars<'a>,),
                 __count,
                 __batch_size,
                 __cache,
@@ -190,7 +254,7 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
         'a,
         (
             __query,
-            (HashMap<&'a str, Value>,),
+            (BindVars<'a>,),
             __count,
             __batch_size,
             __cache,
@@ -209,7 +273,7 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
         'a,
         (
             __query,
-            (HashMap<&'a str, Value>,),
+            (BindVars<'a>,),
             __count,
             __batch_size,
             __cache,
@@ -236,7 +300,7 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
             'a,
             (
                 __query,
-                (HashMap<&'a str, Value>,),
+                (BindVars<'a>,),
                 __count,
                 __batch_size,
                 __cache,
@@ -253,6 +317,186 @@ impl<'a, __query, __count, __batch_size, __cache, __memory_limit, __ttl, __optio
     {
         Ok(self.bind_var(key, serde_json::to_value(value)?))
     }
+
+    #[allow(clippy::type_complexity)]
+    pub fn insert_serialize_seq<K, I>(
+        mut self,
+        key: K,
+        items: I,
+    ) -> Result<
+        AqlQueryBuilder<
+            'a,
+            (
+                __query,
+                (BindVars<'a>,),
+                __count,
+                __batch_size,
+                __cache,
+                __memory_limit,
+                __ttl,
+                __options,
+            ),
+        >,
+        serde_json::Error,
+    >
+    where
+        K: Into<&'a str>,
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        (self.fields.1).0.insert_serialize_seq(key.into(), items)?;
+        Ok(self)
+    }
+}
+
+/// Bind parameters of an [`AqlQuery`], serialized as the `bindVars` object of
+/// the request.
+///
+/// Besides values, a parameter can hold an array already serialized to JSON
+/// text with [`insert_serialize_seq`](Self::insert_serialize_seq), for large
+/// arrays of documents given to write queries like
+/// `FOR doc IN @docs INSERT doc INTO @@collection`.
+#[derive(Debug, Default, Clone)]
+pub struct BindVars<'a> {
+    values: HashMap<&'a str, Value>,
+    serialized: HashMap<&'a str, Box<RawValue>>,
+}
+
+impl<'a> BindVars<'a> {
+    pub fn insert(&mut self, key: &'a str, value: Value) {
+        self.serialized.remove(key);
+        self.values.insert(key, value);
+    }
+
+    /// Bind `key` to the array of `items`, each serialized in turn straight
+    /// to the JSON text of the array.
+    ///
+    /// No `serde_json::Value` of the items is built: the bind parameter holds
+    /// about the size of the JSON text of the array, which is copied once
+    /// into the body of the request when it is sent. Binding the same items
+    /// with [`try_bind`](AqlQueryBuilder::try_bind) keeps a `Value` tree, a
+    /// few times larger than the text, next to the body. The text is parsed
+    /// back into a `Value` only when the body has to be rewritten, with
+    /// [`QueryDefaults`] or deterministic requests.
+    ///
+    /// When an item fails to serialize, the error is returned and the
+    /// parameter is left unbound: a query is never sent with a partial
+    /// array.
+    pub fn insert_serialize_seq<I>(
+        &mut self,
+        key: &'a str,
+        items: I,
+    ) -> Result<(), serde_json::Error>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        use serde::ser::{Error, SerializeSeq};
+
+        let mut text = Vec::new();
+        let mut serializer = serde_json::Serializer::new(&mut text);
+        let mut seq = serializer.serialize_seq(None)?;
+        for item in items {
+            seq.serialize_element(&item)?;
+        }
+        seq.end()?;
+        let text = String::from_utf8(text).map_err(serde_json::Error::custom)?;
+        let array = RawValue::from_string(text)?;
+        self.values.remove(key);
+        self.serialized.insert(key, array);
+        Ok(())
+    }
+
+    /// The value bound to `key`, unless it was serialized ahead.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty() && self.serialized.is_empty()
+    }
+
+    /// Names of the parameters with the JSON text of their value.
+    fn texts(&self) -> impl Iterator<Item = (&'a str, String)> + '_ {
+        let values = self
+            .values
+            .iter()
+            .map(|(name, value)| (*name, sort_keys(value.clone()).to_string()));
+        let serialized = self
+            .serialized
+            .iter()
+            .map(|(name, array)| (*name, array.get().to_owned()));
+        values.chain(serialized)
+    }
+}
+
+impl<'a> From<HashMap<&'a str, Value>> for BindVars<'a> {
+    fn from(values: HashMap<&'a str, Value>) -> Self {
+        BindVars {
+            values,
+            serialized: HashMap::new(),
+        }
+    }
+}
+
+impl Serialize for BindVars<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.values.len() + self.serialized.len()))?;
+        for (name, value) in &self.values {
+            map.serialize_entry(name, value)?;
+        }
+        for (name, array) in &self.serialized {
+            map.serialize_entry(name, array)?;
+        }
+        map.end()
+    }
+}
+
+/// How much profiling information a query returns, see the `profile`
+/// option of [`AqlOptions`].
+///
+/// Converts from a `bool`, as the option used to be, and from the level
+/// numbers of ArangoDB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Off,
+    /// The time spent in each phase of the query, in `extra.profile`.
+    Phases,
+    /// The phases, the plan which was run in `extra.plan` and the statistics
+    /// of its nodes in `extra.stats.nodes`.
+    Nodes,
+}
+
+impl From<bool> for Profile {
+    fn from(profile: bool) -> Self {
+        if profile {
+            Profile::Phases
+        } else {
+            Profile::Off
+        }
+    }
+}
+
+impl From<u8> for Profile {
+    fn from(level: u8) -> Self {
+        match level {
+            0 => Profile::Off,
+            1 => Profile::Phases,
+            _ => Profile::Nodes,
+        }
+    }
+}
+
+impl Serialize for Profile {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(match self {
+            Profile::Off => 0,
+            Profile::Phases => 1,
+            Profile::Nodes => 2,
+        })
+    }
 }
 
 #[derive(Debug, Serialize, TypedBuilder, PartialEq)]
@@ -274,12 +518,15 @@ pub struct AqlOptions {
     #[builder(default, setter(strip_option))]
     fail_on_warning: Option<bool>,
 
-    /// If set to true, then the additional query profiling information will
-    /// be returned in the sub-attribute profile of the extra return attribute
-    /// if the query result is not served from the query cache.
+    /// If set to true or [`Profile::Phases`], then the additional query
+    /// profiling information will be returned in the sub-attribute profile
+    /// of the extra return attribute if the query result is not served from
+    /// the query cache. With [`Profile::Nodes`], or 2, the plan and the
+    /// statistics of each of its nodes are returned as well, see
+    /// [`QueryExtra::executed_plan`].
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[builder(default, setter(strip_option))]
-    profile: Option<bool>,
+    #[builder(default, setter(strip_option, into))]
+    profile: Option<Profile>,
 
     /// Limits the maximum number of warnings a query will return.
     ///
@@ -327,10 +574,42 @@ pub struct AqlOptions {
     #[builder(default)]
     optimizer: Vec<String>,
 
+    /// If set to true, the query is executed as a streaming query, whose
+    /// results are computed lazily while the cursor is consumed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    stream: Option<bool>,
+
+    /// If set to true, the server numbers the batches of the cursor with
+    /// `nextBatchId`, so that a batch can be requested again after a network
+    /// error (ArangoDB 3.11 and later).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    allow_retry: Option<bool>,
+
+    /// The query has to be executed within the given runtime (in seconds) or
+    /// it will be killed.
+    ///
+    /// A value of 0 indicates that there is no time limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    max_runtime: Option<f64>,
+
+    /// If set to false, the query does not populate the RocksDB block cache
+    /// with the data it reads, so that it does not evict more frequently
+    /// accessed data.
+    ///
+    /// Honored by the RocksDB storage engine only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    fill_block_cache: Option<bool>,
+
     /// Maximum number of operations after which an intermediate commit is
     /// performed automatically.
     ///
-    /// Honored by the RocksDB storage engine only.
+    /// Honored by the RocksDB storage engine only. Intermediate commits are
+    /// not possible in a stream transaction, so queries with this option are
+    /// rejected by `Transaction` before being sent.
     #[cfg(feature = "rocksdb")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
@@ -339,7 +618,9 @@ pub struct AqlOptions {
     /// Maximum total size of operations after which an intermediate commit is
     /// performed automatically.
     ///
-    /// Honored by the RocksDB storage engine only.
+    /// Honored by the RocksDB storage engine only. Intermediate commits are
+    /// not possible in a stream transaction, so queries with this option are
+    /// rejected by `Transaction` before being sent.
     #[cfg(feature = "rocksdb")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
@@ -365,6 +646,266 @@ pub struct AqlOptions {
     satellite_sync_wait: Option<bool>,
 }
 
+impl<'a> AqlQuery<'a> {
+    pub(crate) fn query_str(&self) -> &'a str {
+        self.query
+    }
+
+    /// Fetch at most `n` results in the first batch, along with their total
+    /// count.
+    pub(crate) fn first_results(mut self, n: u32) -> Self {
+        self.batch_size = Some(n);
+        self.count = Some(true);
+        self
+    }
+
+    /// Check that the options of the query can be used in a stream
+    /// transaction.
+    #[cfg(feature = "transactions")]
+    pub(crate) fn validate_in_transaction(&self) -> Result<(), ClientError> {
+        #[cfg(feature = "rocksdb")]
+        if let Some(options) = &self.options {
+            if options.intermediate_commit_count.is_some()
+                || options.intermediate_commit_size.is_some()
+            {
+                return Err(ClientError::InvalidOptions(
+                    "intermediate commits are not possible in a stream transaction, unset \
+                     intermediate_commit_count and intermediate_commit_size"
+                        .to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// A stable hash of the query string and the bind variables, which does
+    /// not depend on the order the variables were bound in.
+    ///
+    /// Other attributes and options of the query are not part of the
+    /// fingerprint. It can be used by applications as a cache key of query
+    /// results.
+    pub fn request_fingerprint(&self) -> u64 {
+        let mut hash = fnv1a(self.query.as_bytes(), FNV_OFFSET);
+        let mut vars: Vec<(&str, String)> = self.bind_vars.texts().collect();
+        vars.sort();
+        for (name, value) in vars {
+            hash = fnv1a(&[0], hash);
+            hash = fnv1a(name.as_bytes(), hash);
+            hash = fnv1a(&[0], hash);
+            hash = fnv1a(value.as_bytes(), hash);
+        }
+        hash
+    }
+}
+
+/// AQL operations which modify data.
+const WRITE_OPERATIONS: [&str; 5] = ["insert", "update", "replace", "remove", "upsert"];
+
+/// Whether the AST of a query, as returned by the parse endpoint
+/// `POST /_api/query`, contains a data modification operation.
+pub(crate) fn ast_has_write(ast: &Value) -> bool {
+    match ast {
+        Value::Array(nodes) => nodes.iter().any(ast_has_write),
+        Value::Object(node) => {
+            let is_write = matches!(
+                node.get("type").and_then(Value::as_str),
+                Some(t) if WRITE_OPERATIONS.contains(&t)
+            );
+            is_write || matches!(node.get("subNodes"), Some(nodes) if ast_has_write(nodes))
+        }
+        _ => false,
+    }
+}
+
+/// Conservative check whether a query may modify data, looking for the
+/// keywords of data modification operations outside of string literals,
+/// quoted names and comments.
+///
+/// Attributes named like a keyword, e.g. `doc.remove`, are reported as well.
+pub(crate) fn has_write_keyword(query: &str) -> bool {
+    let mut chars = query.chars().peekable();
+    let mut word = String::new();
+    let is_write = |word: &mut String| {
+        let found = WRITE_OPERATIONS.contains(&word.to_lowercase().as_str());
+        word.clear();
+        found
+    };
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        if is_write(&mut word) {
+            return true;
+        }
+        match c {
+            '\'' | '"' | '`' | '´' => {
+                // skip the literal, honoring escaped quotes
+                while let Some(next) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&next| next == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for next in chars.by_ref() {
+                    if last == '*' && next == '/' {
+                        break;
+                    }
+                    last = next;
+                }
+            }
+            _ => {}
+        }
+    }
+    is_write(&mut word)
+}
+
+/// Keywords of AQL, which cannot be used as names without quoting.
+///
+/// `count` and `prune` are not reserved, but keywords of `COLLECT` and
+/// traversals: they are quoted anyway, which never changes what a name means.
+const KEYWORDS: [&str; 40] = [
+    "aggregate",
+    "all",
+    "all_shortest_paths",
+    "and",
+    "any",
+    "asc",
+    "collect",
+    "count",
+    "desc",
+    "distinct",
+    "false",
+    "filter",
+    "for",
+    "graph",
+    "in",
+    "inbound",
+    "insert",
+    "into",
+    "k_paths",
+    "k_shortest_paths",
+    "let",
+    "like",
+    "limit",
+    "none",
+    "not",
+    "null",
+    "or",
+    "outbound",
+    "prune",
+    "remove",
+    "replace",
+    "return",
+    "search",
+    "shortest_path",
+    "sort",
+    "true",
+    "update",
+    "upsert",
+    "window",
+    "with",
+];
+
+/// Quote `name` with backticks to use it as a collection, variable or
+/// attribute name in a query, e.g. `` `weird key` ``.
+///
+/// Backticks and backslashes in `name` are escaped with a backslash. Empty
+/// names and names with control characters, like newlines, are refused with
+/// [`ClientError::InvalidName`].
+///
+/// Prefer bind parameters, `@@collection` for collections and `@attribute`
+/// for attributes or attribute paths, which need no escaping at all. This
+/// is for the queries whose structure itself is dynamic.
+pub fn escape_identifier(name: &str) -> Result<String, ClientError> {
+    let invalid = |reason| {
+        Err(ClientError::InvalidName {
+            name: name.to_owned(),
+            reason,
+        })
+    };
+    if name.is_empty() {
+        return invalid("empty name");
+    }
+    if name.chars().any(char::is_control) {
+        return invalid("control character");
+    }
+    let mut quoted = String::with_capacity(name.len() + 2);
+    quoted.push('`');
+    for c in name.chars() {
+        if c == '`' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('`');
+    Ok(quoted)
+}
+
+/// The attribute at `path` of the document variable `d`, as named in the
+/// queries generated by this crate, e.g. `` d.a.`weird key` `` for
+/// `["a", "weird key"]`.
+///
+/// Names which are not plain identifiers, or are keywords, are quoted with
+/// [`escape_identifier`]. An empty path is refused with
+/// [`ClientError::InvalidName`].
+pub fn escape_document_path(path: &[&str]) -> Result<String, ClientError> {
+    if path.is_empty() {
+        return Err(ClientError::InvalidName {
+            name: String::new(),
+            reason: "empty attribute path",
+        });
+    }
+    let mut escaped = String::from("d");
+    for name in path {
+        escaped.push('.');
+        if is_plain_name(name) {
+            escaped.push_str(name);
+        } else {
+            escaped.push_str(&escape_identifier(name)?);
+        }
+    }
+    Ok(escaped)
+}
+
+/// Whether `name` can be used without quoting: letters, digits and
+/// underscores, starting with a letter after optional underscores, and not a
+/// keyword.
+fn is_plain_name(name: &str) -> bool {
+    let mut chars = name.trim_start_matches('_').chars();
+    let starts_with_letter = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic());
+    starts_with_letter
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&name.to_lowercase().as_str())
+}
+
+/// Error number of ArangoDB when a resource limit, like the size of a
+/// transaction, is exceeded.
+const ERROR_RESOURCE_LIMIT: u16 = 32;
+
+/// Map the errors of AQL queries which deserve a typed error.
+pub(crate) fn query_error(error: ClientError) -> ClientError {
+    match error {
+        ClientError::Arango(e)
+            if e.error_num() == ERROR_RESOURCE_LIMIT
+                && e.message().to_lowercase().contains("transaction") =>
+        {
+            ClientError::TransactionSizeExceeded {
+                message: e.message().to_owned(),
+            }
+        }
+        e => e,
+    }
+}
+
 impl Default for AqlOptions {
     fn default() -> AqlOptions {
         Self::builder().build()
@@ -377,6 +918,159 @@ impl AqlOptions {
     }
 }
 
+/// Query options applied to every AQL query of a database handle, see
+/// [`Database::with_query_defaults`](crate::Database::with_query_defaults).
+///
+/// Options set on a query itself take precedence over these defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryDefaults {
+    /// Default of the `fillBlockCache` query option.
+    pub fill_block_cache: Option<bool>,
+    /// Default of the `memoryLimit` attribute of a query, in bytes.
+    pub memory_limit: Option<u64>,
+    /// Default of the `maxRuntime` query option, in seconds.
+    pub max_runtime: Option<f64>,
+    /// Default of the `stream` query option.
+    pub stream: Option<bool>,
+    /// Soft limits checked on the statistics of every query, without
+    /// aborting the query.
+    pub budget: Option<QueryBudget>,
+}
+
+/// Soft limits of the resources used by a query, see
+/// [`QueryDefaults::budget`].
+///
+/// A query exceeding its budget is logged as a warning with the
+/// fingerprint of the query, unlike the `memoryLimit` of the server which
+/// aborts the query.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryBudget {
+    /// Peak memory usage in bytes.
+    pub soft_memory_limit: Option<u64>,
+    /// Execution time in seconds.
+    pub soft_time_limit: Option<f64>,
+}
+
+impl QueryBudget {
+    /// Warn about a query exceeding this budget.
+    pub(crate) fn check(&self, record: &QueryRecord) {
+        if let Some(limit) = self.soft_memory_limit {
+            if record.peak_memory_usage > limit {
+                warn!(
+                    "query {:016x} used {} bytes of memory, over its budget of {} bytes: {}",
+                    record.fingerprint, record.peak_memory_usage, limit, record.query
+                );
+            }
+        }
+        if let Some(limit) = self.soft_time_limit {
+            if record.execution_time > limit {
+                warn!(
+                    "query {:016x} ran for {}s, over its budget of {}s: {}",
+                    record.fingerprint, record.execution_time, limit, record.query
+                );
+            }
+        }
+    }
+}
+
+/// Statistics of a query run through a database handle, see
+/// [`Database::top_queries_by_memory`](crate::Database::top_queries_by_memory).
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryRecord {
+    /// The [`request_fingerprint`](AqlQuery::request_fingerprint) of the
+    /// query.
+    pub fingerprint: u64,
+    /// The query string.
+    pub query: String,
+    /// Peak memory usage of the query in bytes, as reported by the server.
+    pub peak_memory_usage: u64,
+    /// Execution time of the query in seconds, as reported by the server.
+    pub execution_time: f64,
+    /// When the statistics were received.
+    pub recorded_at: Instant,
+}
+
+/// Number of queries kept by [`QueryLog`].
+const QUERY_LOG_CAPACITY: usize = 64;
+
+/// How long [`QueryLog`] keeps a query.
+const QUERY_LOG_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// The statistics of the queries using the most memory among the ones
+/// recorded within the retention.
+#[derive(Debug, Default)]
+pub(crate) struct QueryLog {
+    records: Vec<QueryRecord>,
+}
+
+impl QueryLog {
+    /// Keep `record` if it is among the heaviest queries, logging it in any
+    /// case.
+    pub(crate) fn record(&mut self, record: QueryRecord) {
+        debug!(
+            "query {:016x} used {} bytes of memory and ran for {}s: {}",
+            record.fingerprint, record.peak_memory_usage, record.execution_time, record.query
+        );
+        self.records
+            .retain(|kept| kept.recorded_at.elapsed() < QUERY_LOG_RETENTION);
+        self.records.push(record);
+        if self.records.len() > QUERY_LOG_CAPACITY {
+            // the lightest goes, the oldest of them on a tie
+            let lightest = self
+                .records
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, kept)| (kept.peak_memory_usage, kept.recorded_at))
+                .map(|(i, _)| i);
+            if let Some(i) = lightest {
+                self.records.remove(i);
+            }
+        }
+    }
+
+    /// The queries recorded within `window`, using the most memory first.
+    pub(crate) fn top_by_memory(&self, window: Duration) -> Vec<QueryRecord> {
+        let mut records: Vec<QueryRecord> = self
+            .records
+            .iter()
+            .filter(|record| record.recorded_at.elapsed() < window)
+            .cloned()
+            .collect();
+        records.sort_by_key(|record| std::cmp::Reverse(record.peak_memory_usage));
+        records
+    }
+}
+
+impl QueryDefaults {
+    /// Whether no default is set at all.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fill the options missing in the serialized `query` with these
+    /// defaults.
+    pub(crate) fn apply(&self, query: &mut Value) {
+        let mut options = serde_json::Map::new();
+        if let Some(fill_block_cache) = self.fill_block_cache {
+            options.insert("fillBlockCache".into(), fill_block_cache.into());
+        }
+        if let Some(max_runtime) = self.max_runtime {
+            options.insert("maxRuntime".into(), max_runtime.into());
+        }
+        if let Some(stream) = self.stream {
+            options.insert("stream".into(), stream.into());
+        }
+        let mut defaults = serde_json::Map::new();
+        if let Some(memory_limit) = self.memory_limit {
+            defaults.insert("memoryLimit".into(), memory_limit.into());
+        }
+        if !options.is_empty() {
+            defaults.insert("options".into(), Value::Object(options));
+        }
+        merge_defaults(query, Value::Object(defaults));
+    }
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryStats {
@@ -419,6 +1113,12 @@ pub struct QueryStats {
     pub full_count: Option<usize>,
     pub http_requests: usize,
     pub execution_time: f64,
+    /// Maximum memory usage of the query in bytes while it was running
+    /// (ArangoDB 3.7 and later).
+    pub peak_memory_usage: Option<u64>,
+    /// Statistics of each node of the plan, with the profile option set to
+    /// [`Profile::Nodes`].
+    pub nodes: Option<Vec<Value>>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -445,6 +1145,13 @@ pub struct Cursor<T> {
     pub result: Vec<T>,
     ///  id of temporary cursor created on the server
     pub id: Option<String>,
+    /// id of the next batch, to request it with
+    /// `POST /_api/cursor/{id}/{nextBatchId}`
+    ///
+    /// only available from ArangoDB 3.11 on, when the query was executed with
+    /// the `allowRetry` option, and in all but the last batch
+    #[serde(rename = "nextBatchId")]
+    pub next_batch_id: Option<String>,
 
     /// an optional JSON object with extra information about the query
     /// result contained in its stats sub-attribute. For
@@ -456,12 +1163,127 @@ pub struct Cursor<T> {
     pub extra: Option<QueryExtra>,
 }
 
+impl<T> Cursor<T> {
+    /// Id to request the next batch with, checked to be usable in a url.
+    /// Fails when the server announced more results without a valid id.
+    pub(crate) fn next_id(&self) -> Result<&str, ClientError> {
+        let id = self.id.as_deref().ok_or_else(|| {
+            ClientError::MalformedResponse("cursor has more results but no id".to_owned())
+        })?;
+        if let Some(batch) = &self.next_batch_id {
+            checked_id(batch, "batch")?;
+        }
+        checked_id(id, "cursor")
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct QueryExtra {
     // TODO
     pub stats: Option<QueryStats>,
     // TODO
     pub warnings: Option<Vec<Value>>,
+    /// Time spent in each phase of the query, in seconds, with the profile
+    /// option set.
+    pub profile: Option<Value>,
+    /// The plan which was run, with the profile option set to
+    /// [`Profile::Nodes`].
+    pub plan: Option<Value>,
+}
+
+impl QueryExtra {
+    /// The plan which was run joined with the statistics of its nodes, when
+    /// the query was run with the profile option set to [`Profile::Nodes`].
+    ///
+    /// Nodes are joined on their id, including the nodes of the subqueries
+    /// which older versions nest in the plan. A node found on one side only,
+    /// like a node which never ran or the statistics of a node the
+    /// coordinator of a cluster added, is kept as sent by the server.
+    pub fn executed_plan(&self) -> Option<ExecutedPlan> {
+        let stats = self.stats.as_ref().and_then(|stats| stats.nodes.as_ref());
+        if self.plan.is_none() && stats.is_none() {
+            return None;
+        }
+        let mut plan_nodes = Vec::new();
+        if let Some(plan) = &self.plan {
+            flatten_nodes(&plan["nodes"], &mut plan_nodes);
+        }
+        let mut stats: Vec<Option<&Value>> = stats.into_iter().flatten().map(Some).collect();
+
+        let mut executed = ExecutedPlan::default();
+        for node in plan_nodes {
+            let id = node_id(node);
+            let joined = stats
+                .iter_mut()
+                .find(|stat| id.is_some() && matches!(stat, Some(stat) if node_id(stat) == id))
+                .and_then(Option::take);
+            match (id, joined) {
+                (Some(id), Some(stat)) => executed.nodes.push(ExecutedNode {
+                    id,
+                    node_type: node["type"].as_str().unwrap_or_default().to_owned(),
+                    calls: stat["calls"].as_u64().unwrap_or(0),
+                    items: stat["items"].as_u64().unwrap_or(0),
+                    runtime: stat["runtime"].as_f64().unwrap_or(0.0),
+                    estimated_cost: node["estimatedCost"].as_f64().unwrap_or(0.0),
+                }),
+                _ => executed.unmatched_plan_nodes.push(node.clone()),
+            }
+        }
+        executed.unmatched_stats = stats.into_iter().flatten().cloned().collect();
+        executed
+            .nodes
+            .sort_by(|a, b| b.runtime.total_cmp(&a.runtime));
+        Some(executed)
+    }
+}
+
+/// The plan of a profiled query joined with the statistics of its nodes,
+/// see [`QueryExtra::executed_plan`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutedPlan {
+    /// Nodes found in both the plan and the statistics, the slowest first.
+    pub nodes: Vec<ExecutedNode>,
+    /// Nodes of the plan without statistics.
+    pub unmatched_plan_nodes: Vec<Value>,
+    /// Statistics of nodes missing from the plan.
+    pub unmatched_stats: Vec<Value>,
+}
+
+/// A node of the plan of a profiled query, see [`ExecutedPlan`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutedNode {
+    /// Id of the node in the plan.
+    pub id: u64,
+    /// Type of the node, like `IndexNode` or `EnumerateCollectionNode`.
+    pub node_type: String,
+    /// Number of times the node was called.
+    pub calls: u64,
+    /// Number of items the node returned.
+    pub items: u64,
+    /// Time spent in the node, in seconds.
+    pub runtime: f64,
+    /// Cost of the node and the nodes it depends on as estimated by the
+    /// optimizer.
+    pub estimated_cost: f64,
+}
+
+/// Id of a node of a plan or of its statistics.
+fn node_id(node: &Value) -> Option<u64> {
+    match &node["id"] {
+        Value::String(id) => id.parse().ok(),
+        id => id.as_u64(),
+    }
+}
+
+/// All nodes of a plan, including those of the subqueries of the versions
+/// which nest them.
+pub(crate) fn flatten_nodes<'a>(nodes: &'a Value, flat: &mut Vec<&'a Value>) {
+    for node in nodes.as_array().into_iter().flatten() {
+        flat.push(node);
+        if let Some(subquery) = node.get("subquery") {
+            flatten_nodes(&subquery["nodes"], flat);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -548,4 +1370,453 @@ mod test {
             Some(&Value::String("test2_pwd".to_owned()))
         );
     }
+
+    #[test]
+    fn aql_query_builder_insert_serialize_seq() {
+        #[derive(Serialize)]
+        struct Doc {
+            n: u32,
+        }
+        let q = "FOR doc IN @docs INSERT doc INTO @@collection";
+        let aql = AqlQuery::builder()
+            .query(q)
+            .bind_var("@collection", "docs")
+            .insert_serialize_seq("docs", (0..3).map(|n| Doc { n }))
+            .unwrap()
+            .build();
+        let body = serde_json::to_value(&aql).unwrap();
+        assert_eq!(
+            body["bindVars"],
+            serde_json::json!({ "@collection": "docs", "docs": [{ "n": 0 }, { "n": 1 }, { "n": 2 }] })
+        );
+        let bound = AqlQuery::builder()
+            .query(q)
+            .bind_var("@collection", "docs")
+            .try_bind("docs", (0..3).map(|n| Doc { n }).collect::<Vec<_>>())
+            .unwrap()
+            .build();
+        assert_eq!(aql.request_fingerprint(), bound.request_fingerprint());
+
+        let empty = AqlQuery::builder()
+            .query(q)
+            .insert_serialize_seq("docs", Vec::<Doc>::new())
+            .unwrap()
+            .build();
+        assert_eq!(
+            serde_json::to_string(&empty).unwrap(),
+            r#"{"query":"FOR doc IN @docs INSERT doc INTO @@collection","bindVars":{"docs":[]}}"#
+        );
+    }
+
+    #[test]
+    fn insert_serialize_seq_fails_on_any_item() {
+        struct Failing(u32);
+        impl Serialize for Failing {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if self.0 == 2 {
+                    return Err(serde::ser::Error::custom("unserializable"));
+                }
+                serializer.serialize_u32(self.0)
+            }
+        }
+        let mut bind_vars = BindVars::default();
+        bind_vars.insert("docs", Value::Null);
+        let result = bind_vars.insert_serialize_seq("docs", (0..5).map(Failing));
+        assert!(result.unwrap_err().to_string().contains("unserializable"));
+        // the previous value stays bound
+        assert_eq!(bind_vars.get("docs"), Some(&Value::Null));
+        assert_eq!(
+            serde_json::to_string(&bind_vars).unwrap(),
+            r#"{"docs":null}"#
+        );
+    }
+
+    #[test]
+    fn query_defaults_under_query_options() {
+        let defaults = QueryDefaults {
+            fill_block_cache: Some(false),
+            memory_limit: Some(1 << 30),
+            max_runtime: Some(30.0),
+            stream: None,
+            budget: None,
+        };
+
+        let aql = AqlQuery::builder().query("RETURN 1").build();
+        let mut body = serde_json::to_value(&aql).unwrap();
+        defaults.apply(&mut body);
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "query": "RETURN 1",
+                "memoryLimit": 1 << 30,
+                "options": { "fillBlockCache": false, "maxRuntime": 30.0 },
+            })
+        );
+
+        // per-query values win, other defaults are still filled in
+        let aql = AqlQuery::builder()
+            .query("RETURN 1")
+            .memory_limit(1024)
+            .options(
+                AqlOptions::builder()
+                    .fill_block_cache(true)
+                    .stream(true)
+                    .build(),
+            )
+            .build();
+        let mut body = serde_json::to_value(&aql).unwrap();
+        defaults.apply(&mut body);
+        assert_eq!(body["memoryLimit"], 1024);
+        assert_eq!(
+            body["options"],
+            serde_json::json!({ "fillBlockCache": true, "stream": true, "maxRuntime": 30.0 })
+        );
+        assert!(QueryDefaults::default().is_empty());
+    }
+
+    #[test]
+    fn request_fingerprint_ignores_bind_order() {
+        let q = "FOR u IN users FILTER u.name == @name AND u.age > @age RETURN u";
+        let first = AqlQuery::builder()
+            .query(q)
+            .bind_var("name", "alice")
+            .bind_var("age", 18)
+            .build();
+        let second = AqlQuery::builder()
+            .query(q)
+            .bind_var("age", 18)
+            .bind_var("name", "alice")
+            .batch_size(10)
+            .build();
+        assert_eq!(first.request_fingerprint(), second.request_fingerprint());
+
+        let other = AqlQuery::builder()
+            .query(q)
+            .bind_var("name", "bob")
+            .bind_var("age", 18)
+            .build();
+        assert_ne!(first.request_fingerprint(), other.request_fingerprint());
+    }
+
+    #[test]
+    fn write_keywords() {
+        assert!(has_write_keyword("FOR u IN users REMOVE u IN users"));
+        assert!(has_write_keyword("insert { a: 1 } into users"));
+        assert!(has_write_keyword(
+            "UPSERT { a: 1 } INSERT {} UPDATE {} IN c"
+        ));
+        assert!(!has_write_keyword("FOR u IN users RETURN u"));
+        assert!(!has_write_keyword(
+            "FOR u IN users FILTER u.name == 'INSERT' OR u.x == \"it\\\"s update\" RETURN u"
+        ));
+        assert!(!has_write_keyword(
+            "// remove later\nFOR u IN `update` /* replace */ RETURN u"
+        ));
+        assert!(!has_write_keyword("FOR u IN updates RETURN u.inserted"));
+    }
+
+    #[test]
+    fn write_in_ast() {
+        let ast = serde_json::json!([{
+            "type": "root",
+            "subNodes": [
+                { "type": "for", "subNodes": [{ "type": "variable", "name": "u" }] },
+                { "type": "remove", "subNodes": [] },
+            ],
+        }]);
+        assert!(ast_has_write(&ast));
+        let ast = serde_json::json!([{ "type": "root", "subNodes": [{ "type": "return" }] }]);
+        assert!(!ast_has_write(&ast));
+    }
+
+    /// Read a name quoted with backticks back like the AQL tokenizer, with
+    /// the rest of the query after it.
+    fn unquote(quoted: &str) -> (String, &str) {
+        let mut chars = quoted.char_indices();
+        assert_eq!(chars.next(), Some((0, '`')));
+        let mut name = String::new();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => name.push(chars.next().unwrap().1),
+                '`' => return (name, &quoted[i + 1..]),
+                c => name.push(c),
+            }
+        }
+        panic!("unterminated name {:?}", quoted);
+    }
+
+    #[test]
+    fn escaped_identifiers() {
+        assert_eq!(escape_identifier("users").unwrap(), "`users`");
+        assert_eq!(escape_identifier("a`b").unwrap(), "`a\\`b`");
+        assert_eq!(escape_identifier("a\\`").unwrap(), "`a\\\\\\``");
+        for name in &[
+            "",
+            "a\nb",
+            "\r",
+            "\t",
+            "a\0",
+            "\u{7f}",
+            "\u{85}",
+            "\u{1b}[0m",
+        ] {
+            assert!(
+                matches!(
+                    escape_identifier(name),
+                    Err(ClientError::InvalidName { .. })
+                ),
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn escaped_identifiers_fuzz() {
+        use crate::query::SplitMix64;
+
+        let alphabet = [
+            '`',
+            '`',
+            '\\',
+            '\\',
+            '"',
+            '\'',
+            '´',
+            '/',
+            '*',
+            ' ',
+            'a',
+            'R',
+            'é',
+            '日',
+            '\u{2028}',
+            '\u{feff}',
+            '\u{1F600}',
+            '\n',
+            '\u{0}',
+            '\u{9b}',
+        ];
+        let mut rng = SplitMix64::new(226);
+        for _ in 0..5000 {
+            let len = (rng.next_u64() % 12) as usize;
+            let name: String = (0..len)
+                .map(|_| alphabet[(rng.next_u64() % alphabet.len() as u64) as usize])
+                .collect();
+            let name = format!("{} REMOVE", name);
+            match escape_identifier(&name) {
+                Ok(quoted) => {
+                    assert!(!name.chars().any(char::is_control));
+                    let (unquoted, rest) = unquote(&quoted);
+                    assert_eq!(unquoted, name);
+                    assert_eq!(rest, "");
+                    // the name cannot leave its quotes
+                    let query = format!("FOR d IN {} RETURN d", quoted);
+                    assert!(!has_write_keyword(&query), "{}", query);
+                }
+                Err(_) => assert!(name.chars().any(char::is_control), "{:?}", name),
+            }
+        }
+    }
+
+    #[test]
+    fn escaped_document_paths() {
+        let path = |names: &[&str]| escape_document_path(names).unwrap();
+        assert_eq!(path(&["a", "weird key"]), "d.a.`weird key`");
+        assert_eq!(path(&["_key"]), "d._key");
+        assert_eq!(path(&["meta", "createdAt2"]), "d.meta.createdAt2");
+        assert_eq!(
+            path(&["FILTER", "2nd", "_", "_1"]),
+            "d.`FILTER`.`2nd`.`_`.`_1`"
+        );
+        assert_eq!(path(&["with", "Window"]), "d.`with`.`Window`");
+        assert_eq!(path(&["without", "windows"]), "d.without.windows");
+        assert_eq!(path(&["é", "a-b", "a`b"]), "d.`é`.`a-b`.`a\\`b`");
+        assert!(escape_document_path(&[]).is_err());
+        assert!(escape_document_path(&["a", "b\nc"]).is_err());
+    }
+
+    #[test]
+    fn profile_levels() {
+        let profile =
+            |options: AqlOptions| serde_json::to_value(options).unwrap()["profile"].clone();
+        assert_eq!(profile(AqlOptions::builder().profile(true).build()), 1);
+        assert_eq!(profile(AqlOptions::builder().profile(false).build()), 0);
+        assert_eq!(profile(AqlOptions::builder().profile(2).build()), 2);
+        assert_eq!(
+            profile(AqlOptions::builder().profile(Profile::Phases).build()),
+            1
+        );
+        assert_eq!(profile(AqlOptions::builder().build()), Value::Null);
+    }
+
+    fn stats(nodes: Value) -> Value {
+        serde_json::json!({
+            "writesExecuted": 0,
+            "writesIgnored": 0,
+            "scannedFull": 0,
+            "scannedIndex": 500,
+            "filtered": 0,
+            "httpRequests": 0,
+            "executionTime": 0.0042,
+            "nodes": nodes,
+        })
+    }
+
+    /// Last batch of ArangoDB 3.6 for `FOR u IN users FILTER u.age > 30 LET
+    /// n = (FOR o IN orders FILTER o.user == u._key RETURN 1) RETURN
+    /// LENGTH(n)`, profiled with level 2, with the subquery nested in the
+    /// plan.
+    fn cursor_3_6() -> Value {
+        serde_json::json!({
+            "result": [],
+            "hasMore": false,
+            "cached": false,
+            "extra": {
+                "warnings": [],
+                "profile": { "parsing": 0.0001, "executing": 0.004 },
+                "plan": {
+                    "nodes": [
+                        { "type": "SingletonNode", "id": 1, "estimatedCost": 1 },
+                        { "type": "IndexNode", "id": 6, "estimatedCost": 710.5 },
+                        {
+                            "type": "SubqueryNode",
+                            "id": 4,
+                            "estimatedCost": 1210.5,
+                            "subquery": {
+                                "nodes": [
+                                    { "type": "SingletonNode", "id": 2, "estimatedCost": 1 },
+                                    { "type": "IndexNode", "id": 7, "estimatedCost": 2.5 },
+                                    { "type": "ReturnNode", "id": 3, "estimatedCost": 3.5 }
+                                ]
+                            }
+                        },
+                        { "type": "ReturnNode", "id": 5, "estimatedCost": 1710.5 }
+                    ]
+                },
+                "stats": stats(serde_json::json!([
+                    { "id": 1, "calls": 1, "items": 1, "runtime": 0.00001 },
+                    { "id": 6, "calls": 1, "items": 500, "runtime": 0.0031 },
+                    { "id": 4, "calls": 1, "items": 500, "runtime": 0.0008 },
+                    { "id": 2, "calls": 500, "items": 500, "runtime": 0.0001 },
+                    { "id": 7, "calls": 500, "items": 12, "runtime": 0.0005 },
+                    { "id": 3, "calls": 500, "items": 12, "runtime": 0.0001 },
+                    { "id": 5, "calls": 1, "items": 500, "runtime": 0.00002 }
+                ])),
+            },
+        })
+    }
+
+    /// Last batch of ArangoDB 3.11 for the same query, with the subquery
+    /// spliced, statistics of fetching and filtering, a node which never ran
+    /// and statistics of a node added after planning.
+    fn cursor_3_11() -> Value {
+        serde_json::json!({
+            "result": [],
+            "hasMore": false,
+            "cached": false,
+            "extra": {
+                "warnings": [],
+                "plan": {
+                    "nodes": [
+                        { "type": "SingletonNode", "id": 1, "estimatedCost": 1 },
+                        { "type": "IndexNode", "id": 6, "estimatedCost": 710.5 },
+                        { "type": "SubqueryStartNode", "id": 8, "estimatedCost": 1210.5 },
+                        { "type": "IndexNode", "id": 7, "estimatedCost": 1212.5 },
+                        { "type": "SubqueryEndNode", "id": 9, "estimatedCost": 1213.5 },
+                        { "type": "ReturnNode", "id": 5, "estimatedCost": 1710.5 }
+                    ]
+                },
+                "stats": stats(serde_json::json!([
+                    { "id": 1, "calls": 1, "items": 1, "filtered": 0, "runtime": 0.00001, "fetching": 0 },
+                    { "id": 6, "calls": 1, "items": 500, "filtered": 0, "runtime": 0.0029, "fetching": 0.0001 },
+                    { "id": 8, "calls": 1, "items": 500, "filtered": 0, "runtime": 0.0002, "fetching": 0 },
+                    { "id": 7, "calls": 1, "items": 12, "filtered": 488, "runtime": 0.0007, "fetching": 0 },
+                    { "id": 5, "calls": 1, "items": 500, "filtered": 0, "runtime": 0.00002, "fetching": 0 },
+                    { "id": 12, "calls": 1, "items": 500, "filtered": 0, "runtime": 0.0001, "fetching": 0 }
+                ])),
+            },
+        })
+    }
+
+    #[test]
+    fn executed_plan_of_several_versions() {
+        let executed = |cursor: Value| {
+            let cursor: Cursor<Value> = serde_json::from_value(cursor).unwrap();
+            cursor.extra.unwrap().executed_plan().unwrap()
+        };
+
+        let plan = executed(cursor_3_6());
+        let ids: Vec<u64> = plan.nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids, vec![6, 4, 7, 2, 3, 5, 1]);
+        assert_eq!(
+            plan.nodes[0],
+            ExecutedNode {
+                id: 6,
+                node_type: "IndexNode".to_owned(),
+                calls: 1,
+                items: 500,
+                runtime: 0.0031,
+                estimated_cost: 710.5,
+            }
+        );
+        assert!(plan.unmatched_plan_nodes.is_empty());
+        assert!(plan.unmatched_stats.is_empty());
+
+        let plan = executed(cursor_3_11());
+        assert_eq!(plan.nodes[0].node_type, "IndexNode");
+        assert_eq!(plan.nodes[0].id, 6);
+        assert_eq!(plan.nodes.len(), 5);
+        assert_eq!(plan.unmatched_plan_nodes.len(), 1);
+        assert_eq!(plan.unmatched_plan_nodes[0]["type"], "SubqueryEndNode");
+        assert_eq!(plan.unmatched_stats.len(), 1);
+        assert_eq!(plan.unmatched_stats[0]["id"], 12);
+    }
+
+    #[test]
+    fn executed_plan_without_profile() {
+        let mut cursor = cursor_3_11();
+        let extra = cursor["extra"].as_object_mut().unwrap();
+        extra.remove("plan");
+        extra
+            .get_mut("stats")
+            .unwrap()
+            .as_object_mut()
+            .unwrap()
+            .remove("nodes");
+        let cursor: Cursor<Value> = serde_json::from_value(cursor).unwrap();
+        assert_eq!(cursor.extra.unwrap().executed_plan(), None);
+
+        // statistics without the plan are kept as they are
+        let mut cursor = cursor_3_6();
+        cursor["extra"].as_object_mut().unwrap().remove("plan");
+        let cursor: Cursor<Value> = serde_json::from_value(cursor).unwrap();
+        let plan = cursor.extra.unwrap().executed_plan().unwrap();
+        assert!(plan.nodes.is_empty());
+        assert_eq!(plan.unmatched_stats.len(), 7);
+    }
+
+    #[test]
+    fn query_log_keeps_the_heaviest_queries() {
+        let record = |query: &str, peak_memory_usage| QueryRecord {
+            fingerprint: 0,
+            query: query.to_owned(),
+            peak_memory_usage,
+            execution_time: 0.1,
+            recorded_at: Instant::now(),
+        };
+        let mut log = QueryLog::default();
+        log.record(record("heavy", 1 << 30));
+        for i in 0..2 * QUERY_LOG_CAPACITY {
+            log.record(record("light", i as u64));
+        }
+        let top = log.top_by_memory(Duration::from_secs(60));
+        assert_eq!(top.len(), QUERY_LOG_CAPACITY);
+        assert_eq!(top[0].query, "heavy");
+        // the lightest ones went first
+        assert_eq!(
+            top[QUERY_LOG_CAPACITY - 1].peak_memory_usage,
+            QUERY_LOG_CAPACITY as u64 + 1
+        );
+    }
 }
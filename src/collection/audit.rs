@@ -0,0 +1,554 @@
+//! Audit trail of the changes to the documents of a collection, see
+//! [`AuditedCollection`].
+use std::{
+    fmt,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use maybe_async::maybe_async;
+use serde::{de::DeserializeOwned, Serialize};
+use uclient::ClientExt;
+
+use super::Collection;
+use crate::{
+    document::{
+        options::{RemoveOptions, ReplaceOptions, UpdateOptions},
+        response::DocumentResponse,
+    },
+    transaction::{Transaction, TransactionCollections, TransactionSettings},
+    ClientError,
+};
+
+/// Kind of change recorded in the audit trail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOp {
+    Update,
+    Replace,
+    Remove,
+}
+
+/// Record written to the shadow collection for every change.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a, T> {
+    pub key: &'a str,
+    pub old: Option<&'a T>,
+    pub new: Option<&'a T>,
+    pub op: AuditOp,
+    /// Time of the change in milliseconds since the Unix epoch, as seen by
+    /// the client.
+    pub timestamp: u64,
+    pub actor: Option<String>,
+}
+
+/// How the audit record is written along with the change it records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuditMode {
+    /// The change and its record are written in one stream transaction, so
+    /// that a failure to write the record rolls back the change.
+    #[default]
+    Transactional,
+    /// The record is written after the change, without a transaction. A
+    /// failure to write it leaves the change in place.
+    BestEffort,
+}
+
+/// A stream transaction of an audited change, with the handle of the
+/// collection within it.
+type InTransaction<C> = (Transaction<C>, Collection<C>);
+
+/// A collection whose updates, replaces and removes are recorded as
+/// [`AuditRecord`]s in a shadow collection, `<collection>_audit` by default.
+///
+/// The changes are made with `returnOld` and `returnNew` forced on, for the
+/// record. When the record cannot be written, the operation fails with
+/// [`ClientError::AuditFailed`], telling whether the change was rolled back.
+pub struct AuditedCollection<C: ClientExt> {
+    collection: Collection<C>,
+    shadow: Collection<C>,
+    actor: Arc<dyn Fn() -> Option<String> + Send + Sync>,
+    mode: AuditMode,
+}
+
+impl<C: ClientExt> fmt::Debug for AuditedCollection<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditedCollection")
+            .field("collection", &self.collection.name())
+            .field("shadow", &self.shadow.name())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl<C: ClientExt> AuditedCollection<C> {
+    /// Audit the changes to `collection` in `<collection>_audit`, each
+    /// record naming the actor returned by `actor` at the time of the change.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn new<F>(collection: Collection<C>, actor: F) -> Result<Self, ClientError>
+    where
+        F: Fn() -> Option<String> + Send + Sync + 'static,
+    {
+        let shadow = format!("{}_audit", collection.name());
+        Self::with_shadow_collection(collection, &shadow, actor).await
+    }
+
+    /// Audit the changes to `collection` in collection `shadow`.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn with_shadow_collection<F>(
+        collection: Collection<C>,
+        shadow: &str,
+        actor: F,
+    ) -> Result<Self, ClientError>
+    where
+        F: Fn() -> Option<String> + Send + Sync + 'static,
+    {
+        let shadow = collection.db().collection(shadow).await?;
+        Ok(AuditedCollection {
+            collection,
+            shadow,
+            actor: Arc::new(actor),
+            mode: AuditMode::default(),
+        })
+    }
+
+    /// Set how the audit records are written along with the changes,
+    /// [`AuditMode::Transactional`] by default.
+    pub fn with_mode(mut self, mode: AuditMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The audited collection.
+    pub fn collection(&self) -> &Collection<C> {
+        &self.collection
+    }
+
+    /// The collection of the audit records.
+    pub fn shadow_collection(&self) -> &Collection<C> {
+        &self.shadow
+    }
+
+    /// Partially update a document, and record the change.
+    ///
+    /// # Note
+    /// this function would make requests to arango server.
+    #[maybe_async]
+    pub async fn update_document<T>(
+        &self,
+        _key: &str,
+        doc: T,
+        update_options: UpdateOptions,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let options = update_options.returning_old_and_new();
+        match self.begin().await? {
+            Some((tx, collection)) => {
+                let changed = collection.update_document(_key, doc, options).await;
+                self.commit(tx, _key, AuditOp::Update, changed).await
+            }
+            None => {
+                let changed = self.collection.update_document(_key, doc, options).await?;
+                self.record(&self.shadow, _key, AuditOp::Update, &changed)
+                    .await?;
+                Ok(changed)
+            }
+        }
+    }
+
+    /// Replace a document, and record the change.
+    ///
+    /// # Note
+    /// this function would make requests to arango server.
+    #[maybe_async]
+    pub async fn replace_document<T>(
+        &self,
+        _key: &str,
+        doc: T,
+        replace_options: ReplaceOptions,
+        if_match_header: Option<String>,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let options = replace_options.returning_old_and_new();
+        match self.begin().await? {
+            Some((tx, collection)) => {
+                let changed = collection
+                    .replace_document(_key, doc, options, if_match_header)
+                    .await;
+                self.commit(tx, _key, AuditOp::Replace, changed).await
+            }
+            None => {
+                let changed = self
+                    .collection
+                    .replace_document(_key, doc, options, if_match_header)
+                    .await?;
+                self.record(&self.shadow, _key, AuditOp::Replace, &changed)
+                    .await?;
+                Ok(changed)
+            }
+        }
+    }
+
+    /// Remove a document, and record the change.
+    ///
+    /// # Note
+    /// this function would make requests to arango server.
+    #[maybe_async]
+    pub async fn remove_document<T>(
+        &self,
+        _key: &str,
+        remove_options: RemoveOptions,
+        if_match_header: Option<String>,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let options = remove_options.returning_old();
+        match self.begin().await? {
+            Some((tx, collection)) => {
+                let changed = collection
+                    .remove_document(_key, options, if_match_header)
+                    .await;
+                self.commit(tx, _key, AuditOp::Remove, changed).await
+            }
+            None => {
+                let changed = self
+                    .collection
+                    .remove_document(_key, options, if_match_header)
+                    .await?;
+                self.record(&self.shadow, _key, AuditOp::Remove, &changed)
+                    .await?;
+                Ok(changed)
+            }
+        }
+    }
+
+    /// Begin the transaction of a change in transactional mode, with the
+    /// handle of the collection within it.
+    #[maybe_async]
+    async fn begin(&self) -> Result<Option<InTransaction<C>>, ClientError> {
+        if self.mode == AuditMode::BestEffort {
+            return Ok(None);
+        }
+        let settings = TransactionSettings::builder()
+            .collections(
+                TransactionCollections::builder()
+                    .write(vec![
+                        self.collection.name().to_owned(),
+                        self.shadow.name().to_owned(),
+                    ])
+                    .build(),
+            )
+            .build();
+        let tx = self.collection.db().begin_transaction(settings).await?;
+        match self.collection.clone_with_transaction(tx.id().clone()) {
+            Ok(collection) => Ok(Some((tx, collection))),
+            Err(e) => {
+                let _ = tx.abort().await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Record the change made within `tx` and commit both, or abort the
+    /// transaction if either failed.
+    #[maybe_async]
+    async fn commit<T>(
+        &self,
+        tx: Transaction<C>,
+        key: &str,
+        op: AuditOp,
+        changed: Result<DocumentResponse<T>, ClientError>,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize,
+    {
+        let changed = match changed {
+            Ok(changed) => changed,
+            Err(e) => {
+                // the change failed, nothing to keep
+                let _ = tx.abort().await;
+                return Err(e);
+            }
+        };
+        let shadow = match self.shadow.clone_with_transaction(tx.id().clone()) {
+            Ok(shadow) => shadow,
+            Err(e) => {
+                let _ = tx.abort().await;
+                return Err(e);
+            }
+        };
+        if let Err(e) = self.record(&shadow, key, op, &changed).await {
+            let rolled_back = tx.abort().await.is_ok();
+            return Err(match e {
+                ClientError::AuditFailed { source, .. } => ClientError::AuditFailed {
+                    source,
+                    rolled_back,
+                },
+                e => e,
+            });
+        }
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    /// Write the record of `changed` to `shadow`, the shadow collection or
+    /// its handle within the transaction of the change.
+    #[maybe_async]
+    async fn record<T>(
+        &self,
+        shadow: &Collection<C>,
+        key: &str,
+        op: AuditOp,
+        changed: &DocumentResponse<T>,
+    ) -> Result<(), ClientError>
+    where
+        T: Serialize,
+    {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let record = AuditRecord {
+            key,
+            old: changed.old_doc(),
+            new: changed.new_doc(),
+            op,
+            timestamp,
+            actor: (self.actor)(),
+        };
+        let record = serde_json::to_value(record)?;
+        shadow
+            .create_document(record, Default::default())
+            .await
+            .map(|_| ())
+            .map_err(|e| ClientError::AuditFailed {
+                source: Box::new(e),
+                rolled_back: false,
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::Method;
+    use serde_json::{json, Value};
+
+    use super::{AuditMode, AuditedCollection};
+    use crate::{
+        collection::{Collection, CollectionType},
+        test_support::{fixtures, MockClient, MockResponse},
+        transaction::TRANSACTION_HEADER,
+        ClientError,
+    };
+
+    const DOCUMENT: &str = "/_db/test_db/_api/document/users/bob";
+    const AUDIT: &str = "/_db/test_db/_api/document/users_audit/";
+    const TRANSACTION: &str = "/_db/test_db/_api/transaction/42";
+
+    #[maybe_async::maybe_async]
+    async fn users(mock: &MockClient, shadow: &str) -> AuditedCollection<MockClient> {
+        let db = mock.database("test_db");
+        let collection = Collection::new(
+            "users",
+            "100",
+            CollectionType::Document,
+            db.url(),
+            db.shared_session(),
+        );
+        mock.once(
+            Method::GET,
+            &format!("/_db/test_db/_api/collection/{}", shadow),
+            MockResponse::json(200, fixtures::collection_info(shadow, "101")),
+        );
+        let actor = || Some("alice".to_owned());
+        let users = if shadow == "users_audit" {
+            AuditedCollection::new(collection, actor).await
+        } else {
+            AuditedCollection::with_shadow_collection(collection, shadow, actor).await
+        };
+        mock.clear_requests();
+        users.unwrap()
+    }
+
+    fn begun(mock: &MockClient) {
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/transaction/begin",
+            MockResponse::json(
+                201,
+                json!({ "error": false, "code": 201, "result": { "id": "42", "status": "running" } }),
+            ),
+        );
+    }
+
+    fn ended(status: &str) -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({ "error": false, "code": 200, "result": { "id": "42", "status": status } }),
+        )
+    }
+
+    fn updated() -> MockResponse {
+        MockResponse::json(
+            201,
+            json!({
+                "_id": "users/bob", "_key": "bob", "_rev": "_r2", "_oldRev": "_r1",
+                "old": { "age": 1 },
+                "new": { "age": 2 },
+            }),
+        )
+    }
+
+    fn recorded() -> MockResponse {
+        MockResponse::json(
+            202,
+            json!({ "_id": "users_audit/1", "_key": "1", "_rev": "_a" }),
+        )
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn transactional_audit() {
+        let mock = MockClient::default();
+        let users = users(&mock, "users_audit").await;
+        begun(&mock);
+        mock.once(Method::PATCH, DOCUMENT, updated());
+        mock.once(Method::POST, AUDIT, recorded());
+        mock.once(Method::PUT, TRANSACTION, ended("committed"));
+        let changed = users
+            .update_document("bob", json!({ "age": 2 }), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(changed.new_doc(), Some(&json!({ "age": 2 })));
+        mock.assert_no_pending();
+
+        let requests = mock.requests();
+        let begin = requests[0].json_body().unwrap();
+        assert_eq!(
+            begin["collections"]["write"],
+            json!(["users", "users_audit"])
+        );
+        assert_eq!(
+            requests[1].query_param("returnOld").as_deref(),
+            Some("true")
+        );
+        assert_eq!(
+            requests[1].query_param("returnNew").as_deref(),
+            Some("true")
+        );
+        for request in &requests[1..3] {
+            assert_eq!(request.header(TRANSACTION_HEADER), Some("42"));
+        }
+        let record = requests[2].json_body().unwrap();
+        assert_eq!(record["key"], "bob");
+        assert_eq!(record["op"], "update");
+        assert_eq!(record["old"], json!({ "age": 1 }));
+        assert_eq!(record["new"], json!({ "age": 2 }));
+        assert_eq!(record["actor"], "alice");
+        assert!(record["timestamp"].as_u64().unwrap() > 0);
+
+        // the record fails, the change is rolled back
+        mock.clear_requests();
+        begun(&mock);
+        mock.once(Method::PATCH, DOCUMENT, updated());
+        mock.once(
+            Method::POST,
+            AUDIT,
+            MockResponse::json(404, fixtures::error(404, 1203, "collection not found")),
+        );
+        mock.once(Method::DELETE, TRANSACTION, ended("aborted"));
+        let result = users
+            .update_document("bob", json!({ "age": 2 }), Default::default())
+            .await;
+        match result {
+            Err(ClientError::AuditFailed {
+                source,
+                rolled_back,
+            }) => {
+                assert!(rolled_back);
+                assert!(matches!(*source, ClientError::Arango(ref e) if e.error_num() == 1203));
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+        mock.assert_no_pending();
+
+        // the change fails, nothing is recorded
+        mock.clear_requests();
+        begun(&mock);
+        mock.once(
+            Method::DELETE,
+            DOCUMENT,
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        mock.once(Method::DELETE, TRANSACTION, ended("aborted"));
+        let result = users
+            .remove_document::<Value>("bob", Default::default(), None)
+            .await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1202),
+            "{:?}",
+            result.map(|_| ())
+        );
+        mock.assert_no_pending();
+        assert!(mock.requests().iter().all(|r| r.path != AUDIT));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn best_effort_audit() {
+        let mock = MockClient::default();
+        let users = users(&mock, "trail").await.with_mode(AuditMode::BestEffort);
+        let audit = "/_db/test_db/_api/document/trail/";
+        mock.once(
+            Method::DELETE,
+            DOCUMENT,
+            MockResponse::json(
+                200,
+                json!({ "_id": "users/bob", "_key": "bob", "_rev": "_r1", "old": { "age": 1 } }),
+            ),
+        );
+        mock.once(Method::POST, audit, recorded());
+        let removed = users
+            .remove_document::<Value>("bob", Default::default(), None)
+            .await
+            .unwrap();
+        assert_eq!(removed.old_doc(), Some(&json!({ "age": 1 })));
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].header(TRANSACTION_HEADER), None);
+        assert_eq!(
+            requests[0].query_param("returnOld").as_deref(),
+            Some("true")
+        );
+        let record = requests[1].json_body().unwrap();
+        assert_eq!(record["op"], "remove");
+        assert_eq!(record["new"], Value::Null);
+
+        // the record fails, the change is kept and the failure reported
+        mock.clear_requests();
+        mock.once(Method::PUT, DOCUMENT, updated());
+        mock.once(Method::POST, audit, MockResponse::text(503, "unavailable"));
+        let result = users
+            .replace_document("bob", json!({ "age": 2 }), Default::default(), None)
+            .await;
+        match result {
+            Err(
+                e @ ClientError::AuditFailed {
+                    rolled_back: false, ..
+                },
+            ) => {
+                assert!(e.to_string().contains("change kept"), "{}", e)
+            }
+            other => panic!("unexpected result {:?}", other.map(|_| ())),
+        }
+        mock.assert_no_pending();
+    }
+}
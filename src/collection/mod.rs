@@ -2,32 +2,87 @@
 //!
 //! This mod contains struct and type of colleciton info and management, as well
 //! as document related operations.
-use std::{convert::TryFrom, sync::Arc};
+#[cfg(feature = "documents")]
+use std::time::{Duration, Instant};
+#[cfg(all(feature = "documents", feature = "aql"))]
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    ops::Bound,
+};
+use std::{fmt, sync::Arc};
 
+#[cfg(feature = "documents")]
 use http::Request;
 use maybe_async::maybe_async;
-use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+#[cfg(feature = "documents")]
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::json;
+#[cfg(feature = "documents")]
+use serde_json::value::RawValue;
+#[cfg(feature = "documents")]
+use serde_json::Value;
 use uclient::ClientExt;
 use url::Url;
 
 use options::*;
 use response::*;
 
+#[cfg(all(feature = "documents", feature = "aql"))]
+use crate::{
+    aql::{AqlOptions, AqlQuery},
+    connection::capability::{OVERWRITE_MODE_SINCE, SIMPLE_ANY},
+    cursor::QueryCursor,
+    document::{key::DocumentKey, options::UpsertOptions, response::CasOutcome},
+    query::{prefix_upper_bound, stratified_offsets, SplitMix64},
+};
+#[cfg(feature = "documents")]
 use crate::{
+    connection::capability::ServerVersion,
     document::{
-        options::{InsertOptions, ReadOptions, RemoveOptions, ReplaceOptions, UpdateOptions},
-        response::DocumentResponse,
-        Header,
+        key::{document_url, KeyStrategy},
+        options::{
+            BatchReadOptions, DocumentReadOptions, InsertOptions, RemoveOptions, ReplaceOptions,
+            UpdateOptions,
+        },
+        response::{
+            BulkDocumentResult, DocumentLookup, DocumentResponse, HeaderLookup, InsertOrGet,
+            ModifiedDocument,
+        },
+        EdgeDocument, Header,
+    },
+    response::{parse_body, BulkErrorSummary, ResponseMeta},
+    ArangoError, Document,
+};
+use crate::{
+    connection::{
+        capability::{Support, COLLECTION_LOAD, COLLECTION_UNLOAD},
+        endpoint, DisplayEndpoint, Session,
     },
-    response::{deserialize_response, ArangoResult},
-    transaction::Transaction,
+    error::check_writable,
+    options::RequestOptions,
+    response::{deserialize_response, id_header, ArangoResult},
     ClientError,
 };
 
-use super::{Database, Document};
-use crate::transaction::TRANSACTION_HEADER;
+use super::Database;
+#[cfg(feature = "cluster")]
+use crate::connection::cluster_only;
+#[cfg(feature = "rocksdb")]
+use crate::database::not_supported;
+use crate::options::TRANSACTION_HEADER;
+#[cfg(feature = "transactions")]
+use crate::transaction::Transaction;
+#[cfg(all(feature = "enterprise", feature = "documents"))]
+use crate::{connection::capability::COLLECTION_PROPERTIES, error::is_forbidden};
 
+/// Header allowing a follower to answer a read.
+#[cfg(feature = "documents")]
+pub(crate) const DIRTY_READ_HEADER: &str = "x-arango-allow-dirty-read";
+
+#[cfg(all(feature = "transactions", feature = "documents"))]
+pub mod audit;
 pub mod options;
 pub mod response;
 
@@ -41,14 +96,30 @@ pub mod response;
 /// Collections have a type
 /// that is specified by the user when the collection is created. There are
 /// currently two types: document and edge. The default type is document.
-#[derive(Debug, Clone)]
+///
+/// Shown as `mydb/users @ https://arango.internal:8529`, without credentials.
+///
+/// # Sharing between tasks
+///
+/// A collection is `Send + Sync` whenever its HTTP client is, and can be
+/// shared by concurrent tasks, e.g. behind an `Arc`.
+#[derive(Clone)]
 pub struct Collection<C: ClientExt> {
     id: String,
     name: String,
     collection_type: CollectionType,
     base_url: Url,
     document_base_url: Url,
-    session: Arc<C>,
+    session: Session<C>,
+    read_only: bool,
+    field_case: Case,
+    /// Whether edges are checked against disjoint smart graph constraints
+    /// before being sent to the server.
+    #[cfg(all(feature = "enterprise", feature = "documents"))]
+    validate_smart_edges: bool,
+    /// Cached `isDisjoint` property of the collection, fetched on first use.
+    #[cfg(all(feature = "enterprise", feature = "documents"))]
+    disjoint: Arc<std::sync::Mutex<Option<bool>>>,
 }
 
 impl<'a, C: ClientExt> Collection<C> {
@@ -61,7 +132,7 @@ impl<'a, C: ClientExt> Collection<C> {
         id: S,
         collection_type: CollectionType,
         db_url: &Url,
-        session: Arc<C>,
+        session: Session<C>,
     ) -> Collection<C> {
         let name = name.into();
         let path = format!("_api/collection/{}/", &name);
@@ -75,19 +146,28 @@ impl<'a, C: ClientExt> Collection<C> {
             base_url: url,
             document_base_url,
             collection_type,
+            read_only: false,
+            field_case: Case::Snake,
+            #[cfg(all(feature = "enterprise", feature = "documents"))]
+            validate_smart_edges: true,
+            #[cfg(all(feature = "enterprise", feature = "documents"))]
+            disjoint: Arc::new(std::sync::Mutex::new(None)),
         }
     }
 
     pub(crate) fn from_response(database: &Database<C>, collection: &Info) -> Collection<C> {
-        Self::new(
+        let mut collection = Self::new(
             &collection.name,
             &collection.id,
             collection.collection_type,
             database.url(),
-            database.session(),
-        )
+            database.shared_session(),
+        );
+        collection.read_only = database.is_read_only();
+        collection
     }
 
+    #[cfg(feature = "transactions")]
     pub(crate) fn from_transaction_response(
         transaction: &Transaction<C>,
         collection: &Info,
@@ -97,7 +177,7 @@ impl<'a, C: ClientExt> Collection<C> {
             &collection.id,
             collection.collection_type,
             transaction.url(),
-            transaction.session(),
+            transaction.shared_session(),
         )
     }
 
@@ -152,36 +232,129 @@ impl<'a, C: ClientExt> Collection<C> {
 
     /// HTTP Client used to query the server
     pub fn session(&self) -> Arc<C> {
-        Arc::clone(&self.session)
+        self.session.client()
     }
 
-    /// Get the db of current collection
-    pub fn db(&self) -> Database<C> {
+    /// Name of the database of this collection.
+    fn db_name(&self) -> &str {
         // Base url should be like `http://server:port/_db/mydb/_api/collection/{collection-name}`
         let mut paths = self.base_url.path_segments().unwrap();
         // must be `_db`
         paths.next();
         // must be db name
-        let name = paths.next().unwrap();
-        Database::new(name, &self.url().join("/").unwrap(), self.session())
+        paths.next().unwrap()
     }
 
-    /// Drop a collection
+    /// Get the db of current collection
+    pub fn db(&self) -> Database<C> {
+        let db = Database::new(
+            self.db_name(),
+            &self.url().join("/").unwrap(),
+            self.session.clone(),
+        );
+        if self.read_only {
+            db.read_only()
+        } else {
+            db
+        }
+    }
+
+    /// Turn this handle into a read-only one, refusing to modify documents
+    /// and the collection itself with `ClientError::ReadOnlyConnection`.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Whether this handle is read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn check_writable(&self, operation: &str) -> Result<(), ClientError> {
+        check_writable(self.read_only, operation)
+    }
+
+    /// Rename the fields of documents written and read through this handle
+    /// to the naming convention `case` of the stored attributes.
     ///
-    /// # Note
-    /// this function would make a request to arango server.
+    /// Field names are renamed in nested objects as well, and in the
+    /// attribute paths of [`range_scan`](Collection::range_scan) and
+    /// [`prefix_scan`](Collection::prefix_scan), so that snake_case structs
+    /// map onto camelCase attributes without `#[serde(rename_all)]`. Map
+    /// keys which are data rather than field names get renamed too, so keep
+    /// such documents on a handle without a field case.
+    pub fn with_field_case(mut self, case: Case) -> Self {
+        self.field_case = case;
+        self
+    }
+
+    /// Naming convention of the stored attributes.
+    pub fn field_case(&self) -> Case {
+        self.field_case
+    }
+
+    /// Serialize a document with the stored attribute names.
+    ///
+    /// Request bodies are written compactly, and the document is serialized
+    /// exactly once, straight into the body unless it has to be renamed.
+    #[cfg(feature = "documents")]
+    fn encode<T: Serialize>(&self, doc: &T) -> Result<String, ClientError> {
+        match self.field_case {
+            Case::Snake => Ok(serde_json::to_string(doc)?),
+            case => Ok(serde_json::to_string(
+                &case.to_stored(serde_json::to_value(doc)?),
+            )?),
+        }
+    }
+
+    /// Serialize a document to insert, or an array of them, after checking it
+    /// against the disjoint smart graph constraint if it is an edge, still
+    /// serializing it once.
+    #[cfg(feature = "documents")]
     #[maybe_async]
-    pub async fn drop(self) -> Result<String, ClientError> {
-        let url = self.base_url.join("").unwrap();
+    async fn insert_body<T: Serialize>(&self, doc: &T) -> Result<String, ClientError> {
+        #[cfg(feature = "enterprise")]
+        if self.collection_type == CollectionType::Edge && self.validate_smart_edges {
+            let value = serde_json::to_value(doc)?;
+            match &value {
+                Value::Array(edges) => {
+                    for edge in edges {
+                        self.check_smart_edge(edge).await?;
+                    }
+                }
+                edge => self.check_smart_edge(edge).await?,
+            }
+            return Ok(serde_json::to_string(&self.field_case.to_stored(value))?);
+        }
+        self.encode(doc)
+    }
 
-        #[derive(Debug, Deserialize)]
-        struct DropCollectionResponse {
-            id: String,
+    /// Deserialize a response about documents with the Rust field names.
+    #[cfg(feature = "documents")]
+    fn decode<T: DeserializeOwned>(&self, resp: &http::Response<String>) -> Result<T, ClientError> {
+        match self.field_case {
+            Case::Snake => deserialize_response(resp),
+            case => {
+                let value: Value = deserialize_response(resp)?;
+                Ok(serde_json::from_value(case.to_rust(value))?)
+            }
         }
+    }
 
-        let resp: DropCollectionResponse =
-            deserialize_response(self.session.delete(url, "").await?.body())?;
-        Ok(resp.id)
+    /// Drop a collection
+    ///
+    /// The handle is consumed, as the collection does not exist anymore.
+    /// Dropping a collection which does not exist fails with
+    /// `ClientError::CollectionNotFound`, that teardown code may treat as
+    /// success.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn drop(self, options: DropOptions) -> Result<DroppedCollection, ClientError> {
+        self.check_writable("drop a collection")?;
+        drop_collection(&self.session, &self.base_url, &self.name, options).await
     }
 
     /// Truncate current collection
@@ -190,8 +363,9 @@ impl<'a, C: ClientExt> Collection<C> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn truncate(&self) -> Result<Info, ClientError> {
+        self.check_writable("truncate a collection")?;
         let url = self.base_url.join("truncate").unwrap();
-        let resp: Info = deserialize_response(self.session.put(url, "").await?.body())?;
+        let resp: Info = deserialize_response(&self.session.put(url, "").await?)?;
         Ok(resp)
     }
 
@@ -202,7 +376,7 @@ impl<'a, C: ClientExt> Collection<C> {
     #[maybe_async]
     pub async fn properties(&self) -> Result<Properties, ClientError> {
         let url = self.base_url.join("properties").unwrap();
-        let resp: Properties = deserialize_response(self.session.get(url, "").await?.body())?;
+        let resp: Properties = deserialize_response(&self.session.get(url, "").await?)?;
         Ok(resp)
     }
 
@@ -213,9 +387,28 @@ impl<'a, C: ClientExt> Collection<C> {
     #[maybe_async]
     pub async fn document_count(&self) -> Result<Properties, ClientError> {
         let url = self.base_url.join("count").unwrap();
-        let resp: Properties = deserialize_response(self.session.get(url, "").await?.body())?;
+        let resp: Properties = deserialize_response(&self.session.get(url, "").await?)?;
         Ok(resp)
     }
+
+    /// The number of documents in this collection, as counted by
+    /// [`document_count`](Collection::document_count).
+    ///
+    /// The server keeps the number of documents of each collection, so that
+    /// it is not recounted on each call. The `count` in the
+    /// [`statistics`](Collection::statistics) is the same number, at the
+    /// extra cost of the figures.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn count(&self) -> Result<u64, ClientError> {
+        let resp = self.document_count().await?;
+        resp.info.count.ok_or_else(|| {
+            ClientError::MalformedResponse("collection count without a count".to_owned())
+        })
+    }
+
     /// Fetch the statistics of a collection
     ///
     /// The result also contains the number of documents and additional
@@ -246,8 +439,27 @@ impl<'a, C: ClientExt> Collection<C> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn statistics(&self) -> Result<Statistics, ClientError> {
-        let url = self.base_url.join("figures").unwrap();
-        let resp: Statistics = deserialize_response(self.session.get(url, "").await?.body())?;
+        self.statistics_with_options(Default::default()).await
+    }
+
+    /// Fetch the statistics of a collection, see
+    /// [`statistics`](Collection::statistics), with the figures of the
+    /// storage engine when `details` is set in the [`FiguresOptions`].
+    ///
+    /// The figures which are not described by
+    /// [`Figures`](response::Figures) are kept in its `extra` map.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn statistics_with_options(
+        &self,
+        options: FiguresOptions,
+    ) -> Result<Statistics, ClientError> {
+        let mut url = self.base_url.join("figures").unwrap();
+        options.apply_query(&mut url);
+
+        let resp: Statistics = deserialize_response(&self.session.get(url, "").await?)?;
         Ok(resp)
     }
 
@@ -262,9 +474,63 @@ impl<'a, C: ClientExt> Collection<C> {
     #[maybe_async]
     pub async fn revision_id(&self) -> Result<Revision, ClientError> {
         let url = self.base_url.join("revision").unwrap();
-        let resp: Revision = deserialize_response(self.session.get(url, "").await?.body())?;
+        let resp: Revision = deserialize_response(&self.session.get(url, "").await?)?;
         Ok(resp)
     }
+
+    /// The shards of the collection, with the DB-Servers holding each shard
+    /// when `details` is set.
+    ///
+    /// Fails with [`ClientError::ClusterOnly`] when the server is not a
+    /// Coordinator of a cluster.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    #[cfg(feature = "cluster")]
+    pub async fn shards(&self, details: bool) -> Result<Vec<Shard>, ClientError> {
+        let mut url = self.base_url.join("shards").unwrap();
+        if details {
+            url.set_query(Some("details=true"));
+        }
+        let resp = self.session.get(url, "").await?;
+        let result: ShardList =
+            deserialize_response(&resp).map_err(|e| cluster_only(e, "shards"))?;
+        Ok(result.into_shards())
+    }
+
+    /// The id of the shard which holds, or would hold, `doc`, after the
+    /// values of its shard key attributes.
+    ///
+    /// Fails with [`ClientError::ClusterOnly`] when the server is not a
+    /// Coordinator of a cluster.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    #[cfg(feature = "cluster")]
+    pub async fn responsible_shard(&self, doc: &Value) -> Result<String, ClientError> {
+        let url = self.base_url.join("responsibleShard").unwrap();
+        let resp = self.session.put(url, serde_json::to_string(doc)?).await?;
+        let result: ResponsibleShard =
+            deserialize_response(&resp).map_err(|e| cluster_only(e, "responsible_shard"))?;
+        Ok(result.shard_id)
+    }
+
+    /// The revision id of the collection, as in
+    /// [`revision_id`](Collection::revision_id), which changes with every
+    /// write to the collection.
+    ///
+    /// Comparing revisions is a cheap way to find out whether a collection
+    /// changed, unlike [`checksum`](Collection::checksum) which reads all
+    /// documents.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn revision(&self) -> Result<String, ClientError> {
+        Ok(self.revision_id().await?.revision)
+    }
     /// Fetch a checksum for the specified collection
     ///
     /// Will calculate a checksum of the meta-data (keys and optionally
@@ -323,7 +589,7 @@ impl<'a, C: ClientExt> Collection<C> {
         let query = serde_qs::to_string(&options).unwrap();
         url.set_query(Some(query.as_str()));
 
-        let resp: Checksum = deserialize_response(self.session.get(url, "").await?.body())?;
+        let resp: Checksum = deserialize_response(&self.session.get(url, "").await?)?;
         Ok(resp)
     }
 
@@ -340,14 +606,29 @@ impl<'a, C: ClientExt> Collection<C> {
     ///   the number of documents in the collection. Setting count to false may
     ///   speed up loading a collection. The default value for count is true.
     ///
+    /// The endpoint is deprecated since ArangoDB 3.8, where loading has no
+    /// effect, and removed in 3.12, where the collection is read instead.
+    ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn load(&self, count: bool) -> Result<Info, ClientError> {
+        let support = self
+            .session
+            .endpoint_support(&self.base_url, &COLLECTION_LOAD)
+            .await;
+        if support == Support::Removed {
+            let path = if count {
+                "count".to_owned()
+            } else {
+                format!("../{}", self.name)
+            };
+            let url = self.base_url.join(&path).unwrap();
+            return deserialize_response(&self.session.get(url, "").await?);
+        }
         let url = self.base_url.join("load").unwrap();
         let body = json!({ "count": count });
-        let resp: Info =
-            deserialize_response(self.session.put(url, body.to_string()).await?.body())?;
+        let resp: Info = deserialize_response(&self.session.put(url, body.to_string()).await?)?;
         Ok(resp)
     }
 
@@ -356,12 +637,23 @@ impl<'a, C: ClientExt> Collection<C> {
     /// This call does not delete any documents. You can use the collection
     /// afterwards; in which case it will be loaded into memory, again.
     ///
+    /// The endpoint is deprecated since ArangoDB 3.8, where unloading has no
+    /// effect, and removed in 3.12, where the collection is read instead.
+    ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn unload(&self) -> Result<Info, ClientError> {
+        let support = self
+            .session
+            .endpoint_support(&self.base_url, &COLLECTION_UNLOAD)
+            .await;
+        if support == Support::Removed {
+            let url = self.base_url.join(&format!("../{}", self.name)).unwrap();
+            return deserialize_response(&self.session.get(url, "").await?);
+        }
         let url = self.base_url.join("unload").unwrap();
-        let resp: Info = deserialize_response(self.session.put(url, "").await?.body())?;
+        let resp: Info = deserialize_response(&self.session.put(url, "").await?)?;
         Ok(resp)
     }
 
@@ -392,8 +684,7 @@ impl<'a, C: ClientExt> Collection<C> {
     #[maybe_async]
     pub async fn load_indexes(&self) -> Result<bool, ClientError> {
         let url = self.base_url.join("loadIndexesIntoMemory").unwrap();
-        let resp: ArangoResult<bool> =
-            deserialize_response(self.session.put(url, "").await?.body())?;
+        let resp: ArangoResult<bool> = deserialize_response(&self.session.put(url, "").await?)?;
         Ok(resp.unwrap())
     }
 
@@ -406,10 +697,11 @@ impl<'a, C: ClientExt> Collection<C> {
         &self,
         properties: PropertiesOptions,
     ) -> Result<Properties, ClientError> {
+        self.check_writable("change collection properties")?;
         let url = self.base_url.join("properties").unwrap();
 
         let body = serde_json::to_string(&properties).unwrap();
-        let resp: Properties = deserialize_response(self.session.put(url, body).await?.body())?;
+        let resp: Properties = deserialize_response(&self.session.put(url, body).await?)?;
         Ok(resp)
     }
 
@@ -419,29 +711,52 @@ impl<'a, C: ClientExt> Collection<C> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn rename(&mut self, name: &str) -> Result<Info, ClientError> {
+        self.check_writable("rename a collection")?;
         let url = self.base_url.join("rename").unwrap();
         let body = json!({ "name": name });
-        let resp: Info =
-            deserialize_response(self.session.put(url, body.to_string()).await?.body())?;
+        let resp: Info = deserialize_response(&self.session.put(url, body.to_string()).await?)?;
         self.name = name.to_string();
         self.base_url = self.base_url.join(&format!("../{}/", name)).unwrap();
         Ok(resp)
     }
 
-    /// Recalculate the document count of a collection
+    /// Recalculate the document count of a collection, which can drift from
+    /// the documents stored, e.g. after large removals, and return the new
+    /// count.
     ///
-    /// **Note**: this method is specific for the RocksDB storage engine
+    /// **Note**: this method is specific for the RocksDB storage engine.
+    /// Fails with [`ClientError::NotSupported`] when the server does not
+    /// implement it.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[cfg(feature = "rocksdb")]
     #[maybe_async]
-    pub async fn recalculate_count(&self) -> Result<bool, ClientError> {
+    pub async fn recalculate_count(&self) -> Result<u64, ClientError> {
         let url = self.base_url.join("recalculateCount").unwrap();
-        let resp: ArangoResult<bool> =
-            deserialize_response(self.session.put(url, "").await?.body())?;
-        Ok(resp.unwrap())
+        let resp: RecalculatedCount = deserialize_response(&self.session.put(url, "").await?)
+            .map_err(|e| not_supported(e, "recalculate the count of a collection"))?;
+        Ok(resp.count)
+    }
+
+    /// Compact the data of a collection, so that the disk space of the
+    /// documents removed or overwritten gets reclaimed.
+    ///
+    /// **Note**: this method is specific for the RocksDB storage engine, since
+    /// ArangoDB 3.6. Fails with [`ClientError::NotSupported`] when the server
+    /// does not implement it.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[cfg(feature = "rocksdb")]
+    #[maybe_async]
+    pub async fn compact(&self) -> Result<(), ClientError> {
+        let url = self.base_url.join("compact").unwrap();
+        let _: serde_json::Value = deserialize_response(&self.session.put(url, "").await?)
+            .map_err(|e| not_supported(e, "compact a collection"))?;
+        Ok(())
     }
+
     /// Rotate the journal of a collection
     ///
     /// The current journal of the collection will be closed and made a
@@ -455,19 +770,33 @@ impl<'a, C: ClientExt> Collection<C> {
     /// This methods is not documented on 3.7
     ///
     /// **Note**: this method is specific for the MMFiles storage engine, and
-    /// there it is not available in a cluster.
+    /// there it is not available in a cluster. Fails with
+    /// [`ClientError::EndpointRemoved`] from ArangoDB 3.7 on, which removed
+    /// that engine.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[cfg(feature = "mmfiles")]
     #[maybe_async]
     pub async fn rotate_journal(&self) -> Result<bool, ClientError> {
+        use crate::connection::capability::COLLECTION_ROTATE;
+
+        let support = self
+            .session
+            .endpoint_support(&self.base_url, &COLLECTION_ROTATE)
+            .await;
+        if support == Support::Removed {
+            return Err(COLLECTION_ROTATE.removed());
+        }
         let url = self.base_url.join("rotate").unwrap();
-        let resp: ArangoResult<bool> =
-            deserialize_response(self.session.put(url, "").await?.body())?;
+        let resp: ArangoResult<bool> = deserialize_response(&self.session.put(url, "").await?)?;
         Ok(resp.unwrap())
     }
+}
 
+/// Reads and writes of the documents of a collection.
+#[cfg(feature = "documents")]
+impl<C: ClientExt> Collection<C> {
     /// Create a new document from the document given in the body, unless
     /// there is already a document with the _key given.
     ///
@@ -506,6 +835,11 @@ impl<'a, C: ClientExt> Collection<C> {
     /// document, the complete new document is returned under the new attribute
     /// in the result.
     ///
+    /// Fails with `ClientError::InvalidOptions` without any request when
+    /// `keep_null` or `merge_objects` is set without `OverwriteMode::Update`.
+    /// With `overwrite_mode`, the version of the server is fetched by the
+    /// first insert of the connection.
+    ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
@@ -517,310 +851,5714 @@ impl<'a, C: ClientExt> Collection<C> {
     where
         T: Serialize + DeserializeOwned,
     {
-        let mut url = self.document_base_url.join("").unwrap();
-        let body = serde_json::to_string(&doc)?;
-        let query = serde_qs::to_string(&insert_options).unwrap();
-        url.set_query(Some(query.as_str()));
-        let resp: DocumentResponse<T> =
-            deserialize_response(self.session.post(url, body).await?.body())?;
-        Ok(resp)
+        let resp = self.send_create_document(&doc, insert_options).await?;
+        self.decode(&resp)
     }
 
-    /// Read a single document with `_key`
-    ///
-    /// Returns the document identified by document-id. The returned document
-    /// contains three special attributes: _id containing the document
-    /// identifier, _key containing key which uniquely identifies a document in
-    /// a given collection and _rev containing the revision.
+    /// Create a new document like
+    /// [`create_document`](Self::create_document), and also return the
+    /// status and headers of the response.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
-    pub async fn document<T>(&self, _key: &str) -> Result<Document<T>, ClientError>
+    pub async fn create_document_with_meta<T>(
+        &self,
+        doc: T,
+        insert_options: InsertOptions,
+    ) -> Result<(DocumentResponse<T>, ResponseMeta), ClientError>
     where
         T: Serialize + DeserializeOwned,
     {
-        self.document_with_options(_key, Default::default()).await
+        let resp = self.send_create_document(&doc, insert_options).await?;
+        Ok((self.decode(&resp)?, ResponseMeta::new(resp)))
     }
 
-    /// Read a single document with options
+    /// Create a new document like
+    /// [`create_document`](Self::create_document) with `silent` set,
+    /// without reading the response of the server unless it is an error.
     ///
-    /// Returns the document identified by document-id. The returned document
-    /// contains three special attributes: _id containing the document
-    /// identifier, _key containing key which uniquely identifies a document in
-    /// a given collection and _rev containing the revision.
+    /// The insert fails with `ClientError::InvalidOptions` without any
+    /// request when `return_new` or `return_old` is set.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
-    pub async fn document_with_options<T>(
+    pub async fn create_document_silent<T: Serialize>(
         &self,
-        _key: &str,
-        read_options: ReadOptions,
-    ) -> Result<Document<T>, ClientError>
-    where
-        T: Serialize + DeserializeOwned,
-    {
-        let url = self.document_base_url.join(_key).unwrap();
-        let mut build = Request::get(url.to_string());
-
-        let header = make_header_from_options(read_options);
-        if let Some(h) = header {
-            build = build.header(h.0, h.1)
-        }
-        let req = build.body("".to_string()).unwrap();
-        let resp: Document<T> = deserialize_response(self.session.request(req).await?.body())?;
-        Ok(resp)
+        doc: T,
+        insert_options: InsertOptions,
+    ) -> Result<(), ClientError> {
+        let resp = self
+            .send_create_document(&doc, insert_options.into_silent())
+            .await?;
+        check_written(&resp)
     }
 
-    /// Read a single document header
+    /// Create an edge in this edge collection.
     ///
-    /// Like GET, but only returns the header fields and not the body. You can
-    /// use this call to get the current revision of a document or check if the
-    /// document was deleted.
+    /// Its `_from` and `_to` are well-formed ids by construction of
+    /// [`EdgeDocument`], while the server checks that the collection is an
+    /// edge collection. With `return_new`, the edge stored reads back as an
+    /// `EdgeDocument<T>` as well.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
-    pub async fn document_header(&self, _key: &str) -> Result<Header, ClientError> {
-        self.document_header_with_options(_key, Default::default())
-            .await
+    pub async fn create_edge<T>(
+        &self,
+        edge: EdgeDocument<T>,
+        insert_options: InsertOptions,
+    ) -> Result<DocumentResponse<EdgeDocument<T>>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.create_document(edge, insert_options).await
     }
 
-    /// Read a single document header with options
+    /// Create several documents with a single request.
     ///
-    /// Like GET, but only returns the header fields and not the body. You can
-    /// use this call to get the current revision of a document or check if the
-    /// document was deleted.
+    /// The results are in the order of `docs`, with an error for each
+    /// document which could not be created, e.g. on a unique constraint
+    /// violation, while the others are created. The request itself fails
+    /// like [`create_document`](Self::create_document) does, on a missing
+    /// collection for instance.
+    ///
+    /// `return_new` and `return_old` return the documents in each result as
+    /// for a single document. With `silent`, every result is
+    /// `DocumentResponse::Silent`: as the server then only reports the
+    /// failed documents, without their position, the first failure is
+    /// returned as error if any document could not be created.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
-    pub async fn document_header_with_options(
+    pub async fn create_documents<T>(
         &self,
-        _key: &str,
-        read_options: ReadOptions,
-    ) -> Result<Header, ClientError> {
-        let url = self.document_base_url.join(_key).unwrap();
-        let mut build = Request::get(url.to_string());
-
-        let header = make_header_from_options(read_options);
-        if let Some(h) = header {
-            build = build.header(h.0, h.1)
-        }
-        let req = build.body("".to_string()).unwrap();
-        let resp: Header = deserialize_response(self.session.request(req).await?.body())?;
-        Ok(resp)
+        docs: Vec<T>,
+        insert_options: InsertOptions,
+    ) -> Result<BulkDocumentResult<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let (results, _) = self
+            .create_documents_with_meta(docs, insert_options)
+            .await?;
+        Ok(results)
     }
-    /// Partially update a document
+
+    /// Create several documents like
+    /// [`create_documents`](Self::create_documents), and also return the
+    /// status and headers of the response, whose
+    /// [`error_summary`](ResponseMeta::error_summary) counts the documents
+    /// which could not be created per error.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
-    pub async fn update_document<T>(
+    pub async fn create_documents_with_meta<T>(
         &self,
-        _key: &str,
-        doc: T,
-        update_options: UpdateOptions,
-    ) -> Result<DocumentResponse<T>, ClientError>
+        docs: Vec<T>,
+        insert_options: InsertOptions,
+    ) -> Result<(BulkDocumentResult<T>, ResponseMeta), ClientError>
     where
         T: Serialize + DeserializeOwned,
     {
-        let mut url = self.document_base_url.join(_key).unwrap();
-        let body = serde_json::to_string(&doc)?;
-        let query = serde_qs::to_string(&update_options).unwrap();
-        url.set_query(Some(query.as_str()));
+        let silent = insert_options.silent() == Some(true);
+        let resp = self.send_create_document(&docs, insert_options).await?;
+        let results = if silent {
+            self.decode_silent_results(&resp, docs.len())?
+        } else {
+            self.decode_results(&resp)?
+        };
+        Ok((results.into(), ResponseMeta::new(resp)))
+    }
 
-        let resp: DocumentResponse<T> =
-            deserialize_response(self.session.patch(url, body).await?.body())?;
-        Ok(resp)
+    /// Results of a bulk operation on `count` documents with `silent`: the
+    /// response holds either a result per document or only the errors.
+    fn decode_silent_results<T>(
+        &self,
+        resp: &http::Response<String>,
+        count: usize,
+    ) -> Result<Vec<Result<DocumentResponse<T>, ArangoError>>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        if !resp.status().is_success() {
+            // the whole batch failed
+            deserialize_response::<Value>(resp)?;
+        }
+        let results = match parse_body::<Value>(resp.body())? {
+            Value::Array(results) if !results.is_empty() => self.decode_results(resp)?,
+            _ => return Ok((0..count).map(|_| Ok(DocumentResponse::Silent)).collect()),
+        };
+        if results.len() == count {
+            return Ok(results);
+        }
+        let got = results.len();
+        match results.into_iter().find_map(Result::err) {
+            Some(error) => Err(error.into()),
+            None => Err(ClientError::MalformedResponse(format!(
+                "{} results for {} documents",
+                got, count
+            ))),
+        }
     }
 
-    /// Replace a document
-    ///
-    /// Replaces the specified document with the one in the body, provided there
-    /// is such a document and no precondition is violated.
-    ///
-    /// The value of the _key attribute as well as attributes used as sharding
-    /// keys may not be changed.
-    ///
-    /// If the If-Match header is specified and the revision of the document in
-    /// the database is unequal to the given revision, the precondition is
-    /// violated. If If-Match is not given and ignoreRevs is false and there
-    /// is a _rev attribute in the body and its value does not match the
-    /// revision of the document in the database, the precondition is violated.
-    /// If a precondition is violated, an HTTP 412 is returned.
+    /// Create the documents of `docs` with a request per `chunk_size`
+    /// documents, so that only one chunk is held in memory at a time.
     ///
-    /// If the document exists and can be updated, then an HTTP 201 or an HTTP
-    /// 202 is returned (depending on waitForSync, see below), the Etag header
-    /// field contains the new revision of the document and the Location header
-    /// contains a complete URL under which the document can be queried.
-    ///
-    /// Cluster only: The replace documents may contain values for the
-    /// collection’s pre-defined shard keys. Values for the shard keys are
-    /// treated as hints to improve performance. Should the shard keys values be
-    /// incorrect ArangoDB may answer with a not found error. Optionally,
-    /// the query parameter waitForSync can be used to force synchronization of
-    /// the document replacement operation to disk even in case that the
-    /// waitForSync flag had been disabled for the entire collection. Thus, the
-    /// waitForSync query parameter can be used to force synchronization of just
-    /// specific operations. To use this, set the waitForSync parameter to
-    /// true. If the waitForSync parameter is not specified or set to false,
-    /// then the collection’s default waitForSync behavior is applied. The
-    /// waitForSync query parameter cannot be used to disable synchronization
-    /// for collections that have a default waitForSync value of true.
-    ///
-    /// If silent is not set to true, the body of the response contains a JSON
-    /// object with the information about the identifier and the revision. The
-    /// attribute _id contains the known document-id of the updated document,
-    /// _key contains the key which uniquely identifies a document in a given
-    /// collection, and the attribute _rev contains the new document revision.
-    ///
-    /// If the query parameter returnOld is true, then the complete previous
-    /// revision of the document is returned under the old attribute in the
-    /// result. If the query parameter returnNew is true, then the complete
-    /// new document is returned under the new attribute in the result.
-    ///
-    /// If the document does not exist, then a HTTP 404 is returned and the body
-    /// of the response contains an error document.
+    /// Documents rejected by the server do not stop the insert, they are
+    /// counted and kept with their position in the returned report, as are
+    /// the numbers of documents written. The insert stops at the first
+    /// chunk which fails as a whole, e.g. on a transport error or a missing
+    /// collection, with the documents of the previous chunks written.
     ///
-    /// You can conditionally replace a document based on a target revision id
-    /// by using the if-match HTTP header.
+    /// Documents asked for by `return_new` or `return_old` are not kept,
+    /// `silent` saves the server from sending them.
     ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
-    pub async fn replace_document<T>(
+    pub async fn create_documents_chunked<T, I>(
         &self,
-        _key: &str,
-        doc: T,
-        replace_options: ReplaceOptions,
-        if_match_header: Option<String>,
-    ) -> Result<DocumentResponse<T>, ClientError>
+        docs: I,
+        chunk_size: usize,
+        insert_options: InsertOptions,
+    ) -> Result<ChunkedInsertReport, ClientError>
     where
-        T: Serialize + DeserializeOwned,
+        T: Serialize,
+        I: IntoIterator<Item = T>,
     {
-        let mut url = self.document_base_url.join(_key).unwrap();
-        let body = serde_json::to_string(&doc)?;
-        let query = serde_qs::to_string(&replace_options).unwrap();
-        url.set_query(Some(query.as_str()));
+        let mut docs = docs.into_iter();
+        let mut report = ChunkedInsertReport::default();
+        loop {
+            let chunk: Vec<T> = docs.by_ref().take(chunk_size.max(1)).collect();
+            if chunk.is_empty() {
+                return Ok(report);
+            }
+            self.create_chunk(&chunk, &insert_options, &mut report)
+                .await?;
+        }
+    }
 
-        let mut build = Request::put(url.to_string());
+    /// Like [`create_documents_chunked`](Self::create_documents_chunked),
+    /// with the documents of a stream.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async::async_impl]
+    pub async fn create_documents_chunked_stream<T, S>(
+        &self,
+        docs: S,
+        chunk_size: usize,
+        insert_options: InsertOptions,
+    ) -> Result<ChunkedInsertReport, ClientError>
+    where
+        T: Serialize,
+        S: futures_util::Stream<Item = T>,
+    {
+        use futures_util::StreamExt;
 
-        if let Some(if_match_value) = if_match_header {
-            build = build.header("If-Match", if_match_value);
+        let chunks = docs.chunks(chunk_size.max(1));
+        futures_util::pin_mut!(chunks);
+        let mut report = ChunkedInsertReport::default();
+        while let Some(chunk) = chunks.next().await {
+            self.create_chunk(&chunk, &insert_options, &mut report)
+                .await?;
         }
+        Ok(report)
+    }
 
-        let req = build.body(body).unwrap();
-
-        let resp: DocumentResponse<T> =
-            deserialize_response(self.session.request(req).await?.body())?;
-        Ok(resp)
+    /// Create the documents of `chunk` and add the outcome to `report`.
+    #[maybe_async]
+    async fn create_chunk<T: Serialize>(
+        &self,
+        chunk: &[T],
+        insert_options: &InsertOptions,
+        report: &mut ChunkedInsertReport,
+    ) -> Result<(), ClientError> {
+        let resp = self
+            .send_create_document(&chunk, insert_options.clone())
+            .await?;
+        if !resp.status().is_success() {
+            // the whole chunk failed
+            deserialize_response::<Value>(&resp)?;
+        }
+        let results = match parse_body::<Value>(resp.body())? {
+            Value::Array(results) => results,
+            // nothing is returned by a silent insert without errors
+            _ => Vec::new(),
+        };
+        // a silent insert only returns the errors
+        let positional = results.len() == chunk.len();
+        let code = resp.status().as_u16();
+        let mut rejected = 0;
+        for (i, mut result) in results.into_iter().enumerate() {
+            if result.get("error").and_then(Value::as_bool) == Some(true) {
+                result["code"] = code.into();
+                let position = if positional {
+                    Some(report.sent + i as u64)
+                } else {
+                    None
+                };
+                report
+                    .failures
+                    .push((position, serde_json::from_value(result)?));
+                rejected += 1;
+            }
+        }
+        report.sent += chunk.len() as u64;
+        report.created += chunk.len() as u64 - rejected;
+        report.rejected += rejected;
+        report
+            .errors
+            .merge(BulkErrorSummary::from_headers(resp.headers()));
+        Ok(())
     }
 
-    /// Remove a document
+    /// Insert a document, or get the existing document it conflicts with.
     ///
-    /// If silent is not set to true, the body of the response contains a JSON
-    /// object with the information about the identifier and the revision. The
-    /// attribute _id contains the known document-id of the removed document,
-    /// _key contains the key which uniquely identifies a document in a given
-    /// collection, and the attribute _rev contains the document revision.
+    /// When the insert violates a unique constraint, the conflicting document
+    /// is read instead, by the key reported by the server or else the `_key`
+    /// of `doc`. If that document is removed before it could be read, the
+    /// insert is tried again, up to three times, after which the last
+    /// conflict is returned as error.
     ///
-    /// If the waitForSync parameter is not specified or set to false, then the
-    /// collection’s default waitForSync behavior is applied. The
-    /// waitForSync query parameter cannot be used to disable
-    /// synchronization for collections that have a default waitForSync value of
-    /// true.
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn insert_or_get<T>(&self, doc: T) -> Result<InsertOrGet<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let mut conflict = None;
+        for _ in 0..INSERT_OR_GET_ATTEMPTS {
+            let resp = self.send_create_document(&doc, Default::default()).await?;
+            let error = match deserialize_response::<Header>(&resp) {
+                Ok(header) => return Ok(InsertOrGet::Inserted(header)),
+                Err(ClientError::Arango(e)) if e.is_unique_constraint_violated() => e,
+                Err(e) => return Err(e),
+            };
+            let key = match conflicting_key(&error, &doc) {
+                Some(key) => key,
+                None => return Err(ClientError::Arango(error)),
+            };
+            match self.document(&key).await {
+                Ok(existing) => return Ok(InsertOrGet::Existing(existing)),
+                // removed in between, try to insert again
+                Err(ClientError::Arango(e)) if e.is_document_not_found() => {}
+                Err(e) => return Err(e),
+            }
+            conflict = Some(error);
+        }
+        Err(ClientError::Arango(conflict.unwrap()))
+    }
+
+    /// Insert `doc` with key `_key`, or update the document with this key if
+    /// it exists, in a single request.
     ///
-    /// If the query parameter returnOld is true, then the complete previous
-    /// revision of the document is returned under the old attribute in the
-    /// result.
+    /// An update is told from an insert by the previous revision of the
+    /// document, in [`DocumentResponse::old_rev`]. `doc` must serialize to an
+    /// object, whose `_key` if any must be `_key`, else the upsert fails with
+    /// `ClientError::InvalidOptions` without any request.
     ///
-    /// You can conditionally replace a document based on a target revision id
-    /// by using the if-match HTTP header.
+    /// The upsert is an insert with `OverwriteMode::Update`, known since
+    /// ArangoDB 3.7, and an AQL `UPSERT` with an older server, as told by the
+    /// version fetched by the first upsert of the connection.
     ///
     /// # Note
     /// this function would make a request to arango server.
+    #[cfg(feature = "aql")]
     #[maybe_async]
-    pub async fn remove_document<T>(
+    pub async fn upsert_document<T>(
         &self,
         _key: &str,
-        remove_options: RemoveOptions,
-        if_match_header: Option<String>,
+        doc: T,
+        options: UpsertOptions,
     ) -> Result<DocumentResponse<T>, ClientError>
     where
         T: Serialize + DeserializeOwned,
     {
-        let mut url = self.document_base_url.join(_key).unwrap();
-        let query = serde_qs::to_string(&remove_options).unwrap();
-        url.set_query(Some(query.as_str()));
+        self.check_writable("upsert a document")?;
+        let key = DocumentKey::new(_key)?;
+        let mut doc = serde_json::to_value(&doc)?;
+        match doc.as_object_mut() {
+            Some(fields) => match fields.get("_key") {
+                Some(other) if other != key.as_str() => {
+                    return Err(ClientError::InvalidOptions(format!(
+                        "the _key {} of the document is not the key {} to upsert",
+                        other, key
+                    )))
+                }
+                _ => fields.insert("_key".to_owned(), key.as_str().into()),
+            },
+            None => {
+                return Err(ClientError::InvalidOptions(
+                    "only an object can be upserted".to_owned(),
+                ))
+            }
+        };
 
-        let mut build = Request::delete(url.to_string());
+        match self.session.server_version(&self.base_url).await {
+            Some(version) if version < OVERWRITE_MODE_SINCE => {}
+            _ => {
+                let resp = self
+                    .send_create_document(&doc, options.insert_options())
+                    .await?;
+                return self.decode(&resp);
+            }
+        }
+        let mut bind_vars: HashMap<&str, Value> = HashMap::new();
+        bind_vars.insert("@collection", self.name.as_str().into());
+        bind_vars.insert("key", key.as_str().into());
+        bind_vars.insert("doc", self.field_case.to_stored(doc));
+        bind_vars.insert("keepNull", options.keep_null().unwrap_or(true).into());
+        bind_vars.insert(
+            "mergeObjects",
+            options.merge_objects().unwrap_or(true).into(),
+        );
+        bind_vars.insert(
+            "waitForSync",
+            options.wait_for_sync().unwrap_or(false).into(),
+        );
+        bind_vars.insert("returnNew", options.return_new().unwrap_or(false).into());
+        bind_vars.insert("returnOld", options.return_old().unwrap_or(false).into());
+        let aql = AqlQuery::builder()
+            .query(
+                "UPSERT { _key: @key } INSERT @doc UPDATE @doc IN @@collection \
+                 OPTIONS { keepNull: @keepNull, mergeObjects: @mergeObjects, \
+                 waitForSync: @waitForSync } \
+                 RETURN MERGE({ _id: NEW._id, _key: NEW._key, _rev: NEW._rev }, \
+                 OLD ? { _oldRev: OLD._rev } : {}, @returnNew ? { new: NEW } : {}, \
+                 @returnOld && OLD ? { old: OLD } : {})",
+            )
+            .bind_vars(bind_vars)
+            .build();
+        let result = self.db().aql_query::<Value>(aql).await?.pop();
+        let result = result.ok_or_else(|| {
+            ClientError::MalformedResponse("no result for the upserted document".to_owned())
+        })?;
+        Ok(serde_json::from_value(self.field_case.to_rust(result))?)
+    }
 
-        if let Some(if_match_value) = if_match_header {
-            build = build.header("If-Match", if_match_value);
+    /// Url of the document with `_key`, or of the documents of the collection
+    /// without key for a batch.
+    fn write_url(&self, _key: Option<&str>) -> Result<Url, ClientError> {
+        match _key {
+            Some(_key) => document_url(&self.document_base_url, _key),
+            None => Ok(self.document_base_url.clone()),
+        }
+    }
+
+    /// Version of the server, only looked up when `needed` by the options of
+    /// a write.
+    #[maybe_async]
+    async fn version_for(&self, needed: bool) -> Option<ServerVersion> {
+        if needed {
+            self.session.server_version(&self.base_url).await
+        } else {
+            None
+        }
+    }
+
+    #[maybe_async]
+    async fn send_create_document<T: Serialize>(
+        &self,
+        doc: &T,
+        insert_options: InsertOptions,
+    ) -> Result<http::Response<String>, ClientError> {
+        self.check_writable("create a document")?;
+        insert_options.check()?;
+        let insert_options = match self.version_for(insert_options.needs_version()).await {
+            Some(version) => insert_options.for_version(version)?,
+            None => insert_options,
+        };
+        let body = self.insert_body(doc).await?;
+        let mut url = self.document_base_url.join("").unwrap();
+        insert_options.apply_query(&mut url);
+        self.session.post(url, body).await
+    }
+
+    /// Read a single document with `_key`
+    ///
+    /// Returns the document identified by document-id. The returned document
+    /// contains three special attributes: _id containing the document
+    /// identifier, _key containing key which uniquely identifies a document in
+    /// a given collection and _rev containing the revision.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn document<T>(&self, _key: &str) -> Result<Document<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.document_with_options(_key, Default::default()).await
+    }
+
+    /// Read a single document with options
+    ///
+    /// Returns the document identified by document-id. The returned document
+    /// contains three special attributes: _id containing the document
+    /// identifier, _key containing key which uniquely identifies a document in
+    /// a given collection and _rev containing the revision.
+    ///
+    /// Fails with `ClientError::InvalidOptions` without any request for a
+    /// dirty read on a handle belonging to a transaction. Whether a dirty
+    /// read was answered by a follower is told by
+    /// [`document_with_meta`](Self::document_with_meta). A document at
+    /// another revision than given by `if_match` fails with
+    /// `ClientError::PreconditionFailed`, and a document unchanged since
+    /// `if_none_match` is told by [`read_document`](Self::read_document).
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn document_with_options<T>(
+        &self,
+        _key: &str,
+        read_options: DocumentReadOptions,
+    ) -> Result<Document<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self.send_read_document(_key, read_options).await?;
+        self.decode(&resp)
+    }
+
+    /// Read a single document like
+    /// [`document_with_options`](Self::document_with_options), and also
+    /// return the status and headers of the response.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn document_with_meta<T>(
+        &self,
+        _key: &str,
+        read_options: DocumentReadOptions,
+    ) -> Result<(Document<T>, ResponseMeta), ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self.send_read_document(_key, read_options).await?;
+        Ok((self.decode(&resp)?, ResponseMeta::new(resp)))
+    }
+
+    /// Read the edge with `_key`, with its `_from` and `_to` apart from its
+    /// content.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn edge<T>(&self, _key: &str) -> Result<EdgeDocument<T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let resp = self.send_read_document(_key, Default::default()).await?;
+        self.decode(&resp)
+    }
+
+    /// Read a single document, telling apart a document unchanged since the
+    /// revision given by `if_none_match`, e.g. to refresh a cache.
+    ///
+    /// When the document is at another revision than the one given by
+    /// `if_match`, the read fails with `ClientError::PreconditionFailed`,
+    /// holding the current revision, as do the other reads.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn read_document<T>(
+        &self,
+        _key: &str,
+        read_options: DocumentReadOptions,
+    ) -> Result<DocumentLookup<T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let resp = self.send_read_document(_key, read_options).await?;
+        if resp.status() == http::StatusCode::NOT_MODIFIED {
+            return Ok(DocumentLookup::NotModified);
         }
+        Ok(DocumentLookup::Found(self.decode(&resp)?))
+    }
 
+    #[maybe_async]
+    async fn send_read_document(
+        &self,
+        _key: &str,
+        read_options: DocumentReadOptions,
+    ) -> Result<http::Response<String>, ClientError> {
+        self.check_read_options(&read_options)?;
+        let url = document_url(&self.document_base_url, _key)?;
+        let build = read_options.apply_headers(Request::get(url.to_string()));
         let req = build.body("".to_string()).unwrap();
+        precondition_failed(self.session.request(req).await?)
+    }
 
-        let resp: DocumentResponse<T> =
-            deserialize_response(self.session.request(req).await?.body())?;
-        Ok(resp)
+    fn check_read_options(&self, read_options: &DocumentReadOptions) -> Result<(), ClientError> {
+        read_options.check(read_options.allow_dirty_read() == Some(true) && self.in_transaction())
     }
 
-    /// Returns a new Collection with its `session` updated with the transaction id
-    pub fn clone_with_transaction(&self, transaction_id: String) -> Result<Self, ClientError> {
-        let mut session = (*self.session).clone();
-        session
+    /// Whether this handle belongs to a stream transaction.
+    fn in_transaction(&self) -> bool {
+        (*self.session.client())
+            .clone()
             .headers()
-            .insert(TRANSACTION_HEADER, transaction_id.parse().unwrap());
-        Ok(Self {
-            session: Arc::new(session),
-            ..self.clone()
-        })
+            .contains_key(TRANSACTION_HEADER)
+    }
+
+    /// Read a single document header
+    ///
+    /// Like GET, but only returns the header fields and not the body. You can
+    /// use this call to get the current revision of a document or check if the
+    /// document was deleted.
+    ///
+    /// The document is still read in full, see
+    /// [`read_document_header`](Self::read_document_header) to only read its
+    /// revision.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn document_header(&self, _key: &str) -> Result<Header, ClientError> {
+        self.document_header_with_options(_key, Default::default())
+            .await
     }
-}
 
-/// Create header name and header value from read_options
-fn make_header_from_options(
-    document_read_options: ReadOptions,
-) -> Option<(http::header::HeaderName, http::header::HeaderValue)> {
-    match document_read_options {
-        ReadOptions::IfNoneMatch(value) => Some((
-            "If-None-Match".to_string().parse().unwrap(),
-            http::HeaderValue::try_from(value).unwrap(),
-        )),
+    /// Read a single document header with options
+    ///
+    /// Like GET, but only returns the header fields and not the body. You can
+    /// use this call to get the current revision of a document or check if the
+    /// document was deleted.
+    ///
+    /// The document is still read in full, see
+    /// [`read_document_header`](Self::read_document_header) to only read its
+    /// revision.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn document_header_with_options(
+        &self,
+        _key: &str,
+        read_options: DocumentReadOptions,
+    ) -> Result<Header, ClientError> {
+        self.check_read_options(&read_options)?;
+        let url = document_url(&self.document_base_url, _key)?;
+        let build = read_options.apply_headers(Request::get(url.to_string()));
+        let req = build.body("".to_string()).unwrap();
+        let resp = precondition_failed(self.session.request(req).await?)?;
+        let resp: Header = deserialize_response(&resp)?;
+        Ok(resp)
+    }
 
-        ReadOptions::IfMatch(value) => Some((
-            "If-Match".to_string().parse().unwrap(),
-            http::HeaderValue::try_from(value).unwrap(),
-        )),
+    /// Whether the document with `_key` exists, asked with a `HEAD` request
+    /// which transfers no body.
+    ///
+    /// A missing document gives `Ok(false)`, and so does a missing
+    /// collection, which the server answers with the same bodiless 404.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn document_exists(&self, _key: &str) -> Result<bool, ClientError> {
+        let lookup = self.read_document_header(_key, Default::default()).await?;
+        Ok(!matches!(lookup, HeaderLookup::NotFound))
+    }
 
-        ReadOptions::NoHeader => None,
+    /// Read a single document with `_key` as the untouched JSON of the
+    /// response, along with its header.
+    ///
+    /// The body is kept as it was received, system attributes included and
+    /// without renaming the attributes after the
+    /// [field case](Collection::with_field_case) of this handle, for callers
+    /// forwarding the document rather than reading it. Only the header is
+    /// parsed out of it.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn read_document_raw(
+        &self,
+        _key: &str,
+    ) -> Result<(Header, Box<RawValue>), ClientError> {
+        let resp = self.send_read_document(_key, Default::default()).await?;
+        let header: Header = deserialize_response(&resp)?;
+        let body = RawValue::from_string(resp.into_body())?;
+        Ok((header, body))
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize)]
-pub enum CollectionType {
-    #[serde(rename = "2")]
-    Document = 2,
-    #[serde(rename = "3")]
-    Edge = 3,
-}
+    /// Read the header of a single document with a `HEAD` request, without
+    /// transferring its body.
+    ///
+    /// The revision is the `Etag` of the response. The outcomes of
+    /// `if_none_match` and `if_match`, as well as a missing document, are
+    /// told apart by [`HeaderLookup`] rather than returned as errors.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn read_document_header(
+        &self,
+        _key: &str,
+        read_options: DocumentReadOptions,
+    ) -> Result<HeaderLookup, ClientError> {
+        self.check_read_options(&read_options)?;
+        let url = document_url(&self.document_base_url, _key)?;
+        let build = read_options.apply_headers(Request::head(url.to_string()));
+        let req = build.body("".to_string()).unwrap();
+        let resp = self.session.request(req).await?;
+        let status = resp.status();
+        let rev = || {
+            let etag = resp.headers().get(http::header::ETAG);
+            let etag = etag.and_then(|tag| tag.to_str().ok());
+            etag.map(|tag| tag.trim_matches('"').to_owned())
+                .ok_or_else(|| {
+                    ClientError::MalformedResponse(format!(
+                        "no Etag in the {} response to a HEAD of document {}",
+                        status.as_u16(),
+                        _key
+                    ))
+                })
+        };
+        match status.as_u16() {
+            200..=299 => Ok(HeaderLookup::Found(Header {
+                _id: Some(format!("{}/{}", self.name, _key)),
+                _key: _key.to_owned(),
+                _rev: Some(rev()?),
+            })),
+            304 => Ok(HeaderLookup::NotModified),
+            404 => Ok(HeaderLookup::NotFound),
+            412 => Ok(HeaderLookup::PreconditionFailed {
+                current_rev: rev()?,
+            }),
+            _ => Err(deserialize_response::<Value>(&resp)
+                .err()
+                .unwrap_or_else(|| {
+                    ClientError::MalformedResponse(format!(
+                        "unexpected status {} of a HEAD of document {}",
+                        status.as_u16(),
+                        _key
+                    ))
+                })),
+        }
+    }
 
-impl<'de> Deserialize<'de> for CollectionType {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    /// Read several documents with a single request
+    ///
+    /// `keys` are document keys, or objects with `_key` and `_rev` to check
+    /// the revisions when the `ignore_revs` option is false. The results are
+    /// in the order of `keys`, with an error for each document which could
+    /// not be read.
+    ///
+    /// Fails with `ClientError::InvalidOptions` without any request when the
+    /// read is both a dirty read and part of a transaction, either through
+    /// the `transaction_id` option or as this handle belongs to a
+    /// transaction.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn read_documents<K, T>(
+        &self,
+        keys: &[K],
+        options: BatchReadOptions,
+    ) -> Result<Vec<Result<Document<T>, ArangoError>>, ClientError>
     where
-        D: Deserializer<'de>,
+        K: Serialize,
+        T: DeserializeOwned,
     {
-        let value = u8::deserialize(deserializer)?;
-        match value {
-            2 => Ok(CollectionType::Document),
-            3 => Ok(CollectionType::Edge),
-            _ => Err(serde::de::Error::custom(
-                "Undefined behavior. If the crate breaks after an upgrade of ArangoDB, please \
-                 contact the author.",
-            )),
+        let resp = self.send_read_documents(keys, options).await?;
+        self.decode_results(&resp)
+    }
+
+    /// Read several documents like [`read_documents`](Self::read_documents),
+    /// and also return the status and headers of the response, whose
+    /// [`error_summary`](ResponseMeta::error_summary) counts the documents
+    /// which could not be read per error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn read_documents_with_meta<K, T>(
+        &self,
+        keys: &[K],
+        options: BatchReadOptions,
+    ) -> Result<BatchRead<T>, ClientError>
+    where
+        K: Serialize,
+        T: DeserializeOwned,
+    {
+        let resp = self.send_read_documents(keys, options).await?;
+        Ok((self.decode_results(&resp)?, ResponseMeta::new(resp)))
+    }
+
+    #[maybe_async]
+    async fn send_read_documents<K>(
+        &self,
+        keys: &[K],
+        options: BatchReadOptions,
+    ) -> Result<http::Response<String>, ClientError>
+    where
+        K: Serialize,
+    {
+        options.check(options.allow_dirty_read == Some(true) && self.in_transaction())?;
+
+        let mut url = self.document_base_url.join("").unwrap();
+        url.set_query(Some("onlyget=true"));
+        options.apply_query(&mut url);
+        let build = options.apply_headers(Request::put(url.to_string()));
+        let req = build.body(serde_json::to_string(keys)?).unwrap();
+        self.session.request(req).await
+    }
+
+    /// Results of a bulk operation, one per document.
+    fn decode_results<T>(
+        &self,
+        resp: &http::Response<String>,
+    ) -> Result<Vec<Result<T, ArangoError>>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let code = resp.status().as_u16();
+        if !resp.status().is_success() {
+            // the whole batch failed
+            deserialize_response::<Value>(resp)?;
+        }
+        let docs: Vec<Value> = parse_body(resp.body())?;
+        docs.into_iter()
+            .map(|mut doc| {
+                if doc.get("error").and_then(Value::as_bool) == Some(true) {
+                    // errors of single documents only have the code of the batch
+                    doc["code"] = code.into();
+                    Ok(Err(serde_json::from_value(doc)?))
+                } else {
+                    Ok(Ok(serde_json::from_value(self.field_case.to_rust(doc))?))
+                }
+            })
+            .collect()
+    }
+
+    /// Check which of `keys` are the keys of documents of the collection,
+    /// without reading the documents.
+    ///
+    /// The keys are checked by batches of
+    /// [`batch_size`](ExistenceOptions::batch_size), with an AQL query each.
+    /// A key given several times is only checked and reported once, at its
+    /// first position.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[cfg(feature = "aql")]
+    #[maybe_async]
+    pub async fn filter_existing_keys(
+        &self,
+        keys: &[&str],
+        options: ExistenceOptions,
+    ) -> Result<ExistenceReport, ClientError> {
+        let mut seen = HashSet::new();
+        let keys: Vec<&str> = keys.iter().copied().filter(|k| seen.insert(*k)).collect();
+        let mut existing = HashSet::new();
+        for batch in keys.chunks(options.batch_size.max(1)) {
+            let aql = AqlQuery::builder()
+                .query("FOR k IN @keys FILTER DOCUMENT(@@collection, k) != null RETURN k")
+                .bind_var("@collection", self.name.as_str())
+                .bind_var("keys", batch)
+                .build();
+            let found: Vec<String> = self.db().aql_query(aql).await?;
+            existing.extend(found);
+        }
+        let (existing, missing) = keys
+            .into_iter()
+            .map(str::to_owned)
+            .partition(|key| existing.contains(key));
+        Ok(ExistenceReport { existing, missing })
+    }
+
+    /// Read a document of the collection picked at random, `None` when the
+    /// collection is empty.
+    ///
+    /// The simple query reading it is deprecated since ArangoDB 3.4, and
+    /// removed in 3.12, where an AQL query picks the document instead.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[cfg(feature = "aql")]
+    #[maybe_async]
+    pub async fn random_document<T>(&self) -> Result<Option<Document<T>>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        #[derive(Deserialize)]
+        struct Any {
+            document: Option<Value>,
+        }
+
+        let support = self
+            .session
+            .endpoint_support(&self.base_url, &SIMPLE_ANY)
+            .await;
+        let doc = if support == Support::Removed {
+            let aql = AqlQuery::builder()
+                .query("FOR doc IN @@collection SORT RAND() LIMIT 1 RETURN doc")
+                .bind_var("@collection", self.name.as_str())
+                .build();
+            let mut docs: Vec<Value> = self.db().aql_query(aql).await?;
+            docs.pop()
+        } else {
+            let url = self.base_url.join("../../simple/any").unwrap();
+            let body = json!({ "collection": self.name });
+            let any: Any = deserialize_response(&self.session.put(url, body.to_string()).await?)?;
+            any.document
+        };
+        match doc {
+            Some(doc) => Ok(Some(serde_json::from_value(self.field_case.to_rust(doc))?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Set `field` of document `_key` to `new`, if it holds `expected`.
+    ///
+    /// `field` is an attribute path like `stats.count`, renamed after the
+    /// [field case](Collection::with_field_case) of this handle. The check
+    /// and the update run in a single AQL query, which reads the actual value
+    /// when the check fails, so no document is read beforehand. A missing
+    /// field holds `null`.
+    ///
+    /// After a swap the field equals `new`: an object is not merged into the
+    /// value it replaces, while the other attributes along the path are kept.
+    ///
+    /// When another write to the document commits while the query runs, the
+    /// query is tried again, up to three times, after which the write-write
+    /// conflict is returned as error. A missing document fails with the
+    /// `document not found` error of the server.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[cfg(feature = "aql")]
+    #[maybe_async]
+    pub async fn compare_and_swap_field(
+        &self,
+        _key: &str,
+        field: &str,
+        expected: Value,
+        new: Value,
+    ) -> Result<CasOutcome, ClientError> {
+        #[derive(Deserialize, Default)]
+        struct Swap {
+            rev: Option<String>,
+            #[serde(default)]
+            actual: Value,
         }
+
+        self.check_writable("compare and swap a field")?;
+        let path = self.field_case.stored_path(field);
+        // without merging objects, the update replaces the top level
+        // attribute of path `a.b` with `MERGE(doc.a, {b: new})`, so the other
+        // attributes of `a` are kept while `b` becomes `new`
+        let names: Vec<String> = (0..path.len()).map(|i| format!("name{}", i)).collect();
+        let mut patch = "@new".to_owned();
+        for depth in (1..path.len()).rev() {
+            let parent: String = names[..depth].iter().map(|n| format!(".@{}", n)).collect();
+            patch = format!(
+                "MERGE(IS_OBJECT(doc{0}) ? doc{0} : {{}}, {{ [@{1}]: {2} }})",
+                parent, names[depth], patch
+            );
+        }
+        let mut bind_vars: HashMap<&str, Value> = HashMap::new();
+        bind_vars.insert("@collection", self.name.as_str().into());
+        bind_vars.insert("key", _key.into());
+        bind_vars.insert("expected", self.field_case.to_stored(expected));
+        bind_vars.insert("new", self.field_case.to_stored(new));
+        for (name, attribute) in names.iter().zip(&path) {
+            bind_vars.insert(name.as_str(), attribute.as_str().into());
+        }
+        bind_vars.insert("field", path.into());
+        let query = format!(
+            "LET doc = DOCUMENT(@@collection, @key) \
+             LET swapped = (FILTER doc == null || doc.@field == @expected \
+             UPDATE @key WITH {{ [@name0]: {} }} IN @@collection \
+             OPTIONS {{ mergeObjects: false }} RETURN NEW._rev) \
+             RETURN {{ rev: FIRST(swapped), actual: doc.@field }}",
+            patch
+        );
+
+        let mut attempt = 1;
+        let swap = loop {
+            let aql = AqlQuery::builder()
+                .query(query.as_str())
+                .bind_vars(bind_vars.clone())
+                .build();
+            match self.db().aql_query::<Swap>(aql).await {
+                Ok(mut swap) => break swap.pop().unwrap_or_default(),
+                Err(ClientError::Arango(e)) if e.is_conflict() && attempt < CAS_ATTEMPTS => {
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+        Ok(match swap.rev {
+            Some(new_rev) => CasOutcome::Swapped { new_rev },
+            None => CasOutcome::Mismatch {
+                actual: self.field_case.to_rust(swap.actual),
+            },
+        })
+    }
+
+    /// Partially update a document
+    ///
+    /// With the `if_match` option, or the `_rev` of `doc` and `ignore_revs`
+    /// set to false, the document is only updated at this revision, and the
+    /// update fails with `ClientError::PreconditionFailed` otherwise, holding
+    /// the current revision.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn update_document<T>(
+        &self,
+        _key: &str,
+        doc: T,
+        update_options: UpdateOptions,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self
+            .send_update_document(Some(_key), &doc, update_options)
+            .await?;
+        self.decode(&resp)
+    }
+
+    /// Partially update a document like
+    /// [`update_document`](Self::update_document), and also return the
+    /// status and headers of the response.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn update_document_with_meta<T>(
+        &self,
+        _key: &str,
+        doc: T,
+        update_options: UpdateOptions,
+    ) -> Result<(DocumentResponse<T>, ResponseMeta), ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self
+            .send_update_document(Some(_key), &doc, update_options)
+            .await?;
+        Ok((self.decode(&resp)?, ResponseMeta::new(resp)))
+    }
+
+    /// Partially update a document like
+    /// [`update_document`](Self::update_document) with `silent` set,
+    /// without reading the response of the server unless it is an error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn update_document_silent<T: Serialize>(
+        &self,
+        _key: &str,
+        doc: T,
+        update_options: UpdateOptions,
+    ) -> Result<(), ClientError> {
+        let resp = self
+            .send_update_document(Some(_key), &doc, update_options.into_silent())
+            .await?;
+        check_written(&resp)
+    }
+
+    /// Partially update a document with a patch given as JSON, sent and
+    /// returned as it is, regardless of the
+    /// [field case](Collection::with_field_case) of this handle.
+    ///
+    /// Attributes of the patch set to `null` are removed from the document
+    /// with `keep_null` set to false, and set to `null` otherwise. Objects
+    /// of the patch are merged into the objects of the document, unless
+    /// `merge_objects` is set to false, in which case they replace them
+    /// whole. Arrays are always replaced.
+    ///
+    /// Fails with `ClientError::InvalidPatch` without any request when
+    /// `patch` is not a JSON object.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn patch_document(
+        &self,
+        _key: &str,
+        patch: Value,
+        update_options: UpdateOptions,
+    ) -> Result<DocumentResponse<Value>, ClientError> {
+        if !patch.is_object() {
+            return Err(ClientError::InvalidPatch(format!(
+                "a patch should be a JSON object, not {}",
+                patch
+            )));
+        }
+        let body = serde_json::to_string(&patch)?;
+        let resp = self
+            .send_update_body(Some(_key), body, update_options)
+            .await?;
+        deserialize_response(&resp)
+    }
+
+    /// Partially update several documents with a single request.
+    ///
+    /// Each patch carries the `_key` of the document it updates, and a `_rev`
+    /// checked when `ignore_revs` is false. The options apply to the whole
+    /// batch. The results are in the order of `patches`, with an error for
+    /// each document which could not be updated, e.g. on a missing `_key` or
+    /// a revision conflict, while the others are updated. With `silent`,
+    /// the results are as for
+    /// [`create_documents`](Self::create_documents).
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn update_documents<T, U>(
+        &self,
+        patches: Vec<U>,
+        update_options: UpdateOptions,
+    ) -> Result<BulkDocumentResult<T>, ClientError>
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+    {
+        let (results, _) = self
+            .update_documents_with_meta(patches, update_options)
+            .await?;
+        Ok(results)
+    }
+
+    /// Partially update several documents like
+    /// [`update_documents`](Self::update_documents), and also return the
+    /// status and headers of the response, whose
+    /// [`error_summary`](ResponseMeta::error_summary) counts the documents
+    /// which could not be updated per error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn update_documents_with_meta<T, U>(
+        &self,
+        patches: Vec<U>,
+        update_options: UpdateOptions,
+    ) -> Result<(BulkDocumentResult<T>, ResponseMeta), ClientError>
+    where
+        T: DeserializeOwned,
+        U: Serialize,
+    {
+        let silent = update_options.silent() == Some(true);
+        let resp = self
+            .send_update_document(None, &patches, update_options)
+            .await?;
+        let results = if silent {
+            self.decode_silent_results(&resp, patches.len())?
+        } else {
+            self.decode_results(&resp)?
+        };
+        Ok((results.into(), ResponseMeta::new(resp)))
+    }
+
+    #[maybe_async]
+    async fn send_update_document<T: Serialize>(
+        &self,
+        _key: Option<&str>,
+        doc: &T,
+        update_options: UpdateOptions,
+    ) -> Result<http::Response<String>, ClientError> {
+        let body = self.encode(doc)?;
+        self.send_update_body(_key, body, update_options).await
+    }
+
+    /// Send the patch `body` of an update.
+    #[maybe_async]
+    async fn send_update_body(
+        &self,
+        _key: Option<&str>,
+        body: String,
+        update_options: UpdateOptions,
+    ) -> Result<http::Response<String>, ClientError> {
+        self.check_writable("update a document")?;
+        update_options.check()?;
+        let update_options = match self.version_for(update_options.needs_version()).await {
+            Some(version) => update_options.for_version(version)?,
+            None => update_options,
+        };
+        check_if_match(_key, update_options.if_match(), &None)?;
+        let mut url = self.write_url(_key)?;
+        update_options.apply_query(&mut url);
+
+        let build = update_options.apply_headers(Request::patch(url.to_string()));
+        let req = build.body(body).unwrap();
+        precondition_failed(self.session.request(req).await?)
+    }
+
+    /// Replace a document
+    ///
+    /// Replaces the specified document with the one in the body, provided there
+    /// is such a document and no precondition is violated.
+    ///
+    /// The value of the _key attribute as well as attributes used as sharding
+    /// keys may not be changed.
+    ///
+    /// If the If-Match header is specified and the revision of the document in
+    /// the database is unequal to the given revision, the precondition is
+    /// violated. If If-Match is not given and ignoreRevs is false and there
+    /// is a _rev attribute in the body and its value does not match the
+    /// revision of the document in the database, the precondition is violated.
+    /// If a precondition is violated, an HTTP 412 is returned, as
+    /// `ClientError::PreconditionFailed` with the current revision. The
+    /// revision may also be given by the `if_match` option, but not by both.
+    ///
+    /// If the document exists and can be updated, then an HTTP 201 or an HTTP
+    /// 202 is returned (depending on waitForSync, see below), the Etag header
+    /// field contains the new revision of the document and the Location header
+    /// contains a complete URL under which the document can be queried.
+    ///
+    /// Cluster only: The replace documents may contain values for the
+    /// collection’s pre-defined shard keys. Values for the shard keys are
+    /// treated as hints to improve performance. Should the shard keys values be
+    /// incorrect ArangoDB may answer with a not found error. Optionally,
+    /// the query parameter waitForSync can be used to force synchronization of
+    /// the document replacement operation to disk even in case that the
+    /// waitForSync flag had been disabled for the entire collection. Thus, the
+    /// waitForSync query parameter can be used to force synchronization of just
+    /// specific operations. To use this, set the waitForSync parameter to
+    /// true. If the waitForSync parameter is not specified or set to false,
+    /// then the collection’s default waitForSync behavior is applied. The
+    /// waitForSync query parameter cannot be used to disable synchronization
+    /// for collections that have a default waitForSync value of true.
+    ///
+    /// If silent is not set to true, the body of the response contains a JSON
+    /// object with the information about the identifier and the revision. The
+    /// attribute _id contains the known document-id of the updated document,
+    /// _key contains the key which uniquely identifies a document in a given
+    /// collection, and the attribute _rev contains the new document revision.
+    ///
+    /// If the query parameter returnOld is true, then the complete previous
+    /// revision of the document is returned under the old attribute in the
+    /// result. If the query parameter returnNew is true, then the complete
+    /// new document is returned under the new attribute in the result.
+    ///
+    /// If the document does not exist, then a HTTP 404 is returned and the body
+    /// of the response contains an error document.
+    ///
+    /// You can conditionally replace a document based on a target revision id
+    /// by using the if-match HTTP header.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn replace_document<T>(
+        &self,
+        _key: &str,
+        doc: T,
+        replace_options: ReplaceOptions,
+        if_match_header: Option<String>,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self
+            .send_replace_document(Some(_key), &doc, replace_options, if_match_header)
+            .await?;
+        self.decode(&resp)
+    }
+
+    /// Replace a document like [`replace_document`](Self::replace_document),
+    /// and also return the status and headers of the response.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn replace_document_with_meta<T>(
+        &self,
+        _key: &str,
+        doc: T,
+        replace_options: ReplaceOptions,
+        if_match_header: Option<String>,
+    ) -> Result<(DocumentResponse<T>, ResponseMeta), ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self
+            .send_replace_document(Some(_key), &doc, replace_options, if_match_header)
+            .await?;
+        Ok((self.decode(&resp)?, ResponseMeta::new(resp)))
+    }
+
+    /// Replace a document like [`replace_document`](Self::replace_document)
+    /// with `silent` set, without reading the response of the server unless
+    /// it is an error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn replace_document_silent<T: Serialize>(
+        &self,
+        _key: &str,
+        doc: T,
+        replace_options: ReplaceOptions,
+        if_match_header: Option<String>,
+    ) -> Result<(), ClientError> {
+        let resp = self
+            .send_replace_document(
+                Some(_key),
+                &doc,
+                replace_options.into_silent(),
+                if_match_header,
+            )
+            .await?;
+        check_written(&resp)
+    }
+
+    /// Read a document, change it with `modify` and replace it at the
+    /// revision read, reading it again if another write changed it
+    /// meanwhile, at most `max_retries` times.
+    ///
+    /// The revision is checked by the server with `If-Match`, and a
+    /// conflict is told by `ClientError::PreconditionFailed` or error 1200.
+    /// `modify` is called once per attempt, with the document as read. When
+    /// all attempts conflict, the last conflict is returned. A document read
+    /// without `_rev` fails with `ClientError::MalformedResponse`, before
+    /// `modify` is called, rather than being replaced unchecked.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn modify_document<T, F>(
+        &self,
+        _key: &str,
+        mut modify: F,
+        max_retries: usize,
+    ) -> Result<ModifiedDocument<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnMut(&mut T),
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let Document {
+                header,
+                mut document,
+            } = self.document_with_options(_key, Default::default()).await?;
+            let rev = header._rev.ok_or_else(|| {
+                ClientError::MalformedResponse(format!("document {} read without a _rev", _key))
+            })?;
+            modify(&mut document);
+            let options = ReplaceOptions::builder().if_match(rev).build();
+            let written = match self
+                .send_replace_document(Some(_key), &document, options, None)
+                .await
+            {
+                Ok(resp) => self.decode::<Header>(&resp),
+                Err(e) => Err(e),
+            };
+            match written {
+                Ok(header) => {
+                    return Ok(ModifiedDocument {
+                        header,
+                        document,
+                        attempts,
+                    })
+                }
+                Err(ClientError::PreconditionFailed { .. }) if attempts <= max_retries => {}
+                Err(ClientError::Arango(e)) if e.is_conflict() && attempts <= max_retries => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Replace several documents with a single request.
+    ///
+    /// Each document carries the `_key` of the document it replaces, and a
+    /// `_rev` checked when `ignore_revs` is false. The options apply to the
+    /// whole batch. The results are in the order of `docs`, with an error for
+    /// each document which could not be replaced, e.g. on a missing `_key` or
+    /// a revision conflict, while the others are replaced, each with the
+    /// revision it replaced as [`old_rev`](DocumentResponse::old_rev). With
+    /// `silent`, the results are as for
+    /// [`create_documents`](Self::create_documents).
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn replace_documents<T>(
+        &self,
+        docs: Vec<T>,
+        replace_options: ReplaceOptions,
+    ) -> Result<BulkDocumentResult<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let (results, _) = self
+            .replace_documents_with_meta(docs, replace_options)
+            .await?;
+        Ok(results)
+    }
+
+    /// Replace several documents like
+    /// [`replace_documents`](Self::replace_documents), and also return the
+    /// status and headers of the response, whose
+    /// [`error_summary`](ResponseMeta::error_summary) counts the documents
+    /// which could not be replaced per error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn replace_documents_with_meta<T>(
+        &self,
+        docs: Vec<T>,
+        replace_options: ReplaceOptions,
+    ) -> Result<(BulkDocumentResult<T>, ResponseMeta), ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let silent = replace_options.silent() == Some(true);
+        let resp = self
+            .send_replace_document(None, &docs, replace_options, None)
+            .await?;
+        let results = if silent {
+            self.decode_silent_results(&resp, docs.len())?
+        } else {
+            self.decode_results(&resp)?
+        };
+        Ok((results.into(), ResponseMeta::new(resp)))
+    }
+
+    #[maybe_async]
+    async fn send_replace_document<T: Serialize>(
+        &self,
+        _key: Option<&str>,
+        doc: &T,
+        replace_options: ReplaceOptions,
+        if_match_header: Option<String>,
+    ) -> Result<http::Response<String>, ClientError> {
+        self.check_writable("replace a document")?;
+        replace_options.check()?;
+        let replace_options = match self.version_for(replace_options.needs_version()).await {
+            Some(version) => replace_options.for_version(version)?,
+            None => replace_options,
+        };
+        check_if_match(_key, replace_options.if_match(), &if_match_header)?;
+        let mut url = self.write_url(_key)?;
+        let body = self.encode(doc)?;
+        replace_options.apply_query(&mut url);
+
+        let mut build = replace_options.apply_headers(Request::put(url.to_string()));
+
+        if let Some(if_match_value) = if_match_header {
+            build = build.header("If-Match", if_match_value);
+        }
+
+        let req = build.body(body).unwrap();
+        precondition_failed(self.session.request(req).await?)
+    }
+
+    /// Remove a document
+    ///
+    /// If silent is not set to true, the body of the response contains a JSON
+    /// object with the information about the identifier and the revision. The
+    /// attribute _id contains the known document-id of the removed document,
+    /// _key contains the key which uniquely identifies a document in a given
+    /// collection, and the attribute _rev contains the document revision.
+    ///
+    /// If the waitForSync parameter is not specified or set to false, then the
+    /// collection’s default waitForSync behavior is applied. The
+    /// waitForSync query parameter cannot be used to disable
+    /// synchronization for collections that have a default waitForSync value of
+    /// true.
+    ///
+    /// If the query parameter returnOld is true, then the complete previous
+    /// revision of the document is returned under the old attribute in the
+    /// result.
+    ///
+    /// You can conditionally remove a document based on a target revision id
+    /// by using the if-match HTTP header, given either by `if_match_header` or
+    /// the `if_match` option. When the document is at another revision, the
+    /// removal fails with `ClientError::PreconditionFailed`, holding the
+    /// current revision.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn remove_document<T>(
+        &self,
+        _key: &str,
+        remove_options: RemoveOptions,
+        if_match_header: Option<String>,
+    ) -> Result<DocumentResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self
+            .send_remove_document(_key, remove_options, if_match_header)
+            .await?;
+        self.decode(&resp)
+    }
+
+    /// Remove a document like [`remove_document`](Self::remove_document),
+    /// and also return the status and headers of the response.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn remove_document_with_meta<T>(
+        &self,
+        _key: &str,
+        remove_options: RemoveOptions,
+        if_match_header: Option<String>,
+    ) -> Result<(DocumentResponse<T>, ResponseMeta), ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        let resp = self
+            .send_remove_document(_key, remove_options, if_match_header)
+            .await?;
+        Ok((self.decode(&resp)?, ResponseMeta::new(resp)))
+    }
+
+    /// Remove a document like [`remove_document`](Self::remove_document)
+    /// with `silent` set, without reading the response of the server unless
+    /// it is an error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn remove_document_silent(
+        &self,
+        _key: &str,
+        remove_options: RemoveOptions,
+        if_match_header: Option<String>,
+    ) -> Result<(), ClientError> {
+        let resp = self
+            .send_remove_document(_key, remove_options.into_silent(), if_match_header)
+            .await?;
+        check_written(&resp)
+    }
+
+    /// Remove several documents by key with a single request.
+    ///
+    /// The options apply to the whole batch. The results are in the order of
+    /// `keys`, with an error for each document which could not be removed,
+    /// e.g. 1202 for a document already gone, while the others are removed,
+    /// each with its old document when `return_old` is set. With `silent`,
+    /// the results are as for [`create_documents`](Self::create_documents).
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn remove_documents<T>(
+        &self,
+        keys: Vec<String>,
+        remove_options: RemoveOptions,
+    ) -> Result<BulkDocumentResult<T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let (results, _) = self
+            .remove_documents_with_meta(keys, remove_options)
+            .await?;
+        Ok(results)
+    }
+
+    /// Remove several documents like
+    /// [`remove_documents`](Self::remove_documents), and also return the
+    /// status and headers of the response, whose
+    /// [`error_summary`](ResponseMeta::error_summary) counts the documents
+    /// which could not be removed per error.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn remove_documents_with_meta<T>(
+        &self,
+        keys: Vec<String>,
+        remove_options: RemoveOptions,
+    ) -> Result<(BulkDocumentResult<T>, ResponseMeta), ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        self.send_remove_documents(&keys, remove_options).await
+    }
+
+    /// Remove several documents like
+    /// [`remove_documents`](Self::remove_documents), selected by their
+    /// header rather than their key.
+    ///
+    /// When `ignore_revs` is false, a document is only removed if the `_rev`
+    /// of its header, if any, is its current revision, and fails with 1200
+    /// otherwise.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn remove_documents_by_header<T>(
+        &self,
+        headers: Vec<Header>,
+        remove_options: RemoveOptions,
+    ) -> Result<BulkDocumentResult<T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let (results, _) = self.send_remove_documents(&headers, remove_options).await?;
+        Ok(results)
+    }
+
+    #[maybe_async]
+    async fn send_remove_documents<K, T>(
+        &self,
+        selectors: &[K],
+        remove_options: RemoveOptions,
+    ) -> Result<(BulkDocumentResult<T>, ResponseMeta), ClientError>
+    where
+        K: Serialize,
+        T: DeserializeOwned,
+    {
+        self.check_writable("remove a document")?;
+        remove_options.check()?;
+        let remove_options = match self.version_for(remove_options.needs_version()).await {
+            Some(version) => remove_options.for_version(version)?,
+            None => remove_options,
+        };
+        check_if_match(None, remove_options.if_match(), &None)?;
+        let silent = remove_options.silent() == Some(true);
+        let mut url = self.document_base_url.join("").unwrap();
+        remove_options.apply_query(&mut url);
+        let req = Request::delete(url.to_string())
+            .body(self.encode(&selectors)?)
+            .unwrap();
+        let resp = self.session.request(req).await?;
+        let results = if silent {
+            self.decode_silent_results(&resp, selectors.len())?
+        } else {
+            self.decode_results(&resp)?
+        };
+        Ok((results.into(), ResponseMeta::new(resp)))
+    }
+
+    #[maybe_async]
+    async fn send_remove_document(
+        &self,
+        _key: &str,
+        remove_options: RemoveOptions,
+        if_match_header: Option<String>,
+    ) -> Result<http::Response<String>, ClientError> {
+        self.check_writable("remove a document")?;
+        remove_options.check()?;
+        let remove_options = match self.version_for(remove_options.needs_version()).await {
+            Some(version) => remove_options.for_version(version)?,
+            None => remove_options,
+        };
+        check_if_match(Some(_key), remove_options.if_match(), &if_match_header)?;
+        let mut url = document_url(&self.document_base_url, _key)?;
+        remove_options.apply_query(&mut url);
+
+        let mut build = remove_options.apply_headers(Request::delete(url.to_string()));
+
+        if let Some(if_match_value) = if_match_header {
+            build = build.header("If-Match", if_match_value);
+        }
+
+        let req = build.body("".to_string()).unwrap();
+        precondition_failed(self.session.request(req).await?)
+    }
+
+    /// Import `documents` into the collection with a single request to the
+    /// import API, faster than creating them for large loads.
+    ///
+    /// Documents are sent one per line, or as a JSON array when the
+    /// `import_type` option is [`ImportType::List`]. Documents rejected by
+    /// the server do not fail the import, they are counted in
+    /// [`ImportResponse::errors`] unless the `complete` option is set. The
+    /// `batch_size`, `resume_from` and `retries` options only apply to
+    /// [`import_documents`](Collection::import_documents).
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn import<T: Serialize>(
+        &self,
+        documents: &[T],
+        mut options: ImportOptions,
+    ) -> Result<ImportResponse, ClientError> {
+        let mut body = String::new();
+        if options.import_type == Some(ImportType::List) {
+            body.push('[');
+            for (i, doc) in documents.iter().enumerate() {
+                if i > 0 {
+                    body.push(',');
+                }
+                body.push_str(&self.encode(doc)?);
+            }
+            body.push(']');
+        } else {
+            options.import_type = Some(ImportType::Documents);
+            for doc in documents {
+                body.push_str(&self.encode(doc)?);
+                body.push('\n');
+            }
+        }
+        self.import_jsonl(body, options).await
+    }
+
+    /// Like [`import`](Collection::import), with a body already serialized
+    /// as given by the `import_type` option, [`ImportType::Auto`] by
+    /// default. Documents are sent as they are, regardless of the
+    /// [field case](Collection::with_field_case) of this handle.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn import_jsonl(
+        &self,
+        body: String,
+        mut options: ImportOptions,
+    ) -> Result<ImportResponse, ClientError> {
+        self.check_writable("import documents")?;
+        options.import_type.get_or_insert(ImportType::Auto);
+        let resp = self.session.post(self.import_url(&options), body).await?;
+        deserialize_response(&resp)
+    }
+
+    /// Url of the import API with the query of `options`.
+    fn import_url(&self, options: &ImportOptions) -> Url {
+        let mut url = self.document_base_url.join("../../import").unwrap();
+        url.query_pairs_mut().append_pair("collection", &self.name);
+        options.apply_query(&mut url);
+        url
+    }
+
+    /// Import documents in batches through the import API, calling
+    /// `progress_callback` after each batch.
+    ///
+    /// Documents rejected by the server do not fail the import: they are
+    /// counted, and their reasons are passed to the callback when the
+    /// `details` option is set rather than kept in memory. When a batch
+    /// fails, e.g. on a transport error, the error is returned, and the
+    /// import can be resumed after the last reported batch by passing its
+    /// `offset` as the `resume_from` option, provided the source yields the
+    /// same documents in the same order again. The `overwrite` option only
+    /// applies to the first batch of an import which is not resumed.
+    ///
+    /// Returns the progress after the last batch, without details.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn import_documents<I, T, F>(
+        &self,
+        documents: I,
+        options: ImportOptions,
+        progress_callback: F,
+    ) -> Result<ImportProgress, ClientError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+        F: FnMut(ImportProgress),
+    {
+        self.import_documents_with_keys(
+            documents,
+            &KeyStrategy::ServerAssigned,
+            options,
+            progress_callback,
+        )
+        .await
+    }
+
+    /// Like [`import_documents`](Collection::import_documents), with the
+    /// keys of the documents without `_key` chosen by `keys`.
+    ///
+    /// Keys are generated once, when a batch is built, and a batch sent
+    /// again after the `retries` option keeps them. Resuming a failed import
+    /// with `resume_from` builds the failed batch again, so only the keys of
+    /// a [`KeyStrategy::Custom`] derived from the documents are the same.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn import_documents_with_keys<I, T, F>(
+        &self,
+        documents: I,
+        keys: &KeyStrategy<T>,
+        mut options: ImportOptions,
+        mut progress_callback: F,
+    ) -> Result<ImportProgress, ClientError>
+    where
+        I: IntoIterator<Item = T>,
+        T: Serialize,
+        F: FnMut(ImportProgress),
+    {
+        self.check_writable("import documents")?;
+        let start = Instant::now();
+        let skip = options.resume_from.unwrap_or(0);
+        if skip > 0 {
+            // the collection was truncated when the import started
+            options.overwrite = None;
+        }
+        options.import_type = Some(ImportType::Documents);
+        let batch_size = options.batch_size.max(1);
+        let mut documents = documents.into_iter().skip(skip as usize);
+        let mut progress = ImportProgress {
+            offset: skip,
+            ..Default::default()
+        };
+
+        loop {
+            // keys are generated here, never when the batch is sent again
+            let mut body = String::new();
+            let mut count = 0;
+            for doc in documents.by_ref().take(batch_size) {
+                match keys {
+                    KeyStrategy::ServerAssigned => body.push_str(&self.encode(&doc)?),
+                    keys => body.push_str(&self.encode(&keys.assign(&doc)?)?),
+                }
+                body.push('\n');
+                count += 1;
+            }
+            if count == 0 {
+                break;
+            }
+
+            let url = self.import_url(&options);
+            let bytes = body.len() as u64;
+            let mut retries = options.retries;
+            let (errors, resp) = loop {
+                // the body is only kept while it may be sent again
+                let sent = if retries > 0 {
+                    body.clone()
+                } else {
+                    std::mem::take(&mut body)
+                };
+                let imported = match self.session.post(url.clone(), sent).await {
+                    Ok(resp) => deserialize_response::<ImportResponse>(&resp)
+                        .map(|parsed| (BulkErrorSummary::from_headers(resp.headers()), parsed)),
+                    Err(e) => Err(e),
+                };
+                match imported {
+                    Err(e) if e.is_retryable() && retries > 0 => {
+                        retries -= 1;
+                        // transport errors are retried at once
+                        if let ClientError::ServiceUnavailable { retry_after } = e {
+                            pause(retry_after.unwrap_or_default()).await;
+                        }
+                    }
+                    imported => break imported?,
+                }
+            };
+            options.overwrite = None;
+
+            progress.offset += count;
+            progress.bytes_sent += bytes;
+            progress.created += resp.created;
+            progress.updated += resp.updated;
+            progress.ignored += resp.ignored;
+            progress.rejected += resp.errors;
+            progress.empty += resp.empty;
+            progress.errors.merge(errors);
+            progress.elapsed = start.elapsed();
+            progress_callback(ImportProgress {
+                details: resp.details,
+                ..progress.clone()
+            });
+        }
+        Ok(progress)
+    }
+
+    /// Prepare a query of the documents whose `field` lies within `lower`
+    /// and `upper`, fetched batch by batch.
+    ///
+    /// `field` is an attribute path like `meta.created`, whose names are
+    /// renamed after the [field case](Collection::with_field_case) of this
+    /// handle as are the fields of the documents returned. The generated filter
+    /// `FILTER d.field >= @lo && d.field < @hi` can be served by a persistent
+    /// index on `field`, which also makes sorting by `field` cheap.
+    ///
+    /// No request is made until the first batch is fetched with
+    /// [`QueryCursor::next_batch`].
+    #[cfg(feature = "aql")]
+    pub fn range_scan<T>(
+        &self,
+        field: &str,
+        lower: Bound<Value>,
+        upper: Bound<Value>,
+        options: RangeOptions,
+    ) -> Result<QueryCursor<C, T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        self.scan(field, lower, upper, None, options)
+    }
+
+    /// Prepare the query of a [`range_scan`](Collection::range_scan), whose
+    /// `field` also starts with `prefix` if any.
+    #[cfg(feature = "aql")]
+    fn scan<T>(
+        &self,
+        field: &str,
+        lower: Bound<Value>,
+        upper: Bound<Value>,
+        prefix: Option<&str>,
+        options: RangeOptions,
+    ) -> Result<QueryCursor<C, T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut bind_vars: HashMap<&str, Value> = HashMap::new();
+        bind_vars.insert("@collection", self.name.as_str().into());
+        bind_vars.insert("field", self.field_case.stored_path(field).into());
+        let mut filters = Vec::new();
+        for (bound, included, excluded, var) in [
+            (lower, "d.@field >= @lo", "d.@field > @lo", "lo"),
+            (upper, "d.@field <= @hi", "d.@field < @hi", "hi"),
+        ] {
+            match bound {
+                Bound::Included(value) => {
+                    filters.push(included);
+                    bind_vars.insert(var, value);
+                }
+                Bound::Excluded(value) => {
+                    filters.push(excluded);
+                    bind_vars.insert(var, value);
+                }
+                Bound::Unbounded => {}
+            }
+        }
+
+        let mut query = String::from("FOR d IN @@collection");
+        if !filters.is_empty() {
+            query.push_str(" FILTER ");
+            query.push_str(&filters.join(" && "));
+        }
+        if let Some(prefix) = prefix {
+            query.push_str(" FILTER STARTS_WITH(d.@field, @prefix)");
+            bind_vars.insert("prefix", prefix.into());
+        }
+        match options.sort {
+            Some(SortDirection::Asc) => query.push_str(" SORT d.@field ASC"),
+            Some(SortDirection::Desc) => query.push_str(" SORT d.@field DESC"),
+            None => {}
+        }
+        if let Some(limit) = options.limit {
+            query.push_str(" LIMIT @limit");
+            bind_vars.insert("limit", limit.into());
+        }
+        query.push_str(" RETURN d");
+
+        let aql_options = match options.stream {
+            Some(stream) => AqlOptions::builder().stream(stream).build(),
+            None => AqlOptions::default(),
+        };
+        let aql = AqlQuery::builder()
+            .query(&query)
+            .bind_vars(bind_vars)
+            .options(aql_options)
+            .build();
+        Ok(self.db().aql_cursor(aql)?.field_case(self.field_case))
+    }
+
+    /// Prepare a query of the documents whose string `field` starts with
+    /// `prefix`, fetched batch by batch.
+    ///
+    /// This is a [`range_scan`](Collection::range_scan) from `prefix`
+    /// included to the smallest string following all strings starting with
+    /// `prefix` excluded, e.g. `"path/"` to `"path0"`, so that a persistent
+    /// index on `field` can serve it, unlike `LIKE` or `SUBSTRING`. The upper
+    /// bound is computed by code point, and is an empty array when there is
+    /// none as all strings sort before arrays in AQL.
+    ///
+    /// ArangoDB compares strings by ICU collation rather than by code point,
+    /// so that the range also holds other strings, like `"Apple"` or `"ábc"`
+    /// between `"a"` and `"b"`. These are filtered out with `STARTS_WITH`.
+    #[cfg(feature = "aql")]
+    pub fn prefix_scan<T>(
+        &self,
+        field: &str,
+        prefix: &str,
+        options: RangeOptions,
+    ) -> Result<QueryCursor<C, T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let upper = match prefix_upper_bound(prefix) {
+            Some(upper) => Value::String(upper),
+            None => json!([]),
+        };
+        self.scan(
+            field,
+            Bound::Included(prefix.into()),
+            Bound::Excluded(upper),
+            Some(prefix),
+            options,
+        )
+    }
+
+    /// Stream all documents of the collection, fetched by batches of
+    /// `batch_size` documents through a streaming AQL cursor.
+    ///
+    /// Each batch is requested once the previous one has been consumed, so
+    /// that at most one batch is held in memory. See
+    /// [`QueryCursor::into_results`] for the cleanup of the cursor when the
+    /// stream is dropped early.
+    #[cfg(all(feature = "aql", not(feature = "blocking")))]
+    pub fn all_documents<T>(
+        &self,
+        batch_size: usize,
+    ) -> Result<impl futures_util::Stream<Item = Result<Document<T>, ClientError>>, ClientError>
+    where
+        T: DeserializeOwned + Send + 'static,
+        C: Send + 'static,
+    {
+        Ok(self.all_documents_cursor(batch_size)?.into_results())
+    }
+
+    /// Iterate over all documents of the collection, fetched by batches of
+    /// `batch_size` documents through a streaming AQL cursor.
+    ///
+    /// Each batch is requested once the previous one has been consumed, so
+    /// that at most one batch is held in memory. See
+    /// [`QueryCursor::into_results`] for the cleanup of the cursor when the
+    /// iterator is dropped early.
+    #[cfg(all(feature = "aql", feature = "blocking"))]
+    pub fn all_documents<T>(
+        &self,
+        batch_size: usize,
+    ) -> Result<impl Iterator<Item = Result<Document<T>, ClientError>>, ClientError>
+    where
+        T: DeserializeOwned + Send + 'static,
+        C: Send + 'static,
+    {
+        Ok(self.all_documents_cursor(batch_size)?.into_results())
+    }
+
+    #[cfg(feature = "aql")]
+    fn all_documents_cursor<T>(
+        &self,
+        batch_size: usize,
+    ) -> Result<QueryCursor<C, Document<T>>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut bind_vars: HashMap<&str, Value> = HashMap::new();
+        bind_vars.insert("@collection", self.name.as_str().into());
+        let aql = AqlQuery::builder()
+            .query("FOR d IN @@collection RETURN d")
+            .bind_vars(bind_vars)
+            .batch_size(u32::try_from(batch_size).unwrap_or(u32::MAX))
+            .options(AqlOptions::builder().stream(true).build())
+            .build();
+        Ok(self.db().aql_cursor(aql)?.field_case(self.field_case))
+    }
+
+    /// Sample `n` documents spread across the documents matching `filter`.
+    ///
+    /// `filter` is an example object like `{"status": "active"}`, whose
+    /// attributes the sampled documents all have, and whose names are renamed
+    /// after the [field case](Collection::with_field_case) of this handle.
+    ///
+    /// The matching documents are counted first and split into `n` strata of
+    /// equal size. A random offset is picked in each stratum, and its document
+    /// fetched with `LIMIT offset, 1`, running up to `options.concurrency`
+    /// queries at once. Unlike `SORT RAND() LIMIT n`, which reads and sorts
+    /// all matching documents, each query only reads up to its offset, and the
+    /// samples are spread evenly over the order in which the collection is
+    /// iterated. Documents removed in the meantime shift the offsets, so that
+    /// fewer than `n` documents may be returned.
+    ///
+    /// With `options.random_field`, a single query fetches the `n` matching
+    /// documents following a random threshold on that field instead. It is
+    /// much cheaper with a persistent index on the field, but the documents
+    /// are adjacent in the order of the field rather than spread, and only as
+    /// random as the values stored.
+    ///
+    /// When at most `n` documents match, they are all returned.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[cfg(feature = "aql")]
+    #[maybe_async]
+    pub async fn stratified_sample<T>(
+        &self,
+        n: usize,
+        filter: Option<Value>,
+        options: SampleOptions,
+    ) -> Result<Vec<T>, ClientError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut bind_vars = vec![("@collection".to_owned(), Value::from(self.name.as_str()))];
+        let mut conditions = Vec::new();
+        match filter {
+            Some(Value::Object(example)) => {
+                for (i, (field, value)) in example.into_iter().enumerate() {
+                    conditions.push(format!("d.@field{i} == @value{i}", i = i));
+                    let path = self.field_case.stored_path(&field);
+                    bind_vars.push((format!("field{}", i), path.into()));
+                    bind_vars.push((format!("value{}", i), self.field_case.to_stored(value)));
+                }
+            }
+            Some(_) => {
+                return Err(ClientError::InvalidOptions(
+                    "the filter of a sample must be an object".to_owned(),
+                ))
+            }
+            None => {}
+        }
+        let filter = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" FILTER {}", conditions.join(" && "))
+        };
+        let mut rng = options
+            .seed
+            .map_or_else(SplitMix64::from_time, SplitMix64::new);
+
+        let docs = match &options.random_field {
+            Some(field) => {
+                bind_vars.push((
+                    "random".to_owned(),
+                    self.field_case.stored_path(field).into(),
+                ));
+                bind_vars.push(("threshold".to_owned(), rng.next_f64().into()));
+                let query = format!(
+                    "FOR d IN @@collection FILTER d.@random >= @threshold{} SORT d.@random \
+                     LIMIT @limit RETURN d",
+                    filter
+                );
+                let limit = vec![("limit", n.into())];
+                let mut docs = self.sample_query(&query, &bind_vars, limit).await?;
+                // wrap around when the threshold is close to the maximum
+                if docs.len() < n {
+                    let query = query.replace(">= @threshold", "< @threshold");
+                    let limit = vec![("limit", (n - docs.len()).into())];
+                    docs.extend(self.sample_query(&query, &bind_vars, limit).await?);
+                }
+                docs
+            }
+            None => {
+                let query = if filter.is_empty() {
+                    "RETURN LENGTH(@@collection)".to_owned()
+                } else {
+                    format!(
+                        "FOR d IN @@collection{} COLLECT WITH COUNT INTO n RETURN n",
+                        filter
+                    )
+                };
+                let count = self.sample_query(&query, &bind_vars, vec![]).await?;
+                let count = count.first().and_then(Value::as_u64).unwrap_or_default();
+                if count <= n as u64 {
+                    let query = format!("FOR d IN @@collection{} RETURN d", filter);
+                    self.sample_query(&query, &bind_vars, vec![]).await?
+                } else {
+                    let query = format!("FOR d IN @@collection{} LIMIT @skip, 1 RETURN d", filter);
+                    let offsets = stratified_offsets(count, n as u64, &mut rng);
+                    self.sample_offsets(&query, &bind_vars, offsets, options.concurrency)
+                        .await?
+                }
+            }
+        };
+        docs.into_iter()
+            .map(|doc| Ok(serde_json::from_value(self.field_case.to_rust(doc))?))
+            .collect()
+    }
+
+    /// Run a query of [`stratified_sample`](Collection::stratified_sample)
+    /// with the shared bind variables and `extra` ones.
+    #[cfg(feature = "aql")]
+    #[maybe_async]
+    async fn sample_query(
+        &self,
+        query: &str,
+        bind_vars: &[(String, Value)],
+        extra: Vec<(&str, Value)>,
+    ) -> Result<Vec<Value>, ClientError> {
+        let mut vars: HashMap<&str, Value> = bind_vars
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        vars.extend(extra);
+        let aql = AqlQuery::builder().query(query).bind_vars(vars).build();
+        self.db().aql_query(aql).await
+    }
+
+    /// Fetch the documents at `offsets`, in order.
+    #[cfg(feature = "aql")]
+    #[maybe_async::async_impl]
+    async fn sample_offsets(
+        &self,
+        query: &str,
+        bind_vars: &[(String, Value)],
+        offsets: Vec<u64>,
+        concurrency: usize,
+    ) -> Result<Vec<Value>, ClientError> {
+        use futures_util::stream::{self, StreamExt, TryStreamExt};
+
+        let docs: Vec<Vec<Value>> = stream::iter(offsets)
+            .map(|skip| self.sample_query(query, bind_vars, vec![("skip", skip.into())]))
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await?;
+        Ok(docs.into_iter().flatten().collect())
+    }
+
+    #[cfg(feature = "aql")]
+    #[maybe_async::sync_impl]
+    fn sample_offsets(
+        &self,
+        query: &str,
+        bind_vars: &[(String, Value)],
+        offsets: Vec<u64>,
+        _concurrency: usize,
+    ) -> Result<Vec<Value>, ClientError> {
+        let mut docs = Vec::with_capacity(offsets.len());
+        for skip in offsets {
+            docs.extend(self.sample_query(query, bind_vars, vec![("skip", skip.into())])?);
+        }
+        Ok(docs)
+    }
+
+    /// Enable or disable the client side check of edges inserted in a
+    /// disjoint smart graph (Enterprise Edition only).
+    ///
+    /// When enabled, which is the default, inserting an edge whose `_from`
+    /// and `_to` have different smart prefixes into an edge collection of a
+    /// disjoint smart graph fails with
+    /// [`ClientError::SmartGraphConstraint`] without any request for the
+    /// insertion. Disable it to let the server decide instead.
+    #[cfg(feature = "enterprise")]
+    pub fn validate_smart_edges(mut self, validate: bool) -> Self {
+        self.validate_smart_edges = validate;
+        self
+    }
+
+    /// Check `_from` and `_to` of an edge against the disjoint smart graph
+    /// constraint.
+    ///
+    /// The properties of the collection are only fetched when the prefixes
+    /// differ, and then cached in this handle. When the user may not read
+    /// them, the check is left to the server.
+    #[cfg(feature = "enterprise")]
+    #[maybe_async]
+    async fn check_smart_edge(&self, edge: &serde_json::Value) -> Result<(), ClientError> {
+        let prefix = |field: &str| {
+            edge.get(field)
+                .and_then(|id| id.as_str())
+                .and_then(smart_prefix)
+        };
+        let (from_prefix, to_prefix) = match (prefix("_from"), prefix("_to")) {
+            (Some(from), Some(to)) if from != to => (from, to),
+            _ => return Ok(()),
+        };
+
+        let cached = *self.disjoint.lock().unwrap();
+        let disjoint = match cached {
+            Some(disjoint) => disjoint,
+            None => {
+                let disjoint = match self.properties().await {
+                    Ok(properties) => properties.detail.is_disjoint.unwrap_or(false),
+                    // the server checks the edge anyway
+                    Err(e) if is_forbidden(&e) => {
+                        self.session.registry().capabilities().forbid(
+                            COLLECTION_PROPERTIES,
+                            "the checks of edges against disjoint smart graphs",
+                        );
+                        false
+                    }
+                    Err(e) => return Err(e),
+                };
+                *self.disjoint.lock().unwrap() = Some(disjoint);
+                disjoint
+            }
+        };
+        if disjoint {
+            return Err(ClientError::SmartGraphConstraint {
+                from_prefix: from_prefix.to_owned(),
+                to_prefix: to_prefix.to_owned(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<C: ClientExt> Collection<C> {
+    /// Returns a new Collection with its `session` updated with the transaction id
+    ///
+    /// Fails with `ClientError::MalformedResponse` when `transaction_id` is
+    /// not an id as sent by the server.
+    pub fn clone_with_transaction(&self, transaction_id: String) -> Result<Self, ClientError> {
+        let session = self.session.with_header(
+            TRANSACTION_HEADER,
+            id_header(&transaction_id, "transaction")?,
+        );
+        Ok(Self {
+            session,
+            ..self.clone()
+        })
+    }
+}
+
+/// Error number of ArangoDB when a collection or view is not found.
+pub(crate) const ERROR_COLLECTION_NOT_FOUND: u16 = 1203;
+
+/// Fail with the error of the server of a silent write, whose successful
+/// response is not read.
+#[cfg(feature = "documents")]
+fn check_written(resp: &http::Response<String>) -> Result<(), ClientError> {
+    if !resp.status().is_success() {
+        deserialize_response::<Value>(resp)?;
+    }
+    Ok(())
+}
+
+/// Refuse an expected revision given both by the options and the argument
+/// of a write, or for several documents at once, for which the server ignores
+/// `If-Match`.
+#[cfg(feature = "documents")]
+fn check_if_match(
+    _key: Option<&str>,
+    from_options: Option<&str>,
+    if_match_header: &Option<String>,
+) -> Result<(), ClientError> {
+    let reason = match (from_options, if_match_header) {
+        (Some(_), Some(_)) => "the revision is given both by if_match and if_match_header",
+        (Some(_), None) if _key.is_none() => {
+            "if_match only applies to a single document, set ignore_revs to false and give the \
+             _rev of each document instead"
+        }
+        _ => return Ok(()),
+    };
+    Err(ClientError::InvalidOptions(reason.to_owned()))
+}
+
+/// Turn the 412 of a write whose expected revision did not match into
+/// `ClientError::PreconditionFailed`, with the current revision from the
+/// `Etag`, or else the body.
+#[cfg(feature = "documents")]
+fn precondition_failed(
+    resp: http::Response<String>,
+) -> Result<http::Response<String>, ClientError> {
+    if resp.status() != http::StatusCode::PRECONDITION_FAILED {
+        return Ok(resp);
+    }
+    let etag = resp.headers().get(http::header::ETAG);
+    let etag = etag.and_then(|tag| tag.to_str().ok());
+    let current_rev = match etag {
+        Some(tag) => Some(tag.trim_matches('"').to_owned()),
+        None => parse_body::<Value>(resp.body())
+            .ok()
+            .and_then(|body| body.get("_rev")?.as_str().map(str::to_owned)),
+    };
+    match deserialize_response::<Value>(&resp) {
+        Err(ClientError::Arango(source)) => Err(ClientError::PreconditionFailed {
+            current_rev,
+            source,
+        }),
+        Err(e) => Err(e),
+        Ok(_) => Ok(resp),
+    }
+}
+
+/// Number of inserts of [`Collection::insert_or_get`] while the conflicting
+/// document is removed before it can be read.
+#[cfg(feature = "documents")]
+const INSERT_OR_GET_ATTEMPTS: usize = 3;
+
+/// Number of queries of [`Collection::compare_and_swap_field`] while they
+/// conflict with other writes.
+#[cfg(all(feature = "documents", feature = "aql"))]
+const CAS_ATTEMPTS: usize = 3;
+
+/// Key of the document an insert of `doc` conflicted with: the one reported
+/// as `conflicting key: ...` at the end of the error message, or else the
+/// `_key` of `doc`.
+#[cfg(feature = "documents")]
+fn conflicting_key<T: Serialize>(error: &ArangoError, doc: &T) -> Option<String> {
+    const MARKER: &str = "conflicting key: ";
+    if let Some(i) = error.message().rfind(MARKER) {
+        let key = error.message()[i + MARKER.len()..].trim();
+        if !key.is_empty() {
+            return Some(key.to_owned());
+        }
+    }
+    serde_json::to_value(doc)
+        .ok()?
+        .get("_key")?
+        .as_str()
+        .map(str::to_owned)
+}
+
+/// Drop the collection at `url`, shared by `Collection::drop` and
+/// `Database::drop_collection`.
+#[maybe_async]
+pub(crate) async fn drop_collection<C: ClientExt>(
+    session: &Session<C>,
+    url: &Url,
+    name: &str,
+    options: DropOptions,
+) -> Result<DroppedCollection, ClientError> {
+    let mut url = url.clone();
+    options.apply_query(&mut url);
+
+    match deserialize_response(&session.delete(url, "").await?) {
+        Err(ClientError::Arango(ref e)) if e.error_num() == ERROR_COLLECTION_NOT_FOUND => {
+            Err(ClientError::CollectionNotFound {
+                name: name.to_owned(),
+            })
+        }
+        result => result,
+    }
+}
+
+/// Extract the smart prefix from a vertex id of a smart graph, e.g. `DE` from
+/// `persons/DE:alice`.
+#[cfg(all(feature = "enterprise", feature = "documents"))]
+fn smart_prefix(id: &str) -> Option<&str> {
+    let (_, key) = id.split_once('/')?;
+    key.split_once(':').map(|(prefix, _)| prefix)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+pub enum CollectionType {
+    Document = 2,
+    Edge = 3,
+}
+
+/// Serialized as the number of the type, as the server expects it.
+impl Serialize for CollectionType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u8(*self as u8)
+    }
+}
+
+impl<'de> Deserialize<'de> for CollectionType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = u8::deserialize(deserializer)?;
+        match value {
+            2 => Ok(CollectionType::Document),
+            3 => Ok(CollectionType::Edge),
+            _ => Err(serde::de::Error::custom(
+                "Undefined behavior. If the crate breaks after an upgrade of ArangoDB, please \
+                 contact the author.",
+            )),
+        }
+    }
+}
+
+impl<C: ClientExt> fmt::Display for Collection<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} @ {}",
+            self.db_name(),
+            self.name,
+            DisplayEndpoint(&self.base_url)
+        )
+    }
+}
+
+/// Leaves out the HTTP client, whose headers hold the credentials.
+impl<C: ClientExt> fmt::Debug for Collection<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Collection")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("database", &self.db_name())
+            .field("collection_type", &self.collection_type)
+            .field("endpoint", &endpoint(&self.base_url).as_str())
+            .field("read_only", &self.read_only)
+            .field("field_case", &self.field_case)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Wait `duration` before sending a request again.
+#[cfg(feature = "documents")]
+#[maybe_async::async_impl]
+async fn pause(duration: Duration) {
+    crate::connection::endpoints::sleep(duration).await
+}
+
+#[cfg(feature = "documents")]
+#[maybe_async::sync_impl]
+fn pause(duration: Duration) {
+    std::thread::sleep(duration)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "documents")]
+    use std::time::Duration;
+
+    use http::Method;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::{fixtures, MockClient, MockResponse};
+
+    #[cfg(feature = "documents")]
+    fn edges(mock: &MockClient) -> Collection<MockClient> {
+        let db = mock.database("test_db");
+        Collection::new(
+            "knows",
+            "200",
+            CollectionType::Edge,
+            db.url(),
+            db.shared_session(),
+        )
+    }
+
+    #[cfg(all(feature = "enterprise", feature = "documents"))]
+    fn disjoint_properties() -> MockResponse {
+        let mut body = fixtures::collection_properties("knows", "200");
+        body["type"] = json!(3);
+        body["isSmart"] = json!(true);
+        body["isDisjoint"] = json!(true);
+        MockResponse::json(200, body)
+    }
+
+    #[cfg(all(feature = "enterprise", feature = "documents"))]
+    #[test]
+    fn parse_smart_prefix() {
+        assert_eq!(smart_prefix("persons/DE:alice"), Some("DE"));
+        assert_eq!(smart_prefix("persons/alice"), None);
+        assert_eq!(smart_prefix("DE:alice"), None);
+    }
+
+    #[cfg(all(feature = "enterprise", feature = "documents"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn reject_edge_across_disjoint_smart_graph() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_db/test_db/_api/collection/knows/properties",
+            disjoint_properties(),
+        );
+        let coll = edges(&mock);
+        let edge = json!({ "_from": "persons/DE:alice", "_to": "persons/FR:bob" });
+
+        for _ in 0..2 {
+            match coll.create_document(edge.clone(), Default::default()).await {
+                Err(ClientError::SmartGraphConstraint {
+                    from_prefix,
+                    to_prefix,
+                }) => assert_eq!((from_prefix.as_str(), to_prefix.as_str()), ("DE", "FR")),
+                other => panic!(
+                    "should violate the smart graph constraint: {:?}",
+                    other.err()
+                ),
+            }
+        }
+        // properties are fetched once and no insertion is attempted
+        mock.assert_requests(&[(Method::GET, "/_db/test_db/_api/collection/knows/properties")]);
+
+        // edges within one smart prefix need no lookup
+        mock.clear_requests();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/knows/",
+            MockResponse::json(202, fixtures::DocumentFixture::new("knows", "1").build()),
+        );
+        let edge = json!({ "_from": "persons/DE:alice", "_to": "persons/DE:carol" });
+        coll.create_document(edge, Default::default())
+            .await
+            .unwrap();
+        mock.assert_requests(&[(Method::POST, "/_db/test_db/_api/document/knows/")]);
+    }
+
+    #[cfg(all(feature = "enterprise", feature = "documents"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn skip_smart_edge_validation() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/knows/",
+            MockResponse::json(202, fixtures::DocumentFixture::new("knows", "1").build()),
+        );
+        let coll = edges(&mock).validate_smart_edges(false);
+        let edge = json!({ "_from": "persons/DE:alice", "_to": "persons/FR:bob" });
+        let resp = coll
+            .create_document::<serde_json::Value>(edge, Default::default())
+            .await;
+        assert!(resp.is_ok());
+        mock.assert_requests(&[(Method::POST, "/_db/test_db/_api/document/knows/")]);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn drop_system_collection() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/collection/_jobs/",
+            MockResponse::json(200, json!({ "error": false, "code": 200, "id": "42" })),
+        );
+        let db = mock.database("test_db");
+        let coll = Collection::new(
+            "_jobs",
+            "42",
+            CollectionType::Document,
+            db.url(),
+            db.shared_session(),
+        );
+        let dropped = coll
+            .drop(DropOptions::builder().is_system(true).build())
+            .await
+            .unwrap();
+        assert_eq!(dropped.id, "42");
+        assert_eq!(
+            mock.requests()[0].query_param("isSystem"),
+            Some("true".to_owned())
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn drop_missing_collection() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::DELETE,
+            "/_db/test_db/_api/collection/gone/",
+            MockResponse::json(404, fixtures::error(404, 1203, "collection not found")),
+        );
+        let result = mock.database("test_db").drop_collection("gone").await;
+        match result {
+            Err(ClientError::CollectionNotFound { name }) => assert_eq!(name, "gone"),
+            other => panic!("should be a typed not found error: {:?}", other),
+        }
+    }
+
+    fn users(mock: &MockClient) -> Collection<MockClient> {
+        let db = mock.database("test_db");
+        Collection::new(
+            "users",
+            "100",
+            CollectionType::Document,
+            db.url(),
+            db.shared_session(),
+        )
+    }
+
+    /// An application user with read and write access to `users` only, on
+    /// a hardened server, needs no other privilege for its documents and
+    /// queries.
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn app_user_without_introspection() {
+        let mock = MockClient::default();
+        mock.forbid_introspection("test_db", &["users", "knows"]);
+        let document = "/_db/test_db/_api/document/users/alice";
+        let alice = || fixtures::DocumentFixture::new("users", "alice").build();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(201, alice()),
+        );
+        mock.once(
+            Method::GET,
+            document,
+            MockResponse::json(
+                200,
+                fixtures::document("users", "alice", "_a", json!({ "age": 30 })),
+            ),
+        );
+        mock.once(Method::PATCH, document, MockResponse::json(202, alice()));
+        mock.once(Method::PUT, document, MockResponse::json(202, alice()));
+        mock.once(Method::DELETE, document, MockResponse::json(202, alice()));
+        let coll = users(&mock);
+
+        coll.create_document(json!({ "_key": "alice" }), Default::default())
+            .await
+            .unwrap();
+        let read: Document<Value> = coll.document("alice").await.unwrap();
+        assert_eq!(read.document["age"], 30);
+        coll.update_document("alice", json!({ "age": 31 }), Default::default())
+            .await
+            .unwrap();
+        coll.replace_document("alice", json!({ "age": 32 }), Default::default(), None)
+            .await
+            .unwrap();
+        coll.remove_document::<Value>("alice", Default::default(), None)
+            .await
+            .unwrap();
+
+        // the removed endpoints are not checked without the version
+        for _ in 0..2 {
+            mock.once(
+                Method::PUT,
+                "/_db/test_db/_api/collection/users/load",
+                MockResponse::json(200, fixtures::collection_info("users", "100")),
+            );
+            coll.load(false).await.unwrap();
+        }
+
+        // queries of read-only handles are checked with keywords instead
+        let db = mock.database("test_db").read_only();
+        for _ in 0..2 {
+            mock.expect_cursor("test_db", vec![vec![json!(1)]]);
+            let result = db.aql_str::<u32>("FOR u IN users RETURN 1").await;
+            assert_eq!(result.unwrap(), vec![1]);
+        }
+        let result = db.aql_str::<Value>("INSERT {} INTO users").await;
+        assert!(matches!(
+            result,
+            Err(ClientError::ReadOnlyConnection { .. })
+        ));
+
+        // edges are left to the server to check
+        #[cfg(feature = "enterprise")]
+        {
+            let knows = edges(&mock);
+            let edge = json!({ "_from": "persons/DE:alice", "_to": "persons/FR:bob" });
+            for _ in 0..2 {
+                mock.once(
+                    Method::POST,
+                    "/_db/test_db/_api/document/knows/",
+                    MockResponse::json(201, fixtures::DocumentFixture::new("knows", "1").build()),
+                );
+                knows
+                    .create_document(edge.clone(), Default::default())
+                    .await
+                    .unwrap();
+            }
+        }
+
+        // each refused lookup was tried once
+        let requests = mock.requests();
+        let paths = crate::test_support::requests_by_path(&requests);
+        assert_eq!(paths["/_api/version"].len(), 1);
+        assert_eq!(paths["/_db/test_db/_api/query"].len(), 1);
+        #[cfg(feature = "enterprise")]
+        assert_eq!(
+            paths["/_db/test_db/_api/collection/knows/properties"].len(),
+            1
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "cluster")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn shards_with_and_without_servers() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/collection/users/shards";
+        mock.once(
+            Method::GET,
+            path,
+            MockResponse::json(
+                200,
+                json!({ "error": false, "code": 200, "shards": ["s2", "s1"] }),
+            ),
+        );
+        mock.once(
+            Method::GET,
+            path,
+            MockResponse::json(
+                200,
+                json!({
+                    "error": false,
+                    "code": 200,
+                    "shards": { "s2": ["PRMR-b", "PRMR-a"], "s1": ["PRMR-a"] },
+                }),
+            ),
+        );
+        let coll = users(&mock);
+        let shards = coll.shards(false).await.unwrap();
+        let ids: Vec<_> = shards.iter().map(|shard| shard.id.as_str()).collect();
+        assert_eq!(ids, ["s2", "s1"]);
+        assert!(shards[0].servers.is_empty());
+
+        let shards = coll.shards(true).await.unwrap();
+        assert_eq!(
+            shards[1],
+            Shard {
+                id: "s2".to_owned(),
+                servers: vec!["PRMR-b".to_owned(), "PRMR-a".to_owned()],
+            }
+        );
+        let requests = mock.requests();
+        assert_eq!(requests[0].query_param("details"), None);
+        assert_eq!(requests[1].query_param("details").as_deref(), Some("true"));
+    }
+
+    #[cfg(feature = "cluster")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn responsible_shard_of_a_document() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/collection/users/responsibleShard";
+        mock.once(
+            Method::PUT,
+            path,
+            MockResponse::json(200, json!({ "error": false, "code": 200, "shardId": "s7" })),
+        );
+        mock.once(
+            Method::PUT,
+            path,
+            MockResponse::json(
+                501,
+                fixtures::error(501, 9, "shards API is only available in a cluster"),
+            ),
+        );
+        let coll = users(&mock);
+        let doc = json!({ "_key": "alice" });
+        let shard = coll.responsible_shard(&doc).await.unwrap();
+        assert_eq!(shard, "s7");
+        assert_eq!(mock.requests()[0].json_body().unwrap(), doc);
+
+        let single_server = coll.responsible_shard(&doc).await;
+        assert!(matches!(
+            single_server,
+            Err(ClientError::ClusterOnly { ref operation }) if operation == "responsible_shard"
+        ));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn load_and_unload_statuses() {
+        let mock = MockClient::default();
+        let collection = "/_db/test_db/_api/collection/users";
+        for (endpoint, status) in [("load", 3), ("unload", 4), ("unload", 2), ("load", 6)] {
+            let mut info = fixtures::collection_info("users", "100");
+            info["status"] = json!(status);
+            mock.once(
+                Method::PUT,
+                &format!("{}/{}", collection, endpoint),
+                MockResponse::json(200, info),
+            );
+        }
+        mock.once(
+            Method::PUT,
+            &format!("{}/loadIndexesIntoMemory", collection),
+            MockResponse::json(200, json!({ "error": false, "code": 200, "result": true })),
+        );
+        let coll = users(&mock);
+        let loaded = coll.load(false).await.unwrap();
+        assert_eq!(loaded.status, Status::Loaded);
+        let unloading = coll.unload().await.unwrap();
+        assert_eq!(unloading.status, Status::Unloading);
+        let unloaded = coll.unload().await.unwrap();
+        assert_eq!(unloaded.status, Status::Unloaded);
+        let loading = coll.load(false).await.unwrap();
+        assert_eq!(loading.status, Status::Loading);
+        let indexes_loaded = coll.load_indexes().await.unwrap();
+        assert!(indexes_loaded);
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn revision_only() {
+        let mock = MockClient::default();
+        let mut body = fixtures::collection_properties("users", "100");
+        body["revision"] = json!("_h2Hw5Cu---");
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users/revision",
+            MockResponse::json(200, body),
+        );
+        let revision = users(&mock).revision().await.unwrap();
+        assert_eq!(revision, "_h2Hw5Cu---");
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn count_beyond_u32() {
+        let mock = MockClient::default();
+        let mut body = fixtures::collection_properties("users", "100");
+        body["count"] = json!(5_000_000_000_u64);
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users/count",
+            MockResponse::json(200, body),
+        );
+        let count = users(&mock).count().await.unwrap();
+        assert_eq!(count, 5_000_000_000);
+
+        // a missing count is not an empty collection
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users/count",
+            MockResponse::json(200, fixtures::collection_properties("users", "100")),
+        );
+        let count = users(&mock).count().await;
+        assert!(matches!(count, Err(ClientError::MalformedResponse(_))));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn checksum_of_data_only() {
+        let mock = MockClient::default();
+        let mut body = fixtures::collection_info("users", "100");
+        body["revision"] = json!("_h2Hw5Cu---");
+        body["checksum"] = json!("1418603288787806357");
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users/checksum",
+            MockResponse::json(200, body),
+        );
+        let options = ChecksumOptions::builder()
+            .with_revision(false)
+            .with_data(true)
+            .build();
+        let checksum = users(&mock).checksum_with_options(options).await.unwrap();
+        assert_eq!(checksum.checksum, "1418603288787806357");
+        assert_eq!(checksum.revision, "_h2Hw5Cu---");
+
+        let request = &mock.requests()[0];
+        assert_eq!(
+            request.query_param("withRevisions").as_deref(),
+            Some("false")
+        );
+        assert_eq!(request.query_param("withData").as_deref(), Some("true"));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn statistics_with_engine_details() {
+        let mock = MockClient::default();
+        let mut body = fixtures::collection_properties("users", "100");
+        body["count"] = json!(2);
+        body["figures"] = json!({
+            "indexes": { "count": 1, "size": 5_000_000_000_u64 },
+            "documentsSize": 1024,
+            "cacheInUse": false,
+            "cacheSize": 0,
+            "cacheUsage": 0,
+            "engine": { "documents": 2, "indexes": [] },
+        });
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users/figures",
+            MockResponse::json(200, body),
+        );
+        let options = FiguresOptions::builder().details(true).build();
+        let statistics = users(&mock).statistics_with_options(options).await.unwrap();
+        let figures = statistics.figures;
+        assert_eq!(figures.indexes.size, Some(5_000_000_000));
+        assert_eq!(figures.documents_size, Some(1024));
+        assert_eq!(figures.cache_in_use, Some(false));
+        assert_eq!(figures.extra["engine"]["documents"], 2);
+        assert_eq!(
+            mock.requests()[0].query_param("details").as_deref(),
+            Some("true")
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn computed_values_round_trip() {
+        let mock = MockClient::default();
+        let updated_at = ComputedValue::builder()
+            .name("updatedAt")
+            .expression("RETURN DATE_ISO8601(DATE_NOW())")
+            .overwrite(true)
+            .compute_on(vec![ComputeOn::Insert, ComputeOn::Update])
+            .build();
+        let sent = json!([{
+            "name": "updatedAt",
+            "expression": "RETURN DATE_ISO8601(DATE_NOW())",
+            "overwrite": true,
+            "computeOn": ["insert", "update"],
+        }]);
+        let mut body = fixtures::collection_properties("users", "100");
+        body["computedValues"] = json!([{
+            "name": "updatedAt",
+            "expression": "RETURN DATE_ISO8601(DATE_NOW())",
+            "overwrite": true,
+            "computeOn": ["insert", "update"],
+            "keepNull": true,
+            "failOnWarning": false,
+        }]);
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/collection/users/properties",
+            MockResponse::json(200, body),
+        );
+        let options = PropertiesOptions::builder()
+            .computed_values(vec![updated_at.clone()])
+            .build();
+        let properties = users(&mock).change_properties(options).await.unwrap();
+        let computed = properties.detail.computed_values.unwrap();
+        assert_eq!(computed[0].keep_null, Some(true));
+        assert_eq!(
+            ComputedValue {
+                keep_null: None,
+                fail_on_warning: None,
+                ..computed[0].clone()
+            },
+            updated_at
+        );
+        let body = mock.requests()[0].json_body().unwrap();
+        assert_eq!(body, json!({ "computedValues": sent }));
+
+        let options = CreateOptions::builder()
+            .name("users")
+            .computed_values(vec![updated_at])
+            .build();
+        assert_eq!(
+            serde_json::to_value(options).unwrap()["computedValues"],
+            sent
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn change_schema_and_sync() {
+        let mock = MockClient::default();
+        let schema = json!({
+            "rule": { "required": ["name"] },
+            "level": "moderate",
+            "message": "a user needs a name",
+        });
+        let mut body = fixtures::collection_properties("users", "100");
+        body["waitForSync"] = json!(true);
+        body["cacheEnabled"] = json!(false);
+        body["schema"] = schema.clone();
+        body["replicationFactor"] = json!("satellite");
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/collection/users/properties",
+            MockResponse::json(200, body),
+        );
+        let options = PropertiesOptions::builder()
+            .wait_for_sync(true)
+            .schema(schema.clone())
+            .build();
+        let properties = users(&mock).change_properties(options).await.unwrap();
+        assert!(properties.detail.wait_for_sync);
+        assert_eq!(properties.detail.schema, Some(schema.clone()));
+        assert_eq!(
+            properties.detail.replication_factor,
+            Some(ReplicationFactor::Satellite)
+        );
+
+        let body = mock.requests()[0].json_body().unwrap();
+        assert_eq!(body, json!({ "waitForSync": true, "schema": schema }));
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn range_scan_query() {
+        let mock = MockClient::default();
+        mock.expect_cursor("test_db", vec![vec![json!({ "_key": "a", "ts": 5 })]]);
+        let options = RangeOptions::builder()
+            .limit(10)
+            .sort(SortDirection::Desc)
+            .stream(true)
+            .build();
+        let mut cursor = users(&mock)
+            .range_scan::<Value>(
+                "meta.ts",
+                Bound::Excluded(json!(1)),
+                Bound::Included(json!(9)),
+                options,
+            )
+            .unwrap();
+        let batch = cursor.next_batch().await.unwrap().unwrap();
+        assert_eq!(batch.len(), 1);
+
+        let body = mock.requests()[0].json_body().unwrap();
+        assert_eq!(
+            body["query"],
+            "FOR d IN @@collection FILTER d.@field > @lo && d.@field <= @hi \
+             SORT d.@field DESC LIMIT @limit RETURN d"
+        );
+        assert_eq!(
+            body["bindVars"],
+            json!({ "@collection": "users", "field": ["meta", "ts"], "lo": 1, "hi": 9, "limit": 10 })
+        );
+        assert_eq!(body["options"]["stream"], true);
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn prefix_scan_query() {
+        let mock = MockClient::default();
+        mock.expect_cursor("test_db", vec![vec![]]);
+        mock.expect_cursor("test_db", vec![vec![]]);
+        mock.expect_cursor("test_db", vec![vec![]]);
+        let coll = users(&mock);
+        for prefix in &["/home/caf\u{e9}", "\u{10FFFF}", ""] {
+            let mut cursor = coll
+                .prefix_scan::<Value>("path", prefix, Default::default())
+                .unwrap();
+            cursor.next_batch().await.unwrap();
+        }
+
+        let requests = mock.requests();
+        let body = requests[0].json_body().unwrap();
+        assert_eq!(
+            body["query"],
+            "FOR d IN @@collection FILTER d.@field >= @lo && d.@field < @hi \
+             FILTER STARTS_WITH(d.@field, @prefix) RETURN d"
+        );
+        assert_eq!(body["bindVars"]["field"], json!(["path"]));
+        assert_eq!(body["bindVars"]["prefix"], "/home/caf\u{e9}");
+        assert_eq!(body["bindVars"]["lo"], "/home/caf\u{e9}");
+        assert_eq!(body["bindVars"]["hi"], "/home/caf\u{ea}");
+        assert!(body["options"].get("stream").is_none());
+        // without a greater string, bound by the first value after all strings
+        let body = requests[1].json_body().unwrap();
+        assert_eq!(body["bindVars"]["hi"], json!([]));
+        let body = requests[2].json_body().unwrap();
+        assert_eq!(body["bindVars"]["lo"], "");
+        assert_eq!(body["bindVars"]["hi"], json!([]));
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    fn user(key: &str) -> Value {
+        fixtures::document("users", key, "_rev", json!({ "name": key }))
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn all_documents_by_batches() {
+        let mock = MockClient::default();
+        mock.expect_cursor("test_db", vec![vec![user("a"), user("b")], vec![user("c")]]);
+        let docs = users(&mock).all_documents::<Value>(2).unwrap();
+        #[cfg(not(feature = "blocking"))]
+        let docs: Vec<_> = futures_util::StreamExt::collect(docs).await;
+        #[cfg(feature = "blocking")]
+        let docs: Vec<_> = docs.collect();
+        let keys: Vec<_> = docs
+            .into_iter()
+            .map(|doc| doc.unwrap().header._key)
+            .collect();
+        assert_eq!(keys, ["a", "b", "c"]);
+
+        let requests = mock.requests();
+        let body = requests[0].json_body().unwrap();
+        assert_eq!(body["query"], "FOR d IN @@collection RETURN d");
+        assert_eq!(body["bindVars"], json!({ "@collection": "users" }));
+        assert_eq!(body["batchSize"], 2);
+        assert_eq!(body["options"]["stream"], true);
+        assert_eq!(requests[1].method, Method::PUT);
+        assert_eq!(requests.len(), 2);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn all_documents_dropped_early() {
+        let mock = MockClient::default();
+        let batches = fixtures::cursor_batches("mock_cursor", vec![vec![user("a")], vec![]]);
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/cursor",
+            MockResponse::json(201, batches[0].clone()),
+        );
+        let cursor = "/_db/test_db/_api/cursor/mock_cursor";
+        mock.once(
+            Method::DELETE,
+            cursor,
+            MockResponse::json(202, json!({ "error": false, "code": 202 })),
+        );
+        let coll = users(&mock);
+        let docs = coll.all_documents::<Value>(1).unwrap();
+        #[cfg(not(feature = "blocking"))]
+        let first = futures_util::StreamExt::next(&mut Box::pin(docs)).await;
+        #[cfg(feature = "blocking")]
+        let first = { docs }.next();
+        assert_eq!(first.unwrap().unwrap().header._key, "a");
+
+        // deleted with the next request in async mode
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.0")),
+        );
+        coll.db().arango_version().await.unwrap();
+        let requests = mock.requests();
+        assert_eq!(requests[1].method, Method::DELETE);
+        assert_eq!(requests[1].path, cursor);
+        assert_eq!(requests.len(), 3);
+        mock.assert_no_pending();
+        assert!(coll
+            .db()
+            .shared_session()
+            .registry()
+            .open_resources()
+            .cursors
+            .is_empty());
+    }
+
+    #[cfg(feature = "documents")]
+    fn imported(created: u64, errors: u64, details: Vec<&str>) -> MockResponse {
+        MockResponse::json(
+            201,
+            json!({
+                "error": false,
+                "created": created,
+                "errors": errors,
+                "empty": 0,
+                "updated": 0,
+                "ignored": 0,
+                "details": details,
+            }),
+        )
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn import_in_one_request() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/import";
+        mock.once(Method::POST, path, imported(2, 0, vec![]));
+        mock.once(
+            Method::POST,
+            path,
+            imported(1, 1, vec!["at position 1: unique constraint violated"]),
+        );
+        mock.once(Method::POST, path, imported(1, 0, vec![]));
+        let coll = users(&mock).with_field_case(Case::Camel);
+        let docs = [json!({ "first_name": "a" }), json!({ "first_name": "b" })];
+
+        let created = coll.import(&docs, Default::default()).await.unwrap();
+        assert_eq!((created.created, created.errors), (2, 0));
+        let options = ImportOptions::builder()
+            .import_type(ImportType::List)
+            .on_duplicate(OnDuplicate::Ignore)
+            .complete(false)
+            .details(true)
+            .build();
+        let rejected = coll.import(&docs, options).await.unwrap();
+        assert_eq!(
+            rejected.details,
+            vec!["at position 1: unique constraint violated"]
+        );
+        let raw = "{\"first_name\":\"c\"}\n".to_owned();
+        coll.import_jsonl(raw.clone(), Default::default())
+            .await
+            .unwrap();
+
+        let requests = mock.requests();
+        let query = |i: usize, name| requests[i].query_param(name);
+        assert_eq!(query(0, "collection").as_deref(), Some("users"));
+        assert_eq!(query(0, "type").as_deref(), Some("documents"));
+        assert_eq!(
+            requests[0].body,
+            "{\"firstName\":\"a\"}\n{\"firstName\":\"b\"}\n"
+        );
+        assert_eq!(query(1, "type").as_deref(), Some("list"));
+        assert_eq!(query(1, "onDuplicate").as_deref(), Some("ignore"));
+        assert_eq!(query(1, "complete").as_deref(), Some("false"));
+        assert_eq!(query(1, "details").as_deref(), Some("true"));
+        assert_eq!(
+            requests[1].body,
+            "[{\"firstName\":\"a\"},{\"firstName\":\"b\"}]"
+        );
+        assert_eq!(query(2, "type").as_deref(), Some("auto"));
+        assert_eq!(requests[2].body, raw);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn resume_import_after_failure() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/import";
+        mock.once(Method::POST, path, imported(2, 0, vec![]));
+        mock.once(
+            Method::POST,
+            path,
+            imported(1, 1, vec!["at position 1: unique constraint violated"]),
+        );
+        // the third batch fails in transport, as no response is registered
+        let coll = users(&mock);
+        let docs = || (0..7).map(|i| json!({ "_key": i.to_string() }));
+        let options = || ImportOptions::builder().batch_size(2).overwrite(true);
+
+        let mut reports = Vec::new();
+        let result = coll
+            .import_documents(docs(), options().details(true).build(), |p| reports.push(p))
+            .await;
+        assert!(matches!(result, Err(ClientError::HttpClient(_))));
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].offset, 2);
+        assert!(reports[0].details.is_empty());
+        let last = reports[1].clone();
+        assert_eq!((last.offset, last.created, last.rejected), (4, 3, 1));
+        assert_eq!(
+            last.details,
+            vec!["at position 1: unique constraint violated"]
+        );
+
+        mock.once(Method::POST, path, imported(2, 0, vec![]));
+        mock.once(Method::POST, path, imported(1, 0, vec![]));
+        let mut resumed = Vec::new();
+        let progress = coll
+            .import_documents(docs(), options().resume_from(last.offset).build(), |p| {
+                resumed.push(p.offset)
+            })
+            .await
+            .unwrap();
+        assert_eq!(resumed, vec![6, 7]);
+        assert_eq!((progress.offset, progress.created), (7, 3));
+
+        let requests = mock.requests();
+        let query = |i: usize, name| requests[i].query_param(name);
+        assert_eq!(query(0, "collection").as_deref(), Some("users"));
+        assert_eq!(query(0, "type").as_deref(), Some("documents"));
+        assert_eq!(query(0, "overwrite").as_deref(), Some("true"));
+        assert_eq!(query(1, "overwrite"), None);
+        // the resumed import must not truncate the collection again
+        assert_eq!(query(3, "overwrite"), None);
+        let sent: Vec<String> = [0, 1, 3, 4]
+            .iter()
+            .flat_map(|&i| {
+                requests[i]
+                    .body
+                    .lines()
+                    .map(str::to_owned)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let expected: Vec<String> = docs().map(|doc| doc.to_string()).collect();
+        assert_eq!(sent, expected);
+        assert_eq!(
+            progress.bytes_sent,
+            (requests[3].body.len() + requests[4].body.len()) as u64
+        );
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn retried_batches_keep_generated_keys() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use crate::document::key::{DocumentKey, KeyStrategy};
+
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/import";
+        let unavailable = || {
+            MockResponse::json(503, fixtures::error(503, 503, "service unavailable"))
+                .with_header("Retry-After", "0")
+        };
+        mock.once(Method::POST, path, unavailable());
+        mock.once(Method::POST, path, unavailable());
+        mock.once(Method::POST, path, imported(2, 0, vec![]));
+        mock.once(Method::POST, path, imported(1, 0, vec![]));
+        let coll = users(&mock);
+        // a new key on every call, to catch keys generated again
+        let generated = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&generated);
+        let keys = KeyStrategy::Custom(Box::new(move |_: &Value| {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            DocumentKey::new(format!("k{}", n)).unwrap()
+        }));
+        let docs = vec![
+            json!({ "name": "a" }),
+            json!({ "_key": "given", "name": "b" }),
+            json!({ "name": "c" }),
+        ];
+        let options = ImportOptions::builder().batch_size(2).retries(2).build();
+        let progress = coll
+            .import_documents_with_keys(docs, &keys, options, |_| {})
+            .await
+            .unwrap();
+        assert_eq!((progress.offset, progress.created), (3, 3));
+        assert_eq!(generated.load(Ordering::SeqCst), 2);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 4);
+        let first: Vec<Value> = requests[0]
+            .body
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            first,
+            vec![
+                json!({ "_key": "k0", "name": "a" }),
+                json!({ "_key": "given", "name": "b" }),
+            ]
+        );
+        // sent again as it was
+        assert_eq!(requests[1].body, requests[0].body);
+        assert_eq!(requests[2].body, requests[0].body);
+        assert_eq!(requests[3].body.trim_end(), r#"{"_key":"k1","name":"c"}"#);
+
+        // without retries left, the error is returned
+        mock.once(Method::POST, path, unavailable());
+        let options = ImportOptions::builder().retries(0).build();
+        let result = coll
+            .import_documents_with_keys(vec![json!({})], &keys, options, |_| {})
+            .await;
+        assert!(
+            matches!(result, Err(ClientError::ServiceUnavailable { .. })),
+            "{:?}",
+            result
+        );
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn import_retries_transport_errors() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/import";
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::transport_error("connection reset"),
+        );
+        mock.once(Method::POST, path, imported(1, 0, vec![]));
+        let coll = users(&mock);
+        let options = ImportOptions::builder().retries(1).build();
+        let progress = coll
+            .import_documents(vec![json!({ "name": "a" })], options, |_| {})
+            .await
+            .unwrap();
+        assert_eq!((progress.offset, progress.created), (1, 1));
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].body, requests[0].body);
+        mock.assert_no_pending();
+
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::transport_error("connection reset"),
+        );
+        let options = ImportOptions::builder().build();
+        let result = coll
+            .import_documents(vec![json!({ "name": "a" })], options, |_| {})
+            .await;
+        assert!(
+            matches!(result, Err(ClientError::HttpClient(_))),
+            "{:?}",
+            result
+        );
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn batch_read_headers() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::PUT,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(
+                200,
+                json!([
+                    fixtures::document("users", "alice", "_a", json!({ "age": 30 })),
+                    { "error": true, "errorNum": 1202, "errorMessage": "document not found" },
+                ]),
+            ),
+        );
+        let coll = users(&mock);
+        let keys = ["alice", "bob"];
+        let configs = vec![
+            BatchReadOptions::default(),
+            BatchReadOptions::builder().transaction_id("42").build(),
+            BatchReadOptions::builder()
+                .allow_dirty_read(true)
+                .ignore_revs(false)
+                .build(),
+        ];
+        for options in configs {
+            let docs = coll
+                .read_documents::<_, Value>(&keys, options)
+                .await
+                .unwrap();
+            assert_eq!(docs.len(), 2);
+            assert_eq!(docs[0].as_ref().unwrap().document["age"], 30);
+            assert_eq!(docs[1].as_ref().err().map(|e| e.error_num()), Some(1202));
+        }
+
+        let requests = mock.requests();
+        let headers = |i: usize| {
+            (
+                requests[i].header(TRANSACTION_HEADER),
+                requests[i].header(DIRTY_READ_HEADER),
+            )
+        };
+        assert_eq!(headers(0), (None, None));
+        assert_eq!(headers(1), (Some("42"), None));
+        assert_eq!(headers(2), (None, Some("true")));
+        assert_eq!(requests[0].query.as_deref(), Some("onlyget=true"));
+        assert_eq!(
+            requests[2].query_param("ignoreRevs").as_deref(),
+            Some("false")
+        );
+        assert_eq!(requests[0].json_body().unwrap(), json!(["alice", "bob"]));
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_documents_in_order() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        let created = |key: &str| {
+            fixtures::DocumentFixture::new("users", key)
+                .new_doc(json!({ "_key": key, "name": key }))
+                .build()
+        };
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(
+                202,
+                json!([
+                    created("a"),
+                    {
+                        "error": true,
+                        "errorNum": 1210,
+                        "errorMessage": "unique constraint violated",
+                    },
+                    created("c"),
+                ]),
+            )
+            .with_header("x-arango-error-codes", r#"{"1210":1}"#),
+        );
+        let coll = users(&mock);
+        let docs: Vec<Value> = ["a", "b", "c"]
+            .iter()
+            .map(|key| json!({ "_key": key, "name": key }))
+            .collect();
+        let options = InsertOptions::builder().return_new(true).build();
+        let (results, meta) = coll
+            .create_documents_with_meta(docs.clone(), options)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        let created = results[0].as_ref().unwrap();
+        assert_eq!(created.header().unwrap()._key, "a");
+        assert_eq!(created.new_doc(), Some(&docs[0]));
+        assert!(matches!(&results[1], Err(e) if e.error_num() == 1210));
+        assert_eq!(results[2].as_ref().unwrap().new_doc(), Some(&docs[2]));
+        assert!(meta.error_summary().has_only(1210));
+        let failed: Vec<usize> = results.errors().map(|(i, _)| i).collect();
+        assert_eq!(failed, vec![1]);
+        assert_eq!(results.error_summary(), meta.error_summary());
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.query_param("returnNew").as_deref(), Some("true"));
+        assert_eq!(request.json_body().unwrap(), json!(docs));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_and_read_edge() {
+        let mock = MockClient::default();
+        let stored = json!({
+            "_id": "knows/ab", "_key": "ab", "_rev": "_r",
+            "_from": "users/alice", "_to": "users/bob", "since": 2020
+        });
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/knows/",
+            MockResponse::json(
+                202,
+                json!({ "_id": "knows/ab", "_key": "ab", "_rev": "_r", "new": stored }),
+            ),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/knows/ab",
+            MockResponse::json(200, stored),
+        );
+        let coll = edges(&mock);
+        let edge = EdgeDocument::try_new("users/alice", "users/bob", json!({ "since": 2020 }))
+            .unwrap()
+            .with_key("ab");
+        let options = InsertOptions::builder().return_new(true).build();
+        let created = coll.create_edge(edge.clone(), options).await.unwrap();
+        let new = created.new_doc().unwrap();
+        assert_eq!((&new._from, &new._to), (&edge._from, &edge._to));
+        assert_eq!(
+            mock.requests()[0].json_body().unwrap(),
+            json!({ "_key": "ab", "_from": "users/alice", "_to": "users/bob", "since": 2020 })
+        );
+
+        let read: EdgeDocument<Value> = coll.edge("ab").await.unwrap();
+        assert_eq!(read.header.document_id().unwrap().to_string(), "knows/ab");
+        assert_eq!(read._from, edge._from);
+        assert_eq!(read.document, edge.document);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn update_insert_options() {
+        use crate::document::options::OverwriteMode;
+
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.4")),
+        );
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(202, fixtures::DocumentFixture::new("users", "a").build()),
+        );
+        let coll = users(&mock);
+        let doc = json!({ "_key": "a", "email": null });
+        let options = InsertOptions::builder()
+            .overwrite_mode(OverwriteMode::Update)
+            .keep_null(false)
+            .merge_objects(false)
+            .build();
+        coll.create_document(doc.clone(), options).await.unwrap();
+        let request = &mock.requests()[1];
+        assert_eq!(
+            request.query_param("overwriteMode").as_deref(),
+            Some("update")
+        );
+        assert_eq!(request.query_param("keepNull").as_deref(), Some("false"));
+        assert_eq!(
+            request.query_param("mergeObjects").as_deref(),
+            Some("false")
+        );
+
+        // refused for any other insert
+        mock.clear_requests();
+        for options in [
+            InsertOptions::builder().keep_null(false).build(),
+            InsertOptions::builder()
+                .overwrite_mode(OverwriteMode::Replace)
+                .merge_objects(true)
+                .build(),
+        ] {
+            let result = coll.create_document(doc.clone(), options).await;
+            assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+        }
+        assert!(mock.requests().is_empty());
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn conflicting_return_options() {
+        use crate::document::options::OverwriteMode;
+
+        let mock = MockClient::default();
+        let coll = users(&mock);
+        let doc = || json!({ "_key": "a" });
+        let message = |result: Result<DocumentResponse<Value>, ClientError>| match result {
+            Err(ClientError::InvalidOptions(message)) => message,
+            Err(other) => panic!("expected invalid options, got {:?}", other),
+            Ok(_) => panic!("expected invalid options"),
+        };
+
+        let options = InsertOptions::builder()
+            .silent(true)
+            .return_new(true)
+            .build();
+        let error = message(coll.create_document(doc(), options).await);
+        assert!(
+            error.starts_with("return_new conflicts with silent"),
+            "{}",
+            error
+        );
+        for options in [
+            InsertOptions::builder().return_old(true).build(),
+            InsertOptions::builder()
+                .return_old(true)
+                .overwrite(false)
+                .build(),
+            InsertOptions::builder()
+                .return_old(true)
+                .overwrite(true)
+                .overwrite_mode(OverwriteMode::Ignore)
+                .build(),
+        ] {
+            let error = message(coll.create_document(doc(), options).await);
+            assert!(error.starts_with("return_old only applies"), "{}", error);
+        }
+
+        let options = UpdateOptions::builder()
+            .silent(true)
+            .return_old(true)
+            .build();
+        let error = message(coll.update_document("a", doc(), options).await);
+        assert!(
+            error.starts_with("return_old conflicts with silent"),
+            "{}",
+            error
+        );
+        let options = ReplaceOptions::builder()
+            .silent(true)
+            .return_new(true)
+            .build();
+        let result = coll.replace_document("a", doc(), options, None).await;
+        assert!(message(result).starts_with("return_new"));
+        let options = RemoveOptions::builder()
+            .silent(true)
+            .return_old(true)
+            .build();
+        let result = coll
+            .remove_documents::<Value>(vec!["a".to_owned()], options)
+            .await;
+        assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+        assert!(mock.requests().is_empty());
+
+        // still sent when the documents asked back are returned
+        let path = "/_db/test_db/_api/document/users/";
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(202, fixtures::DocumentFixture::new("users", "a").build()),
+        );
+        mock.once(Method::POST, path, MockResponse::json(202, json!({})));
+        let options = InsertOptions::builder()
+            .return_old(true)
+            .overwrite(true)
+            .build();
+        coll.create_document(doc(), options).await.unwrap();
+        let options = InsertOptions::builder()
+            .silent(true)
+            .return_new(false)
+            .build();
+        coll.create_document(doc(), options).await.unwrap();
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn version_gated_write_options() {
+        let path = "/_db/test_db/_api/document/users/";
+        let written =
+            || MockResponse::json(202, fixtures::DocumentFixture::new("users", "a").build());
+        let doc = || json!({ "_key": "a", "v": 2 });
+
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.9.1")),
+        );
+        mock.once(Method::POST, path, written());
+        let coll = users(&mock);
+        let options = InsertOptions::builder().refill_index_caches(true).build();
+        coll.create_document(doc(), options).await.unwrap();
+        assert_eq!(mock.requests()[1].query_param("refillIndexCaches"), None);
+        let options = UpdateOptions::builder().version_attribute("v").build();
+        let result = coll.update_document("a", doc(), options).await;
+        assert!(matches!(result, Err(ClientError::NotSupported { .. })));
+        assert_eq!(mock.requests().len(), 2);
+
+        // the option came with a patch release of 3.10
+        for (release, sent) in [("3.10.1", None), ("3.10.2", Some("true"))] {
+            let mock = MockClient::default();
+            mock.once(
+                Method::GET,
+                "/_api/version",
+                MockResponse::json(200, fixtures::version(release)),
+            );
+            mock.once(Method::POST, path, written());
+            let options = InsertOptions::builder().refill_index_caches(true).build();
+            users(&mock).create_document(doc(), options).await.unwrap();
+            let refill = mock.requests()[1].query_param("refillIndexCaches");
+            assert_eq!(refill.as_deref(), sent, "{}", release);
+        }
+
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.12.0")),
+        );
+        mock.once(Method::PUT, format!("{}a", path).as_str(), written());
+        mock.once(Method::DELETE, format!("{}a", path).as_str(), written());
+        let coll = users(&mock);
+        let options = ReplaceOptions::builder()
+            .refill_index_caches(true)
+            .version_attribute("v")
+            .build();
+        coll.replace_document("a", doc(), options, None)
+            .await
+            .unwrap();
+        let options = RemoveOptions::builder().refill_index_caches(false).build();
+        coll.remove_document::<Value>("a", options, None)
+            .await
+            .unwrap();
+        let requests = mock.requests();
+        assert_eq!(
+            requests[1].query_param("refillIndexCaches").as_deref(),
+            Some("true")
+        );
+        assert_eq!(
+            requests[1].query_param("versionAttribute").as_deref(),
+            Some("v")
+        );
+        assert_eq!(
+            requests[2].query_param("refillIndexCaches").as_deref(),
+            Some("false")
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn upsert_document_after_and_before_3_7() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.4")),
+        );
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(
+                201,
+                json!({ "_id": "users/a", "_key": "a", "_rev": "_b", "_oldRev": "_a" }),
+            ),
+        );
+        let coll = users(&mock);
+        let options = UpsertOptions::builder().keep_null(false).build();
+        let updated = coll
+            .upsert_document("a", json!({ "email": null }), options)
+            .await
+            .unwrap();
+        assert_eq!(updated.old_rev().map(String::as_str), Some("_a"));
+        let request = &mock.requests()[1];
+        assert_eq!(
+            request.query_param("overwriteMode").as_deref(),
+            Some("update")
+        );
+        assert_eq!(request.query_param("keepNull").as_deref(), Some("false"));
+        assert_eq!(
+            request.json_body().unwrap(),
+            json!({ "_key": "a", "email": null })
+        );
+
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.6.12")),
+        );
+        mock.expect_cursor(
+            "test_db",
+            vec![vec![json!({
+                "_id": "users/b",
+                "_key": "b",
+                "_rev": "_c",
+                "new": { "_key": "b", "name": "bob" },
+            })]],
+        );
+        let coll = users(&mock);
+        let options = UpsertOptions::builder().return_new(true).build();
+        let created = coll
+            .upsert_document("b", json!({ "name": "bob" }), options)
+            .await
+            .unwrap();
+        assert_eq!(created.old_rev(), None);
+        assert_eq!(created.new_doc().unwrap()["name"], "bob");
+        let body = mock.requests()[1].json_body().unwrap();
+        assert!(body["query"].as_str().unwrap().starts_with("UPSERT"));
+        assert_eq!(
+            body["bindVars"]["doc"],
+            json!({ "_key": "b", "name": "bob" })
+        );
+        assert_eq!(body["bindVars"]["returnNew"], true);
+        assert_eq!(body["bindVars"]["mergeObjects"], true);
+
+        mock.clear_requests();
+        for doc in [json!({ "_key": "c" }), json!([1])] {
+            let result = coll.upsert_document("b", doc, Default::default()).await;
+            assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+        }
+        assert!(mock.requests().is_empty());
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn overwrite_modes_before_3_7() {
+        use crate::document::options::OverwriteMode;
+
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.6.12")),
+        );
+        let path = "/_db/test_db/_api/document/users/";
+        let created =
+            || MockResponse::json(202, fixtures::DocumentFixture::new("users", "a").build());
+        mock.once(Method::POST, path, created());
+        mock.once(Method::POST, path, created());
+        let coll = users(&mock);
+        let doc = json!({ "_key": "a" });
+
+        // sent as the overwrite flag
+        for (mode, overwrite) in [
+            (OverwriteMode::Replace, "true"),
+            (OverwriteMode::Conflict, "false"),
+        ] {
+            let options = InsertOptions::builder().overwrite_mode(mode).build();
+            coll.create_document(doc.clone(), options).await.unwrap();
+            let requests = mock.requests();
+            let request = requests.last().unwrap();
+            assert_eq!(request.query_param("overwrite").as_deref(), Some(overwrite));
+            assert_eq!(request.query_param("overwriteMode"), None);
+        }
+
+        // refused without any request
+        mock.clear_requests();
+        let options = InsertOptions::builder()
+            .overwrite_mode(OverwriteMode::Update)
+            .keep_null(false)
+            .build();
+        let result = coll.create_document(doc.clone(), options).await;
+        assert!(matches!(result, Err(ClientError::NotSupported { .. })));
+        let options = InsertOptions::builder()
+            .overwrite_mode(OverwriteMode::Ignore)
+            .build();
+        let result = coll.create_documents(vec![doc], options).await;
+        assert!(matches!(result, Err(ClientError::NotSupported { .. })));
+        assert!(mock.requests().is_empty());
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn keys_in_document_paths() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/a%2541",
+            MockResponse::json(200, fixtures::document("users", "a%41", "_a", json!({}))),
+        );
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/document/users/id:1",
+            MockResponse::json(200, fixtures::DocumentFixture::new("users", "id:1").build()),
+        );
+        let coll = users(&mock);
+
+        let doc: Document<Value> = coll.document("a%41").await.unwrap();
+        assert_eq!(doc.header._key, "a%41");
+        coll.remove_document::<Value>("id:1", Default::default(), None)
+            .await
+            .unwrap();
+        mock.assert_no_pending();
+
+        mock.clear_requests();
+        let result = coll.document::<Value>("../../collection/users").await;
+        assert!(matches!(result, Err(ClientError::InvalidKey { .. })));
+        let result = coll
+            .update_document("a b", json!({}), Default::default())
+            .await;
+        assert!(matches!(result, Err(ClientError::InvalidKey { .. })));
+        // an empty key does not address the documents of the collection
+        let result = coll
+            .update_document("", json!({}), Default::default())
+            .await;
+        assert!(matches!(result, Err(ClientError::InvalidKey { .. })));
+        let result = coll
+            .replace_document("", json!({}), Default::default(), None)
+            .await;
+        assert!(matches!(result, Err(ClientError::InvalidKey { .. })));
+        assert!(mock.requests().is_empty());
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn read_document_raw() {
+        let mock = MockClient::default();
+        let body = r#"{"_id":"users/alice","_key":"alice","_rev":"_a","firstName":"Alice"}"#;
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/alice",
+            MockResponse::new(200)
+                .with_header("content-type", "application/json")
+                .with_body(body),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/bob",
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        let coll = users(&mock).with_field_case(Case::Camel);
+
+        let (header, raw) = coll.read_document_raw("alice").await.unwrap();
+        assert_eq!(header._rev.as_deref(), Some("_a"));
+        assert_eq!(raw.get(), body);
+        let missing = coll.read_document_raw("bob").await;
+        assert!(matches!(missing, Err(ClientError::Arango(e)) if e.is_document_not_found()));
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn document_exists() {
+        let mock = MockClient::default();
+        let document = |key: &str| format!("/_db/test_db/_api/document/users/{}", key);
+        mock.once(
+            Method::HEAD,
+            &document("alice"),
+            MockResponse::new(200).with_header("etag", "\"_a\""),
+        );
+        mock.once(Method::HEAD, &document("bob"), MockResponse::new(404));
+        mock.once(Method::HEAD, &document("carol"), MockResponse::new(503));
+        let coll = users(&mock);
+
+        let found = coll.document_exists("alice").await.unwrap();
+        assert!(found);
+        let missing = coll.document_exists("bob").await.unwrap();
+        assert!(!missing);
+        let unavailable = coll.document_exists("carol").await;
+        assert!(unavailable.is_err());
+        let requests = mock.requests();
+        assert!(requests.iter().all(|r| r.method == Method::HEAD));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn read_document_outcomes() {
+        let mock = MockClient::default();
+        let document = "/_db/test_db/_api/document/users/alice";
+        mock.once(
+            Method::GET,
+            document,
+            MockResponse::json(
+                200,
+                fixtures::document("users", "alice", "_a", json!({ "age": 30 })),
+            ),
+        );
+        mock.once(Method::GET, document, MockResponse::new(304));
+        mock.once(
+            Method::GET,
+            document,
+            MockResponse::json(412, fixtures::error(412, 1200, "precondition failed"))
+                .with_header("etag", "\"_a\""),
+        );
+        let coll = users(&mock);
+
+        let options = || DocumentReadOptions::builder().if_none_match("_0").build();
+        match coll
+            .read_document::<Value>("alice", options())
+            .await
+            .unwrap()
+        {
+            DocumentLookup::Found(doc) => assert_eq!(doc.document["age"], 30),
+            DocumentLookup::NotModified => panic!("the document changed"),
+        }
+        let unchanged = coll.read_document::<Value>("alice", options()).await;
+        assert!(matches!(unchanged, Ok(DocumentLookup::NotModified)));
+        let options = DocumentReadOptions::builder().if_match("_0").build();
+        let mismatch = coll.read_document::<Value>("alice", options).await;
+        assert!(matches!(
+            mismatch,
+            Err(ClientError::PreconditionFailed { current_rev: Some(rev), .. }) if rev == "_a"
+        ));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn read_document_header_outcomes() {
+        let mock = MockClient::default();
+        let document = "/_db/test_db/_api/document/users/a";
+        let etag = |status| MockResponse::new(status).with_header("etag", "\"_rev2\"");
+        mock.once(Method::HEAD, document, etag(200));
+        mock.once(Method::HEAD, document, MockResponse::new(304));
+        mock.once(Method::HEAD, document, etag(412));
+        mock.once(
+            Method::HEAD,
+            "/_db/test_db/_api/document/users/b",
+            MockResponse::new(404),
+        );
+        mock.once(Method::HEAD, document, MockResponse::new(200));
+        let coll = users(&mock);
+
+        let found = coll.read_document_header("a", Default::default()).await;
+        assert_eq!(
+            found.unwrap(),
+            HeaderLookup::Found(Header {
+                _id: Some("users/a".to_owned()),
+                _key: "a".to_owned(),
+                _rev: Some("_rev2".to_owned()),
+            })
+        );
+        let options = DocumentReadOptions::builder()
+            .if_none_match("_rev2")
+            .build();
+        let not_modified = coll.read_document_header("a", options).await;
+        assert_eq!(not_modified.unwrap(), HeaderLookup::NotModified);
+        let options = DocumentReadOptions::builder().if_match("_rev1").build();
+        let mismatch = coll.read_document_header("a", options).await;
+        assert_eq!(
+            mismatch.unwrap(),
+            HeaderLookup::PreconditionFailed {
+                current_rev: "_rev2".to_owned()
+            }
+        );
+        let missing = coll.read_document_header("b", Default::default()).await;
+        assert_eq!(missing.unwrap(), HeaderLookup::NotFound);
+        let no_etag = coll.read_document_header("a", Default::default()).await;
+        assert!(matches!(no_etag, Err(ClientError::MalformedResponse(_))));
+
+        let requests = mock.requests();
+        assert_eq!(requests[1].header("if-none-match"), Some("_rev2"));
+        assert_eq!(requests[2].header("if-match"), Some("_rev1"));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn document_errors_by_number() {
+        let mock = MockClient::default();
+        let document = "/_db/test_db/_api/document/users/a";
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(
+                409,
+                fixtures::error(409, 1210, "unique constraint violated"),
+            ),
+        );
+        mock.once(
+            Method::GET,
+            document,
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        mock.once(
+            Method::PATCH,
+            document,
+            MockResponse::json(
+                412,
+                fixtures::error(412, 1200, "conflict, _rev values do not match"),
+            ),
+        );
+        let coll = users(&mock);
+
+        let created = coll
+            .create_document(json!({ "_key": "a" }), Default::default())
+            .await;
+        assert!(matches!(
+            created,
+            Err(ClientError::Arango(e)) if e.is_unique_constraint_violated() && e.code() == 409
+        ));
+        let read = coll.document::<Value>("a").await;
+        assert!(matches!(
+            read,
+            Err(ClientError::Arango(e)) if e.is_document_not_found() && e.code() == 404
+        ));
+        let options = UpdateOptions::builder().ignore_revs(false).build();
+        let updated = coll
+            .update_document("a", json!({ "_rev": "1" }), options)
+            .await;
+        assert!(matches!(
+            updated,
+            Err(ClientError::PreconditionFailed { source, .. })
+                if source.is_conflict() && source.code() == 412
+        ));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn revision_preconditions() {
+        let mock = MockClient::default();
+        let document = "/_db/test_db/_api/document/users/a";
+        let failed = |current: &str| {
+            MockResponse::json(412, fixtures::error(412, 1200, "precondition failed"))
+                .with_header("etag", format!("\"{}\"", current))
+        };
+        let done = || MockResponse::json(202, fixtures::DocumentFixture::new("users", "a").build());
+        mock.once(Method::PATCH, document, failed("_rev2"));
+        mock.once(Method::PUT, document, done());
+        // a compare-and-delete losing once to a concurrent write
+        mock.once(Method::DELETE, document, failed("_rev3"));
+        mock.once(Method::DELETE, document, done());
+        let coll = users(&mock);
+
+        let options = UpdateOptions::builder().if_match("_rev1").build();
+        let updated = coll.update_document("a", json!({ "n": 1 }), options).await;
+        assert!(matches!(
+            updated,
+            Err(ClientError::PreconditionFailed { current_rev: Some(rev), .. }) if rev == "_rev2"
+        ));
+        let options = ReplaceOptions::builder().if_match("_rev2").build();
+        coll.replace_document("a", json!({ "n": 1 }), options, None)
+            .await
+            .unwrap();
+
+        let mut rev = "_rev2".to_owned();
+        loop {
+            let options = RemoveOptions::builder().if_match(rev.clone()).build();
+            match coll.remove_document::<Value>("a", options, None).await {
+                Ok(_) => break,
+                Err(ClientError::PreconditionFailed {
+                    current_rev: Some(current),
+                    ..
+                }) => rev = current,
+                Err(e) => panic!("{}", e),
+            }
+        }
+        let requests = mock.requests();
+        let if_match: Vec<_> = requests.iter().map(|r| r.header("if-match")).collect();
+        assert_eq!(
+            if_match,
+            [Some("_rev1"), Some("_rev2"), Some("_rev2"), Some("_rev3")]
+        );
+
+        // refused without any request
+        mock.clear_requests();
+        let options = || RemoveOptions::builder().if_match("_rev3").build();
+        let both = coll
+            .remove_document::<Value>("a", options(), Some("_rev3".to_owned()))
+            .await;
+        assert!(matches!(both, Err(ClientError::InvalidOptions(_))));
+        let batch = coll
+            .remove_documents::<Value>(vec!["a".to_owned()], options())
+            .await;
+        assert!(matches!(batch, Err(ClientError::InvalidOptions(_))));
+        let options = UpdateOptions::builder().if_match("_rev3").build();
+        let batch = coll
+            .update_documents::<Value, _>(vec![json!({ "_key": "a" })], options)
+            .await;
+        assert!(matches!(batch, Err(ClientError::InvalidOptions(_))));
+        assert!(mock.requests().is_empty());
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_documents_silently() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        mock.once(Method::POST, path, MockResponse::json(202, json!([])));
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(
+                202,
+                json!([{
+                    "error": true,
+                    "errorNum": 1210,
+                    "errorMessage": "unique constraint violated",
+                }]),
+            ),
+        );
+        let coll = users(&mock);
+        let docs = || vec![json!({ "_key": "a" }), json!({ "_key": "b" })];
+        let silent = || InsertOptions::builder().silent(true).build();
+        let results = coll.create_documents(docs(), silent()).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.as_ref().unwrap().is_silent()));
+
+        // the failed document cannot be told apart
+        let result = coll.create_documents(docs(), silent()).await;
+        assert!(
+            matches!(&result, Err(ClientError::Arango(e)) if e.error_num() == 1210),
+            "{:?}",
+            result.map(|r| r.len())
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_with_explicit_key() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct User {
+            name: String,
+        }
+
+        let mock = MockClient::default();
+        let alice = fixtures::DocumentFixture::new("users", "alice").build();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(202, alice),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/alice",
+            MockResponse::json(
+                200,
+                fixtures::document("users", "alice", "_a", json!({ "name": "Alice" })),
+            ),
+        );
+        let coll = users(&mock);
+        let user = || User {
+            name: "Alice".to_owned(),
+        };
+        let created = coll
+            .create_document(Document::with_key("alice", user()), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(created.header().unwrap()._key, "alice");
+        assert_eq!(
+            mock.requests()[0].json_body().unwrap(),
+            json!({ "_key": "alice", "name": "Alice" })
+        );
+
+        let read: Document<User> = coll.document("alice").await.unwrap();
+        assert_eq!(read.header._id.as_deref(), Some("users/alice"));
+        assert_eq!(read.document, user());
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn insert_with_autoincrement_keys() {
+        use crate::collection::options::{CreateOptions, KeyGenerator, KeyOptions};
+
+        let mock = MockClient::default();
+        let mut body = fixtures::collection_properties("users", "100");
+        body["keyOptions"] = json!({
+            "type": "autoincrement",
+            "allowUserKeys": false,
+            "increment": 5,
+            "offset": 10,
+            "lastValue": 0,
+        });
+        body["waitForSync"] = json!(false);
+        body["writeConcern"] = json!(1);
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/collection",
+            MockResponse::json(200, body),
+        );
+        let generated = fixtures::DocumentFixture::new("users", "15").build();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(202, generated),
+        );
+        let generator = KeyGenerator::Autoincrement {
+            increment: Some(5),
+            offset: Some(10),
+        };
+        let options = CreateOptions::builder()
+            .name("users")
+            .key_options(
+                KeyOptions::builder()
+                    .allow_user_keys(false)
+                    .generator(generator.clone())
+                    .build(),
+            )
+            .build();
+        let (coll, properties) = mock
+            .database("test_db")
+            .create_collection_with_properties(options, Default::default())
+            .await
+            .unwrap();
+        assert_eq!(properties.detail.key_options.generator, generator);
+        assert!(!properties.detail.key_options.allow_user_keys);
+        assert_eq!(properties.detail.key_options.last_value, Some(0));
+
+        let created = coll
+            .create_document(json!({ "name": "Alice" }), Default::default())
+            .await
+            .unwrap();
+        let key = &created.header().unwrap()._key;
+        assert_eq!(key.parse::<u64>().ok(), Some(15));
+
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].json_body().unwrap()["keyOptions"],
+            json!({
+                "allowUserKeys": false,
+                "type": "autoincrement",
+                "increment": 5,
+                "offset": 10,
+            })
+        );
+        assert_eq!(requests[1].json_body().unwrap(), json!({ "name": "Alice" }));
+        mock.assert_no_pending();
+    }
+
+    #[test]
+    fn key_generators_round_trip() {
+        use crate::collection::options::{KeyGenerator, KeyOptions};
+
+        let padded: KeyOptions = serde_json::from_value(json!({
+            "type": "padded",
+            "allowUserKeys": true,
+            "lastValue": 7,
+        }))
+        .unwrap();
+        assert_eq!(padded.generator, KeyGenerator::Padded);
+        assert_eq!(padded.last_value, Some(7));
+        let uuid: KeyOptions =
+            serde_json::from_value(json!({ "type": "uuid", "allowUserKeys": false })).unwrap();
+        assert_eq!(uuid.generator, KeyGenerator::Uuid);
+        assert!(!uuid.allow_user_keys);
+
+        let options = KeyOptions::builder().generator(KeyGenerator::Uuid).build();
+        assert_eq!(
+            serde_json::to_value(&options).unwrap(),
+            json!({ "type": "uuid" })
+        );
+        assert_eq!(
+            serde_json::to_value(KeyOptions::default()).unwrap(),
+            json!({ "type": "traditional" })
+        );
+    }
+
+    #[cfg(feature = "rocksdb")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn recalculate_count_and_compact() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/collection/users/recalculateCount",
+            MockResponse::json(
+                200,
+                json!({ "error": false, "code": 200, "result": true, "count": 5_000_000_000u64 }),
+            ),
+        );
+        let mut compacted = fixtures::collection_info("users", "100");
+        compacted["error"] = json!(false);
+        compacted["code"] = json!(200);
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/collection/users/compact",
+            MockResponse::json(200, compacted),
+        );
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/collection/users/compact",
+            MockResponse::json(501, fixtures::error(501, 9, "not implemented")),
+        );
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/collection/users/recalculateCount",
+            MockResponse::json(404, fixtures::error(404, 1203, "collection not found")),
+        );
+        let coll = users(&mock);
+
+        let count = coll.recalculate_count().await.unwrap();
+        assert_eq!(count, 5_000_000_000);
+        coll.compact().await.unwrap();
+        let unsupported = coll.compact().await;
+        assert!(matches!(
+            unsupported,
+            Err(ClientError::NotSupported { ref operation }) if operation == "compact a collection"
+        ));
+        // a missing collection is not an unsupported operation
+        let missing = coll.recalculate_count().await;
+        assert!(matches!(missing, Err(ClientError::Arango(_))));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn patch_with_json() {
+        let mock = MockClient::default();
+        let patched = json!({ "status": "archived", "meta": { "by": "bob" }, "last_seen": 1 });
+        mock.once(
+            Method::PATCH,
+            "/_db/test_db/_api/document/users/a",
+            MockResponse::json(
+                202,
+                fixtures::DocumentFixture::new("users", "a")
+                    .new_doc(patched.clone())
+                    .build(),
+            ),
+        );
+        // the patch is sent as it is, without renaming its attributes
+        let coll = users(&mock).with_field_case(Case::Camel);
+        let patch = json!({ "status": "archived", "note": null, "meta": { "by": "bob" } });
+        let options = UpdateOptions::builder()
+            .keep_null(false)
+            .merge_objects(false)
+            .return_new(true)
+            .build();
+        let updated = coll
+            .patch_document("a", patch.clone(), options)
+            .await
+            .unwrap();
+        assert_eq!(updated.new_doc(), Some(&patched));
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.json_body().unwrap(), patch);
+        assert_eq!(request.query_param("keepNull").as_deref(), Some("false"));
+        assert_eq!(
+            request.query_param("mergeObjects").as_deref(),
+            Some("false")
+        );
+
+        for patch in [json!(["status"]), json!("archived"), Value::Null] {
+            let refused = coll.patch_document("a", patch, Default::default()).await;
+            assert!(matches!(refused, Err(ClientError::InvalidPatch(_))));
+        }
+        assert_eq!(mock.requests().len(), 1);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn modify_document_until_no_conflict() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/a";
+        let read = |rev: &str, visits: u64| {
+            MockResponse::json(
+                200,
+                fixtures::document("users", "a", rev, json!({ "visits": visits })),
+            )
+        };
+        let conflict = || MockResponse::json(409, fixtures::error(409, 1200, "conflict"));
+        mock.once(Method::GET, path, read("_a", 1));
+        mock.once(
+            Method::PUT,
+            path,
+            MockResponse::json(412, fixtures::error(412, 1200, "precondition failed"))
+                .with_header("etag", "\"_b\""),
+        );
+        mock.once(Method::GET, path, read("_b", 2));
+        mock.once(Method::PUT, path, conflict());
+        mock.once(Method::GET, path, read("_c", 3));
+        mock.once(
+            Method::PUT,
+            path,
+            MockResponse::json(
+                201,
+                json!({ "_id": "users/a", "_key": "a", "_rev": "_d", "_oldRev": "_c" }),
+            ),
+        );
+        let coll = users(&mock);
+        let bump = |doc: &mut Value| doc["visits"] = json!(doc["visits"].as_u64().unwrap() + 1);
+        let modified = coll.modify_document("a", bump, 2).await.unwrap();
+        assert_eq!(modified.attempts, 3);
+        assert_eq!(modified.header._rev.as_deref(), Some("_d"));
+        assert_eq!(modified.document["visits"], 4);
+
+        let requests = mock.requests();
+        let writes: Vec<_> = requests
+            .iter()
+            .filter(|r| r.method == Method::PUT)
+            .map(|r| r.header("if-match"))
+            .collect();
+        assert_eq!(writes, vec![Some("_a"), Some("_b"), Some("_c")]);
+        assert_eq!(requests[5].json_body().unwrap()["visits"], 4);
+        mock.assert_no_pending();
+
+        // the last conflict is returned once the retries are used up
+        mock.once(Method::GET, path, read("_d", 4));
+        mock.once(Method::PUT, path, conflict());
+        let result = coll.modify_document("a", bump, 0).await;
+        assert!(matches!(result, Err(ClientError::Arango(e)) if e.is_conflict()));
+        mock.assert_no_pending();
+        mock.clear_requests();
+
+        // without a revision to check, the document is not replaced
+        mock.once(
+            Method::GET,
+            path,
+            MockResponse::json(200, json!({ "_id": "users/a", "_key": "a", "visits": 5 })),
+        );
+        let mut called = false;
+        let result = coll
+            .modify_document("a", |_: &mut Value| called = true, 2)
+            .await;
+        assert!(
+            matches!(result, Err(ClientError::MalformedResponse(_))),
+            "{:?}",
+            result
+        );
+        assert!(!called);
+        assert_eq!(mock.requests().len(), 1);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn silent_writes() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/a";
+        // the body of a successful silent write is not read
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::new(202).with_body("not json"),
+        );
+        mock.once(Method::PATCH, path, MockResponse::json(202, json!({})));
+        mock.once(
+            Method::PUT,
+            path,
+            MockResponse::json(412, fixtures::error(412, 1200, "conflict"))
+                .with_header("etag", "\"_b\""),
+        );
+        mock.once(
+            Method::DELETE,
+            path,
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        let coll = users(&mock);
+        let doc = json!({ "_key": "a", "name": "alice" });
+        coll.create_document_silent(&doc, Default::default())
+            .await
+            .unwrap();
+        coll.update_document_silent("a", json!({ "seen": true }), Default::default())
+            .await
+            .unwrap();
+        let replaced = coll
+            .replace_document_silent("a", &doc, Default::default(), Some("_a".to_owned()))
+            .await;
+        assert!(matches!(
+            replaced,
+            Err(ClientError::PreconditionFailed { current_rev: Some(ref rev), .. }) if rev == "_b"
+        ));
+        let removed = coll
+            .remove_document_silent("a", Default::default(), None)
+            .await;
+        assert!(matches!(removed, Err(ClientError::Arango(ref e)) if e.is_document_not_found()));
+        for request in mock.requests() {
+            assert_eq!(request.query_param("silent").as_deref(), Some("true"));
+        }
+
+        let options = InsertOptions::builder().return_new(true).build();
+        let refused = coll.create_document_silent(&doc, options).await;
+        assert!(matches!(refused, Err(ClientError::InvalidOptions(_))));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    fn duplicate() -> Value {
+        json!({
+            "error": true,
+            "errorNum": 1210,
+            "errorMessage": "unique constraint violated",
+        })
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_documents_by_chunks() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        let created = |key: &str| fixtures::DocumentFixture::new("users", key).build();
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(202, json!([created("a"), duplicate()]))
+                .with_header("x-arango-error-codes", r#"{"1210":1}"#),
+        );
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(202, json!([duplicate(), created("d")]))
+                .with_header("x-arango-error-codes", r#"{"1210":1}"#),
+        );
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(201, json!([created("e")])),
+        );
+        let coll = users(&mock);
+        let docs = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|key| json!({ "_key": key }));
+        let report = coll
+            .create_documents_chunked(docs, 2, Default::default())
+            .await
+            .unwrap();
+        assert_eq!(report.sent, 5);
+        assert_eq!(report.created, 3);
+        assert_eq!(report.rejected, 2);
+        assert_eq!(report.errors.count(1210), 2);
+        let positions: Vec<_> = report.failures.iter().map(|(at, _)| *at).collect();
+        assert_eq!(positions, vec![Some(1), Some(2)]);
+        assert!(report.failures.iter().all(|(_, e)| e.error_num() == 1210));
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[1].json_body().unwrap(),
+            json!([{ "_key": "c" }, { "_key": "d" }])
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_documents_by_chunks_until_a_chunk_fails() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        // a silent insert only returns the errors
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(202, json!([duplicate()])),
+        );
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(404, fixtures::error(404, 1203, "collection not found")),
+        );
+        let coll = users(&mock);
+        let docs = (0..6).map(|i| json!({ "_key": i.to_string() }));
+        let options = InsertOptions::builder().silent(true).build();
+        let result = coll.create_documents_chunked(docs, 2, options).await;
+        assert!(matches!(result, Err(ClientError::Arango(e)) if e.error_num() == 1203));
+        // the last chunk is not sent
+        assert_eq!(mock.requests().len(), 2);
+        mock.assert_no_pending();
+
+        let docs = (0..2).map(|i| json!({ "_key": i.to_string() }));
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(202, json!([duplicate()])),
+        );
+        let options = InsertOptions::builder().silent(true).build();
+        let report = coll
+            .create_documents_chunked(docs, 2, options)
+            .await
+            .unwrap();
+        assert_eq!((report.created, report.rejected), (1, 1));
+        assert_eq!(report.failures[0].0, None);
+    }
+
+    #[cfg(all(not(feature = "blocking"), feature = "documents"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shared_between_tasks() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_db/app/_api/collection/users",
+            MockResponse::json(200, fixtures::collection_info("users", "1")),
+        );
+        mock.on(
+            Method::POST,
+            "/_db/app/_api/document/users/",
+            MockResponse::json(202, fixtures::DocumentFixture::new("users", "k").build()),
+        );
+        for i in 0..16 {
+            let doc = fixtures::document("users", &i.to_string(), "r", json!({ "n": i }));
+            mock.on(
+                Method::GET,
+                &format!("/_db/app/_api/document/users/{}", i),
+                MockResponse::json(200, doc),
+            );
+        }
+
+        let users = Arc::new(mock.database("app").collection("users").await.unwrap());
+        let tasks: Vec<_> = (0..16)
+            .map(|i| {
+                let users = users.clone();
+                tokio::spawn(async move {
+                    users
+                        .create_document(json!({ "n": i }), Default::default())
+                        .await
+                        .unwrap();
+                    let doc = users.document::<Value>(&i.to_string()).await.unwrap();
+                    assert_eq!(doc.document["n"], i);
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(mock.requests().len(), 33);
+    }
+
+    #[cfg(all(not(feature = "blocking"), feature = "documents"))]
+    #[tokio::test]
+    async fn create_documents_from_stream_by_chunks() {
+        use futures_util::StreamExt;
+
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        for keys in &[["a", "b"], ["c", "d"]] {
+            let results: Vec<Value> = keys
+                .iter()
+                .map(|key| fixtures::DocumentFixture::new("users", key).build())
+                .collect();
+            mock.once(Method::POST, path, MockResponse::json(202, json!(results)));
+        }
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(202, json!([duplicate()])),
+        );
+        let coll = users(&mock);
+        let docs = futures_util::stream::iter(["a", "b", "c", "d", "a"].iter())
+            .map(|key| json!({ "_key": key }));
+        let report = coll
+            .create_documents_chunked_stream(docs, 2, Default::default())
+            .await
+            .unwrap();
+        assert_eq!((report.sent, report.created, report.rejected), (5, 4, 1));
+        assert_eq!(report.failures[0].0, Some(4));
+        assert_eq!(
+            mock.requests()[2].json_body().unwrap(),
+            json!([{ "_key": "a" }])
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn update_documents_in_order() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        mock.once(
+            Method::PATCH,
+            path,
+            MockResponse::json(
+                202,
+                json!([
+                    fixtures::DocumentFixture::new("users", "a")
+                        .new_doc(json!({ "_key": "a", "name": "alice" }))
+                        .build(),
+                    { "error": true, "errorNum": 1200, "errorMessage": "conflict" },
+                    { "error": true, "errorNum": 1221, "errorMessage": "illegal document key" },
+                ]),
+            )
+            .with_header("x-arango-error-codes", r#"{"1200":1,"1221":1}"#),
+        );
+        let coll = users(&mock);
+        let patches = vec![
+            json!({ "_key": "a", "name": "alice" }),
+            json!({ "_key": "b", "_rev": "1", "name": "bob" }),
+            json!({ "name": "carol" }),
+        ];
+        let options = UpdateOptions::builder()
+            .keep_null(false)
+            .merge_objects(false)
+            .ignore_revs(false)
+            .return_new(true)
+            .return_old(true)
+            .build();
+        let (results, meta) = coll
+            .update_documents_with_meta::<Value, _>(patches.clone(), options)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().unwrap().new_doc(),
+            Some(&json!({ "_key": "a", "name": "alice" }))
+        );
+        assert!(matches!(&results[1], Err(e) if e.error_num() == 1200));
+        assert!(matches!(&results[2], Err(e) if e.error_num() == 1221));
+        assert_eq!(meta.error_summary().total_errors(), 2);
+
+        let request = &mock.requests()[0];
+        for (param, value) in [
+            ("keepNull", "false"),
+            ("mergeObjects", "false"),
+            ("ignoreRevs", "false"),
+            ("returnNew", "true"),
+            ("returnOld", "true"),
+        ] {
+            assert_eq!(request.query_param(param).as_deref(), Some(value));
+        }
+        assert_eq!(request.json_body().unwrap(), json!(patches));
+        mock.assert_no_pending();
+
+        // the whole batch fails on a missing collection
+        mock.once(
+            Method::PATCH,
+            path,
+            MockResponse::json(404, fixtures::error(404, 1203, "collection not found")),
+        );
+        let result = coll
+            .update_documents::<Value, _>(vec![json!({ "_key": "a" })], Default::default())
+            .await;
+        assert!(matches!(result, Err(ClientError::Arango(e)) if e.error_num() == 1203));
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn replace_documents_in_order() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(
+                202,
+                json!([
+                    fixtures::DocumentFixture::new("users", "a")
+                        .rev("_a2")
+                        .old_rev("_a1")
+                        .old_doc(json!({ "_key": "a", "name": "alice" }))
+                        .build(),
+                    { "error": true, "errorNum": 1200, "errorMessage": "conflict" },
+                    fixtures::DocumentFixture::new("users", "c")
+                        .rev("_c2")
+                        .old_rev("_c1")
+                        .build(),
+                ]),
+            )
+            .with_header("x-arango-error-codes", r#"{"1200":1}"#),
+        );
+        let coll = users(&mock);
+        let docs = vec![
+            json!({ "_key": "a", "name": "Alice" }),
+            json!({ "_key": "b", "_rev": "_b0", "name": "Bob" }),
+            json!({ "_key": "c", "name": "Carol" }),
+        ];
+        let options = ReplaceOptions::builder()
+            .ignore_revs(false)
+            .return_old(true)
+            .build();
+        let (results, meta) = coll
+            .replace_documents_with_meta(docs.clone(), options)
+            .await
+            .unwrap();
+        let old_revs: Vec<Option<&str>> = results
+            .iter()
+            .map(|r| {
+                r.as_ref()
+                    .ok()
+                    .and_then(|d| d.old_rev())
+                    .map(String::as_str)
+            })
+            .collect();
+        assert_eq!(old_revs, vec![Some("_a1"), None, Some("_c1")]);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.header().unwrap()._rev.as_deref(), Some("_a2"));
+        assert_eq!(
+            first.old_doc(),
+            Some(&json!({ "_key": "a", "name": "alice" }))
+        );
+        assert!(matches!(&results[1], Err(e) if e.error_num() == 1200));
+        assert_eq!(meta.error_summary().total_errors(), 1);
+
+        let request = &mock.requests()[0];
+        assert_eq!(request.query_param("ignoreRevs").as_deref(), Some("false"));
+        assert_eq!(request.query_param("returnOld").as_deref(), Some("true"));
+        assert_eq!(request.json_body().unwrap(), json!(docs));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn remove_documents_in_order() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/document/users/";
+        mock.once(
+            Method::DELETE,
+            path,
+            MockResponse::json(
+                200,
+                json!([
+                    { "error": true, "errorNum": 1202, "errorMessage": "document not found" },
+                    fixtures::DocumentFixture::new("users", "b")
+                        .old_doc(json!({ "_key": "b", "name": "bob" }))
+                        .build(),
+                ]),
+            )
+            .with_header("x-arango-error-codes", r#"{"1202":1}"#),
+        );
+        let coll = users(&mock);
+        let options = RemoveOptions::builder().return_old(true).build();
+        let (results, meta) = coll
+            .remove_documents_with_meta::<Value>(vec!["a".to_owned(), "b".to_owned()], options)
+            .await
+            .unwrap();
+        assert!(matches!(&results[0], Err(e) if e.error_num() == 1202));
+        assert_eq!(
+            results[1].as_ref().unwrap().old_doc(),
+            Some(&json!({ "_key": "b", "name": "bob" }))
+        );
+        assert_eq!(meta.error_summary().total_errors(), 1);
+        let request = &mock.requests()[0];
+        assert_eq!(request.query_param("returnOld").as_deref(), Some("true"));
+        assert_eq!(request.json_body().unwrap(), json!(["a", "b"]));
+
+        // revisions are checked with ignore_revs off
+        mock.clear_requests();
+        mock.once(
+            Method::DELETE,
+            path,
+            MockResponse::json(
+                202,
+                json!([
+                    fixtures::DocumentFixture::new("users", "a").build(),
+                    { "error": true, "errorNum": 1200, "errorMessage": "conflict" },
+                ]),
+            ),
+        );
+        let headers = vec![
+            Header {
+                _rev: Some("_a1".to_owned()),
+                ..Header::with_key("a")
+            },
+            Header::with_key("b"),
+        ];
+        let options = RemoveOptions::builder().ignore_revs(false).build();
+        let results = coll
+            .remove_documents_by_header::<Value>(headers, options)
+            .await
+            .unwrap();
+        assert!(results[0].as_ref().unwrap().has_response());
+        assert!(matches!(&results[1], Err(e) if e.error_num() == 1200));
+        let request = &mock.requests()[0];
+        assert_eq!(request.query_param("ignoreRevs").as_deref(), Some("false"));
+        assert_eq!(
+            request.json_body().unwrap(),
+            json!([{ "_key": "a", "_rev": "_a1" }, { "_key": "b" }])
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn bulk_error_summary() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(
+                200,
+                json!([
+                    { "error": true, "errorNum": 1202, "errorMessage": "document not found" },
+                    { "error": true, "errorNum": 1202, "errorMessage": "document not found" },
+                ]),
+            )
+            .with_header("x-arango-error-codes", r#"{"1202":2}"#),
+        );
+        let coll = users(&mock);
+        let (docs, meta) = coll
+            .read_documents_with_meta::<_, Value>(&["a", "b"], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(docs.len(), 2);
+        let summary = meta.error_summary();
+        assert!(summary.has_only(1202));
+        assert_eq!(summary.total_errors(), 2);
+
+        let path = "/_db/test_db/_api/import";
+        mock.once(
+            Method::POST,
+            path,
+            imported(1, 1, vec![]).with_header("x-arango-error-codes", r#"{"1210":1}"#),
+        );
+        mock.once(Method::POST, path, imported(2, 0, vec![]));
+        mock.once(
+            Method::POST,
+            path,
+            imported(0, 1, vec![]).with_header("x-arango-error-codes", r#"{"1221":1}"#),
+        );
+        let docs = (0..5).map(|i| json!({ "_key": i.to_string() }));
+        let options = ImportOptions::builder().batch_size(2).build();
+        let mut totals = Vec::new();
+        let progress = coll
+            .import_documents(docs, options, |p| totals.push(p.errors.total_errors()))
+            .await
+            .unwrap();
+        assert_eq!(totals, vec![1, 1, 2]);
+        assert_eq!(progress.rejected, progress.errors.total_errors());
+        assert_eq!(
+            progress.errors.to_string(),
+            "2 errors: 1 x 1210 (unique constraint violated), 1 x 1221 (illegal document key)"
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    fn conflict(key: Option<&str>) -> MockResponse {
+        let mut message = "unique constraint violated - in index primary of type primary over \
+                           '_key'"
+            .to_owned();
+        if let Some(key) = key {
+            message.push_str("; conflicting key: ");
+            message.push_str(key);
+        }
+        MockResponse::json(409, fixtures::error(409, 1210, &message))
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn insert_or_get() {
+        let mock = MockClient::default();
+        let insert = "/_db/test_db/_api/document/users/";
+        mock.once(
+            Method::POST,
+            insert,
+            MockResponse::json(
+                201,
+                fixtures::DocumentFixture::new("users", "alice").build(),
+            ),
+        );
+        let coll = users(&mock);
+        let result = coll
+            .insert_or_get(json!({ "_key": "alice", "age": 30 }))
+            .await
+            .unwrap();
+        assert!(matches!(result, InsertOrGet::Inserted(ref h) if h._key == "alice"));
+
+        // the key reported by the server wins, e.g. for a secondary index
+        mock.once(Method::POST, insert, conflict(Some("bob")));
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/bob",
+            MockResponse::json(
+                200,
+                fixtures::document("users", "bob", "_b", json!({ "email": "b@x" })),
+            ),
+        );
+        let result = coll.insert_or_get(json!({ "email": "b@x" })).await.unwrap();
+        match result {
+            InsertOrGet::Existing(doc) => assert_eq!(doc.header._key, "bob"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // otherwise the key of the document
+        mock.once(Method::POST, insert, conflict(None));
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/carol",
+            MockResponse::json(200, fixtures::document("users", "carol", "_c", json!({}))),
+        );
+        let result = coll
+            .insert_or_get(json!({ "_key": "carol" }))
+            .await
+            .unwrap();
+        assert!(matches!(result, InsertOrGet::Existing(_)));
+
+        // without any key the conflict is returned
+        mock.once(Method::POST, insert, conflict(None));
+        let result = coll.insert_or_get(json!({ "email": "d@x" })).await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1210),
+            "{:?}",
+            result
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn insert_or_get_when_existing_is_removed() {
+        let mock = MockClient::default();
+        let insert = "/_db/test_db/_api/document/users/";
+        let read = "/_db/test_db/_api/document/users/alice";
+        let not_found =
+            || MockResponse::json(404, fixtures::error(404, 1202, "document not found"));
+        // removed between the failed insert and the read, then inserted
+        mock.once(Method::POST, insert, conflict(Some("alice")));
+        mock.once(Method::GET, read, not_found());
+        mock.once(
+            Method::POST,
+            insert,
+            MockResponse::json(
+                201,
+                fixtures::DocumentFixture::new("users", "alice").build(),
+            ),
+        );
+        let coll = users(&mock);
+        let result = coll
+            .insert_or_get(json!({ "_key": "alice" }))
+            .await
+            .unwrap();
+        assert!(matches!(result, InsertOrGet::Inserted(_)));
+        mock.assert_no_pending();
+        mock.clear_requests();
+
+        // the race is lost every time
+        for _ in 0..3 {
+            mock.once(Method::POST, insert, conflict(Some("alice")));
+            mock.once(Method::GET, read, not_found());
+        }
+        let result = coll.insert_or_get(json!({ "_key": "alice" })).await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1210),
+            "{:?}",
+            result
+        );
+        assert_eq!(mock.requests().len(), 6);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn compare_and_swap_field() {
+        let mock = MockClient::default();
+        let coll = users(&mock).with_field_case(Case::Camel);
+        mock.expect_cursor("test_db", vec![vec![json!({ "rev": "_b", "actual": 1 })]]);
+        let outcome = coll
+            .compare_and_swap_field("alice", "stats.login_count", json!(1), json!(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            CasOutcome::Swapped {
+                new_rev: "_b".to_owned()
+            }
+        );
+        let body = mock.requests()[0].json_body().unwrap();
+        assert_eq!(
+            body["bindVars"],
+            json!({
+                "@collection": "users",
+                "key": "alice",
+                "field": ["stats", "loginCount"],
+                "expected": 1,
+                "new": 2,
+                "name0": "stats",
+                "name1": "loginCount",
+            })
+        );
+        let query = body["query"].as_str().unwrap();
+        assert!(query.contains(
+            "UPDATE @key WITH { [@name0]: MERGE(IS_OBJECT(doc.@name0) ? doc.@name0 : {}, \
+             { [@name1]: @new }) } IN @@collection OPTIONS { mergeObjects: false }"
+        ));
+
+        // an object replaces the value of the field instead of being merged
+        mock.clear_requests();
+        mock.expect_cursor("test_db", vec![vec![json!({ "rev": "_c", "actual": {} })]]);
+        coll.compare_and_swap_field(
+            "alice",
+            "flags",
+            json!({ "a": 1, "b": 2 }),
+            json!({ "c": 3 }),
+        )
+        .await
+        .unwrap();
+        let body = mock.requests()[0].json_body().unwrap();
+        assert_eq!(body["bindVars"]["new"], json!({ "c": 3 }));
+        assert!(body["query"].as_str().unwrap().contains(
+            "UPDATE @key WITH { [@name0]: @new } IN @@collection OPTIONS { mergeObjects: false }"
+        ));
+
+        // the value found is returned with the field case of the handle
+        mock.expect_cursor(
+            "test_db",
+            vec![vec![json!({ "rev": null, "actual": { "lastSeen": 7 } })]],
+        );
+        let outcome = coll
+            .compare_and_swap_field("alice", "stats", json!(null), json!({ "last_seen": 8 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            CasOutcome::Mismatch {
+                actual: json!({ "last_seen": 7 })
+            }
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn compare_and_swap_field_retries_conflicts() {
+        let mock = MockClient::default();
+        let coll = users(&mock);
+        let cursor = "/_db/test_db/_api/cursor";
+        let conflict =
+            || MockResponse::json(409, fixtures::error(409, 1200, "write-write conflict"));
+        mock.once(Method::POST, cursor, conflict());
+        mock.expect_cursor("test_db", vec![vec![json!({ "rev": null, "actual": 3 })]]);
+        let outcome = coll
+            .compare_and_swap_field("alice", "count", json!(1), json!(2))
+            .await
+            .unwrap();
+        assert_eq!(outcome, CasOutcome::Mismatch { actual: json!(3) });
+        mock.assert_no_pending();
+        mock.clear_requests();
+
+        for _ in 0..3 {
+            mock.once(Method::POST, cursor, conflict());
+        }
+        let result = coll
+            .compare_and_swap_field("alice", "count", json!(1), json!(2))
+            .await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1200),
+            "{:?}",
+            result
+        );
+        assert_eq!(mock.requests().len(), 3);
+
+        // a missing document fails on the update
+        mock.once(
+            Method::POST,
+            cursor,
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        let result = coll
+            .compare_and_swap_field("nobody", "count", json!(1), json!(2))
+            .await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1202),
+            "{:?}",
+            result
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn dirty_document_read() {
+        let mock = MockClient::default();
+        let document = "/_db/test_db/_api/document/users/alice";
+        let alice = || fixtures::document("users", "alice", "_a", json!({ "age": 30 }));
+        mock.once(
+            Method::GET,
+            document,
+            MockResponse::json(200, alice()).with_header("x-arango-potential-dirty-read", "true"),
+        );
+        mock.once(Method::GET, document, MockResponse::json(200, alice()));
+        let coll = users(&mock);
+
+        let options = DocumentReadOptions::builder()
+            .allow_dirty_read(true)
+            .if_none_match("_z")
+            .build();
+        let (doc, meta) = coll
+            .document_with_meta::<Value>("alice", options)
+            .await
+            .unwrap();
+        assert_eq!(doc.document["age"], 30);
+        assert!(meta.potential_dirty_read());
+        let (_, meta) = coll
+            .document_with_meta::<Value>("alice", Default::default())
+            .await
+            .unwrap();
+        assert!(!meta.potential_dirty_read());
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].header(DIRTY_READ_HEADER), Some("true"));
+        assert_eq!(requests[0].header("if-none-match"), Some("_z"));
+        assert_eq!(requests[1].header(DIRTY_READ_HEADER), None);
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn reject_dirty_read_in_transaction() {
+        let mock = MockClient::default();
+        let coll = users(&mock);
+        let options = BatchReadOptions::builder()
+            .transaction_id("42")
+            .allow_dirty_read(true)
+            .build();
+        let docs = coll.read_documents::<_, Value>(&["alice"], options).await;
+        assert!(matches!(docs, Err(ClientError::InvalidOptions(_))));
+
+        let coll = coll.clone_with_transaction("42".to_owned()).unwrap();
+        let options = BatchReadOptions::builder().allow_dirty_read(true).build();
+        let docs = coll.read_documents::<_, Value>(&["alice"], options).await;
+        assert!(matches!(docs, Err(ClientError::InvalidOptions(_))));
+        mock.assert_requests(&[]);
+
+        let options = DocumentReadOptions::builder()
+            .allow_dirty_read(true)
+            .build();
+        let doc = coll
+            .document_with_options::<Value>("alice", options.clone())
+            .await;
+        assert!(matches!(doc, Err(ClientError::InvalidOptions(_))));
+        let head = coll.read_document_header("alice", options).await;
+        assert!(matches!(head, Err(ClientError::InvalidOptions(_))));
+        mock.assert_requests(&[]);
+
+        let result = coll.clone_with_transaction("42\r\nx-arango-async: store".to_owned());
+        assert!(matches!(result, Err(ClientError::MalformedResponse(_))));
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[test]
+    fn field_case_names() {
+        let camel = Case::Camel;
+        assert_eq!(camel.stored_name("created_at"), "createdAt");
+        assert_eq!(camel.stored_name("age"), "age");
+        assert_eq!(camel.stored_name("_key"), "_key");
+        assert_eq!(camel.stored_name("_old_rev"), "_old_rev");
+        assert_eq!(camel.stored_name("trailing_"), "trailing_");
+        assert_eq!(camel.rust_name("createdAt"), "created_at");
+        assert_eq!(camel.rust_name("_oldRev"), "_oldRev");
+        assert_eq!(camel.rust_name("Upper"), "upper");
+        for field in &["created_at", "last_login_ip", "x", "é_à"] {
+            assert_eq!(camel.rust_name(&camel.stored_name(field)), *field);
+        }
+        assert_eq!(Case::Snake.stored_name("created_at"), "created_at");
+        assert_eq!(
+            camel.stored_path("meta.created_at.unix_time"),
+            vec!["meta", "createdAt", "unixTime"]
+        );
+
+        let doc = json!({
+            "_key": "alice",
+            "home_address": { "zip_code": "1000", "geo_point": [{ "lat_deg": 1 }] },
+        });
+        let stored = camel.to_stored(doc.clone());
+        assert_eq!(
+            stored,
+            json!({
+                "_key": "alice",
+                "homeAddress": { "zipCode": "1000", "geoPoint": [{ "latDeg": 1 }] },
+            })
+        );
+        assert_eq!(camel.to_rust(stored), doc);
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Account {
+        user_name: String,
+        last_login: Login,
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Login {
+        unix_time: u64,
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn camel_case_documents() {
+        let mock = MockClient::default();
+        let stored = json!({ "userName": "alice", "lastLogin": { "unixTime": 42 } });
+        mock.on(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(
+                202,
+                fixtures::DocumentFixture::new("users", "alice")
+                    .new_doc(stored.clone())
+                    .build(),
+            ),
+        );
+        mock.expect_cursor(
+            "test_db",
+            vec![vec![fixtures::document(
+                "users",
+                "alice",
+                "_a",
+                stored.clone(),
+            )]],
+        );
+        let coll = users(&mock).with_field_case(Case::Camel);
+        let account = Account {
+            user_name: "alice".to_owned(),
+            last_login: Login { unix_time: 42 },
+        };
+
+        let options = InsertOptions::builder().return_new(true).build();
+        let created = coll.create_document(account, options).await.unwrap();
+        match created {
+            DocumentResponse::Response { new: Some(doc), .. } => {
+                assert_eq!(doc.last_login.unix_time, 42)
+            }
+            _ => panic!("should return the new document"),
+        }
+        assert_eq!(mock.requests()[0].json_body().unwrap(), stored);
+
+        let mut cursor = coll
+            .range_scan::<Account>(
+                "last_login.unix_time",
+                Bound::Included(json!(40)),
+                Bound::Unbounded,
+                Default::default(),
+            )
+            .unwrap();
+        let batch = cursor.next_batch().await.unwrap().unwrap();
+        assert_eq!(batch[0].user_name, "alice");
+        let body = mock.requests()[1].json_body().unwrap();
+        assert_eq!(body["bindVars"]["field"], json!(["lastLogin", "unixTime"]));
+    }
+
+    /// Counts how many times it is serialized.
+    #[cfg(feature = "documents")]
+    #[derive(Debug, Deserialize)]
+    struct Counted {
+        value: u32,
+        #[serde(skip)]
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[cfg(feature = "documents")]
+    impl Serialize for Counted {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeStruct;
+
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let mut state = serializer.serialize_struct("Counted", 1)?;
+            state.serialize_field("value", &self.value)?;
+            state.end()
+        }
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn documents_serialized_once() {
+        let mock = MockClient::default();
+        let ok = || MockResponse::json(202, fixtures::DocumentFixture::new("users", "k").build());
+        mock.on(Method::POST, "/_db/test_db/_api/document/users/", ok());
+        mock.on(Method::PATCH, "/_db/test_db/_api/document/users/k", ok());
+        mock.on(Method::PUT, "/_db/test_db/_api/document/users/k", ok());
+        let db = mock.database("test_db");
+        let edges = Collection::new(
+            "users",
+            "100",
+            CollectionType::Edge,
+            db.url(),
+            db.shared_session(),
+        );
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let doc = || Counted {
+            value: 1,
+            calls: calls.clone(),
+        };
+
+        for coll in [
+            users(&mock),
+            users(&mock).with_field_case(Case::Camel),
+            edges,
+        ] {
+            coll.create_document(doc(), Default::default())
+                .await
+                .unwrap();
+            coll.update_document("k", doc(), Default::default())
+                .await
+                .unwrap();
+            coll.replace_document("k", doc(), Default::default(), None)
+                .await
+                .unwrap();
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 9);
+        // bodies are compact
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|request| request.body == r#"{"value":1}"#));
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    fn cursor_response(result: Vec<Value>) -> MockResponse {
+        MockResponse::json(201, fixtures::cursor_batches("c", vec![result]).remove(0))
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::maybe_async]
+    async fn sample_skips(seed: u64) -> Vec<u64> {
+        let mock = MockClient::default();
+        let cursor = "/_db/test_db/_api/cursor";
+        mock.once(Method::POST, cursor, cursor_response(vec![json!(100)]));
+        mock.on(
+            Method::POST,
+            cursor,
+            cursor_response(vec![json!({ "_key": "k", "status": "active" })]),
+        );
+        let options = SampleOptions::builder().seed(seed).concurrency(3).build();
+        let docs: Vec<Value> = users(&mock)
+            .stratified_sample(4, Some(json!({ "status": "active" })), options)
+            .await
+            .unwrap();
+        assert_eq!(docs.len(), 4);
+
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 5);
+        let count = requests[0].json_body().unwrap();
+        assert!(count["query"]
+            .as_str()
+            .unwrap()
+            .contains("FILTER d.@field0 == @value0 COLLECT WITH COUNT INTO n"));
+        assert_eq!(count["bindVars"]["field0"], json!(["status"]));
+        assert_eq!(count["bindVars"]["value0"], "active");
+        let mut skips: Vec<u64> = requests[1..]
+            .iter()
+            .map(|request| {
+                let body = request.json_body().unwrap();
+                assert!(body["query"].as_str().unwrap().contains("LIMIT @skip, 1"));
+                body["bindVars"]["skip"].as_u64().unwrap()
+            })
+            .collect();
+        skips.sort_unstable();
+        skips
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn stratified_sample_offsets() {
+        let mut seen = std::collections::HashSet::new();
+        for seed in 0..20 {
+            let skips = sample_skips(seed).await;
+            // one sample in each quarter of the matching documents
+            for (i, skip) in skips.iter().enumerate() {
+                assert_eq!(*skip / 25, i as u64, "{:?}", skips);
+            }
+            let again = sample_skips(seed).await;
+            assert_eq!(skips, again);
+            seen.insert(skips);
+        }
+        // different seeds pick different documents
+        assert!(seen.len() > 15);
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn stratified_sample_small_and_random_field() {
+        let mock = MockClient::default();
+        let cursor = "/_db/test_db/_api/cursor";
+        // fewer documents than requested, all are returned
+        mock.once(Method::POST, cursor, cursor_response(vec![json!(2)]));
+        mock.once(
+            Method::POST,
+            cursor,
+            cursor_response(vec![json!({ "_key": "a" }), json!({ "_key": "b" })]),
+        );
+        let coll = users(&mock);
+        let docs: Vec<Value> = coll
+            .stratified_sample(5, None, Default::default())
+            .await
+            .unwrap();
+        assert_eq!(docs.len(), 2);
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].json_body().unwrap()["query"],
+            "RETURN LENGTH(@@collection)"
+        );
+        assert_eq!(
+            requests[1].json_body().unwrap()["query"],
+            "FOR d IN @@collection RETURN d"
+        );
+        mock.clear_requests();
+
+        // the threshold is close to the maximum, wrapping around
+        mock.once(
+            Method::POST,
+            cursor,
+            cursor_response(vec![json!({ "_key": "c" })]),
+        );
+        mock.once(
+            Method::POST,
+            cursor,
+            cursor_response(vec![json!({ "_key": "a" }), json!({ "_key": "b" })]),
+        );
+        let options = SampleOptions::builder()
+            .seed(3)
+            .random_field("meta.random")
+            .build();
+        let docs: Vec<Value> = coll.stratified_sample(3, None, options).await.unwrap();
+        assert_eq!(docs.len(), 3);
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 2);
+        let (first, second) = (
+            requests[0].json_body().unwrap(),
+            requests[1].json_body().unwrap(),
+        );
+        assert!(first["query"]
+            .as_str()
+            .unwrap()
+            .contains("d.@random >= @threshold"));
+        assert!(second["query"]
+            .as_str()
+            .unwrap()
+            .contains("d.@random < @threshold"));
+        assert_eq!(first["bindVars"]["random"], json!(["meta", "random"]));
+        assert_eq!(first["bindVars"]["limit"], 3);
+        assert_eq!(second["bindVars"]["limit"], 2);
+        assert_eq!(
+            first["bindVars"]["threshold"],
+            second["bindVars"]["threshold"]
+        );
+        mock.assert_no_pending();
+
+        let result = coll
+            .stratified_sample::<Value>(3, Some(json!(["status"])), Default::default())
+            .await;
+        assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+    }
+
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn filter_existing_keys() {
+        let mock = MockClient::default();
+        let cursor = "/_db/test_db/_api/cursor";
+        mock.once(
+            Method::POST,
+            cursor,
+            cursor_response(vec![json!("c"), json!("a")]),
+        );
+        mock.once(Method::POST, cursor, cursor_response(vec![json!("d")]));
+        mock.once(Method::POST, cursor, cursor_response(vec![]));
+        let coll = users(&mock);
+        let options = ExistenceOptions::builder().batch_size(2).build();
+        let report = coll
+            .filter_existing_keys(&["c", "b", "a", "c", "d", "b", "e"], options)
+            .await
+            .unwrap();
+        assert_eq!(report.existing, vec!["c", "a", "d"]);
+        assert_eq!(report.missing, vec!["b", "e"]);
+
+        // duplicates are only checked once
+        let requests = mock.requests();
+        assert_eq!(requests.len(), 3);
+        let batches: Vec<Value> = requests
+            .iter()
+            .map(|request| request.json_body().unwrap()["bindVars"].take())
+            .collect();
+        assert_eq!(
+            batches[0],
+            json!({ "@collection": "users", "keys": ["c", "b"] })
+        );
+        assert_eq!(batches[1]["keys"], json!(["a", "d"]));
+        assert_eq!(batches[2]["keys"], json!(["e"]));
+        mock.assert_no_pending();
+
+        let report = coll
+            .filter_existing_keys(&[], Default::default())
+            .await
+            .unwrap();
+        assert_eq!(report, ExistenceReport::default());
+        assert_eq!(mock.requests().len(), 3);
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn document_operations_with_meta() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/k",
+            MockResponse::json(
+                200,
+                fixtures::document("users", "k", "_rev1", json!({ "name": "alice" })),
+            )
+            .with_header("Etag", "\"_rev1\"")
+            .with_header("x-arango-queue-time-seconds", "0.012500")
+            .with_header("X-Proxy-Request-Id", "req-42"),
+        );
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            MockResponse::json(202, fixtures::DocumentFixture::new("users", "n").build())
+                .with_header("etag", "\"_mock_rev\""),
+        );
+        let coll = users(&mock);
+
+        let (doc, meta) = coll
+            .document_with_meta::<Value>("k", Default::default())
+            .await
+            .unwrap();
+        assert_eq!(doc.document["name"], "alice");
+        assert_eq!(meta.status(), 200);
+        assert_eq!(meta.etag(), Some("_rev1"));
+        assert_eq!(meta.header("ETAG"), Some("\"_rev1\""));
+        assert_eq!(meta.queue_time(), Some(Duration::from_micros(12_500)));
+        assert_eq!(meta.header("x-proxy-request-id"), Some("req-42"));
+        assert_eq!(meta.header("X-PROXY-REQUEST-ID"), Some("req-42"));
+        assert_eq!(meta.async_id(), None);
+
+        let (created, meta) = coll
+            .create_document_with_meta(json!({ "_key": "n" }), Default::default())
+            .await
+            .unwrap();
+        assert_eq!(created.header().unwrap()._key, "n");
+        assert_eq!(meta.status(), 202);
+        assert_eq!(meta.etag(), Some("_mock_rev"));
+        assert_eq!(meta.queue_time(), None);
+        mock.assert_no_pending();
+
+        // errors are returned as without meta
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/document/users/gone",
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        let result = coll
+            .remove_document_with_meta::<Value>("gone", Default::default(), None)
+            .await;
+        assert!(matches!(result, Err(ClientError::Arango(_))));
     }
 }
@@ -1,8 +1,22 @@
 //! Types of response related to collection
 use serde::{Deserialize, Serialize, Serializer};
+#[cfg(any(feature = "documents", feature = "aql"))]
+use serde_json::Map;
+use serde_json::Value;
 use typed_builder::TypedBuilder;
 
-use crate::collection::CollectionType;
+#[cfg(feature = "cluster")]
+use crate::collection::response::ReplicationFactor;
+use crate::{collection::CollectionType, options::request_options};
+
+/// Ascending or descending order, of range scans and of the primary sort of
+/// views.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
 
 /// Options for create a collection
 #[derive(Serialize, PartialEq, TypedBuilder)]
@@ -77,6 +91,12 @@ pub struct CreateOptions<'a> {
     #[builder(default, setter(strip_option))]
     schema: Option<serde_json::Value>,
 
+    /// Attributes computed by the server when documents are written, since
+    /// ArangoDB 3.10.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    computed_values: Option<Vec<ComputedValue>>,
+
     /// This attribute specifies the name of the sharding strategy to use for
     /// the collection. Since ArangoDB 3.4 there are different sharding
     /// strategies to select from when creating a new collection. The selected
@@ -171,7 +191,7 @@ pub struct CreateOptions<'a> {
     #[cfg(feature = "cluster")]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
-    replication_factor: Option<usize>,
+    replication_factor: Option<ReplicationFactor>,
 
     /// Write concern for this collection (default: 1).
     ///
@@ -221,7 +241,7 @@ fn is_true(x: &bool) -> bool {
     *x
 }
 
-#[derive(Debug, Deserialize, Serialize, TypedBuilder, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, TypedBuilder, PartialEq)]
 #[builder(doc)]
 #[serde(rename_all = "camelCase")]
 pub struct KeyOptions {
@@ -233,30 +253,523 @@ pub struct KeyOptions {
     #[builder(default = true)]
     pub allow_user_keys: bool,
 
-    /// specifies the type of the key generator. The currently available
-    /// generators are traditional and autoincrement.
-    #[serde(skip_serializing_if = "Option::is_none", rename = "type")]
+    /// The generator of the keys of the documents inserted without a `_key`.
+    /// (default: traditional)
+    #[serde(flatten)]
+    #[builder(default)]
+    pub generator: KeyGenerator,
+
+    /// The latest key generated, of the generators keeping track of it.
+    #[serde(skip_serializing)]
+    #[builder(setter(skip), default = None)]
+    pub last_value: Option<u64>,
+}
+
+/// A generator of document keys, set in the
+/// [`KeyOptions`] of a collection.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum KeyGenerator {
+    /// Keys increasing with time, as numeric strings.
+    #[default]
+    Traditional,
+    /// Keys as integers increasing by `increment` from `offset`. Not
+    /// supported by collections with several shards.
+    Autoincrement {
+        /// The difference between two keys generated. (default: 1)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        increment: Option<u32>,
+        /// The first key generated. (default: 0)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+    },
+    /// Keys increasing with time, as 16 hexadecimal digits, so that their
+    /// lexicographical order is the order of their insertion.
+    Padded,
+    /// Universally unique keys, as in `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`.
+    Uuid,
+}
+
+impl Default for KeyOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// An attribute computed by the server when documents are written, since
+/// ArangoDB 3.10.
+#[derive(Debug, Clone, Deserialize, Serialize, TypedBuilder, PartialEq)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct ComputedValue {
+    /// The name of the attribute to compute.
+    #[builder(setter(into))]
+    pub name: String,
+
+    /// The AQL expression computing the value, as a `RETURN` operation on
+    /// the document `@doc`, like `RETURN DATE_NOW()`.
+    #[builder(setter(into))]
+    pub expression: String,
+
+    /// Whether the computed value replaces a value of the attribute given in
+    /// the document. An error is raised otherwise.
+    pub overwrite: bool,
+
+    /// The writes computing the value. All of them by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
-    pub key_type: Option<String>,
+    pub compute_on: Option<Vec<ComputeOn>>,
 
-    /// increment value for autoincrement key generator. Not used for other key
-    /// generator types.
+    /// Whether the attribute is set when the expression evaluates to `null`.
+    /// (default: true)
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
-    pub increment: Option<u32>,
+    pub keep_null: Option<bool>,
 
-    /// Initial offset value for autoincrement key generator. Not used for other
-    /// key generator types.
+    /// Whether a warning of the expression fails the write. (default: false)
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
-    pub offset: Option<u32>,
+    pub fail_on_warning: Option<bool>,
+}
 
-    #[serde(skip_serializing)]
-    #[builder(setter(skip), default = None)]
-    pub last_value: Option<u32>,
+/// A write computing a [`ComputedValue`].
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ComputeOn {
+    Insert,
+    Update,
+    Replace,
 }
 
-impl Default for KeyOptions {
+/// Options for dropping a collection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct DropOptions {
+    /// Whether the collection to drop is a system collection. This parameter
+    /// must be set to true in order to drop a system collection.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    is_system: Option<bool>,
+}
+
+request_options!(DropOptions {
+    query: [is_system => "isSystem"],
+    headers: [],
+});
+
+impl DropOptions {
+    /// Whether the collection to drop is a system collection.
+    pub fn is_system(&self) -> Option<bool> {
+        self.is_system
+    }
+}
+
+impl Default for DropOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Options for scanning the documents of a collection by the value of a
+/// field
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct RangeOptions {
+    /// Maximum number of documents to return.
+    #[builder(default, setter(strip_option))]
+    pub(crate) limit: Option<u64>,
+    /// Order of the documents by the scanned field, unspecified by default.
+    ///
+    /// Sorting is cheap when the field is covered by a persistent index.
+    #[builder(default, setter(strip_option))]
+    pub(crate) sort: Option<SortDirection>,
+    /// Whether to run the query as a streaming query, see
+    /// [`AqlOptions`](crate::AqlOptions).
+    #[builder(default, setter(strip_option))]
+    pub(crate) stream: Option<bool>,
+}
+
+impl Default for RangeOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Options for sampling the documents of a collection, see
+/// [`Collection::stratified_sample`](crate::Collection::stratified_sample)
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct SampleOptions {
+    /// Seed picking the documents, random by default. The same seed samples
+    /// the same documents as long as the collection does not change.
+    #[builder(default, setter(strip_option))]
+    pub(crate) seed: Option<u64>,
+    /// Maximum number of sampling queries running at once, 4 by default.
+    #[builder(default = 4)]
+    pub(crate) concurrency: usize,
+    /// Attribute of the documents holding a random number in `[0, 1)`, e.g.
+    /// set with `RAND()` on insertion, to sample with a single query.
+    #[builder(default, setter(strip_option, into))]
+    pub(crate) random_field: Option<String>,
+}
+
+impl Default for SampleOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Options for checking which keys exist in a collection, see
+/// [`Collection::filter_existing_keys`](crate::Collection::filter_existing_keys)
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct ExistenceOptions {
+    /// Number of keys checked per request, 1000 by default. Lower it when
+    /// the keys are long enough for a request to exceed the size limit of
+    /// the server.
+    #[builder(default = 1000)]
+    pub(crate) batch_size: usize,
+}
+
+impl ExistenceOptions {
+    /// Number of keys checked per request.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+}
+
+impl Default for ExistenceOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// What to do with an imported document whose key already exists
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OnDuplicate {
+    /// Reject the document, the default.
+    Error,
+    /// Merge the document into the existing one.
+    Update,
+    /// Replace the existing document.
+    Replace,
+    /// Keep the existing document.
+    Ignore,
+}
+
+/// Format of the body of an import, see
+/// [`Collection::import_jsonl`](crate::Collection::import_jsonl)
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportType {
+    /// One JSON document per line.
+    Documents,
+    /// A JSON array of documents.
+    List,
+    /// Either of the above, as detected by the server.
+    Auto,
+}
+
+/// Options for importing documents into a collection
+#[derive(Debug, Clone, Serialize, PartialEq, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOptions {
+    /// Number of documents sent per request. Default: 1000
+    #[serde(skip)]
+    #[builder(default = 1000)]
+    pub(crate) batch_size: usize,
+
+    /// Number of documents to skip at the start of the source without
+    /// sending them, to resume an import from the `offset` reported by its
+    /// last [`ImportProgress`](super::response::ImportProgress).
+    ///
+    /// This only resumes at the right document if the source yields the same
+    /// documents in the same order each time.
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    pub(crate) resume_from: Option<u64>,
+
+    /// Number of times a batch failing with a
+    /// [retryable](crate::ClientError::is_retryable) error is sent again
+    /// before the import fails: after the delay asked for by the server on
+    /// 503 Service Unavailable, and at once on a transport error or timeout.
+    /// The batch is sent again as it was, with the keys generated for it, so
+    /// that a batch imported before its response was lost only conflicts
+    /// with itself when its keys are not left to the server.
+    /// Default: 0
+    #[serde(skip)]
+    #[builder(default)]
+    pub(crate) retries: u32,
+
+    /// Format of the body sent. [`Collection::import`](crate::Collection::import)
+    /// sends a JSON array for [`ImportType::List`] and lines otherwise,
+    /// [`Collection::import_jsonl`](crate::Collection::import_jsonl) defaults
+    /// to [`ImportType::Auto`], and
+    /// [`Collection::import_documents`](crate::Collection::import_documents)
+    /// always sends lines.
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    pub(crate) import_type: Option<ImportType>,
+
+    /// Prefix prepended to the `_from` values of edges without a collection
+    /// name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    from_prefix: Option<String>,
+
+    /// Prefix prepended to the `_to` values of edges without a collection
+    /// name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    to_prefix: Option<String>,
+
+    /// If true, all documents of the collection are removed before the
+    /// first batch is imported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    pub(crate) overwrite: Option<bool>,
+
+    /// Wait until the documents have been synced to disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    wait_for_sync: Option<bool>,
+
+    /// What to do with documents whose key already exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    on_duplicate: Option<OnDuplicate>,
+
+    /// If true, a batch is rejected as a whole as soon as one of its
+    /// documents is rejected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    complete: Option<bool>,
+
+    /// If true, the reasons of rejected documents are reported in
+    /// [`ImportProgress::details`](super::response::ImportProgress::details),
+    /// or [`ImportResponse::details`](super::response::ImportResponse::details).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    details: Option<bool>,
+}
+
+request_options!(ImportOptions {
+    query: [
+        import_type => "type",
+        from_prefix => "fromPrefix",
+        to_prefix => "toPrefix",
+        overwrite => "overwrite",
+        wait_for_sync => "waitForSync",
+        on_duplicate => "onDuplicate",
+        complete => "complete",
+        details => "details",
+    ],
+    headers: [],
+});
+
+impl ImportOptions {
+    /// Number of documents sent per request.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// Number of documents to skip at the start of the source.
+    pub fn resume_from(&self) -> Option<u64> {
+        self.resume_from
+    }
+
+    /// Number of times a failed batch is sent again.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Format of the body sent.
+    pub fn import_type(&self) -> Option<ImportType> {
+        self.import_type
+    }
+
+    /// Prefix prepended to the `_from` values without a collection name.
+    pub fn from_prefix(&self) -> Option<&str> {
+        self.from_prefix.as_deref()
+    }
+
+    /// Prefix prepended to the `_to` values without a collection name.
+    pub fn to_prefix(&self) -> Option<&str> {
+        self.to_prefix.as_deref()
+    }
+
+    /// Whether the documents of the collection are removed first.
+    pub fn overwrite(&self) -> Option<bool> {
+        self.overwrite
+    }
+
+    /// Whether to wait until the documents have been synced to disk.
+    pub fn wait_for_sync(&self) -> Option<bool> {
+        self.wait_for_sync
+    }
+
+    /// What to do with documents whose key already exists.
+    pub fn on_duplicate(&self) -> Option<OnDuplicate> {
+        self.on_duplicate
+    }
+
+    /// Whether a batch is rejected as a whole on a rejected document.
+    pub fn complete(&self) -> Option<bool> {
+        self.complete
+    }
+
+    /// Whether the reasons of rejected documents are reported.
+    pub fn details(&self) -> Option<bool> {
+        self.details
+    }
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Naming convention of the attributes stored in a collection, see
+/// [`Collection::with_field_case`](super::Collection::with_field_case).
+///
+/// Field names on the Rust side are expected in snake_case. Attributes
+/// starting with an underscore, like `_key`, are never renamed.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Case {
+    /// Attributes are stored in snake_case, like the Rust fields.
+    #[default]
+    Snake,
+    /// Attributes are stored in camelCase, e.g. `created_at` as `createdAt`.
+    Camel,
+}
+
+impl Case {
+    /// The stored name of the attribute called `field` in Rust.
+    pub fn stored_name(self, field: &str) -> String {
+        match self {
+            Case::Camel if !field.starts_with('_') => {
+                let mut name = String::with_capacity(field.len());
+                let mut upper = false;
+                for c in field.chars() {
+                    if c == '_' {
+                        upper = true;
+                    } else if upper {
+                        name.extend(c.to_uppercase());
+                        upper = false;
+                    } else {
+                        name.push(c);
+                    }
+                }
+                if upper {
+                    name.push('_');
+                }
+                name
+            }
+            _ => field.to_owned(),
+        }
+    }
+
+    /// The Rust name of the stored attribute `name`.
+    ///
+    /// This reverses [`stored_name`](Case::stored_name) for names without
+    /// consecutive underscores or uppercase letters.
+    pub fn rust_name(self, name: &str) -> String {
+        match self {
+            Case::Camel if !name.starts_with('_') => {
+                let mut field = String::with_capacity(name.len() + 4);
+                for c in name.chars() {
+                    if c.is_uppercase() {
+                        if !field.is_empty() {
+                            field.push('_');
+                        }
+                        field.extend(c.to_lowercase());
+                    } else {
+                        field.push(c);
+                    }
+                }
+                field
+            }
+            _ => name.to_owned(),
+        }
+    }
+
+    /// The stored attribute names along a dotted path like `meta.created_at`.
+    #[cfg(all(feature = "documents", feature = "aql"))]
+    pub(crate) fn stored_path(self, path: &str) -> Vec<String> {
+        path.split('.')
+            .map(|field| self.stored_name(field))
+            .collect()
+    }
+
+    /// Rename the attributes of a document with Rust names to stored names,
+    /// in nested objects and arrays as well.
+    #[cfg(feature = "documents")]
+    pub(crate) fn to_stored(self, doc: Value) -> Value {
+        match self {
+            Case::Snake => doc,
+            _ => rename_keys(doc, &|field| self.stored_name(field)),
+        }
+    }
+
+    /// Rename the stored attributes of a document to Rust names, in nested
+    /// objects and arrays as well.
+    #[cfg(any(feature = "documents", feature = "aql"))]
+    pub(crate) fn to_rust(self, doc: Value) -> Value {
+        match self {
+            Case::Snake => doc,
+            _ => rename_keys(doc, &|name| self.rust_name(name)),
+        }
+    }
+}
+
+#[cfg(any(feature = "documents", feature = "aql"))]
+fn rename_keys(value: Value, rename: &dyn Fn(&str) -> String) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (rename(&key), rename_keys(value, rename)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| rename_keys(value, rename))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Options for the statistics of a collection
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct FiguresOptions {
+    /// Whether to include the figures of the storage engine, per document
+    /// and per index, which may take a while to compute on big collections.
+    /// For the RocksDB engine only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    details: Option<bool>,
+}
+
+request_options!(FiguresOptions {
+    query: [details => "details"],
+    headers: [],
+});
+
+impl FiguresOptions {
+    /// Whether to include the figures of the storage engine.
+    pub fn details(&self) -> Option<bool> {
+        self.details
+    }
+}
+
+impl Default for FiguresOptions {
     fn default() -> Self {
         Self::builder().build()
     }
@@ -270,7 +783,7 @@ pub struct ChecksumOptions {
     /// By setting the optional query parameter withRevisions to true, then
     /// revision ids (_rev system attributes) are included in the
     /// checksumming.
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "withRevisions", skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     with_revision: Option<bool>,
     /// By providing the optional query parameter withData with a value of true,
@@ -299,9 +812,36 @@ pub struct PropertiesOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     wait_for_sync: Option<bool>,
-    /* TODO need to implement this with feature gate between versions maybe
-     *  for ArangoDB 3.7
-     * schema: Option<SchemaRules>, */
+
+    /// The schema validating the documents, with the attributes `rule`,
+    /// `level` and `message`, or `null` to remove the current schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    schema: Option<Value>,
+
+    /// Attributes computed by the server when documents are written, since
+    /// ArangoDB 3.10. An empty list removes them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    computed_values: Option<Vec<ComputedValue>>,
+
+    /// Whether the in-memory hash cache of the documents is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    cache_enabled: Option<bool>,
+
+    /// The number of copies of each shard kept on different DB-Servers.
+    #[cfg(feature = "cluster")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    replication_factor: Option<ReplicationFactor>,
+
+    /// The number of copies of each shard which must be in sync for writes
+    /// to succeed, at most the replication factor.
+    #[cfg(feature = "cluster")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    write_concern: Option<usize>,
 }
 
 impl Default for PropertiesOptions {
@@ -1,14 +1,23 @@
 //! Types of response related to collection
-use crate::collection::{options::KeyOptions, CollectionType};
+use std::{convert::TryFrom, ops::RangeInclusive, time::Duration};
+
+use crate::{
+    collection::{
+        options::{ComputedValue, KeyOptions},
+        CollectionType,
+    },
+    ArangoError, BulkErrorSummary,
+};
 use serde::{
     de::{Deserializer, Error as DeError},
-    Deserialize,
+    Deserialize, Serialize, Serializer,
 };
+use serde_json::{Map, Value};
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Info {
-    pub count: Option<u32>,
+    pub count: Option<u64>,
     pub id: String,
     pub name: String,
     pub globally_unique_id: String,
@@ -49,6 +58,184 @@ impl<'de> Deserialize<'de> for Status {
     }
 }
 
+/// Result of dropping a collection.
+#[derive(Debug, Deserialize)]
+pub struct DroppedCollection {
+    /// The identifier of the dropped collection.
+    pub id: String,
+}
+
+/// Result of importing a batch of documents, as returned by
+/// [`Collection::import`](crate::Collection::import).
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResponse {
+    /// Number of documents created.
+    pub created: u64,
+    /// Number of documents rejected.
+    pub errors: u64,
+    /// Number of empty lines ignored.
+    pub empty: u64,
+    /// Number of documents updated or replaced.
+    pub updated: u64,
+    /// Number of documents ignored as duplicates.
+    pub ignored: u64,
+    /// Reasons of the documents rejected, when the `details` option is set.
+    #[serde(default)]
+    pub details: Vec<String>,
+}
+
+/// Progress of an import, reported after each batch by
+/// [`Collection::import_documents`](crate::Collection::import_documents).
+///
+/// Counters cover the whole import since it started or was resumed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportProgress {
+    /// Number of documents of the source consumed so far, including the
+    /// skipped ones when resuming. Pass it as `resume_from` to resume after
+    /// this batch.
+    pub offset: u64,
+    /// Number of bytes of documents sent.
+    pub bytes_sent: u64,
+    /// Number of documents created.
+    pub created: u64,
+    /// Number of documents updated or replaced.
+    pub updated: u64,
+    /// Number of documents ignored as duplicates.
+    pub ignored: u64,
+    /// Number of documents rejected.
+    pub rejected: u64,
+    /// Numbers of documents rejected per error, when the server counts them
+    /// in the `x-arango-error-codes` header of its responses.
+    pub errors: BulkErrorSummary,
+    /// Number of empty lines ignored.
+    pub empty: u64,
+    /// Time spent since the import started.
+    pub elapsed: Duration,
+    /// Reasons of the documents rejected in the latest batch, when the
+    /// `details` option is set.
+    pub details: Vec<String>,
+}
+
+/// Outcome of an insert by chunks, as returned by
+/// [`Collection::create_documents_chunked`](crate::Collection::create_documents_chunked).
+///
+/// Only the failed documents are kept, so that the memory needed does not
+/// grow with the documents created.
+#[derive(Debug, Clone, Default)]
+pub struct ChunkedInsertReport {
+    /// Number of documents sent.
+    pub sent: u64,
+    /// Number of documents written, including the ones overwritten with an
+    /// `overwrite_mode`.
+    pub created: u64,
+    /// Number of documents rejected.
+    pub rejected: u64,
+    /// Numbers of documents rejected per error, when the server counts them
+    /// in the `x-arango-error-codes` header of its responses.
+    pub errors: BulkErrorSummary,
+    /// The errors of the documents rejected, with the position of each
+    /// document in the input. The position is unknown with `silent`, as the
+    /// server then only returns the errors.
+    pub failures: Vec<(Option<u64>, ArangoError)>,
+}
+
+/// Which keys exist in a collection, as reported by
+/// [`Collection::filter_existing_keys`](crate::Collection::filter_existing_keys).
+///
+/// Both lists are in the order of the keys checked, without duplicates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExistenceReport {
+    /// Keys of the documents in the collection.
+    pub existing: Vec<String>,
+    /// Keys of no document of the collection.
+    pub missing: Vec<String>,
+}
+
+/// Health of a collection, as reported by
+/// [`Database::collection_health`](crate::Database::collection_health).
+#[derive(Debug)]
+pub struct CollectionHealth {
+    pub name: String,
+    /// Whether the collection exists. `false` as well when its state could
+    /// not be retrieved, in which case `error` is set.
+    pub exists: bool,
+    pub status: Option<Status>,
+    /// The number of documents in the collection.
+    pub count: Option<u64>,
+    /// The error of the server, other than the collection not being found.
+    pub error: Option<ArangoError>,
+}
+
+impl CollectionHealth {
+    /// Whether the collection exists, is loaded and has a number of documents
+    /// within `range`.
+    pub fn is_healthy(&self, range: RangeInclusive<u64>) -> bool {
+        self.exists
+            && self.status == Some(Status::Loaded)
+            && matches!(self.count, Some(count) if range.contains(&count))
+    }
+}
+
+/// A shard of a collection, as listed by
+/// [`Collection::shards`](crate::Collection::shards).
+#[cfg(feature = "cluster")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shard {
+    pub id: String,
+    /// The DB-Servers holding the shard, the leader first. Only listed with
+    /// `details`.
+    pub servers: Vec<String>,
+}
+
+/// The shards of a collection, with their servers or not.
+#[cfg(feature = "cluster")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct ShardList {
+    shards: ShardIds,
+}
+
+#[cfg(feature = "cluster")]
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ShardIds {
+    Ids(Vec<String>),
+    Servers(std::collections::BTreeMap<String, Vec<String>>),
+}
+
+#[cfg(feature = "cluster")]
+impl ShardList {
+    pub(crate) fn into_shards(self) -> Vec<Shard> {
+        match self.shards {
+            ShardIds::Ids(ids) => ids
+                .into_iter()
+                .map(|id| Shard {
+                    id,
+                    servers: Vec::new(),
+                })
+                .collect(),
+            ShardIds::Servers(servers) => servers
+                .into_iter()
+                .map(|(id, servers)| Shard { id, servers })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "cluster")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ResponsibleShard {
+    pub shard_id: String,
+}
+
+/// The document count of a collection once recalculated.
+#[cfg(feature = "rocksdb")]
+#[derive(Debug, Deserialize)]
+pub(crate) struct RecalculatedCount {
+    pub count: u64,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Properties {
@@ -65,8 +252,18 @@ pub struct Details {
     pub key_options: KeyOptions,
     pub wait_for_sync: bool,
     pub write_concern: u16,
-    #[cfg(rocksdb)]
+    /// Whether the in-memory hash cache of the documents is enabled.
+    #[serde(default)]
     pub cache_enabled: bool,
+    /// The schema validating the documents, if any.
+    #[serde(default)]
+    pub schema: Option<Value>,
+    /// The attributes computed by the server when documents are written.
+    #[serde(default)]
+    pub computed_values: Option<Vec<ComputedValue>>,
+    /// The number of copies of each shard, in a cluster.
+    #[serde(default)]
+    pub replication_factor: Option<ReplicationFactor>,
     #[cfg(rocksdb)]
     pub object_id: String,
     #[cfg(mmfiles)]
@@ -77,26 +274,93 @@ pub struct Details {
     pub journal_size: usize,
     #[cfg(mmfiles)]
     pub index_buckets: usize,
+    /// Whether the collection is part of a smart graph (Enterprise Edition
+    /// only).
+    #[cfg(feature = "enterprise")]
+    pub is_smart: Option<bool>,
+    /// Whether the collection is part of a disjoint smart graph (Enterprise
+    /// Edition only).
+    #[cfg(feature = "enterprise")]
+    pub is_disjoint: Option<bool>,
+    /// The collection whose sharding is imitated (Enterprise Edition only).
+    #[cfg(feature = "enterprise")]
+    pub distribute_shards_like: Option<String>,
+    /// The attribute holding the shard key value of the collection whose
+    /// sharding is imitated, for SmartJoins (Enterprise Edition only).
+    #[cfg(feature = "enterprise")]
+    pub smart_join_attribute: Option<String>,
+}
+
+/// Number of copies of each shard of a collection kept in a cluster.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicationFactor {
+    Copies(u32),
+    /// A copy on every DB-Server, for a SatelliteCollection (Enterprise
+    /// Edition only).
+    Satellite,
+}
+
+impl Serialize for ReplicationFactor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ReplicationFactor::Copies(n) => serializer.serialize_u32(*n),
+            ReplicationFactor::Satellite => serializer.serialize_str("satellite"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ReplicationFactor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Value::deserialize(deserializer)? {
+            Value::String(s) if s == "satellite" => Ok(ReplicationFactor::Satellite),
+            Value::Number(n) => n
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+                .map(ReplicationFactor::Copies)
+                .ok_or_else(|| DeError::custom(format!("invalid replication factor {}", n))),
+            v => Err(DeError::custom(format!("invalid replication factor {}", v))),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ArangoIndex {
-    pub count: Option<u32>,
-    pub size: Option<u32>,
+    pub count: Option<u64>,
+    /// The memory used by the indexes, in bytes.
+    pub size: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Figures {
     pub indexes: ArangoIndex,
+    /// The disk space used by the documents, in bytes.
+    pub documents_size: Option<u64>,
+    /// Whether the document cache is in use.
+    pub cache_in_use: Option<bool>,
+    /// The memory allotted to the document cache, in bytes.
+    pub cache_size: Option<u64>,
+    /// The memory used by the document cache, in bytes.
+    pub cache_usage: Option<u64>,
+    /// The figures specific to a storage engine or server version, like the
+    /// `engine` figures returned with `details` set in the
+    /// [`FiguresOptions`](super::options::FiguresOptions).
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Statistics {
     /// The number of documents currently present in the collection.
-    pub count: Option<u32>,
+    pub count: Option<u64>,
     /// metrics of the collection
     pub figures: Figures,
 
@@ -128,3 +392,12 @@ pub struct Checksum {
     #[serde(flatten)]
     pub info: Info,
 }
+
+/// Documents read by
+/// [`read_documents_with_meta`](crate::Collection::read_documents_with_meta),
+/// each one or its error, along with the status and headers of the response.
+#[cfg(feature = "documents")]
+pub type BatchRead<T> = (
+    Vec<Result<crate::Document<T>, ArangoError>>,
+    crate::ResponseMeta,
+);
@@ -0,0 +1,498 @@
+//! Cached results of probing the access to the databases of a connection,
+//! see [`GenericConnection::db`](super::GenericConnection::db).
+//!
+//! Granted access is cached for the lifetime of the connection, denied access
+//! for a TTL, so that a misconfigured database is not probed again on every
+//! use. Concurrent probes of the same database are made once: the callers
+//! coming while a probe is in flight wait for its result.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use super::Permission;
+use crate::ArangoError;
+
+/// How long a denied access is cached by default.
+pub(crate) const DEFAULT_DENIED_TTL: Duration = Duration::from_secs(30);
+
+/// Cached result of probing the access to a database, see
+/// [`GenericConnection::database_access`](super::GenericConnection::database_access).
+#[derive(Debug, Clone)]
+pub enum DatabaseAccess {
+    /// The user can access the database. `granted` is known once looked up
+    /// by [`Database::access`](crate::Database::access).
+    Granted { granted: Option<Permission> },
+    /// The server refused the access, which is not probed again before
+    /// `expires_in`.
+    Denied {
+        error: ArangoError,
+        expires_in: Duration,
+    },
+}
+
+#[derive(Debug)]
+enum Entry {
+    Probing,
+    Granted(Option<Permission>),
+    Denied { error: ArangoError, until: Instant },
+}
+
+impl Entry {
+    /// Whether the entry is still valid at `now`.
+    fn is_alive(&self, now: Instant) -> bool {
+        match self {
+            Entry::Denied { until, .. } => *until > now,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    entries: HashMap<String, Entry>,
+    denied_ttl: Duration,
+    #[cfg(not(feature = "blocking"))]
+    wakers: Vec<std::task::Waker>,
+}
+
+/// Shared by all handles derived from one connection.
+#[derive(Debug)]
+pub(crate) struct AccessCache {
+    user: Option<String>,
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// What a caller of [`AccessCache::turn`] has to do.
+pub(crate) enum Turn<'a> {
+    /// Nothing, the access is known.
+    Cached(Result<Option<Permission>, ArangoError>),
+    /// Probe the access and report the outcome to the guard. Dropping the
+    /// guard without an outcome lets the next caller probe.
+    Probe(ProbeGuard<'a>),
+}
+
+impl AccessCache {
+    /// A cache for the access of `user`, when known.
+    pub(crate) fn new(user: Option<String>) -> Self {
+        AccessCache {
+            user,
+            state: Mutex::new(State {
+                entries: HashMap::new(),
+                denied_ttl: DEFAULT_DENIED_TTL,
+                #[cfg(not(feature = "blocking"))]
+                wakers: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Name of the user whose access is probed.
+    pub(crate) fn user(&self) -> Option<&str> {
+        self.user.as_deref()
+    }
+
+    pub(crate) fn set_denied_ttl(&self, ttl: Duration) {
+        self.state().denied_ttl = ttl;
+    }
+
+    /// The cached access to database `name`, or the turn to probe it when
+    /// unknown or expired. `None` while another caller is probing.
+    fn next_turn<'a>(&'a self, state: &mut State, name: &str) -> Option<Turn<'a>> {
+        match state.entries.get(name) {
+            Some(Entry::Probing) => return None,
+            Some(Entry::Granted(granted)) => return Some(Turn::Cached(Ok(*granted))),
+            Some(entry @ Entry::Denied { error, .. }) if entry.is_alive(Instant::now()) => {
+                return Some(Turn::Cached(Err(error.clone())))
+            }
+            _ => {}
+        }
+        state.entries.insert(name.to_owned(), Entry::Probing);
+        Some(Turn::Probe(ProbeGuard {
+            cache: self,
+            name: name.to_owned(),
+            settled: false,
+        }))
+    }
+
+    /// Wait for the access to database `name` to be known, or for the turn
+    /// to probe it.
+    #[maybe_async::async_impl]
+    pub(crate) async fn turn<'a>(&'a self, name: &str) -> Turn<'a> {
+        futures_util::future::poll_fn(|cx| {
+            let mut state = self.state();
+            match self.next_turn(&mut state, name) {
+                Some(turn) => std::task::Poll::Ready(turn),
+                None => {
+                    if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                        state.wakers.push(cx.waker().clone());
+                    }
+                    std::task::Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    #[maybe_async::sync_impl]
+    pub(crate) fn turn<'a>(&'a self, name: &str) -> Turn<'a> {
+        let mut state = self.state();
+        loop {
+            if let Some(turn) = self.next_turn(&mut state, name) {
+                return turn;
+            }
+            state = self
+                .changed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Record the permission of the user on database `name`, if access to
+    /// it is granted.
+    pub(crate) fn set_permission(&self, name: &str, permission: Permission) {
+        if let Some(Entry::Granted(granted)) = self.state().entries.get_mut(name) {
+            *granted = Some(permission);
+        }
+    }
+
+    /// Forget the access to database `name`, to probe it again on next use.
+    pub(crate) fn forget(&self, name: &str) {
+        let mut state = self.state();
+        if !matches!(state.entries.get(name), Some(Entry::Probing)) {
+            state.entries.remove(name);
+        }
+    }
+
+    /// The access to all databases probed so far, without the expired
+    /// denials.
+    pub(crate) fn snapshot(&self) -> HashMap<String, DatabaseAccess> {
+        let now = Instant::now();
+        self.state()
+            .entries
+            .iter()
+            .filter_map(|(name, entry)| {
+                let access = match entry {
+                    Entry::Probing => return None,
+                    Entry::Granted(granted) => DatabaseAccess::Granted { granted: *granted },
+                    Entry::Denied { .. } if !entry.is_alive(now) => return None,
+                    Entry::Denied { error, until } => DatabaseAccess::Denied {
+                        error: error.clone(),
+                        expires_in: *until - now,
+                    },
+                };
+                Some((name.clone(), access))
+            })
+            .collect()
+    }
+
+    /// Replace the entry of database `name`, and wake up the callers waiting
+    /// for it.
+    fn settle(&self, name: &str, entry: Option<Entry>) {
+        let mut state = self.state();
+        match entry {
+            Some(entry) => state.entries.insert(name.to_owned(), entry),
+            None => state.entries.remove(name),
+        };
+        self.changed.notify_all();
+        #[cfg(not(feature = "blocking"))]
+        state.wakers.drain(..).for_each(std::task::Waker::wake);
+    }
+}
+
+/// The turn to probe the access to a database, see [`Turn::Probe`].
+pub(crate) struct ProbeGuard<'a> {
+    cache: &'a AccessCache,
+    name: String,
+    settled: bool,
+}
+
+impl ProbeGuard<'_> {
+    pub(crate) fn granted(mut self) {
+        self.settled = true;
+        self.cache.settle(&self.name, Some(Entry::Granted(None)));
+    }
+
+    pub(crate) fn denied(mut self, error: ArangoError) {
+        self.settled = true;
+        let until = Instant::now() + self.cache.state().denied_ttl;
+        self.cache
+            .settle(&self.name, Some(Entry::Denied { error, until }));
+    }
+}
+
+impl Drop for ProbeGuard<'_> {
+    fn drop(&mut self) {
+        if !self.settled {
+            self.cache.settle(&self.name, None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use http::Method;
+    use serde_json::json;
+
+    use crate::{
+        connection::{DatabaseAccess, Permission},
+        test_support::{fixtures, MockClient, MockResponse},
+        ClientError,
+    };
+
+    const CURRENT: &str = "/_db/tenant/_api/database/current";
+
+    fn database_info() -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({
+                "error": false,
+                "code": 200,
+                "result": { "name": "tenant", "id": "1", "path": "", "isSystem": false },
+            }),
+        )
+    }
+
+    fn denied() -> MockResponse {
+        MockResponse::json(
+            401,
+            fixtures::error(401, 11, "not authorized to execute this request"),
+        )
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn granted_access_is_cached() {
+        let mock = MockClient::default();
+        mock.once(Method::GET, CURRENT, database_info());
+        let conn = mock.connection();
+        conn.db("tenant").await.unwrap();
+        let db = conn.db("tenant").await.unwrap();
+        assert!(matches!(
+            conn.database_access().get("tenant"),
+            Some(DatabaseAccess::Granted { granted: None })
+        ));
+
+        mock.once(
+            Method::GET,
+            "/_db/tenant/_api/user/root/database/tenant",
+            MockResponse::json(200, json!({ "error": false, "code": 200, "result": "ro" })),
+        );
+        let granted = db.access().await.unwrap();
+        assert_eq!(granted, Permission::ReadOnly);
+        let granted = db.access().await.unwrap();
+        assert_eq!(granted, Permission::ReadOnly);
+        assert!(matches!(
+            conn.database_access().get("tenant"),
+            Some(DatabaseAccess::Granted {
+                granted: Some(Permission::ReadOnly)
+            })
+        ));
+        assert_eq!(mock.requests().len(), 2);
+
+        // permissions changed on the server
+        mock.once(Method::GET, CURRENT, database_info());
+        mock.once(
+            Method::GET,
+            "/_db/tenant/_api/user/root/database/tenant",
+            MockResponse::json(200, json!({ "error": false, "code": 200, "result": "rw" })),
+        );
+        let granted = db.reverify().await.unwrap();
+        assert_eq!(granted, Permission::ReadWrite);
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn denied_access_is_cached_for_ttl() {
+        let mock = MockClient::default();
+        mock.once(Method::GET, CURRENT, denied());
+        let conn = mock.connection();
+        for _ in 0..3 {
+            let result = conn.db("tenant").await;
+            assert!(
+                matches!(result, Err(ClientError::Arango(ref e)) if e.code() == 401),
+                "{:?}",
+                result
+            );
+        }
+        assert_eq!(mock.requests().len(), 1);
+        match conn.database_access().get("tenant") {
+            Some(DatabaseAccess::Denied { error, expires_in }) => {
+                assert_eq!(error.error_num(), 11);
+                assert!(*expires_in <= Duration::from_secs(30));
+            }
+            other => panic!("unexpected access {:?}", other),
+        }
+
+        // an expired denial is probed again
+        let mock = MockClient::default();
+        let conn = mock.connection().denied_access_ttl(Duration::ZERO);
+        mock.once(Method::GET, CURRENT, denied());
+        let result = conn.db("tenant").await;
+        assert!(result.is_err());
+        assert!(conn.database_access().is_empty());
+        mock.once(Method::GET, CURRENT, database_info());
+        let result = conn.db("tenant").await;
+        assert!(result.is_ok());
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn transport_errors_are_not_cached() {
+        let mock = MockClient::default();
+        let conn = mock.connection();
+        // no response registered
+        let result = conn.db("tenant").await;
+        assert!(
+            matches!(result, Err(ClientError::HttpClient(_))),
+            "{:?}",
+            result
+        );
+        assert!(conn.database_access().is_empty());
+
+        mock.once(Method::GET, CURRENT, database_info());
+        let result = conn.db("tenant").await;
+        assert!(result.is_ok());
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn lifecycle_errors_are_not_cached() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            CURRENT,
+            MockResponse::json(404, fixtures::error(404, 1228, "database not found")),
+        );
+        let conn = mock.connection();
+        let result = conn.db("tenant").await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1228),
+            "{:?}",
+            result
+        );
+        assert!(conn.database_access().is_empty());
+
+        mock.once(Method::GET, CURRENT, database_info());
+        let result = conn.db("tenant").await;
+        assert!(result.is_ok());
+        mock.assert_no_pending();
+    }
+
+    fn system_info() -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({
+                "error": false,
+                "code": 200,
+                "result": { "name": "_system", "id": "1", "path": "", "isSystem": true },
+            }),
+        )
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn created_database_is_probed_again() {
+        let mock = MockClient::default();
+        let conn = mock.connection();
+        mock.once(Method::GET, CURRENT, denied());
+        let result = conn.db("tenant").await;
+        assert!(result.is_err());
+
+        mock.once(
+            Method::GET,
+            "/_db/_system/_api/database/current",
+            system_info(),
+        );
+        mock.once(
+            Method::POST,
+            "/_db/_system/_api/database",
+            MockResponse::json(201, json!({ "error": false, "code": 201, "result": true })),
+        );
+        mock.once(Method::GET, CURRENT, database_info());
+        let sys = conn.system_db().await.unwrap();
+        sys.create_database("tenant").await.unwrap();
+
+        mock.once(Method::GET, CURRENT, database_info());
+        let result = conn.db("tenant").await;
+        assert!(result.is_ok());
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn dropped_database_is_probed_again() {
+        let mock = MockClient::default();
+        let conn = mock.connection();
+        mock.once(Method::GET, CURRENT, database_info());
+        conn.db("tenant").await.unwrap();
+
+        mock.once(
+            Method::GET,
+            "/_db/_system/_api/database/current",
+            system_info(),
+        );
+        mock.once(
+            Method::DELETE,
+            "/_db/_system/_api/database/tenant",
+            MockResponse::json(200, json!({ "error": false, "code": 200, "result": true })),
+        );
+        let sys = conn.system_db().await.unwrap();
+        sys.drop_database("tenant").await.unwrap();
+        assert!(conn.database_access().get("tenant").is_none());
+
+        mock.once(
+            Method::GET,
+            CURRENT,
+            MockResponse::json(404, fixtures::error(404, 1228, "database not found")),
+        );
+        let result = conn.db("tenant").await;
+        assert!(
+            matches!(result, Err(ClientError::Arango(ref e)) if e.error_num() == 1228),
+            "{:?}",
+            result
+        );
+        mock.assert_no_pending();
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn concurrent_probes_are_shared() {
+        let mock = MockClient::default();
+        mock.once(Method::GET, CURRENT, database_info().with_yields(3));
+        let conn = mock.connection();
+        let (first, second, third) =
+            futures_util::join!(conn.db("tenant"), conn.db("tenant"), conn.db("tenant"));
+        assert!(first.is_ok() && second.is_ok() && third.is_ok());
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[test]
+    fn waiting_turn_keeps_one_waker() {
+        use std::{future::Future, task::Context};
+
+        use super::{AccessCache, Turn};
+
+        let cache = AccessCache::new(None);
+        let _probe = match futures_util::FutureExt::now_or_never(cache.turn("tenant")) {
+            Some(Turn::Probe(probe)) => probe,
+            _ => panic!("the first caller should probe"),
+        };
+        let mut waiting = Box::pin(cache.turn("tenant"));
+        let waker = futures_util::task::noop_waker();
+        for _ in 0..3 {
+            assert!(waiting
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_pending());
+        }
+        assert_eq!(cache.state().wakers.len(), 1);
+    }
+}
@@ -19,7 +19,7 @@
 /// let no_auth = Auth::default();
 /// ```
 #[derive(Debug, Clone)]
-pub(crate) enum Auth<'a> {
+pub enum Auth<'a> {
     /// Basic auth
     Basic(Credential<'a>),
     /// JSON Web Token (JWT) auth
@@ -46,7 +46,7 @@ impl<'a> Auth<'a> {
 
 /// Username and password holder for authentication
 #[derive(Debug, Clone, Hash)]
-pub(crate) struct Credential<'a> {
+pub struct Credential<'a> {
     /// username
     pub username: &'a str,
     /// password
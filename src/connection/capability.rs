@@ -0,0 +1,466 @@
+//! Endpoints deprecated or removed by the server across versions.
+//!
+//! The helpers using one of the endpoints of the table check it against the
+//! version of the server before sending their request: a deprecated endpoint
+//! is still used, with a warning logged once per connection, while a removed
+//! one is either replaced by its modern equivalent or refused with
+//! [`ClientError::EndpointRemoved`], without any request.
+//!
+//! The version is fetched from `_api/version` by the first such check of a
+//! connection, or its [warm-up](super::GenericConnection::warm_up), and
+//! cached for the lifetime of the connection. Options only known by recent
+//! servers, like the overwrite modes of inserts, are checked against it the
+//! same way.
+//!
+//! The same goes for the other lookups the driver makes on its own, like the
+//! parsing of queries on a read-only handle: a user not allowed to make one,
+//! e.g. on a server started with `--server.harden`, is only refused once.
+//! The refusal is cached with a warning, and what depends on the lookup
+//! skipped, rather than the operation which needed it failed.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::{collections::HashSet, sync::Mutex};
+
+use log::warn;
+use maybe_async::maybe_async;
+use uclient::ClientExt;
+use url::Url;
+
+use super::{Session, Version};
+use crate::{error::is_forbidden, response::deserialize_response, ClientError};
+
+/// Version of the server, compared by major, minor and patch version, a
+/// missing patch version reading as 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ServerVersion {
+    major: u16,
+    minor: u16,
+    patch: u16,
+}
+
+impl ServerVersion {
+    /// Parse versions like `3.11.4` or `3.12.0-devel`.
+    pub(crate) fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.split(|c: char| !c.is_ascii_digit());
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+        Some(ServerVersion {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+/// First version with the overwrite modes of inserts, see
+/// [`InsertOptions::overwrite_mode`](crate::document::options::InsertOptions::overwrite_mode).
+#[cfg(feature = "documents")]
+pub(crate) const OVERWRITE_MODE_SINCE: ServerVersion = ServerVersion {
+    major: 3,
+    minor: 7,
+    patch: 0,
+};
+
+/// First version refilling index caches on writes.
+#[cfg(feature = "documents")]
+pub(crate) const REFILL_INDEX_CACHES_SINCE: ServerVersion = ServerVersion {
+    major: 3,
+    minor: 10,
+    patch: 2,
+};
+
+/// First version with external versioning of documents by a
+/// `versionAttribute`.
+#[cfg(feature = "documents")]
+pub(crate) const VERSION_ATTRIBUTE_SINCE: ServerVersion = ServerVersion {
+    major: 3,
+    minor: 12,
+    patch: 0,
+};
+
+/// An endpoint of the server which is or will be removed.
+#[derive(Debug)]
+pub(crate) struct Endpoint {
+    /// Method and path, like `PUT /_api/collection/{name}/load`.
+    pub(crate) name: &'static str,
+    pub(crate) deprecated_in: &'static str,
+    pub(crate) removed_in: Option<&'static str>,
+    /// What to use instead.
+    pub(crate) alternative: &'static str,
+}
+
+/// Whether the server still serves an [`Endpoint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Support {
+    Available,
+    Deprecated,
+    Removed,
+}
+
+impl Endpoint {
+    fn support(&self, version: ServerVersion) -> Support {
+        let reached = |v: &str| matches!(ServerVersion::parse(v), Some(v) if version >= v);
+        if matches!(self.removed_in, Some(v) if reached(v)) {
+            Support::Removed
+        } else if reached(self.deprecated_in) {
+            Support::Deprecated
+        } else {
+            Support::Available
+        }
+    }
+
+    /// The error refusing to use this endpoint.
+    #[cfg(any(feature = "admin", feature = "mmfiles"))]
+    pub(crate) fn removed(&self) -> ClientError {
+        ClientError::EndpointRemoved {
+            endpoint: self.name,
+            removed_in: self.removed_in.unwrap_or(self.deprecated_in),
+            alternative: self.alternative,
+        }
+    }
+}
+
+/// Loading and unloading collections had no effect since the MMFiles storage
+/// engine was removed: collections are always available.
+pub(crate) const COLLECTION_LOAD: Endpoint = Endpoint {
+    name: "PUT /_api/collection/{name}/load",
+    deprecated_in: "3.8",
+    removed_in: Some("3.12"),
+    alternative: "GET /_api/collection/{name}, collections are always loaded",
+};
+
+pub(crate) const COLLECTION_UNLOAD: Endpoint = Endpoint {
+    name: "PUT /_api/collection/{name}/unload",
+    deprecated_in: "3.8",
+    removed_in: Some("3.12"),
+    alternative: "nothing, collections are always loaded",
+};
+
+/// The simple queries are superseded by AQL.
+#[cfg(all(feature = "documents", feature = "aql"))]
+pub(crate) const SIMPLE_ANY: Endpoint = Endpoint {
+    name: "PUT /_api/simple/any",
+    deprecated_in: "3.4",
+    removed_in: Some("3.12"),
+    alternative: "an AQL query like FOR doc IN @@collection SORT RAND() LIMIT 1 RETURN doc",
+};
+
+#[cfg(feature = "mmfiles")]
+pub(crate) const COLLECTION_ROTATE: Endpoint = Endpoint {
+    name: "PUT /_api/collection/{name}/rotate",
+    deprecated_in: "3.6",
+    removed_in: Some("3.7"),
+    alternative: "nothing, the RocksDB storage engine has no journals",
+};
+
+#[cfg(feature = "admin")]
+pub(crate) const WAL_PROPERTIES: Endpoint = Endpoint {
+    name: "GET /_admin/wal/properties",
+    deprecated_in: "3.6",
+    removed_in: Some("3.7"),
+    alternative: "the --rocksdb.* startup options of arangod",
+};
+
+#[cfg(feature = "admin")]
+pub(crate) const SET_WAL_PROPERTIES: Endpoint = Endpoint {
+    name: "PUT /_admin/wal/properties",
+    deprecated_in: "3.6",
+    removed_in: Some("3.7"),
+    alternative: "the --rocksdb.* startup options of arangod",
+};
+
+/// Lookups made by the driver on its own, see [`Capabilities::forbid`].
+pub(crate) const VERSION_LOOKUP: &str = "GET /_api/version";
+#[cfg(feature = "aql")]
+pub(crate) const QUERY_PARSE: &str = "POST /_api/query";
+#[cfg(all(feature = "enterprise", feature = "documents"))]
+pub(crate) const COLLECTION_PROPERTIES: &str = "GET /_api/collection/{name}/properties";
+
+/// Version of the server, the deprecated endpoints already warned about and
+/// the lookups refused to the user, shared by a connection and the handles
+/// derived from it.
+#[derive(Debug, Default)]
+pub(crate) struct Capabilities {
+    version: Mutex<Option<ServerVersion>>,
+    warned: Mutex<HashSet<&'static str>>,
+    forbidden: Mutex<HashSet<&'static str>>,
+}
+
+impl Capabilities {
+    pub(crate) fn set_version(&self, version: &str) {
+        if let Some(version) = ServerVersion::parse(version) {
+            *self.version.lock().unwrap_or_else(|e| e.into_inner()) = Some(version);
+        }
+    }
+
+    fn version(&self) -> Option<ServerVersion> {
+        *self.version.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether `lookup` was refused to the user before.
+    pub(crate) fn is_forbidden(&self, lookup: &'static str) -> bool {
+        self.forbidden
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(lookup)
+    }
+
+    /// Remember that `lookup` is refused to the user, logging a warning the
+    /// first time that `skipped` goes without it.
+    pub(crate) fn forbid(&self, lookup: &'static str, skipped: &str) {
+        let first = self
+            .forbidden
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(lookup);
+        if first {
+            warn!("{} is forbidden to the user, skipping {}", lookup, skipped);
+        }
+    }
+
+    /// Whether `endpoint` is served by `version`, logging a warning the first
+    /// time a deprecated one is used.
+    fn check(&self, endpoint: &'static Endpoint, version: ServerVersion) -> Support {
+        let support = endpoint.support(version);
+        if support == Support::Deprecated {
+            let first = self
+                .warned
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(endpoint.name);
+            if first {
+                warn!(
+                    "{} is deprecated since ArangoDB {}, use {} instead",
+                    endpoint.name, endpoint.deprecated_in, endpoint.alternative
+                );
+            }
+        }
+        support
+    }
+}
+
+impl<C: ClientExt> Session<C> {
+    /// Whether the server at `url` serves `endpoint`, after its version.
+    ///
+    /// When the version cannot be fetched or parsed the endpoint is deemed
+    /// available, for the request to tell.
+    #[maybe_async]
+    pub(crate) async fn endpoint_support(&self, url: &Url, endpoint: &'static Endpoint) -> Support {
+        match self.server_version(url).await {
+            Some(version) => self.registry().capabilities().check(endpoint, version),
+            None => Support::Available,
+        }
+    }
+
+    /// Version of the server at `url`, fetched by the first call of the
+    /// connection, if it can be fetched and parsed.
+    #[maybe_async]
+    pub(crate) async fn server_version(&self, url: &Url) -> Option<ServerVersion> {
+        let capabilities = self.registry().capabilities();
+        match capabilities.version() {
+            Some(version) => Some(version),
+            None if capabilities.is_forbidden(VERSION_LOOKUP) => None,
+            None => {
+                match self.fetch_version(url).await {
+                    Ok(version) => capabilities.set_version(&version.version),
+                    Err(e) if is_forbidden(&e) => {
+                        capabilities.forbid(VERSION_LOOKUP, "the checks against the server version")
+                    }
+                    Err(_) => {}
+                }
+                capabilities.version()
+            }
+        }
+    }
+
+    #[maybe_async]
+    async fn fetch_version(&self, url: &Url) -> Result<Version, ClientError> {
+        let url = url
+            .join("/_api/version")
+            .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", url)))?;
+        let resp = self.get(url, "").await?;
+        deserialize_response(&resp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(any(feature = "admin", feature = "mmfiles"))]
+    use http::Method;
+    #[cfg(any(feature = "admin", feature = "mmfiles"))]
+    use serde_json::json;
+
+    use super::*;
+    #[cfg(any(feature = "admin", feature = "mmfiles"))]
+    use crate::test_support::{fixtures, MockClient, MockResponse};
+    #[cfg(feature = "admin")]
+    use crate::wal::WalProperties;
+
+    #[cfg(any(feature = "admin", feature = "mmfiles"))]
+    #[test]
+    fn parse_versions() {
+        let parse = |v| ServerVersion::parse(v).map(|v| (v.major, v.minor, v.patch));
+        assert_eq!(parse("3.11.4"), Some((3, 11, 4)));
+        assert_eq!(parse("3.12.0-devel"), Some((3, 12, 0)));
+        assert_eq!(parse("3.8"), Some((3, 8, 0)));
+        assert_eq!(parse("devel"), None);
+        assert!(ServerVersion::parse("3.10.0") > ServerVersion::parse("3.9.9"));
+        assert!(ServerVersion::parse("3.10.2") > ServerVersion::parse("3.10.1"));
+    }
+
+    #[test]
+    fn support_by_version() {
+        let support = |v| COLLECTION_LOAD.support(ServerVersion::parse(v).unwrap());
+        assert_eq!(support("3.7.2"), Support::Available);
+        assert_eq!(support("3.8.0"), Support::Deprecated);
+        assert_eq!(support("3.11.9"), Support::Deprecated);
+        assert_eq!(support("3.12.0"), Support::Removed);
+        assert_eq!(support("4.0.0"), Support::Removed);
+
+        #[cfg(all(feature = "documents", feature = "aql"))]
+        for (version, expected) in [
+            ("3.3.9", Support::Available),
+            ("3.8.0", Support::Deprecated),
+            ("3.10.13", Support::Deprecated),
+            ("3.12.0", Support::Removed),
+        ] {
+            let version = ServerVersion::parse(version).unwrap();
+            assert_eq!(SIMPLE_ANY.support(version), expected);
+        }
+    }
+
+    #[cfg(feature = "admin")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn helpers_by_server_version() {
+        let collection = "/_db/test_db/_api/collection/users";
+        for (server, removed) in [("3.8.9", false), ("3.10.13", false), ("3.12.0", true)] {
+            let mock = MockClient::default();
+            mock.once(
+                Method::GET,
+                "/_api/version",
+                MockResponse::json(200, fixtures::version(server)),
+            );
+            mock.once(
+                Method::GET,
+                collection,
+                MockResponse::json(200, fixtures::collection_info("users", "100")),
+            );
+            let db = mock.database("test_db");
+            let coll = db.collection("users").await.unwrap();
+
+            let mut info = fixtures::collection_info("users", "100");
+            info["count"] = json!(3);
+            let info = MockResponse::json(200, info);
+            if removed {
+                // read instead of loading
+                mock.once(Method::GET, &format!("{}/count", collection), info.clone());
+                mock.once(Method::GET, collection, info);
+            } else {
+                mock.once(Method::PUT, &format!("{}/load", collection), info.clone());
+                mock.once(Method::PUT, &format!("{}/unload", collection), info);
+            }
+            let loaded = coll.load(true).await.unwrap();
+            assert_eq!(loaded.count, Some(3));
+            coll.unload().await.unwrap();
+
+            // picked by an AQL query instead of the simple query
+            #[cfg(all(feature = "documents", feature = "aql"))]
+            {
+                let doc = fixtures::document("users", "1", "_a", json!({ "name": "ada" }));
+                if removed {
+                    mock.expect_cursor("test_db", vec![vec![doc]]);
+                } else {
+                    mock.once(
+                        Method::PUT,
+                        "/_db/test_db/_api/simple/any",
+                        MockResponse::json(
+                            200,
+                            json!({ "error": false, "code": 200, "document": doc }),
+                        ),
+                    );
+                }
+                let picked = coll.random_document::<serde_json::Value>().await.unwrap();
+                assert_eq!(picked.unwrap().document["name"], "ada");
+            }
+
+            // refused without any request
+            let read = db.wal_properties().await;
+            let change = db.set_wal_properties(WalProperties::default()).await;
+            for result in [read.map(|_| ()), change.map(|_| ())] {
+                assert!(
+                    matches!(
+                        result,
+                        Err(ClientError::EndpointRemoved {
+                            removed_in: "3.7",
+                            ..
+                        })
+                    ),
+                    "{}: {:?}",
+                    server,
+                    result
+                );
+            }
+            mock.assert_no_pending();
+
+            // the version is fetched once for the connection
+            let versions = mock
+                .requests()
+                .iter()
+                .filter(|r| r.path == "/_api/version")
+                .count();
+            assert_eq!(versions, 1);
+            let helpers = if cfg!(all(feature = "documents", feature = "aql")) {
+                5
+            } else {
+                4
+            };
+            assert_eq!(mock.requests().len(), helpers);
+        }
+    }
+
+    #[cfg(feature = "admin")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn unknown_version_uses_the_endpoint() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("devel")),
+        );
+        let path = "/_db/test_db/_admin/wal/properties";
+        mock.once(
+            Method::GET,
+            path,
+            MockResponse::json(
+                200,
+                json!({ "error": false, "code": 200, "logfileSize": 1 }),
+            ),
+        );
+        let db = mock.database("test_db");
+        let properties = db.wal_properties().await.unwrap();
+        assert_eq!(properties.logfile_size, Some(1));
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "mmfiles")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn rotate_journal_after_mmfiles() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.10.0")),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users",
+            MockResponse::json(200, fixtures::collection_info("users", "100")),
+        );
+        let db = mock.database("test_db");
+        let coll = db.collection("users").await.unwrap();
+        let result = coll.rotate_journal().await;
+        assert!(matches!(result, Err(ClientError::EndpointRemoved { .. })));
+        mock.assert_no_pending();
+    }
+}
@@ -0,0 +1,365 @@
+//! Racing the handshake against several endpoints of a deployment, like the
+//! Coordinators of a cluster, see
+//! [`GenericConnection::establish_any`](super::GenericConnection::establish_any).
+//!
+//! Every endpoint is probed at once, so that a dead endpoint listed first
+//! does not delay the connection by its connect timeout. The first endpoint
+//! to answer the handshake wins, and the reasons of the others for failing
+//! are reported only if none answers before the deadline.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::time::Duration;
+#[cfg(not(feature = "blocking"))]
+use std::{
+    sync::{Arc, Mutex},
+    task::Waker,
+    time::Instant,
+};
+
+use http::header::SERVER;
+use log::trace;
+use uclient::ClientExt;
+use url::Url;
+
+use crate::{error::EndpointFailure, ClientError};
+
+/// Check that the server at `url` is an ArangoDB.
+#[maybe_async::maybe_async]
+pub(crate) async fn handshake<C: ClientExt>(client: &C, url: Url) -> Result<(), ClientError> {
+    let resp = client.get(url, "").await?;
+    // have `Server` in header
+    match resp.headers().get(SERVER) {
+        Some(server) => {
+            // value of `Server` is `ArangoDB`
+            let server_value = String::from_utf8_lossy(server.as_bytes());
+            if server_value.eq_ignore_ascii_case("ArangoDB") {
+                trace!("Validate arangoDB server done.");
+                Ok(())
+            } else {
+                Err(ClientError::InvalidServer(server_value.into_owned()))
+            }
+        }
+        None => Err(ClientError::InvalidServer("Unknown".to_owned())),
+    }
+}
+
+/// Short reason of a failed handshake, as reported by
+/// [`ClientError::NoEndpointAvailable`].
+pub(crate) fn failure_reason(error: &ClientError) -> String {
+    match error {
+        ClientError::HttpClient(uclient::ClientError::HttpClient(message)) => {
+            let lower = message.to_lowercase();
+            if lower.contains("refused") {
+                "refused".to_owned()
+            } else if lower.contains("tls")
+                || lower.contains("ssl")
+                || lower.contains("certificate")
+            {
+                "TLS error".to_owned()
+            } else if lower.contains("timed out") || lower.contains("timeout") {
+                "timeout".to_owned()
+            } else if lower.contains("dns") || lower.contains("resolve") {
+                "DNS error".to_owned()
+            } else {
+                message.clone()
+            }
+        }
+        ClientError::InvalidServer(server) => format!("not ArangoDB ({})", server),
+        e => e.to_string(),
+    }
+}
+
+/// The failures of all endpoints, in the order they were given. The
+/// endpoints without outcome did not answer before the deadline.
+fn failures(endpoints: Vec<Url>, mut outcomes: Vec<Option<String>>) -> ClientError {
+    let failures = endpoints
+        .into_iter()
+        .zip(outcomes.drain(..))
+        .map(|(url, reason)| EndpointFailure {
+            endpoint: super::endpoint(&url),
+            reason: reason.unwrap_or_else(|| "timeout".to_owned()),
+        })
+        .collect();
+    ClientError::NoEndpointAvailable { failures }
+}
+
+/// Make the handshake with every endpoint at once, each with its own client,
+/// and return the first endpoint to succeed within `timeout`.
+#[maybe_async::async_impl]
+pub(crate) async fn race<C: ClientExt>(
+    candidates: Vec<(Url, C)>,
+    timeout: Duration,
+) -> Result<Url, ClientError> {
+    use futures_util::{
+        future::{self, Either},
+        stream::{FuturesUnordered, StreamExt},
+    };
+
+    let endpoints: Vec<Url> = candidates.iter().map(|(url, _)| url.clone()).collect();
+    let mut outcomes = vec![None; endpoints.len()];
+    let mut pending: FuturesUnordered<_> = candidates
+        .into_iter()
+        .enumerate()
+        .map(|(i, (url, client))| async move { (i, handshake(&client, url).await) })
+        .collect();
+    let mut deadline = sleep(timeout);
+    loop {
+        match future::select(pending.next(), &mut deadline).await {
+            Either::Left((Some((i, Ok(()))), _)) => return Ok(endpoints[i].clone()),
+            Either::Left((Some((i, Err(e))), _)) => {
+                trace!("Handshake with {} failed: {}", endpoints[i], e);
+                outcomes[i] = Some(failure_reason(&e));
+            }
+            Either::Left((None, _)) | Either::Right(_) => break,
+        }
+    }
+    Err(failures(endpoints, outcomes))
+}
+
+#[maybe_async::sync_impl]
+pub(crate) fn race<C: ClientExt + Send + 'static>(
+    candidates: Vec<(Url, C)>,
+    timeout: Duration,
+) -> Result<Url, ClientError> {
+    use std::{sync::mpsc, time::Instant};
+
+    let deadline = Instant::now() + timeout;
+    let endpoints: Vec<Url> = candidates.iter().map(|(url, _)| url.clone()).collect();
+    let mut outcomes = vec![None; endpoints.len()];
+    let (sender, receiver) = mpsc::channel();
+    for (i, (url, client)) in candidates.into_iter().enumerate() {
+        let sender = sender.clone();
+        // the handshakes still running at the deadline are left behind, their
+        // outcome is dropped with the channel
+        std::thread::spawn(move || {
+            let _ = sender.send((i, handshake(&client, url)));
+        });
+    }
+    drop(sender);
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    while let Ok((i, outcome)) = receiver.recv_timeout(remaining()) {
+        match outcome {
+            Ok(()) => return Ok(endpoints[i].clone()),
+            Err(e) => {
+                trace!("Handshake with {} failed: {}", endpoints[i], e);
+                outcomes[i] = Some(failure_reason(&e));
+            }
+        }
+    }
+    Err(failures(endpoints, outcomes))
+}
+
+/// Future ready once `duration` elapsed, without depending on an async
+/// runtime: the time is kept by a thread parked until then, and woken to stop
+/// as soon as the future is dropped.
+#[cfg(not(feature = "blocking"))]
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        until: Instant::now() + duration,
+        timer: None,
+    }
+}
+
+/// See [`sleep`].
+#[cfg(not(feature = "blocking"))]
+pub(crate) struct Sleep {
+    until: Instant,
+    timer: Option<Timer>,
+}
+
+/// The thread of a [`Sleep`] waking its task, stopped once the waker is
+/// taken away.
+#[cfg(not(feature = "blocking"))]
+struct Timer {
+    waker: Arc<Mutex<Option<Waker>>>,
+    thread: std::thread::Thread,
+}
+
+#[cfg(not(feature = "blocking"))]
+impl Timer {
+    fn start(until: Instant, waker: Waker) -> Self {
+        let waker = Arc::new(Mutex::new(Some(waker)));
+        let shared = Arc::clone(&waker);
+        let thread = std::thread::spawn(move || loop {
+            let mut waker = shared.lock().unwrap_or_else(|e| e.into_inner());
+            if waker.is_none() {
+                return;
+            }
+            let remaining = until.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                if let Some(waker) = waker.take() {
+                    waker.wake();
+                }
+                return;
+            }
+            drop(waker);
+            std::thread::park_timeout(remaining);
+        });
+        Timer {
+            waker,
+            thread: thread.thread().clone(),
+        }
+    }
+
+    fn waker(&self) -> std::sync::MutexGuard<'_, Option<Waker>> {
+        self.waker.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.waker().take();
+        self.thread.unpark();
+    }
+}
+
+#[cfg(not(feature = "blocking"))]
+impl std::future::Future for Sleep {
+    type Output = ();
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<()> {
+        use std::task::Poll;
+
+        if Instant::now() >= self.until {
+            self.timer = None;
+            return Poll::Ready(());
+        }
+        match &self.timer {
+            Some(timer) => match &mut *timer.waker() {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                waker => *waker = Some(cx.waker().clone()),
+            },
+            None => self.timer = Some(Timer::start(self.until, cx.waker().clone())),
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use http::Method;
+    use url::Url;
+
+    use super::race;
+    use crate::{
+        error::EndpointFailure,
+        test_support::{MockClient, MockResponse},
+        ClientError,
+    };
+
+    fn endpoint(url: &str, response: MockResponse) -> (Url, MockClient) {
+        let mock = MockClient::default();
+        mock.on(Method::GET, "/", response);
+        (Url::parse(url).unwrap(), mock)
+    }
+
+    fn arango() -> MockResponse {
+        MockResponse::new(200).with_header("Server", "ArangoDB")
+    }
+
+    fn refused() -> MockResponse {
+        MockResponse::transport_error("error trying to connect: Connection refused (os error 111)")
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn first_endpoint_to_answer_wins() {
+        let started = Instant::now();
+        let winner = race(
+            vec![
+                endpoint("http://dead:8529/", refused()),
+                endpoint(
+                    "http://slow:8529/",
+                    arango().with_delay(Duration::from_secs(5)),
+                ),
+                endpoint("http://fast:8529/", arango()),
+            ],
+            Duration::from_secs(10),
+        )
+        .await
+        .unwrap();
+        assert_eq!(winner.as_str(), "http://fast:8529/");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn failures_are_aggregated() {
+        let started = Instant::now();
+        let result = race(
+            vec![
+                endpoint("http://dead:8529/", refused()),
+                endpoint(
+                    "http://slow:8529/",
+                    arango().with_delay(Duration::from_secs(5)),
+                ),
+                endpoint(
+                    "https://tls:8529/",
+                    MockResponse::transport_error("invalid peer certificate: UnknownIssuer"),
+                ),
+            ],
+            Duration::from_millis(100),
+        )
+        .await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "3 endpoints tried: refused, timeout, TLS error"
+        );
+        match error {
+            ClientError::NoEndpointAvailable { failures } => assert_eq!(
+                failures[1],
+                EndpointFailure {
+                    endpoint: Url::parse("http://slow:8529/").unwrap(),
+                    reason: "timeout".to_owned(),
+                }
+            ),
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn answer_not_from_arango_is_a_failure() {
+        let result = race(
+            vec![
+                endpoint("http://proxy:8529/", MockResponse::new(502)),
+                endpoint(
+                    "http://other:8529/",
+                    MockResponse::new(200).with_header("Server", "Arango\u{e9}"),
+                ),
+            ],
+            Duration::from_secs(1),
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "2 endpoints tried: not ArangoDB (Unknown), not ArangoDB (Arango\u{e9})"
+        );
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[test]
+    fn dropped_sleep_stops_its_thread() {
+        use std::{future::Future, sync::Arc, task::Context};
+
+        let mut nap = Box::pin(super::sleep(Duration::from_secs(60)));
+        let waker = futures_util::task::noop_waker();
+        assert!(nap
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        let shared = Arc::downgrade(&nap.timer.as_ref().unwrap().waker);
+        drop(nap);
+
+        let started = Instant::now();
+        while shared.upgrade().is_some() {
+            assert!(started.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -33,10 +33,19 @@
 //! use arangors::Connection;
 //! let conn = Connection::establish_without_auth("http://localhost:8529").await.unwrap();
 //! ```
+//!
+//! To connect to the first available of several Coordinators of a cluster,
+//! see [`GenericConnection::establish_any`].
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug},
+    sync::Arc,
+    time::Duration,
+};
 
-use http::header::{HeaderMap, AUTHORIZATION, SERVER};
+use http::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use log::{debug, trace};
 use maybe_async::maybe_async;
 use serde::{Deserialize, Serialize};
@@ -46,18 +55,32 @@ use url::Url;
 
 use crate::{response::ArangoResult, ClientError};
 
-use super::{database::Database, response::deserialize_response};
+use super::{
+    database::{system_only, Database, SystemDatabase, SYSTEM_DATABASE},
+    response::deserialize_response,
+};
 
 #[cfg(feature = "cluster")]
-use self::options::{ClusterHealth, CreateDatabase, CreateDatabaseOptions};
+use self::options::{ClusterHealth, ClusterScaleTargets, ClusterServer, CreateDatabaseOptions};
 
-use self::{
-    auth::Auth,
-    role::{Admin, Normal},
-};
+use self::options::WarmUpOptions;
+use self::role::{Admin, Normal};
 
+pub(crate) mod access;
 mod auth;
+pub(crate) mod capability;
+pub(crate) mod endpoints;
 pub mod options;
+mod registry;
+mod stats;
+mod warm_up;
+
+pub use self::access::DatabaseAccess;
+pub use self::auth::{Auth, Credential};
+pub(crate) use self::registry::Session;
+pub use self::registry::{OpenResource, OpenResources, ShutdownReport};
+pub use self::stats::ConnectionStats;
+pub use self::warm_up::{WarmUpFailure, WarmUpReport};
 
 pub mod role {
     #[derive(Debug)]
@@ -67,7 +90,7 @@ pub mod role {
     pub struct Admin;
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Permission {
     #[serde(rename = "none")]
     NoAccess,
@@ -92,11 +115,17 @@ pub type Connection = GenericConnection<uclient::surf::SurfClient>;
 
 /// Connection is the top level API for this crate.
 /// It contains a http client, information about authentication, arangodb url.
-#[derive(Debug, Clone)]
+///
+/// Neither `Display` nor `Debug` show the credentials, see
+/// [`endpoint`](GenericConnection::endpoint).
+#[derive(Clone)]
 pub struct GenericConnection<C: ClientExt, S = Normal> {
-    session: Arc<C>,
+    session: Session<C>,
     arango_url: Url,
     username: String,
+    #[cfg(feature = "aql")]
+    deterministic_requests: bool,
+    read_only: bool,
     pub state: S,
 }
 
@@ -109,22 +138,11 @@ impl<S, C: ClientExt> GenericConnection<C, S> {
     /// - SERVER header in response header is not `ArangoDB` or empty
     #[maybe_async]
     pub async fn validate_server(arango_url: &str) -> Result<(), ClientError> {
+        let url = arango_url
+            .parse()
+            .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", arango_url)))?;
         let client = C::new(None)?;
-        let resp = client.get(arango_url.parse().unwrap(), "").await?;
-        // have `Server` in header
-        match resp.headers().get(SERVER) {
-            Some(server) => {
-                // value of `Server` is `ArangoDB`
-                let server_value = server.to_str().unwrap();
-                if server_value.eq_ignore_ascii_case("ArangoDB") {
-                    trace!("Validate arangoDB server done.");
-                    Ok(())
-                } else {
-                    Err(ClientError::InvalidServer(server_value.to_owned()))
-                }
-            }
-            None => Err(ClientError::InvalidServer("Unknown".to_owned())),
-        }
+        endpoints::handshake(&client, url).await
     }
 
     /// Get url for remote arangoDB server.
@@ -132,6 +150,43 @@ impl<S, C: ClientExt> GenericConnection<C, S> {
         &self.arango_url
     }
 
+    /// Url of the remote arangoDB server without the user name and password
+    /// it may contain, safe to be logged.
+    pub fn endpoint(&self) -> Url {
+        endpoint(&self.arango_url)
+    }
+
+    /// Serialize the bodies of AQL queries of the databases obtained from this
+    /// connection with sorted keys, see
+    /// [`Database::with_deterministic_requests`].
+    #[cfg(feature = "aql")]
+    pub fn deterministic_requests(mut self, enabled: bool) -> Self {
+        self.deterministic_requests = enabled;
+        self
+    }
+
+    /// Turn this connection into a read-only one.
+    ///
+    /// The databases, collections and transactions obtained from a read-only
+    /// connection refuse to run operations modifying data or schema with
+    /// `ClientError::ReadOnlyConnection`, without any request to the server.
+    /// This holds whatever the permissions of the user on the server. See
+    /// [`Database::read_only`] about AQL queries and transactions.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Whether this connection is read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    #[cfg(feature = "cluster")]
+    fn check_writable(&self, operation: &str) -> Result<(), ClientError> {
+        crate::error::check_writable(self.read_only, operation)
+    }
+
     /// Get HTTP session.
     ///
     /// Users can use this method to get a authorized session to access
@@ -140,20 +195,140 @@ impl<S, C: ClientExt> GenericConnection<C, S> {
     /// TODO This method should only be public in this crate when all features
     ///     are implemented.
     pub fn session(&self) -> Arc<C> {
-        Arc::clone(&self.session)
+        self.session.client()
     }
 
     /// Get database object with name.
     ///
+    /// The access to the database is probed with `GET _api/database/current`
+    /// the first time only: a granted access is cached for the lifetime of
+    /// the connection, a denied one for the
+    /// [`denied_access_ttl`](Self::denied_access_ttl), during which the
+    /// error of the server is returned again without any request. Concurrent
+    /// calls for the same database share one probe. See
+    /// [`Database::reverify`] to probe again after a change of permissions.
+    ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn db(&self, name: &str) -> Result<Database<C>, ClientError> {
-        let db = Database::new(name, self.url(), self.session());
-        db.info().await?;
+        let db = self.database(name);
+        db.verify_access().await?;
         Ok(db)
     }
 
+    /// How long [`db`](Self::db) caches a denied access before probing the
+    /// database again, 30 seconds by default.
+    ///
+    /// The setting is shared by the clones of this connection.
+    pub fn denied_access_ttl(self, ttl: Duration) -> Self {
+        self.session.access().set_denied_ttl(ttl);
+        self
+    }
+
+    /// The cached access to the databases probed so far by [`db`](Self::db),
+    /// by name, e.g. to render the status of each tenant database.
+    pub fn database_access(&self) -> HashMap<String, DatabaseAccess> {
+        self.session.access().snapshot()
+    }
+
+    /// Get the handle of the `_system` database, for the operations only
+    /// available there.
+    ///
+    /// Fails with [`ClientError::RequiresSystemDatabase`] when the user
+    /// cannot access `_system`.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn system_db(&self) -> Result<SystemDatabase<C>, ClientError> {
+        let db = self.database(SYSTEM_DATABASE);
+        db.verify_access()
+            .await
+            .map_err(|e| system_only(e, "access the _system database"))?;
+        Ok(SystemDatabase::new(db))
+    }
+
+    /// Handle of database `name` with the settings of the connection, without
+    /// any request.
+    fn database(&self, name: &str) -> Database<C> {
+        let db = Database::new(name, self.url(), self.session.clone());
+        #[cfg(feature = "aql")]
+        let db = db.with_deterministic_requests(self.deterministic_requests);
+        if self.read_only {
+            db.read_only()
+        } else {
+            db
+        }
+    }
+
+    /// Cursors and stream transactions opened through this connection which
+    /// are still alive on the server, and the number of requests in flight.
+    ///
+    /// Only resources opened by the handles derived from the connection are
+    /// tracked, not the ones opened through [`session`](Self::session).
+    pub fn open_resources(&self) -> OpenResources {
+        self.session.registry().open_resources()
+    }
+
+    /// Gauges of the requests sent through this connection and the handles
+    /// derived from it: in flight now and at the peak, waiting time, and
+    /// per endpoint counts.
+    ///
+    /// The peak of requests in flight is the one since the previous
+    /// snapshot, of any handle. Taking a snapshot only reads atomics.
+    pub fn stats(&self) -> ConnectionStats {
+        self.session.registry().stats().snapshot()
+    }
+
+    /// Open connections to the server and probe the access to `databases`
+    /// ahead of the first requests, e.g. while a serverless instance starts,
+    /// so that they do not pay for the TCP and TLS handshakes.
+    ///
+    /// All steps run at once, bounded by the
+    /// [`deadline`](options::WarmUpOptionsBuilder::deadline):
+    /// - [`connections`](options::WarmUpOptionsBuilder::connections)
+    ///   requests of `GET /_api/version`, whose connections are kept alive by
+    ///   the HTTP client for the next requests, reqwest keeping idle
+    ///   connections for 90 seconds;
+    /// - the probe of the access to each database done by [`db`](Self::db),
+    ///   cached for the next calls, and with
+    ///   [`collections`](options::WarmUpOptionsBuilder::collections) the
+    ///   listing of its collections.
+    ///
+    /// The credentials are sent along every request, and a JWT is obtained
+    /// once when the connection is established, so no authentication is left
+    /// to the first request. The steps which failed or were not done by the
+    /// deadline are listed in the report.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn warm_up(&self, databases: &[&str], options: WarmUpOptions) -> WarmUpReport
+    where
+        C: Send + Sync + 'static,
+    {
+        let databases = databases.iter().map(|name| self.database(name)).collect();
+        warm_up::warm_up(&self.session, &self.arango_url, databases, &options).await
+    }
+
+    /// Shut down the connection and all handles derived from it, like
+    /// databases, collections and cursors.
+    ///
+    /// New operations fail with [`ClientError::ShuttingDown`] right away.
+    /// The requests in flight are given up to `grace` to complete, then the
+    /// open stream transactions are aborted and the open cursors deleted, so
+    /// that they do not hold locks until their TTL expires. Releasing is best
+    /// effort: the resources which could not be released are listed in the
+    /// report.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        self.session.shutdown(grace).await
+    }
+
     /// Get a list of accessible database
     ///
     /// This function uses the API that is used to retrieve a list of
@@ -163,12 +338,12 @@ impl<S, C: ClientExt> GenericConnection<C, S> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn accessible_databases(&self) -> Result<HashMap<String, Permission>, ClientError> {
-        let url = self
-            .arango_url
-            .join(&format!("/_api/user/{}/database", &self.username))
-            .unwrap();
+        let url = api_url(
+            &self.arango_url,
+            &format!("/_api/user/{}/database", &self.username),
+        )?;
         let resp = self.session.get(url, "").await?;
-        let result: ArangoResult<HashMap<String, Permission>> = deserialize_response(resp.body())?;
+        let result: ArangoResult<HashMap<String, Permission>> = deserialize_response(&resp)?;
         Ok(result.unwrap())
     }
 
@@ -188,11 +363,16 @@ impl<S, C: ClientExt> GenericConnection<C, S> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn server_role(&self) -> Result<String, ClientError> {
-        let url = self.arango_url.join("/_admin/server/role").unwrap();
+        let url = api_url(&self.arango_url, "/_admin/server/role")?;
         let resp = self.session.get(url, "").await?;
-        let result: HashMap<String, Value> = deserialize_response(resp.body())?;
+        let result: HashMap<String, Value> = deserialize_response(&resp)?;
 
-        Ok(result.get("role").unwrap().as_str().unwrap().to_owned())
+        match result.get("role").and_then(Value::as_str) {
+            Some(role) => Ok(role.to_owned()),
+            None => Err(ClientError::MalformedResponse(
+                "server role without role attribute".to_owned(),
+            )),
+        }
     }
 
     /// Returns the health of the cluster as assessed by the supervision
@@ -203,12 +383,135 @@ impl<S, C: ClientExt> GenericConnection<C, S> {
     #[maybe_async]
     #[cfg(feature = "cluster")]
     pub async fn cluster_health(&self) -> Result<ClusterHealth, ClientError> {
-        let url = self.arango_url.join("/_admin/cluster/health").unwrap();
+        let url = api_url(&self.arango_url, "/_admin/cluster/health")?;
         let resp = self.session.get(url, "").await?;
-        let result: ClusterHealth = deserialize_response(resp.body())?;
+        let result: ClusterHealth = deserialize_response(&resp)?;
 
         Ok(result)
     }
+
+    /// Returns the target number of Coordinators and DB-Servers of the
+    /// cluster, along with the servers which have been cleaned out.
+    ///
+    /// Fails with [`ClientError::ClusterOnly`] when the server is not a
+    /// Coordinator of a cluster.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    #[cfg(feature = "cluster")]
+    pub async fn cluster_scale_targets(&self) -> Result<ClusterScaleTargets, ClientError> {
+        let url = api_url(&self.arango_url, "/_admin/cluster/numberOfServers")?;
+        let resp = self.session.get(url, "").await?;
+        deserialize_response(&resp).map_err(|e| cluster_only(e, "cluster_scale_targets"))
+    }
+
+    /// Set the target number of Coordinators and/or DB-Servers of the
+    /// cluster, and the servers which are cleaned out.
+    ///
+    /// Targets left to `None` are not changed.
+    ///
+    /// Fails with [`ClientError::ClusterOnly`] when the server is not a
+    /// Coordinator of a cluster.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    #[cfg(feature = "cluster")]
+    pub async fn set_cluster_scale_targets(
+        &self,
+        coordinators: Option<u32>,
+        dbservers: Option<u32>,
+        clean_out: Vec<String>,
+    ) -> Result<(), ClientError> {
+        self.check_writable("set cluster scale targets")?;
+        let url = api_url(&self.arango_url, "/_admin/cluster/numberOfServers")?;
+        let targets = ClusterScaleTargets {
+            number_of_coordinators: coordinators,
+            number_of_db_servers: dbservers,
+            cleaned_servers: clean_out,
+        };
+        let resp = self
+            .session
+            .put(url, &serde_json::to_string(&targets)?)
+            .await?;
+        deserialize_response::<Value>(&resp)
+            .map_err(|e| cluster_only(e, "set_cluster_scale_targets"))?;
+        Ok(())
+    }
+
+    /// List the servers registered in the cluster with their roles and
+    /// versions, sorted by id.
+    ///
+    /// Servers whose endpoint is listed by `/_api/cluster/endpoints` are
+    /// marked as advertised.
+    ///
+    /// Fails with [`ClientError::ClusterOnly`] when the server is not a
+    /// Coordinator of a cluster.
+    ///
+    /// # Note
+    /// this function would make two requests to arango server.
+    #[maybe_async]
+    #[cfg(feature = "cluster")]
+    pub async fn cluster_servers(&self) -> Result<Vec<ClusterServer>, ClientError> {
+        #[derive(Deserialize)]
+        struct Endpoint {
+            endpoint: String,
+        }
+        #[derive(Deserialize)]
+        struct Endpoints {
+            endpoints: Vec<Endpoint>,
+        }
+
+        let health = self
+            .cluster_health()
+            .await
+            .map_err(|e| cluster_only(e, "cluster_servers"))?;
+        let url = api_url(&self.arango_url, "/_api/cluster/endpoints")?;
+        let resp = self.session.get(url, "").await?;
+        let endpoints: Endpoints =
+            deserialize_response(&resp).map_err(|e| cluster_only(e, "cluster_servers"))?;
+
+        let mut servers: Vec<ClusterServer> = health
+            .health
+            .into_iter()
+            .map(|(id, server)| ClusterServer {
+                advertised: endpoints
+                    .endpoints
+                    .iter()
+                    .any(|e| e.endpoint == server.endpoint),
+                id,
+                role: server.role,
+                status: server.status,
+                version: server.version,
+                endpoint: server.endpoint,
+            })
+            .collect();
+        servers.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(servers)
+    }
+}
+
+/// Error number of ArangoDB when an operation is only valid on a Coordinator.
+#[cfg(feature = "cluster")]
+const ERROR_CLUSTER_ONLY_ON_COORDINATOR: u16 = 1471;
+
+/// Turn the error of a cluster operation sent to a server which is not a
+/// Coordinator into [`ClientError::ClusterOnly`].
+#[cfg(feature = "cluster")]
+pub(crate) fn cluster_only(error: ClientError, operation: &str) -> ClientError {
+    match error {
+        ClientError::Arango(ref e)
+            if e.error_num() == ERROR_CLUSTER_ONLY_ON_COORDINATOR
+                || (e.code() == 403 && e.message().contains("coordinator"))
+                || (e.code() == 501 && e.message().contains("cluster")) =>
+        {
+            ClientError::ClusterOnly {
+                operation: operation.to_owned(),
+            }
+        }
+        e => e,
+    }
 }
 
 impl<C: ClientExt> GenericConnection<C, Normal> {
@@ -229,25 +532,88 @@ impl<C: ClientExt> GenericConnection<C, Normal> {
     ) -> Result<GenericConnection<C, Normal>, ClientError> {
         let url_str = arango_url.into();
         let arango_url = Url::parse(&url_str)
-            .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", url_str)))?
-            .join("/")
-            .unwrap();
+            .and_then(|url| url.join("/"))
+            .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", url_str)))?;
 
         Self::validate_server(&url_str).await?;
+        Self::authorize(arango_url, auth).await
+    }
 
+    /// Establish connection to the first of several endpoints of a deployment
+    /// to answer, like the Coordinators of a cluster.
+    ///
+    /// The handshake is made with all endpoints at once, so that an endpoint
+    /// which is down does not delay the connection. The winning endpoint is
+    /// used for all requests of the connection, see
+    /// [`url`](GenericConnection::url). If none answers within
+    /// `handshake_timeout`, [`ClientError::NoEndpointAvailable`] tells why
+    /// each endpoint failed, like `3 endpoints tried: refused, timeout, TLS
+    /// error`.
+    ///
+    /// With JWT, the login is sent to the winning endpoint once the handshake
+    /// is done.
+    ///
+    /// Example:
+    /// ```rust, ignore
+    /// use std::time::Duration;
+    ///
+    /// use arangors::{connection::Auth, Connection};
+    ///
+    /// let conn = Connection::establish_any(
+    ///     &["http://coordinator1:8529", "http://coordinator2:8529"],
+    ///     Auth::jwt("username", "password"),
+    ///     Duration::from_secs(5),
+    /// )
+    /// .await
+    /// .unwrap();
+    /// ```
+    #[maybe_async]
+    pub async fn establish_any<T: AsRef<str>>(
+        endpoints: &[T],
+        auth: Auth<'_>,
+        handshake_timeout: Duration,
+    ) -> Result<GenericConnection<C, Normal>, ClientError>
+    where
+        C: Send + 'static,
+    {
+        if endpoints.is_empty() {
+            return Err(ClientError::InvalidOptions(
+                "at least one endpoint is required".to_owned(),
+            ));
+        }
+        let mut candidates = Vec::with_capacity(endpoints.len());
+        for url_str in endpoints {
+            let url_str = url_str.as_ref();
+            let url = Url::parse(url_str)
+                .and_then(|url| url.join("/"))
+                .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", url_str)))?;
+            candidates.push((url, C::new(None)?));
+        }
+        let arango_url = endpoints::race(candidates, handshake_timeout).await?;
+        debug!("Handshake won by {}", DisplayEndpoint(&arango_url));
+        Self::authorize(arango_url, auth).await
+    }
+
+    /// Set up the authentication with the server at `arango_url`, already
+    /// known to be an ArangoDB.
+    #[maybe_async]
+    async fn authorize(
+        arango_url: Url,
+        auth: Auth<'_>,
+    ) -> Result<GenericConnection<C, Normal>, ClientError> {
         let username: String;
-        let authorization = match auth {
+        let header = match auth {
             Auth::Basic(cred) => {
                 username = String::from(cred.username);
 
                 let token = base64::encode(&format!("{}:{}", cred.username, cred.password));
-                Some(format!("Basic {}", token))
+                Some(authorization("Basic", &token)?)
             }
             Auth::Jwt(cred) => {
                 username = String::from(cred.username);
 
                 let token = Self::jwt_login(&arango_url, cred.username, cred.password).await?;
-                Some(format!("Bearer {}", token))
+                Some(authorization("Bearer", &token)?)
             }
             Auth::None => {
                 username = String::from("root");
@@ -256,17 +622,30 @@ impl<C: ClientExt> GenericConnection<C, Normal> {
         };
 
         let mut headers = HeaderMap::new();
-        if let Some(value) = authorization {
-            headers.insert(AUTHORIZATION, value.parse().unwrap());
+        if let Some(value) = header {
+            headers.insert(AUTHORIZATION, value);
         }
 
         debug!("Established");
-        Ok(GenericConnection {
+        Ok(GenericConnection::new(
+            arango_url,
+            username,
+            Arc::new(C::new(headers)?),
+        ))
+    }
+
+    /// Construct a connection from an already authorized session, without
+    /// any request to the server.
+    pub(crate) fn new(arango_url: Url, username: String, session: Arc<C>) -> Self {
+        GenericConnection {
+            session: Session::for_user(session, &username),
             arango_url,
             username,
-            session: Arc::new(C::new(headers)?),
+            #[cfg(feature = "aql")]
+            deterministic_requests: false,
+            read_only: false,
             state: Normal,
-        })
+        }
     }
 
     /// Establish connection to ArangoDB sever without Authentication.
@@ -355,18 +734,22 @@ impl<C: ClientExt> GenericConnection<C, Normal> {
         struct Jwt {
             pub jwt: String,
         }
-        let url = arango_url.join("/_open/auth").unwrap();
+        let url = api_url(arango_url, "/_open/auth")?;
 
         let mut map = HashMap::new();
         map.insert("username", username.into());
         map.insert("password", password.into());
 
         let jwt: Jwt = deserialize_response(
-            C::new(None)?
+            &C::new(None)?
                 .post(url, &serde_json::to_string(&map)?)
-                .await?
-                .body(),
+                .await?,
         )?;
+        if HeaderValue::from_str(&jwt.jwt).is_err() {
+            return Err(ClientError::MalformedResponse(
+                "JWT not fit for a header".to_owned(),
+            ));
+        }
         Ok(jwt.jwt)
     }
 
@@ -398,17 +781,7 @@ impl<C: ClientExt> GenericConnection<C, Normal> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn create_database(&self, name: &str) -> Result<Database<C>, ClientError> {
-        let mut map = HashMap::new();
-        map.insert("name", name);
-        let url = self.arango_url.join("/_api/database").unwrap();
-
-        let resp = self
-            .session
-            .post(url, &serde_json::to_string(&map)?)
-            .await?;
-
-        deserialize_response::<ArangoResult<bool>>(resp.body())?;
-        self.db(name).await
+        self.system().create_database(name).await
     }
 
     #[maybe_async]
@@ -418,19 +791,9 @@ impl<C: ClientExt> GenericConnection<C, Normal> {
         name: &str,
         options: CreateDatabaseOptions,
     ) -> Result<Database<C>, ClientError> {
-        let url = self.arango_url.join("/_api/database").unwrap();
-        let final_options = CreateDatabase::builder()
-            .name(name)
-            .options(options)
-            .build();
-
-        let resp = self
-            .session
-            .post(url, &serde_json::to_string(&final_options)?)
-            .await?;
-
-        deserialize_response::<ArangoResult<bool>>(resp.body())?;
-        self.db(name).await
+        self.system()
+            .create_database_with_options(name, options)
+            .await
     }
 
     /// Drop database with name.
@@ -439,12 +802,12 @@ impl<C: ClientExt> GenericConnection<C, Normal> {
     /// this function would make a request to arango server.
     #[maybe_async]
     pub async fn drop_database(&self, name: &str) -> Result<(), ClientError> {
-        let url_path = format!("/_api/database/{}", name);
-        let url = self.arango_url.join(&url_path).unwrap();
+        self.system().drop_database(name).await
+    }
 
-        let resp = self.session.delete(url, "").await?;
-        deserialize_response::<ArangoResult<bool>>(resp.body())?;
-        Ok(())
+    /// The `_system` database, without any request.
+    fn system(&self) -> SystemDatabase<C> {
+        SystemDatabase::new(self.database(SYSTEM_DATABASE))
     }
 
     #[maybe_async]
@@ -478,6 +841,9 @@ impl<C: ClientExt> From<GenericConnection<C, Normal>> for GenericConnection<C, A
             arango_url: conn.arango_url,
             session: conn.session,
             username: conn.username,
+            #[cfg(feature = "aql")]
+            deterministic_requests: conn.deterministic_requests,
+            read_only: conn.read_only,
             state: Admin,
         }
     }
@@ -489,7 +855,217 @@ impl<C: ClientExt> From<GenericConnection<C, Admin>> for GenericConnection<C, No
             arango_url: conn.arango_url,
             session: conn.session,
             username: conn.username,
+            #[cfg(feature = "aql")]
+            deterministic_requests: conn.deterministic_requests,
+            read_only: conn.read_only,
             state: Normal,
         }
     }
 }
+
+/// Root of `url` without user name and password.
+pub(crate) fn endpoint(url: &Url) -> Url {
+    let mut endpoint = url.clone();
+    endpoint.set_path("/");
+    endpoint.set_query(None);
+    endpoint.set_fragment(None);
+    // only fails for urls which cannot have credentials anyway
+    let _ = endpoint.set_username("");
+    let _ = endpoint.set_password(None);
+    endpoint
+}
+
+/// `path` on the server at `arango_url`.
+fn api_url(arango_url: &Url, path: &str) -> Result<Url, ClientError> {
+    arango_url
+        .join(path)
+        .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", arango_url)))
+}
+
+/// The `Authorization` header with `token` of `scheme`, like `Basic`.
+fn authorization(scheme: &str, token: &str) -> Result<HeaderValue, ClientError> {
+    HeaderValue::from_str(&format!("{} {}", scheme, token)).map_err(|_| {
+        ClientError::InvalidOptions(format!("invalid {} authentication token", scheme))
+    })
+}
+
+/// Format `url` like `https://arango.internal:8529`, without credentials.
+pub(crate) struct DisplayEndpoint<'a>(pub(crate) &'a Url);
+
+impl fmt::Display for DisplayEndpoint<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(endpoint(self.0).as_str().trim_end_matches('/'))
+    }
+}
+
+impl<C: ClientExt, S> fmt::Display for GenericConnection<C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DisplayEndpoint(&self.arango_url).fmt(f)
+    }
+}
+
+/// Leaves out the HTTP client, whose headers hold the credentials.
+impl<C: ClientExt, S: Debug> Debug for GenericConnection<C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("GenericConnection");
+        debug
+            .field("endpoint", &self.endpoint().as_str())
+            .field("username", &self.username);
+        #[cfg(feature = "aql")]
+        debug.field("deterministic_requests", &self.deterministic_requests);
+        debug
+            .field("read_only", &self.read_only)
+            .field("state", &self.state)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "cluster"))]
+mod test {
+    use http::Method;
+    use serde_json::json;
+
+    use crate::{
+        test_support::{fixtures, MockClient, MockResponse},
+        ClientError,
+    };
+
+    #[test]
+    fn invalid_authentication_token() {
+        assert_eq!(super::authorization("Bearer", "abc").unwrap(), "Bearer abc");
+        let result = super::authorization("Bearer", "abc\ndef");
+        assert!(
+            matches!(result, Err(ClientError::InvalidOptions(_))),
+            "{:?}",
+            result
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn server_role() {
+        let mock = MockClient::default();
+        let conn = mock.connection();
+        mock.once(
+            Method::GET,
+            "/_admin/server/role",
+            MockResponse::json(
+                200,
+                json!({ "error": false, "code": 200, "role": "SINGLE" }),
+            ),
+        );
+        let role = conn.server_role().await.unwrap();
+        assert_eq!(role, "SINGLE");
+
+        for body in &[json!({ "error": false, "code": 200 }), json!({ "role": 1 })] {
+            mock.once(
+                Method::GET,
+                "/_admin/server/role",
+                MockResponse::json(200, body.clone()),
+            );
+            let result = conn.server_role().await;
+            assert!(
+                matches!(result, Err(ClientError::MalformedResponse(_))),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn scale_targets() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_admin/cluster/numberOfServers",
+            MockResponse::json(
+                200,
+                json!({
+                    "error": false,
+                    "code": 200,
+                    "numberOfCoordinators": 2,
+                    "numberOfDBServers": 3,
+                    "cleanedServers": ["PRMR-1"],
+                }),
+            ),
+        );
+        mock.once(
+            Method::PUT,
+            "/_admin/cluster/numberOfServers",
+            MockResponse::json(200, json!({ "error": false, "code": 200 })),
+        );
+        let conn = mock.connection();
+
+        let targets = conn.cluster_scale_targets().await.unwrap();
+        assert_eq!(targets.number_of_coordinators, Some(2));
+        assert_eq!(targets.number_of_db_servers, Some(3));
+        assert_eq!(targets.cleaned_servers, vec!["PRMR-1".to_owned()]);
+
+        conn.set_cluster_scale_targets(None, Some(4), vec![])
+            .await
+            .unwrap();
+        let body = mock.requests()[1].json_body().unwrap();
+        assert_eq!(
+            body,
+            json!({ "numberOfDBServers": 4, "cleanedServers": [] })
+        );
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn scale_targets_on_single_server() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_admin/cluster/numberOfServers",
+            MockResponse::json(
+                403,
+                fixtures::error(403, 1471, "this operation is only valid on a coordinator"),
+            ),
+        );
+        let result = mock.connection().cluster_scale_targets().await;
+        assert!(matches!(result, Err(ClientError::ClusterOnly { .. })));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn servers_with_endpoints() {
+        let mock = MockClient::default();
+        let server = |role: &str, endpoint: &str| {
+            json!({
+                "Endpoint": endpoint,
+                "Role": role,
+                "Status": "GOOD",
+                "Engine": "rocksdb",
+                "Version": "3.7.2",
+            })
+        };
+        mock.once(
+            Method::GET,
+            "/_admin/cluster/health",
+            MockResponse::json(
+                200,
+                json!({
+                    "error": false,
+                    "code": 200,
+                    "ClusterId": "cluster",
+                    "Health": {
+                        "PRMR-1": server("DBServer", "tcp://[::1]:8629"),
+                        "CRDN-1": server("Coordinator", "tcp://[::1]:8529"),
+                    },
+                }),
+            ),
+        );
+        mock.once(
+            Method::GET,
+            "/_api/cluster/endpoints",
+            MockResponse::json(
+                200,
+                json!({ "error": false, "code": 200, "endpoints": [{ "endpoint": "tcp://[::1]:8529" }] }),
+            ),
+        );
+        let servers = mock.connection().cluster_servers().await.unwrap();
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].id, "CRDN-1");
+        assert!(servers[0].advertised);
+        assert_eq!(servers[1].version, "3.7.2");
+        assert!(!servers[1].advertised);
+    }
+}
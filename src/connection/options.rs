@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
@@ -57,21 +59,21 @@ pub(crate) struct CreateDatabase<'a> {
     options: Option<CreateDatabaseOptions>,
 }
 
-#[derive(Serialize, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Deserialize)]
 pub enum ClusterRole {
     Coordinator,
     DBServer,
     Agent,
 }
 
-#[derive(Serialize, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Engine {
     RocksDB,
     MMFiles,
 }
 
-#[derive(Serialize, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum ClusterStatus {
     Good,
@@ -79,7 +81,7 @@ pub enum ClusterStatus {
     Failed,
 }
 
-#[derive(Serialize, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Deserialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum SyncStatus {
     Serving,
@@ -91,7 +93,7 @@ pub enum SyncStatus {
     Unknown,
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 #[cfg(feature = "cluster")]
 pub struct ServerHealth {
@@ -110,7 +112,7 @@ pub struct ServerHealth {
     pub sync_status: Option<SyncStatus>,
 }
 
-#[derive(Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "PascalCase")]
 #[cfg(feature = "cluster")]
 pub struct ClusterHealth {
@@ -118,3 +120,69 @@ pub struct ClusterHealth {
 
     pub health: HashMap<String, ServerHealth>,
 }
+
+/// Target number of servers of a cluster, as read from and written to
+/// `/_admin/cluster/numberOfServers`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "camelCase")]
+#[cfg(feature = "cluster")]
+pub struct ClusterScaleTargets {
+    /// Target number of Coordinators, if set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_of_coordinators: Option<u32>,
+
+    /// Target number of DB-Servers, if set.
+    #[serde(rename = "numberOfDBServers")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_of_db_servers: Option<u32>,
+
+    /// Ids of the servers which have been cleaned out, i.e. from which all
+    /// shards have been moved away so that they can be shut down.
+    #[serde(default)]
+    pub cleaned_servers: Vec<String>,
+}
+
+/// A server registered in a cluster, combining its health with the
+/// Coordinator endpoints of the cluster.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg(feature = "cluster")]
+pub struct ClusterServer {
+    /// Id of the server, e.g. `PRMR-...`.
+    pub id: String,
+
+    pub role: ClusterRole,
+
+    pub status: ClusterStatus,
+
+    pub version: String,
+
+    pub endpoint: String,
+
+    /// Whether the endpoint is listed by `/_api/cluster/endpoints`, i.e.
+    /// whether clients can send requests to this server.
+    pub advertised: bool,
+}
+
+/// Options for warming up a connection, see
+/// [`GenericConnection::warm_up`](super::GenericConnection::warm_up)
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct WarmUpOptions {
+    /// Connections opened to the server at once, 1 by default. Open as many
+    /// as the requests expected at once right after the warm-up.
+    #[builder(default = 1)]
+    pub(crate) connections: usize,
+    /// Also fetch the names of the collections of the databases, false by
+    /// default.
+    #[builder(default)]
+    pub(crate) collections: bool,
+    /// Time given to the whole warm-up, 5 seconds by default.
+    #[builder(default = Duration::from_secs(5))]
+    pub(crate) deadline: Duration,
+}
+
+impl Default for WarmUpOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
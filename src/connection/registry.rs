@@ -0,0 +1,806 @@
+//! Requests in flight and server-side resources opened through a connection,
+//! for [`GenericConnection::shutdown`](super::GenericConnection::shutdown).
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::{
+    fmt,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+use http::{header::IntoHeaderName, HeaderValue, Method, Request, Response};
+use maybe_async::maybe_async;
+use uclient::ClientExt;
+use url::Url;
+
+use super::{access::AccessCache, capability::Capabilities, stats::RequestStats};
+use crate::ClientError;
+
+/// A cursor or stream transaction left open on the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenResource {
+    database: String,
+    id: String,
+    db_url: Url,
+}
+
+impl OpenResource {
+    fn new(db_url: &Url, id: &str) -> Self {
+        let database = db_url
+            .path_segments()
+            .and_then(|mut path| match path.next() {
+                Some("_db") => path.next(),
+                _ => None,
+            })
+            .unwrap_or("_system");
+        OpenResource {
+            database: database.to_owned(),
+            id: id.to_owned(),
+            db_url: db_url.clone(),
+        }
+    }
+
+    /// Name of the database of the resource.
+    pub fn database(&self) -> &str {
+        &self.database
+    }
+
+    /// Id of the cursor or transaction.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Resources opened through a connection which are still alive, see
+/// [`GenericConnection::open_resources`](super::GenericConnection::open_resources).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenResources {
+    /// Cursors with results left to fetch.
+    pub cursors: Vec<OpenResource>,
+    /// Stream transactions neither committed nor aborted.
+    pub transactions: Vec<OpenResource>,
+    /// Requests sent and not answered yet.
+    pub in_flight: usize,
+}
+
+/// Outcome of [`GenericConnection::shutdown`](super::GenericConnection::shutdown).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Whether all requests in flight completed within the grace period.
+    pub drained: bool,
+    /// Number of stream transactions aborted.
+    pub aborted_transactions: usize,
+    /// Number of cursors deleted.
+    pub deleted_cursors: usize,
+    /// Resources which could not be released, and expire with their TTL.
+    pub failed: OpenResources,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    closed: bool,
+    in_flight: usize,
+    #[cfg(not(feature = "blocking"))]
+    wakers: Vec<std::task::Waker>,
+    cursors: Vec<OpenResource>,
+    /// Cursors dropped before their last batch, to delete.
+    abandoned: Vec<OpenResource>,
+    transactions: Vec<OpenResource>,
+}
+
+/// Shared by all handles derived from one connection.
+#[derive(Debug, Default)]
+pub(crate) struct Registry {
+    state: Mutex<State>,
+    idle: Condvar,
+    stats: RequestStats,
+    capabilities: Capabilities,
+}
+
+impl Registry {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn stats(&self) -> &RequestStats {
+        &self.stats
+    }
+
+    pub(crate) fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Count a request in flight until the guard is dropped, or refuse it once
+    /// the connection is shutting down.
+    fn enter(self: &Arc<Self>) -> Result<InFlight, ClientError> {
+        let mut state = self.state();
+        if state.closed {
+            return Err(ClientError::ShuttingDown);
+        }
+        state.in_flight += 1;
+        Ok(InFlight {
+            registry: Arc::clone(self),
+            sent: false,
+        })
+    }
+
+    /// Record the state of cursor `id` after one of its batches: it is
+    /// open while it has more results.
+    pub(crate) fn cursor_batch(&self, db_url: &Url, id: Option<&str>, more: bool) {
+        let id = match id {
+            Some(id) => id,
+            None => return,
+        };
+        let mut state = self.state();
+        let cursors = &mut state.cursors;
+        let open = cursors
+            .iter()
+            .position(|c| c.id == id && &c.db_url == db_url);
+        match (open, more) {
+            (None, true) => cursors.push(OpenResource::new(db_url, id)),
+            (Some(i), false) => {
+                cursors.swap_remove(i);
+            }
+            _ => {}
+        }
+    }
+
+    /// Record that cursor `id` was deleted.
+    #[cfg(feature = "aql")]
+    pub(crate) fn cursor_deleted(&self, db_url: &Url, id: &str) {
+        self.cursor_batch(db_url, Some(id), false);
+    }
+
+    /// Record that open cursor `id` will not be read anymore, so that it is
+    /// deleted by [`Session::release_abandoned`].
+    #[cfg(feature = "aql")]
+    pub(crate) fn cursor_abandoned(&self, db_url: &Url, id: &str) {
+        let mut state = self.state();
+        let open = state
+            .cursors
+            .iter()
+            .position(|c| c.id == id && &c.db_url == db_url);
+        if let Some(i) = open {
+            let cursor = state.cursors.swap_remove(i);
+            state.abandoned.push(cursor);
+        }
+    }
+
+    fn take_abandoned(&self) -> Vec<OpenResource> {
+        std::mem::take(&mut self.state().abandoned)
+    }
+
+    /// Record that stream transaction `id` was begun.
+    #[cfg(feature = "transactions")]
+    pub(crate) fn transaction_begun(&self, db_url: &Url, id: &str) {
+        self.state()
+            .transactions
+            .push(OpenResource::new(db_url, id));
+    }
+
+    /// Record that stream transaction `id` was committed or aborted.
+    #[cfg(feature = "transactions")]
+    pub(crate) fn transaction_ended(&self, db_url: &Url, id: &str) {
+        self.state()
+            .transactions
+            .retain(|t| !(t.id == id && &t.db_url == db_url));
+    }
+
+    pub(crate) fn open_resources(&self) -> OpenResources {
+        let state = self.state();
+        OpenResources {
+            cursors: [&state.cursors[..], &state.abandoned[..]].concat(),
+            transactions: state.transactions.clone(),
+            in_flight: state.in_flight,
+        }
+    }
+
+    /// Refuse new requests from now on.
+    fn close(&self) {
+        self.state().closed = true;
+    }
+
+    /// Take the resources left to release.
+    fn take_open(&self) -> OpenResources {
+        let mut state = self.state();
+        let mut cursors = std::mem::take(&mut state.cursors);
+        cursors.append(&mut state.abandoned);
+        OpenResources {
+            cursors,
+            transactions: std::mem::take(&mut state.transactions),
+            in_flight: state.in_flight,
+        }
+    }
+
+    fn leave(&self) {
+        let mut state = self.state();
+        state.in_flight -= 1;
+        if state.in_flight == 0 {
+            self.idle.notify_all();
+            #[cfg(not(feature = "blocking"))]
+            state.wakers.drain(..).for_each(std::task::Waker::wake);
+        }
+    }
+
+    /// Wait until no request is in flight, for at most `grace`. Returns
+    /// whether all requests completed.
+    #[maybe_async::async_impl]
+    async fn drain(self: &Arc<Self>, grace: Duration) -> bool {
+        Drain {
+            registry: Arc::clone(self),
+            deadline: super::endpoints::sleep(grace),
+        }
+        .await
+    }
+
+    #[maybe_async::sync_impl]
+    fn drain(self: &Arc<Self>, grace: Duration) -> bool {
+        let state = self.state();
+        let (state, _) = self
+            .idle
+            .wait_timeout_while(state, grace, |state| state.in_flight > 0)
+            .unwrap_or_else(PoisonError::into_inner);
+        state.in_flight == 0
+    }
+}
+
+/// Waits for the requests in flight without depending on an async runtime:
+/// the deadline is kept by a [`sleep`](super::endpoints::sleep), stopped
+/// along with the drain.
+#[cfg(not(feature = "blocking"))]
+struct Drain {
+    registry: Arc<Registry>,
+    deadline: super::endpoints::Sleep,
+}
+
+#[cfg(not(feature = "blocking"))]
+impl std::future::Future for Drain {
+    type Output = bool;
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<bool> {
+        use std::task::Poll;
+
+        let mut state = self.registry.state();
+        if state.in_flight == 0 {
+            return Poll::Ready(true);
+        }
+        if !state.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            state.wakers.push(cx.waker().clone());
+        }
+        drop(state);
+        if std::pin::Pin::new(&mut self.deadline).poll(cx).is_ready() {
+            return Poll::Ready(false);
+        }
+        Poll::Pending
+    }
+}
+
+/// A request in flight, counted by the stats once handed to the HTTP client.
+struct InFlight {
+    registry: Arc<Registry>,
+    sent: bool,
+}
+
+impl InFlight {
+    fn sent(&mut self, endpoint: &str, waited: Duration) {
+        self.registry.stats.sent(endpoint, waited);
+        self.sent = true;
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.sent {
+            self.registry.stats.answered();
+        }
+        self.registry.leave();
+    }
+}
+
+/// The HTTP client of a connection, counting the requests in flight of all
+/// handles derived from the connection.
+pub(crate) struct Session<C> {
+    client: Arc<C>,
+    registry: Arc<Registry>,
+    access: Arc<AccessCache>,
+}
+
+impl<C> Clone for Session<C> {
+    fn clone(&self) -> Self {
+        Session {
+            client: Arc::clone(&self.client),
+            registry: Arc::clone(&self.registry),
+            access: Arc::clone(&self.access),
+        }
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for Session<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.client.fmt(f)
+    }
+}
+
+impl<C: ClientExt> Session<C> {
+    /// A session of its own, not shared with any connection, whose user is
+    /// unknown.
+    #[cfg(test)]
+    pub(crate) fn new(client: Arc<C>) -> Self {
+        Self::with_access(client, AccessCache::new(None))
+    }
+
+    /// A session of its own authorized as `user`.
+    pub(crate) fn for_user(client: Arc<C>, user: &str) -> Self {
+        Self::with_access(client, AccessCache::new(Some(user.to_owned())))
+    }
+
+    fn with_access(client: Arc<C>, access: AccessCache) -> Self {
+        Session {
+            client,
+            registry: Default::default(),
+            access: Arc::new(access),
+        }
+    }
+
+    pub(crate) fn client(&self) -> Arc<C> {
+        Arc::clone(&self.client)
+    }
+
+    pub(crate) fn registry(&self) -> &Registry {
+        &self.registry
+    }
+
+    pub(crate) fn access(&self) -> &AccessCache {
+        &self.access
+    }
+
+    /// A session sharing the registry, with header `name` added to the
+    /// requests.
+    pub(crate) fn with_header<K: IntoHeaderName>(&self, name: K, value: HeaderValue) -> Self {
+        let mut client = (*self.client).clone();
+        client.headers().insert(name, value);
+        Session {
+            client: Arc::new(client),
+            registry: Arc::clone(&self.registry),
+            access: Arc::clone(&self.access),
+        }
+    }
+
+    #[maybe_async]
+    pub(crate) async fn request(
+        &self,
+        request: Request<String>,
+    ) -> Result<Response<String>, ClientError> {
+        let called = Instant::now();
+        let mut in_flight = self.registry.enter()?;
+        // deleting the abandoned cursors is not waiting in the queue
+        let waited = called.elapsed();
+        self.release_abandoned().await;
+        let endpoint = request.uri().authority().map_or("", |a| a.as_str());
+        in_flight.sent(endpoint, waited);
+        let resp = self.client.request(request).await?;
+        Ok(resp)
+    }
+
+    #[maybe_async]
+    pub(crate) async fn get<T: Into<String>>(
+        &self,
+        url: Url,
+        text: T,
+    ) -> Result<Response<String>, ClientError> {
+        self.request(build(Method::GET, &url, text.into())?).await
+    }
+
+    #[maybe_async]
+    pub(crate) async fn post<T: Into<String>>(
+        &self,
+        url: Url,
+        text: T,
+    ) -> Result<Response<String>, ClientError> {
+        self.request(build(Method::POST, &url, text.into())?).await
+    }
+
+    #[maybe_async]
+    pub(crate) async fn put<T: Into<String>>(
+        &self,
+        url: Url,
+        text: T,
+    ) -> Result<Response<String>, ClientError> {
+        self.request(build(Method::PUT, &url, text.into())?).await
+    }
+
+    #[cfg(feature = "views")]
+    #[maybe_async]
+    pub(crate) async fn patch<T: Into<String>>(
+        &self,
+        url: Url,
+        text: T,
+    ) -> Result<Response<String>, ClientError> {
+        self.request(build(Method::PATCH, &url, text.into())?).await
+    }
+
+    #[maybe_async]
+    pub(crate) async fn delete<T: Into<String>>(
+        &self,
+        url: Url,
+        text: T,
+    ) -> Result<Response<String>, ClientError> {
+        self.request(build(Method::DELETE, &url, text.into())?)
+            .await
+    }
+
+    /// Refuse new requests, wait for the ones in flight for at most `grace`,
+    /// and release the open resources.
+    #[maybe_async]
+    pub(crate) async fn shutdown(&self, grace: Duration) -> ShutdownReport {
+        self.registry.close();
+        let drained = self.registry.drain(grace).await;
+        let open = self.registry.take_open();
+        let mut report = ShutdownReport {
+            drained,
+            ..Default::default()
+        };
+        for tx in open.transactions {
+            if self.release(&tx, "transaction").await {
+                report.aborted_transactions += 1;
+            } else {
+                report.failed.transactions.push(tx);
+            }
+        }
+        for cursor in open.cursors {
+            if self.release(&cursor, "cursor").await {
+                report.deleted_cursors += 1;
+            } else {
+                report.failed.cursors.push(cursor);
+            }
+        }
+        report.failed.in_flight = self.registry.open_resources().in_flight;
+        report
+    }
+
+    /// Delete the cursors dropped before their last batch. A cursor which
+    /// cannot be deleted is left open, for the shutdown to try again.
+    #[maybe_async]
+    pub(crate) async fn release_abandoned(&self) {
+        for cursor in self.registry.take_abandoned() {
+            if !self.release(&cursor, "cursor").await {
+                self.registry
+                    .cursor_batch(&cursor.db_url, Some(&cursor.id), true);
+            }
+        }
+    }
+
+    /// Delete `resource` behind the back of the closed registry. A resource
+    /// which does not exist anymore counts as released.
+    #[maybe_async]
+    async fn release(&self, resource: &OpenResource, kind: &str) -> bool {
+        let request = resource
+            .db_url
+            .join(&format!("_api/{}/{}", kind, resource.id))
+            .ok()
+            .and_then(|url| build(Method::DELETE, &url, String::new()).ok());
+        let request = match request {
+            Some(request) => request,
+            None => return false,
+        };
+        match self.client.request(request).await {
+            Ok(resp) => resp.status().is_success() || resp.status() == 404,
+            Err(_) => false,
+        }
+    }
+}
+
+/// A request of `method` to `url` with `body`.
+fn build(method: Method, url: &Url, body: String) -> Result<Request<String>, ClientError> {
+    Request::builder()
+        .method(method)
+        .uri(url.as_str())
+        .body(body)
+        .map_err(|_| ClientError::InvalidServer(format!("invalid url: {}", url)))
+}
+
+// the tests of the open resources need stream transactions and AQL cursors,
+// the shutdown tests need async mode
+#[cfg(all(
+    test,
+    any(
+        all(feature = "transactions", feature = "aql"),
+        not(feature = "blocking")
+    )
+))]
+mod test {
+    use std::time::Duration;
+
+    use http::Method;
+    use serde_json::json;
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    use serde_json::Value;
+
+    #[cfg(any(
+        not(feature = "blocking"),
+        all(feature = "transactions", feature = "aql")
+    ))]
+    use crate::test_support::fixtures;
+    use crate::test_support::{MockClient, MockResponse};
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    use crate::{
+        transaction::{TransactionCollections, TransactionSettings},
+        AqlQuery, ClientError, Database,
+    };
+
+    fn mock_database_info(mock: &MockClient) {
+        mock.on(
+            Method::GET,
+            "/_db/test_db/_api/database/current",
+            MockResponse::json(
+                200,
+                json!({
+                    "error": false,
+                    "code": 200,
+                    "result": { "name": "test_db", "id": "1", "path": "", "isSystem": false },
+                }),
+            ),
+        );
+    }
+
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    fn settings() -> TransactionSettings {
+        TransactionSettings::builder()
+            .collections(
+                TransactionCollections::builder()
+                    .write(vec!["users".to_owned()])
+                    .build(),
+            )
+            .build()
+    }
+
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    fn mock_begin(mock: &MockClient, id: &str) {
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/transaction/begin",
+            MockResponse::json(
+                201,
+                json!({ "error": false, "code": 201, "result": { "id": id, "status": "running" } }),
+            ),
+        );
+    }
+
+    /// Open a cursor with a batch left on the server.
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    #[maybe_async::maybe_async]
+    async fn open_cursor(mock: &MockClient, db: &Database<MockClient>) {
+        let batch = fixtures::cursor_batches("mock_cursor", vec![vec![json!(1)], vec![json!(2)]]);
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/cursor",
+            MockResponse::json(201, batch[0].clone()),
+        );
+        let cursor = db
+            .aql_query_batch::<Value>(AqlQuery::builder().query("FOR i IN 1..2 RETURN i").build())
+            .await
+            .unwrap();
+        assert!(cursor.more);
+    }
+
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn shutdown_releases_open_resources() {
+        let mock = MockClient::default();
+        mock_database_info(&mock);
+        let conn = mock.connection();
+        let db = conn.db("test_db").await.unwrap();
+
+        mock_begin(&mock, "tx1");
+        db.begin_transaction(settings()).await.unwrap();
+        open_cursor(&mock, &db).await;
+
+        let open = conn.open_resources();
+        assert_eq!(open.in_flight, 0);
+        assert_eq!(open.transactions.len(), 1);
+        assert_eq!(
+            (open.transactions[0].database(), open.transactions[0].id()),
+            ("test_db", "tx1")
+        );
+        assert_eq!(open.cursors.len(), 1);
+        assert_eq!(open.cursors[0].id(), "mock_cursor");
+
+        mock.clear_requests();
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/transaction/tx1",
+            MockResponse::json(200, json!({ "error": false, "code": 200 })),
+        );
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/cursor/mock_cursor",
+            MockResponse::json(404, fixtures::error(404, 1600, "cursor not found")),
+        );
+        let report = conn.shutdown(Duration::from_secs(1)).await;
+        assert!(report.drained);
+        assert_eq!(report.aborted_transactions, 1);
+        assert_eq!(report.deleted_cursors, 1);
+        assert_eq!(report.failed, Default::default());
+        mock.assert_requests(&[
+            (Method::DELETE, "/_db/test_db/_api/transaction/tx1"),
+            (Method::DELETE, "/_db/test_db/_api/cursor/mock_cursor"),
+        ]);
+        mock.assert_no_pending();
+
+        // nothing is sent anymore, through any handle
+        let info = db.info().await;
+        assert!(matches!(info, Err(ClientError::ShuttingDown)));
+        let coll = db.collection("users").await;
+        assert!(matches!(coll, Err(ClientError::ShuttingDown)));
+        assert_eq!(conn.open_resources(), Default::default());
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn resources_closed_by_the_client_are_not_tracked() {
+        let mock = MockClient::default();
+        mock_database_info(&mock);
+        let conn = mock.connection();
+        let db = conn.db("test_db").await.unwrap();
+
+        mock_begin(&mock, "tx1");
+        let tx = db.begin_transaction(settings()).await.unwrap();
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/transaction/tx1",
+            MockResponse::json(
+                200,
+                json!({ "error": false, "code": 200, "result": { "id": "tx1", "status": "committed" } }),
+            ),
+        );
+        tx.commit().await.unwrap();
+
+        open_cursor(&mock, &db).await;
+        let batch = fixtures::cursor_batches("mock_cursor", vec![vec![json!(1)], vec![json!(2)]]);
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/cursor/mock_cursor",
+            MockResponse::json(200, batch[1].clone()),
+        );
+        let last = db.aql_next_batch::<Value>("mock_cursor").await.unwrap();
+        assert!(!last.more);
+        assert_eq!(conn.open_resources(), Default::default());
+
+        // a cursor which cannot be deleted is reported
+        open_cursor(&mock, &db).await;
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/cursor/mock_cursor",
+            MockResponse::json(503, fixtures::service_unavailable()),
+        );
+        let report = conn.shutdown(Duration::from_secs(1)).await;
+        assert_eq!(report.deleted_cursors, 0);
+        assert_eq!(report.failed.cursors.len(), 1);
+        assert_eq!(report.failed.cursors[0].id(), "mock_cursor");
+    }
+
+    #[cfg(all(feature = "transactions", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn transactions_unknown_to_the_server_are_not_tracked() {
+        let mock = MockClient::default();
+        mock_database_info(&mock);
+        let conn = mock.connection();
+        let db = conn.db("test_db").await.unwrap();
+
+        let not_found =
+            || MockResponse::json(404, fixtures::error(404, 1655, "transaction not found"));
+        mock_begin(&mock, "tx1");
+        let tx = db.begin_transaction(settings()).await.unwrap();
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/transaction/tx1",
+            not_found(),
+        );
+        let result = tx.commit().await;
+        assert!(result.is_err());
+        assert_eq!(conn.open_resources(), Default::default());
+
+        mock_begin(&mock, "tx2");
+        let tx = db.begin_transaction(settings()).await.unwrap();
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/transaction/tx2",
+            not_found(),
+        );
+        let result = tx.abort().await;
+        assert!(result.is_err());
+        assert_eq!(conn.open_resources(), Default::default());
+
+        // still running after any other failure
+        mock_begin(&mock, "tx3");
+        let tx = db.begin_transaction(settings()).await.unwrap();
+        mock.once(
+            Method::PUT,
+            "/_db/test_db/_api/transaction/tx3",
+            MockResponse::json(503, fixtures::service_unavailable()),
+        );
+        let result = tx.commit().await;
+        assert!(result.is_err());
+        assert_eq!(conn.open_resources().transactions.len(), 1);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn shutdown_waits_for_requests_in_flight() {
+        let mock = MockClient::default();
+        mock_database_info(&mock);
+        let conn = mock.connection();
+        let db = conn.db("test_db").await.unwrap();
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.0")).with_yields(3),
+        );
+
+        // the request sent first completes within the grace period
+        let (version, report) =
+            futures_util::join!(db.arango_version(), conn.shutdown(Duration::from_secs(5)));
+        assert!(version.is_ok());
+        assert!(report.drained);
+        assert_eq!(report.failed.in_flight, 0);
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    #[tokio::test]
+    async fn shutdown_gives_up_after_grace() {
+        let mock = MockClient::default();
+        mock_database_info(&mock);
+        let conn = mock.connection();
+        let db = conn.db("test_db").await.unwrap();
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.0")).with_yields(10),
+        );
+
+        let (version, report) =
+            futures_util::join!(db.arango_version(), conn.shutdown(Duration::ZERO));
+        assert!(!report.drained);
+        assert_eq!(report.failed.in_flight, 1);
+        // the request in flight is not cut short
+        assert!(version.is_ok());
+        assert_eq!(conn.open_resources().in_flight, 0);
+    }
+
+    #[cfg(all(feature = "aql", not(feature = "blocking")))]
+    #[tokio::test]
+    async fn dropped_request_is_not_counted_as_answered() {
+        use std::{future::Future, task::Context};
+
+        let mock = MockClient::default();
+        let conn = mock.connection();
+        let db_url = conn.url().join("/_db/test_db/").unwrap();
+        let registry = conn.session.registry();
+        registry.cursor_batch(&db_url, Some("abandoned"), true);
+        registry.cursor_abandoned(&db_url, "abandoned");
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/cursor/abandoned",
+            MockResponse::json(202, json!({ "error": false, "code": 202 })).with_yields(5),
+        );
+
+        // dropped while deleting the abandoned cursor, before being sent
+        let url = conn.url().join("/_api/version").unwrap();
+        let mut request = Box::pin(conn.session.get(url, ""));
+        let waker = futures_util::task::noop_waker();
+        assert!(request
+            .as_mut()
+            .poll(&mut Context::from_waker(&waker))
+            .is_pending());
+        drop(request);
+
+        let stats = conn.stats();
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.requests, 0);
+        assert_eq!(conn.open_resources().in_flight, 0);
+    }
+}
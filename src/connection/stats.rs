@@ -0,0 +1,167 @@
+//! Gauges of the requests sent through a connection, see
+//! [`GenericConnection::stats`](super::GenericConnection::stats).
+//!
+//! Every counter is an atomic shared by all handles derived from the
+//! connection, so that requests are counted without taking a lock, except
+//! the first request to an endpoint, which adds its counter.
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        RwLock,
+    },
+    time::Duration,
+};
+
+/// Snapshot of the requests of a connection, see
+/// [`GenericConnection::stats`](super::GenericConnection::stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Requests sent and not answered yet.
+    pub in_flight: usize,
+    /// Most requests in flight at once since the previous snapshot.
+    pub peak_in_flight: usize,
+    /// Requests sent since the connection was established.
+    pub requests: u64,
+    /// Total time requests waited before being handed to the HTTP client,
+    /// leaving out the deletion of the cursors abandoned before them.
+    ///
+    /// The HTTP clients do not tell how long a request waits for a pooled
+    /// connection, which is part of the time to answer it instead.
+    pub queue_wait: Duration,
+    /// Longest time a single request waited before being handed to the HTTP
+    /// client.
+    pub max_queue_wait: Duration,
+    /// Requests sent to each endpoint, by `host:port`.
+    pub per_endpoint: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct RequestStats {
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+    requests: AtomicU64,
+    queue_wait_nanos: AtomicU64,
+    max_queue_wait_nanos: AtomicU64,
+    per_endpoint: RwLock<HashMap<String, AtomicU64>>,
+}
+
+impl RequestStats {
+    /// Count a request to `endpoint` handed to the HTTP client after
+    /// waiting `waited`.
+    pub(crate) fn sent(&self, endpoint: &str, waited: Duration) {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(waited.as_nanos()).unwrap_or(u64::MAX);
+        self.queue_wait_nanos.fetch_add(nanos, Ordering::Relaxed);
+        self.max_queue_wait_nanos
+            .fetch_max(nanos, Ordering::Relaxed);
+
+        let counted = match self.per_endpoint.read() {
+            Ok(counts) => counts
+                .get(endpoint)
+                .map(|count| count.fetch_add(1, Ordering::Relaxed))
+                .is_some(),
+            Err(_) => false,
+        };
+        if !counted {
+            let mut counts = self.per_endpoint.write().unwrap_or_else(|e| e.into_inner());
+            counts
+                .entry(endpoint.to_owned())
+                .or_default()
+                .fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count the answer, or failure, of a request.
+    pub(crate) fn answered(&self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// The gauges, starting a new interval for the peak of requests in
+    /// flight.
+    pub(crate) fn snapshot(&self) -> ConnectionStats {
+        let in_flight = self.in_flight.load(Ordering::Relaxed);
+        let peak_in_flight = self
+            .peak_in_flight
+            .swap(in_flight, Ordering::Relaxed)
+            .max(in_flight);
+        let per_endpoint = self
+            .per_endpoint
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(endpoint, count)| (endpoint.clone(), count.load(Ordering::Relaxed)))
+            .collect();
+        ConnectionStats {
+            in_flight,
+            peak_in_flight,
+            requests: self.requests.load(Ordering::Relaxed),
+            queue_wait: Duration::from_nanos(self.queue_wait_nanos.load(Ordering::Relaxed)),
+            max_queue_wait: Duration::from_nanos(self.max_queue_wait_nanos.load(Ordering::Relaxed)),
+            per_endpoint,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use http::Method;
+    use serde_json::json;
+
+    use crate::test_support::{MockClient, MockResponse};
+
+    fn role() -> MockResponse {
+        let response = MockResponse::json(
+            200,
+            json!({ "error": false, "code": 200, "role": "SINGLE" }),
+        );
+        #[cfg(not(feature = "blocking"))]
+        let response = response.with_yields(20);
+        #[cfg(feature = "blocking")]
+        let response = response.with_delay(std::time::Duration::from_millis(200));
+        response
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn concurrent_requests_move_the_gauges() {
+        let mock = MockClient::default();
+        mock.on(Method::GET, "/_admin/server/role", role());
+        let conn = Arc::new(mock.connection());
+        assert_eq!(conn.stats().requests, 0);
+
+        #[cfg(not(feature = "blocking"))]
+        {
+            let requests = (0..100).map(|_| conn.server_role());
+            let results = futures_util::future::join_all(requests).await;
+            assert!(results.iter().all(Result::is_ok));
+        }
+        #[cfg(feature = "blocking")]
+        {
+            let threads: Vec<_> = (0..100)
+                .map(|_| {
+                    let conn = Arc::clone(&conn);
+                    std::thread::spawn(move || conn.server_role().is_ok())
+                })
+                .collect();
+            assert!(threads.into_iter().all(|t| t.join().unwrap()));
+        }
+
+        let stats = conn.stats();
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.requests, 100);
+        // all requests were pending at once
+        assert!(stats.peak_in_flight > 50, "{:?}", stats);
+        assert!(stats.max_queue_wait <= stats.queue_wait);
+        assert_eq!(stats.per_endpoint.get("localhost:8529"), Some(&100));
+
+        // the peak is per interval, between snapshots
+        let stats = conn.stats();
+        assert_eq!(stats.peak_in_flight, 0);
+        assert_eq!(stats.requests, 100);
+    }
+}
@@ -0,0 +1,378 @@
+//! Opening connections and priming the caches of a connection ahead of its
+//! first requests, see
+//! [`GenericConnection::warm_up`](super::GenericConnection::warm_up).
+//!
+//! Every step runs at once, and the steps not done by the deadline are
+//! reported as timed out. They are dropped in async mode, while in blocking
+//! mode their threads are left to finish the request in flight in the
+//! background, without sending the next one.
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
+
+use std::collections::BTreeMap;
+
+use log::trace;
+use uclient::ClientExt;
+use url::Url;
+
+use super::{endpoints::failure_reason, options::WarmUpOptions, Session, Version};
+use crate::{ClientError, Database};
+
+/// What [`GenericConnection::warm_up`](super::GenericConnection::warm_up)
+/// got done before its deadline.
+#[derive(Debug, Default)]
+pub struct WarmUpReport {
+    /// Connections opened to the server, kept alive by the HTTP client for
+    /// the next requests.
+    pub connections: usize,
+    /// Version of the server, received along the connections.
+    pub version: Option<Version>,
+    /// Databases whose access was probed and granted, in the order given.
+    pub databases: Vec<String>,
+    /// Names of the collections of each database, when asked for.
+    pub collections: BTreeMap<String, Vec<String>>,
+    /// The steps which failed or did not complete before the deadline.
+    pub failures: Vec<WarmUpFailure>,
+}
+
+impl WarmUpReport {
+    /// Whether every step succeeded within the deadline.
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn record(&mut self, step: &str, outcome: Outcome) {
+        match outcome {
+            Outcome::Connected(Ok(version)) => {
+                self.connections += 1;
+                self.version.get_or_insert(version);
+            }
+            Outcome::Connected(Err(e)) => self.fail(step, failure_reason(&e)),
+            Outcome::Database {
+                name,
+                access,
+                collections,
+            } => {
+                if let Err(e) = access {
+                    return self.fail(step, failure_reason(&e));
+                }
+                match collections {
+                    Some(Ok(names)) => {
+                        self.collections.insert(name.clone(), names);
+                    }
+                    Some(Err(e)) => {
+                        self.fail(&format!("collections of {}", name), failure_reason(&e))
+                    }
+                    None => {}
+                }
+                self.databases.push(name);
+            }
+        }
+    }
+
+    fn fail(&mut self, step: &str, reason: String) {
+        trace!("Warm-up of {} failed: {}", step, reason);
+        self.failures.push(WarmUpFailure {
+            step: step.to_owned(),
+            reason,
+        });
+    }
+
+    /// Report the steps without outcome as timed out, and sort the databases
+    /// after `databases`.
+    fn finish(mut self, steps: &[String], done: &[bool], databases: &[&str]) -> Self {
+        for (step, _) in steps.iter().zip(done).filter(|(_, done)| !**done) {
+            self.fail(step, "timeout".to_owned());
+        }
+        self.databases
+            .sort_by_key(|name| databases.iter().position(|d| d == name));
+        self
+    }
+}
+
+/// A step of the warm-up which failed, like `connection` or
+/// `database tenant`, with a short reason like `refused` or `timeout`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WarmUpFailure {
+    pub step: String,
+    pub reason: String,
+}
+
+enum Outcome {
+    Connected(Result<Version, ClientError>),
+    Database {
+        name: String,
+        access: Result<(), ClientError>,
+        collections: Option<Result<Vec<String>, ClientError>>,
+    },
+}
+
+/// Names of the steps: one per connection, then one per database.
+fn steps(connections: usize, databases: &[&str]) -> Vec<String> {
+    let connections = (0..connections).map(|_| "connection".to_owned());
+    let databases = databases.iter().map(|name| format!("database {}", name));
+    connections.chain(databases).collect()
+}
+
+#[maybe_async::maybe_async]
+async fn connect<C: ClientExt>(session: &Session<C>, url: Url) -> Outcome {
+    let version = match session.get(url, "").await {
+        Ok(resp) => serde_json::from_str::<Version>(resp.body()).map_err(ClientError::from),
+        Err(e) => Err(e),
+    };
+    if let Ok(version) = &version {
+        session
+            .registry()
+            .capabilities()
+            .set_version(&version.version);
+    }
+    Outcome::Connected(version)
+}
+
+#[maybe_async::maybe_async]
+async fn probe<C: ClientExt>(db: &Database<C>, collections: impl Fn() -> bool) -> Outcome {
+    let access = db.verify_access().await.map(|_| ());
+    let collections = match (&access, collections()) {
+        (Ok(()), true) => Some(
+            db.accessible_collections()
+                .await
+                .map(|infos| infos.into_iter().map(|info| info.name).collect()),
+        ),
+        _ => None,
+    };
+    Outcome::Database {
+        name: db.name().to_owned(),
+        access,
+        collections,
+    }
+}
+
+#[maybe_async::async_impl]
+pub(super) async fn warm_up<C: ClientExt>(
+    session: &Session<C>,
+    arango_url: &Url,
+    databases: Vec<Database<C>>,
+    options: &WarmUpOptions,
+) -> WarmUpReport {
+    use futures_util::{
+        future::{self, Either, FutureExt, LocalBoxFuture},
+        stream::{FuturesUnordered, StreamExt},
+    };
+
+    use super::endpoints::sleep;
+
+    let names: Vec<&str> = databases.iter().map(|db| db.name()).collect();
+    let steps = steps(options.connections, &names);
+    let mut done = vec![false; steps.len()];
+    let mut pending: FuturesUnordered<LocalBoxFuture<'_, (usize, Outcome)>> =
+        FuturesUnordered::new();
+    for i in 0..options.connections {
+        let url = arango_url
+            .join("/_api/version")
+            .unwrap_or_else(|_| arango_url.clone());
+        pending.push(async move { (i, connect(session, url).await) }.boxed_local());
+    }
+    for (i, db) in databases.iter().enumerate() {
+        let i = options.connections + i;
+        pending.push(async move { (i, probe(db, || options.collections).await) }.boxed_local());
+    }
+
+    let mut report = WarmUpReport::default();
+    let mut deadline = sleep(options.deadline);
+    while let Either::Left((Some((i, outcome)), _)) =
+        future::select(pending.next(), &mut deadline).await
+    {
+        done[i] = true;
+        report.record(&steps[i], outcome);
+    }
+    report.finish(&steps, &done, &names)
+}
+
+#[maybe_async::sync_impl]
+pub(super) fn warm_up<C: ClientExt + Send + Sync + 'static>(
+    session: &Session<C>,
+    arango_url: &Url,
+    databases: Vec<Database<C>>,
+    options: &WarmUpOptions,
+) -> WarmUpReport {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            mpsc, Arc,
+        },
+        thread,
+        time::Instant,
+    };
+
+    let deadline = Instant::now() + options.deadline;
+    let names: Vec<String> = databases.iter().map(|db| db.name().to_owned()).collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+    let steps = steps(options.connections, &names);
+    let mut done = vec![false; steps.len()];
+    let (sender, receiver) = mpsc::channel();
+    // the steps still running at the deadline are left behind, their outcome
+    // is dropped with the channel and they give up on their next request
+    let abandoned = Arc::new(AtomicBool::new(false));
+    for i in 0..options.connections {
+        let (sender, session) = (sender.clone(), session.clone());
+        let url = arango_url
+            .join("/_api/version")
+            .unwrap_or_else(|_| arango_url.clone());
+        thread::spawn(move || {
+            let _ = sender.send((i, connect(&session, url)));
+        });
+    }
+    for (i, db) in databases.into_iter().enumerate() {
+        let (sender, i, collections) =
+            (sender.clone(), options.connections + i, options.collections);
+        let abandoned = Arc::clone(&abandoned);
+        thread::spawn(move || {
+            let collections = || collections && !abandoned.load(Ordering::Relaxed);
+            let _ = sender.send((i, probe(&db, collections)));
+        });
+    }
+    drop(sender);
+
+    let mut report = WarmUpReport::default();
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    while let Ok((i, outcome)) = receiver.recv_timeout(remaining()) {
+        done[i] = true;
+        report.record(&steps[i], outcome);
+    }
+    abandoned.store(true, Ordering::Relaxed);
+    report.finish(&steps, &done, &names)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use http::Method;
+    use serde_json::json;
+
+    use crate::{
+        connection::options::WarmUpOptions,
+        test_support::{fixtures, MockClient, MockResponse},
+    };
+
+    fn current(name: &str) -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({
+                "error": false,
+                "code": 200,
+                "result": { "name": name, "id": "1", "path": "", "isSystem": false },
+            }),
+        )
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn warmed_up_databases_need_no_probe() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.4")),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/app/_api/database/current",
+            current("app"),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/app/_api/collection",
+            MockResponse::json(
+                200,
+                json!({
+                    "error": false,
+                    "code": 200,
+                    "result": [fixtures::collection_info("users", "100")],
+                }),
+            ),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/locked/_api/database/current",
+            MockResponse::json(401, fixtures::error(401, 11, "not authorized")),
+        );
+        let conn = mock.connection();
+        let options = WarmUpOptions::builder()
+            .connections(3)
+            .collections(true)
+            .build();
+        let report = conn.warm_up(&["app", "locked"], options).await;
+        assert_eq!(report.connections, 3);
+        assert_eq!(report.version.unwrap().version, "3.11.4");
+        assert_eq!(report.databases, vec!["app"]);
+        assert_eq!(report.collections["app"], vec!["users"]);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].step, "database locked");
+        mock.assert_no_pending();
+        mock.clear_requests();
+
+        // the first request after the warm-up goes straight to the server
+        mock.once(
+            Method::GET,
+            "/_db/app/_api/collection/users",
+            MockResponse::json(200, fixtures::collection_info("users", "100")),
+        );
+        let db = conn.db("app").await.unwrap();
+        db.collection("users").await.unwrap();
+        let paths: Vec<_> = mock.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["/_db/app/_api/collection/users"]);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn slow_steps_time_out() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.4")),
+        );
+        mock.on(
+            Method::GET,
+            "/_db/slow/_api/database/current",
+            current("slow").with_delay(Duration::from_secs(5)),
+        );
+        let conn = mock.connection();
+        let options = WarmUpOptions::builder()
+            .deadline(Duration::from_millis(200))
+            .build();
+        let report = conn.warm_up(&["slow"], options).await;
+        assert_eq!(report.connections, 1);
+        assert!(report.databases.is_empty());
+        assert!(!report.is_complete());
+        assert_eq!(report.failures[0].step, "database slow");
+        assert_eq!(report.failures[0].reason, "timeout");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn left_behind_probes_send_no_more_requests() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_api/version",
+            MockResponse::json(200, fixtures::version("3.11.4")),
+        );
+        mock.on(
+            Method::GET,
+            "/_db/slow/_api/database/current",
+            current("slow").with_delay(Duration::from_millis(300)),
+        );
+        let conn = mock.connection();
+        let options = WarmUpOptions::builder()
+            .deadline(Duration::from_millis(100))
+            .collections(true)
+            .build();
+        let report = conn.warm_up(&["slow"], options);
+        assert_eq!(report.failures[0].reason, "timeout");
+
+        std::thread::sleep(Duration::from_millis(600));
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|r| r.path != "/_db/slow/_api/collection"));
+    }
+}
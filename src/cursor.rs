@@ -0,0 +1,796 @@
+//! Cursors consumed batch by batch, which can be cancelled from elsewhere.
+//!
+//! Obtain a [`QueryCursor`] with
+//! [`Database::aql_cursor`](crate::Database::aql_cursor), and hand clones of
+//! its [`CursorHandle`] to whoever may need to stop the query, e.g. when the
+//! user who started it navigates away.
+use std::{
+    collections::VecDeque,
+    fmt,
+    marker::PhantomData,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use http::Request;
+use maybe_async::maybe_async;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use uclient::ClientExt;
+use url::Url;
+
+use crate::{
+    aql::{query_error, Cursor},
+    collection::options::Case,
+    connection::Session,
+    database::ensure_read_only_query,
+    response::deserialize_response,
+    ClientError,
+};
+
+/// Error number of ArangoDB when a cursor does not exist (anymore).
+const ERROR_CURSOR_NOT_FOUND: u16 = 1600;
+
+type HttpResult = Result<http::Response<String>, ClientError>;
+
+/// Request of a batch sent ahead of its consumption.
+#[cfg(not(feature = "blocking"))]
+type Prefetch = std::pin::Pin<Box<dyn std::future::Future<Output = HttpResult> + Send>>;
+
+#[derive(Debug, Default)]
+struct CursorState {
+    id: Option<String>,
+    /// Id of the next batch, if the server numbers them.
+    next_batch_id: Option<String>,
+    cancelled: bool,
+    exhausted: bool,
+}
+
+/// A query whose results are fetched one batch at a time.
+///
+/// The query is only sent to the server on the first call to
+/// [`next_batch`](QueryCursor::next_batch).
+pub struct QueryCursor<C: ClientExt, R> {
+    url: Url,
+    query: Option<String>,
+    /// Query text to check before creating the cursor, on read-only handles.
+    read_only_query: Option<String>,
+    /// Naming convention of the attributes of the results.
+    field_case: Case,
+    /// Maximum number of batches fetched ahead of consumption.
+    prefetch: usize,
+    /// Batches received but not consumed yet, in order.
+    buffered: VecDeque<Result<Vec<R>, ClientError>>,
+    /// Request of the batch following the buffered ones, behind a mutex only
+    /// for the cursor to be `Sync`: it is always reached through `&mut self`.
+    #[cfg(not(feature = "blocking"))]
+    in_flight: Option<Mutex<Prefetch>>,
+    handle: CursorHandle<C>,
+    phantom: PhantomData<R>,
+}
+
+impl<C: ClientExt, R: DeserializeOwned> QueryCursor<C, R> {
+    pub(crate) fn new(query: String, db_url: &Url, session: Session<C>) -> Self {
+        QueryCursor {
+            url: db_url.join("_api/cursor").unwrap(),
+            query: Some(query),
+            read_only_query: None,
+            field_case: Case::Snake,
+            prefetch: 0,
+            buffered: VecDeque::new(),
+            #[cfg(not(feature = "blocking"))]
+            in_flight: None,
+            handle: CursorHandle {
+                url: db_url.join("_api/cursor/").unwrap(),
+                session,
+                state: Arc::new(Mutex::new(CursorState::default())),
+            },
+            phantom: PhantomData,
+        }
+    }
+
+    /// Refuse to create the cursor if `query` modifies data.
+    pub(crate) fn check_read_only(mut self, query: &str) -> Self {
+        self.read_only_query = Some(query.to_owned());
+        self
+    }
+
+    /// Rename the attributes of the results after `case`.
+    #[cfg(feature = "documents")]
+    pub(crate) fn field_case(mut self, case: Case) -> Self {
+        self.field_case = case;
+        self
+    }
+
+    /// Request up to `batches` batches ahead of consumption, to save the
+    /// round trips between them. The default of 0 requests each batch when it
+    /// is needed.
+    ///
+    /// The server serves the batches of a cursor one after another, so the
+    /// next batch is requested as soon as the previous one arrived, until
+    /// `batches` batches are buffered. Unless the server numbers the batches,
+    /// see [`AqlOptions::allow_retry`](crate::AqlOptions), at most one batch
+    /// is fetched ahead, as a batch lost with its response could not be
+    /// requested again.
+    ///
+    /// Prefetching only starts the requests: no task of an async runtime
+    /// drives them, so their responses are received as the cursor is polled,
+    /// by the following calls to `next_batch`. What is saved is the time the
+    /// server takes to produce a batch, rather than its transfer.
+    ///
+    /// In blocking mode batches are always requested when needed.
+    pub fn prefetch(mut self, batches: usize) -> Self {
+        self.prefetch = batches;
+        self
+    }
+
+    /// A cloneable token to cancel this cursor.
+    pub fn handle(&self) -> CursorHandle<C> {
+        self.handle.clone()
+    }
+
+    /// Number of batches which may be fetched ahead.
+    #[cfg(not(feature = "blocking"))]
+    fn prefetch_depth(&self) -> usize {
+        if self.handle.state().next_batch_id.is_some() {
+            self.prefetch
+        } else {
+            self.prefetch.min(1)
+        }
+    }
+
+    /// Drop the prefetched batches and the request in flight.
+    fn abort_prefetch(&mut self) {
+        self.buffered.clear();
+        #[cfg(not(feature = "blocking"))]
+        {
+            self.in_flight = None;
+        }
+    }
+
+    /// Request of the batch following the last received one, if any.
+    fn next_request(&self) -> Option<Request<String>> {
+        let state = self.handle.state();
+        if state.exhausted || state.cancelled {
+            return None;
+        }
+        let id = state.id.as_ref()?;
+        let request = match &state.next_batch_id {
+            Some(batch) => {
+                let url = self.handle.url.join(&format!("{}/{}", id, batch)).unwrap();
+                Request::post(url.to_string())
+            }
+            None => Request::put(self.handle.url.join(id).unwrap().to_string()),
+        };
+        Some(request.body(String::new()).unwrap())
+    }
+
+    /// Parse a batch, and update the state of the cursor after it.
+    #[maybe_async]
+    async fn receive(&self, resp: HttpResult) -> Result<Vec<R>, ClientError> {
+        let cursor: Result<Cursor<R>, ClientError> =
+            resp.and_then(|resp| parse_batch(&resp, self.field_case).map_err(query_error));
+
+        let orphan = {
+            let mut state = self.handle.state();
+            let cursor = match cursor {
+                Ok(cursor) => cursor,
+                // the cursor was deleted by a concurrent cancellation
+                Err(ClientError::Arango(ref e))
+                    if state.cancelled && e.error_num() == ERROR_CURSOR_NOT_FOUND =>
+                {
+                    return Err(ClientError::Cancelled)
+                }
+                Err(e) => return Err(e),
+            };
+            if let (true, Err(e)) = (cursor.more, cursor.next_id()) {
+                // the following batches cannot be requested
+                state.exhausted = true;
+                return Err(e);
+            }
+            let id = cursor.id.as_deref().or(state.id.as_deref());
+            self.handle
+                .session
+                .registry()
+                .cursor_batch(&self.handle.db_url(), id, cursor.more);
+            if !cursor.more {
+                // the query completed anyway, so a late cancellation is moot
+                state.exhausted = true;
+                state.id = None;
+                state.next_batch_id = None;
+                return Ok(cursor.result);
+            }
+            state.id = cursor.id;
+            state.next_batch_id = cursor.next_batch_id;
+            if !state.cancelled {
+                return Ok(cursor.result);
+            }
+            // cancelled while the cursor was being created, so the handle
+            // could not delete it
+            state.id.clone()
+        };
+        if let Some(id) = orphan {
+            self.handle.delete(&id).await?;
+        }
+        Err(ClientError::Cancelled)
+    }
+}
+
+impl<C, R> QueryCursor<C, R>
+where
+    C: ClientExt + Send + 'static,
+    R: DeserializeOwned,
+{
+    /// Fetch the next batch of results, or `None` once all results have been
+    /// returned.
+    ///
+    /// Fails with [`ClientError::Cancelled`] when the cursor was cancelled
+    /// before all results were returned, dropping the batches fetched ahead.
+    /// A cursor cancelled after its last batch was received keeps returning
+    /// its remaining batches, and then `None`.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn next_batch(&mut self) -> Result<Option<Vec<R>>, ClientError> {
+        let cancelled = {
+            let state = self.handle.state();
+            state.cancelled && !state.exhausted
+        };
+        if cancelled {
+            self.abort_prefetch();
+            return Err(ClientError::Cancelled);
+        }
+
+        self.prefetch_batches().await;
+        let batch = match self.buffered.pop_front() {
+            Some(batch) => batch,
+            None => match self.wait_in_flight().await {
+                Some(batch) => batch,
+                None => match self.fetch().await? {
+                    Some(batch) => Ok(batch),
+                    None => return Ok(None),
+                },
+            },
+        }?;
+        self.prefetch_batches().await;
+        Ok(Some(batch))
+    }
+
+    /// Request the next batch and wait for it.
+    #[maybe_async]
+    async fn fetch(&mut self) -> Result<Option<Vec<R>>, ClientError> {
+        if let Some(query) = &self.read_only_query {
+            let db_url = self.url.join("../").unwrap();
+            ensure_read_only_query(&self.handle.session, &db_url, query).await?;
+            self.read_only_query = None;
+        }
+        let resp = match self.query.take() {
+            Some(query) => self.handle.session.post(self.url.clone(), &query).await,
+            None => match self.next_request() {
+                Some(request) => self.handle.session.request(request).await,
+                None => return Ok(None),
+            },
+        };
+        self.receive(resp).await.map(Some)
+    }
+
+    /// Collect the batch fetched ahead if it arrived, and request the
+    /// following ones while fewer than allowed are buffered.
+    #[maybe_async::async_impl]
+    async fn prefetch_batches(&mut self) {
+        loop {
+            if self.in_flight.is_none() {
+                if self.buffered.len() >= self.prefetch_depth() {
+                    return;
+                }
+                let request = match self.next_request() {
+                    Some(request) => request,
+                    None => return,
+                };
+                let session = self.handle.session.clone();
+                let request = Box::pin(async move { session.request(request).await });
+                self.in_flight = Some(Mutex::new(request));
+            }
+            // poll once to send the request, without waiting for the response,
+            // which then only progresses when the cursor is polled again
+            let in_flight = self.in_flight.as_mut().unwrap();
+            let in_flight = in_flight.get_mut().unwrap_or_else(|e| e.into_inner());
+            let resp = match futures_util::poll!(in_flight) {
+                std::task::Poll::Ready(resp) => resp,
+                std::task::Poll::Pending => return,
+            };
+            self.in_flight = None;
+            let batch = self.receive(resp).await;
+            let failed = batch.is_err();
+            self.buffered.push_back(batch);
+            if failed {
+                return;
+            }
+        }
+    }
+
+    /// Batches are requested when needed in blocking mode.
+    #[maybe_async::sync_impl]
+    fn prefetch_batches(&mut self) {}
+
+    /// Wait for the batch fetched ahead, if any.
+    #[maybe_async::async_impl]
+    async fn wait_in_flight(&mut self) -> Option<Result<Vec<R>, ClientError>> {
+        let in_flight = self.in_flight.take()?;
+        let resp = in_flight
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .await;
+        Some(self.receive(resp).await)
+    }
+
+    #[maybe_async::sync_impl]
+    fn wait_in_flight(&mut self) -> Option<Result<Vec<R>, ClientError>> {
+        None
+    }
+}
+
+impl<C, R> QueryCursor<C, R>
+where
+    C: ClientExt + Send + 'static,
+    R: DeserializeOwned + Send + 'static,
+{
+    /// The results one at a time, as a stream in async mode and as an
+    /// iterator in blocking mode, fetching each batch when the previous one
+    /// has been consumed.
+    ///
+    /// The results end after the first error. When they are dropped before
+    /// the last batch was received, the server side cursor is deleted: right
+    /// away in blocking mode, and with the next request made through the
+    /// connection in async mode, or at its
+    /// [`shutdown`](crate::GenericConnection::shutdown).
+    #[cfg(not(feature = "blocking"))]
+    pub fn into_results(self) -> impl futures_util::Stream<Item = Result<R, ClientError>> {
+        futures_util::stream::unfold(CursorResults::new(self), |mut results| async move {
+            let result = results.next_result().await?;
+            Some((result, results))
+        })
+    }
+
+    /// The results one at a time, as a stream in async mode and as an
+    /// iterator in blocking mode, fetching each batch when the previous one
+    /// has been consumed.
+    ///
+    /// The results end after the first error. When they are dropped before
+    /// the last batch was received, the server side cursor is deleted: right
+    /// away in blocking mode, and with the next request made through the
+    /// connection in async mode, or at its
+    /// [`shutdown`](crate::GenericConnection::shutdown).
+    #[cfg(feature = "blocking")]
+    pub fn into_results(self) -> impl Iterator<Item = Result<R, ClientError>> {
+        CursorResults::new(self)
+    }
+}
+
+/// Results of a [`QueryCursor`] one at a time, which abandon the cursor when
+/// dropped early.
+struct CursorResults<C: ClientExt, R> {
+    cursor: QueryCursor<C, R>,
+    batch: std::vec::IntoIter<R>,
+    done: bool,
+}
+
+impl<C, R> CursorResults<C, R>
+where
+    C: ClientExt + Send + 'static,
+    R: DeserializeOwned,
+{
+    fn new(cursor: QueryCursor<C, R>) -> Self {
+        CursorResults {
+            cursor,
+            batch: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    #[maybe_async]
+    async fn next_result(&mut self) -> Option<Result<R, ClientError>> {
+        loop {
+            if let Some(result) = self.batch.next() {
+                return Some(Ok(result));
+            }
+            if self.done {
+                return None;
+            }
+            match self.cursor.next_batch().await {
+                Ok(Some(batch)) => self.batch = batch.into_iter(),
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<C, R> Iterator for CursorResults<C, R>
+where
+    C: ClientExt + Send + 'static,
+    R: DeserializeOwned,
+{
+    type Item = Result<R, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_result()
+    }
+}
+
+impl<C: ClientExt, R> Drop for CursorResults<C, R> {
+    fn drop(&mut self) {
+        let handle = &self.cursor.handle;
+        let id = {
+            let state = handle.state();
+            if state.exhausted || state.cancelled {
+                return;
+            }
+            match &state.id {
+                Some(id) => id.clone(),
+                None => return,
+            }
+        };
+        let registry = handle.session.registry();
+        registry.cursor_abandoned(&handle.db_url(), &id);
+        #[cfg(feature = "blocking")]
+        handle.session.release_abandoned();
+    }
+}
+
+/// Leaves out the query, whose bind variables may be sensitive, and the HTTP
+/// client, whose headers hold the credentials.
+impl<C: ClientExt, R> fmt::Debug for QueryCursor<C, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryCursor")
+            .field("state", &*self.handle.state())
+            .field("field_case", &self.field_case)
+            .field("prefetch", &self.prefetch)
+            .field("buffered", &self.buffered.len())
+            .finish_non_exhaustive()
+    }
+}
+
+fn parse_batch<R>(resp: &http::Response<String>, case: Case) -> Result<Cursor<R>, ClientError>
+where
+    R: DeserializeOwned,
+{
+    if case == Case::Snake {
+        return deserialize_response(resp);
+    }
+    let cursor: Cursor<Value> = deserialize_response(resp)?;
+    let result = cursor
+        .result
+        .into_iter()
+        .map(|doc| serde_json::from_value(case.to_rust(doc)))
+        .collect::<Result<_, _>>()?;
+    Ok(Cursor {
+        count: cursor.count,
+        cached: cursor.cached,
+        more: cursor.more,
+        result,
+        id: cursor.id,
+        next_batch_id: cursor.next_batch_id,
+        extra: cursor.extra,
+    })
+}
+
+/// Cheap cloneable token to cancel a [`QueryCursor`], possibly from another
+/// task or thread.
+#[derive(Debug, Clone)]
+pub struct CursorHandle<C: ClientExt> {
+    url: Url,
+    session: Session<C>,
+    state: Arc<Mutex<CursorState>>,
+}
+
+impl<C: ClientExt> CursorHandle<C> {
+    fn state(&self) -> std::sync::MutexGuard<'_, CursorState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn db_url(&self) -> Url {
+        self.url.join("../../").unwrap()
+    }
+
+    /// Whether the cursor was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state().cancelled
+    }
+
+    /// Cancel the cursor.
+    ///
+    /// The server side cursor is deleted right away if it exists, and the
+    /// next call to [`QueryCursor::next_batch`] fails with
+    /// [`ClientError::Cancelled`]. Cancelling a cursor which has returned all
+    /// its results, or which was already cancelled, does nothing.
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn cancel(&self) -> Result<(), ClientError> {
+        let id = {
+            let mut state = self.state();
+            if state.exhausted || state.cancelled {
+                return Ok(());
+            }
+            state.cancelled = true;
+            state.id.clone()
+        };
+        match id {
+            Some(id) => self.delete(&id).await,
+            None => Ok(()),
+        }
+    }
+
+    #[maybe_async]
+    async fn delete(&self, id: &str) -> Result<(), ClientError> {
+        let url = self.url.join(id).unwrap();
+        let resp = self.session.delete(url, "").await?;
+        match deserialize_response::<Value>(&resp) {
+            Err(ClientError::Arango(ref e)) if e.error_num() == ERROR_CURSOR_NOT_FOUND => {}
+            Err(e) => return Err(e),
+            Ok(_) => {}
+        }
+        self.session.registry().cursor_deleted(&self.db_url(), id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::Method;
+    use serde_json::json;
+
+    use super::QueryCursor;
+    use crate::{
+        test_support::{fixtures, MockClient, MockResponse},
+        AqlQuery, ClientError,
+    };
+
+    const CURSOR_PATH: &str = "/_db/test_db/_api/cursor/mock_cursor";
+
+    fn mock_three_batches() -> MockClient {
+        let mock = MockClient::default();
+        mock.expect_cursor(
+            "test_db",
+            vec![vec![json!(1)], vec![json!(2)], vec![json!(3)]],
+        );
+        mock.on(
+            Method::DELETE,
+            CURSOR_PATH,
+            MockResponse::json(
+                202,
+                json!({ "error": false, "code": 202, "id": "mock_cursor" }),
+            ),
+        );
+        mock
+    }
+
+    fn query() -> AqlQuery<'static> {
+        AqlQuery::builder()
+            .query("FOR i IN 1..3 RETURN i")
+            .batch_size(1)
+            .build()
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn malformed_cursor_id() {
+        let mock = MockClient::default();
+        let batches = fixtures::cursor_batches("../../_api/database", vec![vec![json!(1)]; 2]);
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/cursor",
+            MockResponse::json(201, batches[0].clone()),
+        );
+        let mut cursor = mock.database("test_db").aql_cursor::<u32>(query()).unwrap();
+        let batch = cursor.next_batch().await;
+        assert!(
+            matches!(batch, Err(ClientError::MalformedResponse(_))),
+            "{:?}",
+            batch
+        );
+        let batch = cursor.next_batch().await.unwrap();
+        assert_eq!(batch, None);
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn cancel_before_first_batch() {
+        let mock = mock_three_batches();
+        let mut cursor = mock.database("test_db").aql_cursor::<u32>(query()).unwrap();
+        cursor.handle().cancel().await.unwrap();
+
+        let batch = cursor.next_batch().await;
+        assert!(matches!(batch, Err(ClientError::Cancelled)));
+        let batch = cursor.next_batch().await;
+        assert!(matches!(batch, Err(ClientError::Cancelled)));
+        mock.assert_requests(&[]);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn cancel_mid_stream() {
+        let mock = mock_three_batches();
+        let mut cursor = mock.database("test_db").aql_cursor::<u32>(query()).unwrap();
+        let handle = cursor.handle();
+        let batch = cursor.next_batch().await.unwrap();
+        assert_eq!(batch, Some(vec![1]));
+
+        handle.cancel().await.unwrap();
+        assert!(handle.is_cancelled());
+        let batch = cursor.next_batch().await;
+        assert!(matches!(batch, Err(ClientError::Cancelled)));
+        // cancelling again does not delete again
+        handle.clone().cancel().await.unwrap();
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::DELETE, CURSOR_PATH),
+        ]);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn cancel_after_exhaustion() {
+        let mock = mock_three_batches();
+        let mut cursor = mock.database("test_db").aql_cursor::<u32>(query()).unwrap();
+        let mut results = Vec::new();
+        while let Some(batch) = cursor.next_batch().await.unwrap() {
+            results.extend(batch);
+        }
+        assert_eq!(results, vec![1, 2, 3]);
+
+        cursor.handle().cancel().await.unwrap();
+        let batch = cursor.next_batch().await.unwrap();
+        assert_eq!(batch, None);
+        assert!(mock
+            .requests()
+            .iter()
+            .all(|request| request.method != Method::DELETE));
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn cancel_racing_deletion() {
+        let mock = MockClient::default();
+        mock.expect_cursor("test_db", vec![vec![json!(1)], vec![json!(2)]]);
+        // the server already dropped the cursor, e.g. after its ttl
+        mock.on(
+            Method::DELETE,
+            CURSOR_PATH,
+            MockResponse::json(404, fixtures::error(404, 1600, "cursor not found")),
+        );
+        let mut cursor = mock.database("test_db").aql_cursor::<u32>(query()).unwrap();
+        cursor.next_batch().await.unwrap();
+        cursor.handle().cancel().await.unwrap();
+        let batch = cursor.next_batch().await;
+        assert!(matches!(batch, Err(ClientError::Cancelled)));
+    }
+
+    /// Batches `1..=n` of one number each, numbered with `nextBatchId` if
+    /// `batch_ids`, the second batch answered after `yields` polls.
+    fn mock_numbered(n: u32, batch_ids: bool, yields: usize) -> MockClient {
+        let mock = MockClient::default();
+        let batches = (1..=n).map(|i| vec![json!(i)]).collect();
+        for (i, mut batch) in fixtures::cursor_batches("mock_cursor", batches)
+            .into_iter()
+            .enumerate()
+        {
+            let more = batch["hasMore"] == true;
+            if batch_ids && more {
+                batch["nextBatchId"] = json!((i + 2).to_string());
+            }
+            let (method, path) = match i {
+                0 => (Method::POST, "/_db/test_db/_api/cursor".to_owned()),
+                _ if batch_ids => (Method::POST, format!("{}/{}", CURSOR_PATH, i + 1)),
+                _ => (Method::PUT, CURSOR_PATH.to_owned()),
+            };
+            let response = MockResponse::json(201, batch);
+            let response = if i == 1 {
+                response.with_yields(yields)
+            } else {
+                response
+            };
+            mock.once(method, &path, response);
+        }
+        mock.on(
+            Method::DELETE,
+            CURSOR_PATH,
+            MockResponse::json(202, json!({ "error": false, "code": 202 })),
+        );
+        mock
+    }
+
+    #[maybe_async::maybe_async]
+    async fn drain(cursor: &mut QueryCursor<MockClient, u32>) -> Vec<u32> {
+        let mut results = Vec::new();
+        while let Some(batch) = cursor.next_batch().await.unwrap() {
+            results.extend(batch);
+        }
+        results
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn prefetch_overlaps_consumption() {
+        let mock = mock_numbered(3, false, 2);
+        let db = mock.database("test_db");
+        let mut cursor = db.aql_cursor::<u32>(query()).unwrap().prefetch(1);
+
+        let batch = cursor.next_batch().await.unwrap();
+        assert_eq!(batch, Some(vec![1]));
+        // the second batch is requested before the first one is consumed
+        #[cfg(not(feature = "blocking"))]
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::PUT, CURSOR_PATH),
+        ]);
+        let rest = drain(&mut cursor).await;
+        assert_eq!(rest, vec![2, 3]);
+        assert_eq!(mock.requests().len(), 3);
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn prefetch_with_batch_ids() {
+        let mock = mock_numbered(5, true, 0);
+        let db = mock.database("test_db");
+        let mut cursor = db.aql_cursor::<u32>(query()).unwrap().prefetch(2);
+
+        let batch = cursor.next_batch().await.unwrap();
+        assert_eq!(batch, Some(vec![1]));
+        // at most two batches are buffered
+        #[cfg(not(feature = "blocking"))]
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::POST, "/_db/test_db/_api/cursor/mock_cursor/2"),
+            (Method::POST, "/_db/test_db/_api/cursor/mock_cursor/3"),
+        ]);
+        let rest = drain(&mut cursor).await;
+        assert_eq!(rest, vec![2, 3, 4, 5]);
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn prefetch_one_without_batch_ids() {
+        let mock = mock_numbered(4, false, 0);
+        let db = mock.database("test_db");
+        let mut cursor = db.aql_cursor::<u32>(query()).unwrap().prefetch(3);
+
+        let batch = cursor.next_batch().await.unwrap();
+        assert_eq!(batch, Some(vec![1]));
+        #[cfg(not(feature = "blocking"))]
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::PUT, CURSOR_PATH),
+        ]);
+        let rest = drain(&mut cursor).await;
+        assert_eq!(rest, vec![2, 3, 4]);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn cancel_drops_prefetch() {
+        let mock = mock_numbered(3, false, 5);
+        let db = mock.database("test_db");
+        let mut cursor = db.aql_cursor::<u32>(query()).unwrap().prefetch(1);
+        cursor.next_batch().await.unwrap();
+
+        cursor.handle().cancel().await.unwrap();
+        let batch = cursor.next_batch().await;
+        assert!(matches!(batch, Err(ClientError::Cancelled)));
+        let batch = cursor.next_batch().await;
+        assert!(matches!(batch, Err(ClientError::Cancelled)));
+        // the prefetched request is dropped instead of being waited for
+        #[cfg(not(feature = "blocking"))]
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::PUT, CURSOR_PATH),
+            (Method::DELETE, CURSOR_PATH),
+        ]);
+        #[cfg(feature = "blocking")]
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::DELETE, CURSOR_PATH),
+        ]);
+    }
+}
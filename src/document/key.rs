@@ -0,0 +1,378 @@
+//! Document keys and ids, and keys assigned by the client, see
+//! [`KeyStrategy`].
+use std::{convert::TryFrom, fmt, ops::Deref, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "documents")]
+use serde_json::Value;
+#[cfg(feature = "documents")]
+use url::Url;
+
+use crate::ClientError;
+
+/// Longest key accepted by ArangoDB, in bytes.
+const MAX_KEY_LEN: usize = 254;
+
+/// Punctuation allowed in keys besides letters and digits.
+const KEY_PUNCTUATION: &str = "_-:.@()+,=;$!*'%";
+
+/// A document key following the rules of ArangoDB: 1 to 254 ASCII letters,
+/// digits and a few punctuation characters, `_-:.@()+,=;$!*'%`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct DocumentKey(String);
+
+impl DocumentKey {
+    /// Check `key` against the rules of ArangoDB, failing with
+    /// [`ClientError::InvalidKey`] otherwise.
+    pub fn new(key: impl Into<String>) -> Result<Self, ClientError> {
+        let key = key.into();
+        let invalid = |reason| {
+            Err(ClientError::InvalidKey {
+                key: key.clone(),
+                reason,
+            })
+        };
+        if key.is_empty() {
+            return invalid("empty key");
+        }
+        if key.len() > MAX_KEY_LEN {
+            return invalid("longer than 254 bytes");
+        }
+        if !key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || KEY_PUNCTUATION.contains(c))
+        {
+            return invalid("character not allowed in keys");
+        }
+        Ok(DocumentKey(key))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for DocumentKey {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for DocumentKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<DocumentKey> for String {
+    fn from(key: DocumentKey) -> Self {
+        key.0
+    }
+}
+
+impl AsRef<str> for DocumentKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for DocumentKey {
+    type Error = ClientError;
+
+    fn try_from(key: &str) -> Result<Self, ClientError> {
+        DocumentKey::new(key)
+    }
+}
+
+impl TryFrom<String> for DocumentKey {
+    type Error = ClientError;
+
+    fn try_from(key: String) -> Result<Self, ClientError> {
+        DocumentKey::new(key)
+    }
+}
+
+impl FromStr for DocumentKey {
+    type Err = ClientError;
+
+    fn from_str(key: &str) -> Result<Self, ClientError> {
+        DocumentKey::new(key)
+    }
+}
+
+/// The `_id` of a document, like `users/alice`: the name of its collection
+/// and its key, as also found in the `_from` and `_to` of edges.
+///
+/// It is (de)serialized as the string of the id, so it can type the `_id`,
+/// `_from` and `_to` fields of documents.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct DocumentId {
+    collection: String,
+    key: DocumentKey,
+}
+
+impl DocumentId {
+    /// The id of the document with `key` in `collection`.
+    pub fn new(collection: impl Into<String>, key: DocumentKey) -> Self {
+        DocumentId {
+            collection: collection.into(),
+            key,
+        }
+    }
+
+    /// The name of the collection of the document.
+    pub fn collection(&self) -> &str {
+        &self.collection
+    }
+
+    pub fn key(&self) -> &DocumentKey {
+        &self.key
+    }
+
+    /// Split the id into the name of the collection and the key.
+    pub fn into_parts(self) -> (String, DocumentKey) {
+        (self.collection, self.key)
+    }
+}
+
+impl fmt::Display for DocumentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.collection, self.key)
+    }
+}
+
+impl From<DocumentId> for String {
+    fn from(id: DocumentId) -> Self {
+        id.to_string()
+    }
+}
+
+impl FromStr for DocumentId {
+    type Err = ClientError;
+
+    /// Parse `collection/key`, failing with [`ClientError::InvalidId`]
+    /// unless both parts are set and the key follows the rules of ArangoDB.
+    fn from_str(id: &str) -> Result<Self, ClientError> {
+        let invalid = |reason| ClientError::InvalidId {
+            id: id.to_owned(),
+            reason,
+        };
+        let (collection, key) = id
+            .split_once('/')
+            .ok_or_else(|| invalid("no slash between collection and key"))?;
+        if collection.is_empty() {
+            return Err(invalid("empty collection name"));
+        }
+        let key = DocumentKey::new(key).map_err(|e| match e {
+            ClientError::InvalidKey { reason, .. } => invalid(reason),
+            e => e,
+        })?;
+        Ok(DocumentId::new(collection, key))
+    }
+}
+
+impl TryFrom<&str> for DocumentId {
+    type Error = ClientError;
+
+    fn try_from(id: &str) -> Result<Self, ClientError> {
+        id.parse()
+    }
+}
+
+impl TryFrom<String> for DocumentId {
+    type Error = ClientError;
+
+    fn try_from(id: String) -> Result<Self, ClientError> {
+        id.parse()
+    }
+}
+
+/// Url of the document with `key` under `base`, which ends with a slash.
+///
+/// The key is checked against the rules of ArangoDB, failing with
+/// [`ClientError::InvalidKey`], and percent-encoded, as `%` or a leading
+/// `name:` would otherwise be read as an escape or a scheme.
+#[cfg(feature = "documents")]
+pub(crate) fn document_url(base: &Url, key: &str) -> Result<Url, ClientError> {
+    let key = DocumentKey::new(key)?;
+    let mut url = base.clone();
+    url.path_segments_mut()
+        .expect("document urls have a path")
+        .pop_if_empty()
+        .push(&key);
+    Ok(url)
+}
+
+/// How the key of a document sent without `_key` is chosen.
+///
+/// A key is only generated for the documents serialized without a `_key`
+/// attribute, the others are sent as they are.
+#[derive(Default)]
+pub enum KeyStrategy<T> {
+    /// The server generates the key, after the key options of the
+    /// collection.
+    #[default]
+    ServerAssigned,
+    /// A ULID, sorting by creation time. Needs the `ulid` feature.
+    #[cfg(feature = "ulid")]
+    Ulid,
+    /// A UUID of version 7, sorting by creation time. Needs the `uuid`
+    /// feature.
+    #[cfg(feature = "uuid")]
+    UuidV7,
+    /// A key derived from the document.
+    Custom(Box<dyn Fn(&T) -> DocumentKey + Send + Sync>),
+}
+
+impl<T> fmt::Debug for KeyStrategy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStrategy::ServerAssigned => f.write_str("ServerAssigned"),
+            #[cfg(feature = "ulid")]
+            KeyStrategy::Ulid => f.write_str("Ulid"),
+            #[cfg(feature = "uuid")]
+            KeyStrategy::UuidV7 => f.write_str("UuidV7"),
+            KeyStrategy::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl<T> KeyStrategy<T> {
+    /// A new key for `doc`, `None` when the server assigns it.
+    pub fn generate(&self, doc: &T) -> Result<Option<DocumentKey>, ClientError> {
+        match self {
+            KeyStrategy::ServerAssigned => Ok(None),
+            #[cfg(feature = "ulid")]
+            KeyStrategy::Ulid => DocumentKey::new(ulid::Ulid::new().to_string()).map(Some),
+            #[cfg(feature = "uuid")]
+            KeyStrategy::UuidV7 => DocumentKey::new(uuid::Uuid::now_v7().to_string()).map(Some),
+            KeyStrategy::Custom(generate) => Ok(Some(generate(doc))),
+        }
+    }
+}
+
+impl<T: Serialize> KeyStrategy<T> {
+    /// Serialize `doc`, with a generated `_key` if it has none.
+    #[cfg(feature = "documents")]
+    pub(crate) fn assign(&self, doc: &T) -> Result<Value, ClientError> {
+        let mut value = serde_json::to_value(doc)?;
+        if let Value::Object(fields) = &mut value {
+            if !fields.contains_key("_key") {
+                if let Some(key) = self.generate(doc)? {
+                    fields.insert("_key".to_owned(), Value::String(key.into()));
+                }
+            }
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn key_rules() {
+        for key in [
+            "a",
+            "0",
+            "a_b-c:d.e@f(g)+h,i=j;k$l!m*n'o%p",
+            &"k".repeat(254),
+        ] {
+            assert_eq!(DocumentKey::new(key).unwrap().as_str(), key);
+        }
+        for key in ["", "a/b", "a b", "é", "a\n", &"k".repeat(255)] {
+            assert!(
+                matches!(DocumentKey::new(key), Err(ClientError::InvalidKey { .. })),
+                "{:?}",
+                key
+            );
+        }
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn parsed_and_encoded_keys() {
+        let parsed: DocumentKey = "alice".parse().unwrap();
+        assert_eq!(DocumentKey::try_from("alice").unwrap(), parsed);
+        assert!(DocumentKey::try_from("a/b".to_owned()).is_err());
+
+        let base = Url::parse("http://localhost:8529/_db/test/_api/document/users/").unwrap();
+        for (key, path) in [
+            ("alice", "/_db/test/_api/document/users/alice"),
+            ("a%41", "/_db/test/_api/document/users/a%2541"),
+            ("a:b", "/_db/test/_api/document/users/a:b"),
+        ] {
+            assert_eq!(document_url(&base, key).unwrap().path(), path);
+        }
+        for key in ["", "a/b", "../other/a", "a?b"] {
+            assert!(document_url(&base, key).is_err(), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn document_ids() {
+        let id: DocumentId = "users/alice".parse().unwrap();
+        assert_eq!((id.collection(), id.key().as_str()), ("users", "alice"));
+        assert_eq!(id.to_string(), "users/alice");
+        let edge: std::collections::HashMap<String, DocumentId> =
+            serde_json::from_value(json!({ "_from": "users/alice", "_to": "cities/a:1" })).unwrap();
+        assert_eq!(edge["_to"].key().as_str(), "a:1");
+        assert_eq!(serde_json::to_value(&edge["_from"]).unwrap(), "users/alice");
+
+        for id in ["alice", "/alice", "users/", "users/a/b", "users/a b"] {
+            assert!(
+                matches!(id.parse::<DocumentId>(), Err(ClientError::InvalidId { .. })),
+                "{:?}",
+                id
+            );
+        }
+        assert!(serde_json::from_value::<DocumentId>(json!("alice")).is_err());
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn keys_are_only_assigned_when_missing() {
+        let strategy: KeyStrategy<Value> = KeyStrategy::Custom(Box::new(|doc| {
+            DocumentKey::new(doc["name"].as_str().unwrap()).unwrap()
+        }));
+        assert_eq!(
+            strategy.assign(&json!({ "name": "bob" })).unwrap(),
+            json!({ "_key": "bob", "name": "bob" })
+        );
+        assert_eq!(
+            strategy
+                .assign(&json!({ "_key": "k", "name": "bob" }))
+                .unwrap(),
+            json!({ "_key": "k", "name": "bob" })
+        );
+        let server = KeyStrategy::<Value>::ServerAssigned;
+        assert_eq!(
+            server.assign(&json!({ "name": "bob" })).unwrap(),
+            json!({ "name": "bob" })
+        );
+    }
+
+    #[cfg(feature = "ulid")]
+    #[test]
+    fn ulid() {
+        let strategy = KeyStrategy::<()>::Ulid;
+        let key = strategy.generate(&()).unwrap().unwrap();
+        assert_eq!(key.len(), 26);
+        assert_ne!(strategy.generate(&()).unwrap(), Some(key));
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn uuid_v7() {
+        let key = KeyStrategy::<()>::UuidV7.generate(&()).unwrap().unwrap();
+        assert_eq!(key.len(), 36);
+        assert_eq!(&key[14..15], "7");
+    }
+}
@@ -3,19 +3,97 @@
 //! This mod contains document related types.
 //! Operations are conducted on collection level struct
 use serde::{de::DeserializeOwned, de::Error as DeError, Deserialize, Deserializer, Serialize};
-use std::ops::Deref;
+use std::{borrow::Cow, ops::Deref};
 
+use key::DocumentId;
+
+pub mod key;
+#[cfg(feature = "documents")]
 pub mod options;
+#[cfg(feature = "documents")]
 pub mod response;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Identity of a document. Only `_key` is required: a document about to be
+/// inserted has no `_id` nor `_rev`, and no `_key` either when the server
+/// generates the key. Absent fields are left out on the wire.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 pub struct Header {
-    #[serde(skip_serializing_if = "String::is_empty")]
-    pub _id: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub _id: Option<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub _key: String,
-    #[serde(skip_serializing_if = "String::is_empty")]
-    pub _rev: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub _rev: Option<String>,
+}
+
+impl Header {
+    /// Header of a document to insert with key `key`.
+    pub fn with_key(key: impl Into<String>) -> Self {
+        Header {
+            _key: key.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The `_id` split into the name of the collection and the key, or
+    /// `None` without a valid `_id`, as for a document about to be inserted.
+    pub fn document_id(&self) -> Option<DocumentId> {
+        self._id.as_deref()?.parse().ok()
+    }
+}
+
+/// [`Header`] borrowing its fields from the data it was deserialized from,
+/// which saves allocations when reading many documents.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct HeaderRef<'a> {
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrowed_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub _id: Option<Cow<'a, str>>,
+    #[serde(borrow, default, skip_serializing_if = "str::is_empty")]
+    pub _key: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrowed_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub _rev: Option<Cow<'a, str>>,
+}
+
+impl HeaderRef<'_> {
+    /// Copy the borrowed fields.
+    pub fn into_owned(self) -> Header {
+        Header {
+            _id: self._id.map(Cow::into_owned),
+            _key: self._key.into_owned(),
+            _rev: self._rev.map(Cow::into_owned),
+        }
+    }
+}
+
+impl<'a> From<&'a Header> for HeaderRef<'a> {
+    fn from(header: &'a Header) -> Self {
+        HeaderRef {
+            _id: header._id.as_deref().map(Cow::Borrowed),
+            _key: Cow::Borrowed(&header._key),
+            _rev: header._rev.as_deref().map(Cow::Borrowed),
+        }
+    }
+}
+
+/// serde only borrows a `Cow` which is not wrapped in an `Option`.
+fn borrowed_option<'de, D>(deserializer: D) -> Result<Option<Cow<'de, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Ok(Option::<Borrowed>::deserialize(deserializer)?.map(|borrowed| borrowed.0))
 }
 
 /// Structure that represents a document within its content and header
@@ -34,11 +112,16 @@ where
     pub fn new(data: T) -> Self {
         Document {
             document: data,
-            header: Header {
-                _id: String::new(),
-                _key: String::new(),
-                _rev: String::new(),
-            },
+            header: Header::default(),
+        }
+    }
+
+    /// Document to insert with the key `key`, without a `_key` field in
+    /// `data`.
+    pub fn with_key(key: impl Into<String>, data: T) -> Self {
+        Document {
+            document: data,
+            header: Header::with_key(key),
         }
     }
 }
@@ -65,28 +148,173 @@ where
     where
         D: Deserializer<'de>,
     {
-        let mut obj = serde_json::Value::deserialize(deserializer)?;
+        let obj = serde_json::Value::deserialize(deserializer)?;
 
         let json = obj
-            .as_object_mut()
+            .as_object()
             .ok_or_else(|| DeError::custom("should be a json object"))?;
 
-        let _id = json
-            .get("_id")
-            .ok_or_else(|| DeError::missing_field("_id"))?;
-        let _key = json
-            .get("_key")
-            .ok_or_else(|| DeError::missing_field("_key"))?;
-        let _rev = json
-            .get("_rev")
-            .ok_or_else(|| DeError::missing_field("_rev"))?;
-        let header: Header = Header {
-            _id: serde_json::from_value(_id.clone()).map_err(DeError::custom)?,
-            _key: serde_json::from_value(_key.clone()).map_err(DeError::custom)?,
-            _rev: serde_json::from_value(_rev.clone()).map_err(DeError::custom)?,
-        };
+        if !json.contains_key("_key") {
+            return Err(DeError::missing_field("_key"));
+        }
+        // the header is borrowed from `obj`, which is still needed for the
+        // fields of `document`
+        let header = HeaderRef::deserialize(&obj)
+            .map_err(DeError::custom)?
+            .into_owned();
         let document = serde_json::from_value(obj).map_err(DeError::custom)?;
 
         Ok(Document { header, document })
     }
 }
+
+/// An edge with its header, its endpoints and its content, which reads back
+/// into the same type.
+///
+/// The endpoints are [`DocumentId`]s, so an edge cannot be built with a
+/// malformed `_from` or `_to`. The fields of `document` are written next to
+/// them, and should not be named after a system attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EdgeDocument<T> {
+    #[serde(flatten)]
+    pub header: Header,
+    pub _from: DocumentId,
+    pub _to: DocumentId,
+    #[serde(flatten)]
+    pub document: T,
+}
+
+impl<T> EdgeDocument<T> {
+    /// Edge from `from` to `to`, with no key so that the server assigns it.
+    pub fn new(from: DocumentId, to: DocumentId, document: T) -> Self {
+        EdgeDocument {
+            header: Header::default(),
+            _from: from,
+            _to: to,
+            document,
+        }
+    }
+
+    /// Edge between the ids `from` and `to` like `users/alice`, failing
+    /// with [`ClientError::InvalidId`](crate::ClientError::InvalidId) if
+    /// either is not the id of a document.
+    pub fn try_new(from: &str, to: &str, document: T) -> Result<Self, crate::ClientError> {
+        Ok(EdgeDocument::new(from.parse()?, to.parse()?, document))
+    }
+
+    /// The same edge with the key `key`.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.header._key = key.into();
+        self
+    }
+}
+
+impl<T> Deref for EdgeDocument<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.document
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    #[test]
+    fn absent_fields_are_not_serialized() {
+        let doc = Document::new(json!({ "name": "a" }));
+        assert_eq!(serde_json::to_value(&doc).unwrap(), json!({ "name": "a" }));
+
+        let doc = Document {
+            header: Header::with_key("k"),
+            document: json!({ "name": "a" }),
+        };
+        assert_eq!(
+            serde_json::to_value(&doc).unwrap(),
+            json!({ "_key": "k", "name": "a" })
+        );
+        assert_eq!(
+            serde_json::to_value(HeaderRef::from(&doc.header)).unwrap(),
+            json!({ "_key": "k" })
+        );
+    }
+
+    #[test]
+    fn optional_id_and_rev() {
+        let doc: Document<Value> =
+            serde_json::from_value(json!({ "_key": "k", "name": "a" })).unwrap();
+        assert_eq!(doc.header, Header::with_key("k"));
+        assert_eq!(doc.header.document_id(), None);
+        assert_eq!(doc.document["name"], "a");
+
+        let doc: Document<Value> = serde_json::from_value(json!({
+            "_id": "c/k", "_key": "k", "_rev": "_r", "name": "a"
+        }))
+        .unwrap();
+        assert_eq!(doc.header._id.as_deref(), Some("c/k"));
+        let id = doc.header.document_id().unwrap();
+        assert_eq!((id.collection(), id.key().as_str()), ("c", "k"));
+        assert_eq!(doc.header._rev.as_deref(), Some("_r"));
+        // the header is kept in the document
+        assert_eq!(doc.document["_rev"], "_r");
+
+        let result = serde_json::from_value::<Document<Value>>(json!({ "name": "a" }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn borrowed_header() {
+        let text = r#"{"_id":"c/k","_key":"k","_rev":"_r","name":"a"}"#;
+        let header: HeaderRef = serde_json::from_str(text).unwrap();
+        assert!(matches!(header._id, Some(Cow::Borrowed("c/k"))));
+        assert!(matches!(header._key, Cow::Borrowed("k")));
+        assert!(matches!(header._rev, Some(Cow::Borrowed("_r"))));
+
+        // escapes need a copy
+        let header: HeaderRef = serde_json::from_str(r#"{"_key":"a\"b"}"#).unwrap();
+        assert!(matches!(header._key, Cow::Owned(_)));
+        assert_eq!(header._rev, None);
+        assert_eq!(header.into_owned(), Header::with_key("a\"b"));
+    }
+
+    #[test]
+    fn edges_round_trip() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Knows {
+            since: u16,
+        }
+
+        let edge = EdgeDocument::try_new("users/alice", "users/bob", Knows { since: 2020 })
+            .unwrap()
+            .with_key("ab");
+        let value = serde_json::to_value(&edge).unwrap();
+        assert_eq!(
+            value,
+            json!({ "_key": "ab", "_from": "users/alice", "_to": "users/bob", "since": 2020 })
+        );
+        assert_eq!(
+            serde_json::from_value::<EdgeDocument<Knows>>(value).unwrap(),
+            edge
+        );
+
+        let stored = json!({
+            "_id": "knows/ab", "_key": "ab", "_rev": "_r",
+            "_from": "users/alice", "_to": "users/bob", "since": 2020
+        });
+        let read: EdgeDocument<Value> = serde_json::from_value(stored).unwrap();
+        assert_eq!(read.header._rev.as_deref(), Some("_r"));
+        assert_eq!(read._to.key().as_str(), "bob");
+        // the system attributes are not repeated in the content
+        assert_eq!(read.document, json!({ "since": 2020 }));
+
+        let invalid = EdgeDocument::try_new("alice", "users/bob", ());
+        assert!(matches!(invalid, Err(crate::ClientError::InvalidId { .. })));
+        let missing = serde_json::from_value::<EdgeDocument<Value>>(json!({ "_from": "users/a" }));
+        assert!(missing.is_err());
+    }
+}
@@ -2,8 +2,17 @@
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
+use crate::{
+    collection::DIRTY_READ_HEADER,
+    connection::capability::{
+        ServerVersion, OVERWRITE_MODE_SINCE, REFILL_INDEX_CACHES_SINCE, VERSION_ATTRIBUTE_SINCE,
+    },
+    options::{request_options, TRANSACTION_HEADER},
+    ClientError,
+};
+
 /// Options for document insertion.
-#[derive(Debug, Serialize, Deserialize, PartialEq, TypedBuilder)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
 #[builder(doc)]
 #[serde(rename_all = "camelCase")]
 pub struct InsertOptions {
@@ -17,13 +26,17 @@ pub struct InsertOptions {
     #[builder(default, setter(strip_option))]
     return_new: Option<bool>,
     /// Additionally return the complete old document under the attribute old in
-    /// the result. Only available if the overwrite option is used.
+    /// the result. Only available if the overwrite option is used, or an
+    /// overwrite mode of `Replace` or `Update`, otherwise the insert fails
+    /// with `ClientError::InvalidOptions` without any request.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     return_old: Option<bool>,
     /// If set to true, an empty object will be returned as response.
     /// No meta-data will be returned for the created document.
-    /// This option can be used to save some network traffic.
+    /// This option can be used to save some network traffic. Setting it
+    /// along with `return_new` or `return_old` fails with
+    /// `ClientError::InvalidOptions`, as for the other write options.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     silent: Option<bool>,
@@ -34,8 +47,12 @@ pub struct InsertOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     overwrite: Option<bool>,
-    /// TODO add nice formatted documentation from official doc
-    #[cfg(feature = "arango3_7")]
+    /// What happens to an existing document with the same _key, taking
+    /// precedence over `overwrite`.
+    ///
+    /// The overwrite modes are known since ArangoDB 3.7: with an older server
+    /// `Replace` and `Conflict` are sent as `overwrite`, while the other
+    /// modes fail with `ClientError::NotSupported` without any request.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     overwrite_mode: Option<OverwriteMode>,
@@ -44,8 +61,8 @@ pub struct InsertOptions {
     /// the URL query parameter keepNull can be used with a value of false.
     /// This will modify the behavior of the patch command to remove any attributes
     /// from the existing document that are contained in the patch document with an
-    /// attribute value of null. This option controls the update-insert behavior only.
-    #[cfg(feature = "arango3_7")]
+    /// attribute value of null. This option controls the update-insert behavior only,
+    /// and is refused with any other `overwrite_mode`.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     keep_null: Option<bool>,
@@ -54,11 +71,212 @@ pub struct InsertOptions {
     /// and the update-insert document.
     /// If set to false, the value in the patch document will overwrite the existing document’s value.
     /// If set to true, objects will be merged. The default is true.
-    /// This option controls the update-insert behavior only.
-    #[cfg(feature = "arango3_7")]
+    /// This option controls the update-insert behavior only, and is refused with any
+    /// other `overwrite_mode`.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     merge_objects: Option<bool>,
+
+    /// Whether to refill the in-memory caches of the indexes with
+    /// `cacheEnabled` for the documents written. Dropped for a server older
+    /// than ArangoDB 3.10.2, which does not know it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    refill_index_caches: Option<bool>,
+
+    /// Attribute holding an external version of the documents: an overwriting
+    /// insert only writes over an existing document whose version is lower than
+    /// the one given. Known since ArangoDB 3.12, an older server fails the
+    /// insert with `ClientError::NotSupported` without any request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    version_attribute: Option<String>,
+}
+
+request_options!(InsertOptions {
+    query: [
+        wait_for_sync => "waitForSync",
+        return_new => "returnNew",
+        return_old => "returnOld",
+        silent => "silent",
+        overwrite => "overwrite",
+        overwrite_mode => "overwriteMode",
+        keep_null => "keepNull",
+        merge_objects => "mergeObjects",
+        refill_index_caches => "refillIndexCaches",
+        version_attribute => "versionAttribute",
+    ],
+    headers: [],
+});
+
+impl InsertOptions {
+    /// The options with `silent` set, for the writes returning nothing.
+    pub(crate) fn into_silent(mut self) -> Self {
+        self.silent = Some(true);
+        self
+    }
+
+    /// Whether to wait until the document has been synced to disk.
+    pub fn wait_for_sync(&self) -> Option<bool> {
+        self.wait_for_sync
+    }
+
+    /// Whether to return the new document.
+    pub fn return_new(&self) -> Option<bool> {
+        self.return_new
+    }
+
+    /// Whether to return the replaced document, if any.
+    pub fn return_old(&self) -> Option<bool> {
+        self.return_old
+    }
+
+    /// Whether to return no meta-data at all.
+    pub fn silent(&self) -> Option<bool> {
+        self.silent
+    }
+
+    /// Whether an existing document with the same key is replaced.
+    pub fn overwrite(&self) -> Option<bool> {
+        self.overwrite
+    }
+
+    /// What happens to an existing document with the same key.
+    pub fn overwrite_mode(&self) -> Option<OverwriteMode> {
+        self.overwrite_mode
+    }
+
+    /// Whether null attributes are kept by an update-insert.
+    pub fn keep_null(&self) -> Option<bool> {
+        self.keep_null
+    }
+
+    /// Whether objects are merged by an update-insert.
+    pub fn merge_objects(&self) -> Option<bool> {
+        self.merge_objects
+    }
+
+    /// Whether to refill the index caches for the documents written.
+    pub fn refill_index_caches(&self) -> Option<bool> {
+        self.refill_index_caches
+    }
+
+    /// The attribute holding the external version of the documents.
+    pub fn version_attribute(&self) -> Option<&str> {
+        self.version_attribute.as_deref()
+    }
+
+    /// Fail when options of update-inserts are set for another kind of
+    /// insert, for which the server would silently ignore them, or when
+    /// documents are asked back which the server would not return.
+    pub(crate) fn check(&self) -> Result<(), ClientError> {
+        check_silent(self.silent, self.return_old, self.return_new)?;
+        let overwrites = match self.overwrite_mode {
+            Some(mode) => mode == OverwriteMode::Replace || mode == OverwriteMode::Update,
+            None => self.overwrite == Some(true),
+        };
+        if self.return_old == Some(true) && !overwrites {
+            return Err(ClientError::InvalidOptions(
+                "return_old only applies to inserts overwriting a document, set overwrite or \
+                 an overwrite_mode of Replace or Update"
+                    .to_owned(),
+            ));
+        }
+        if self.overwrite_mode != Some(OverwriteMode::Update) {
+            let option = match (self.keep_null, self.merge_objects) {
+                (Some(_), _) => "keep_null",
+                (_, Some(_)) => "merge_objects",
+                _ => return Ok(()),
+            };
+            return Err(ClientError::InvalidOptions(format!(
+                "{} only applies to update-inserts, set overwrite_mode to OverwriteMode::Update",
+                option
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether these options depend on the version of the server, see
+    /// [`for_version`](Self::for_version).
+    pub(crate) fn needs_version(&self) -> bool {
+        self.overwrite_mode.is_some()
+            || self.refill_index_caches.is_some()
+            || self.version_attribute.is_some()
+    }
+
+    /// These options as understood by a server of `version`, which may
+    /// predate the overwrite modes.
+    pub(crate) fn for_version(mut self, version: ServerVersion) -> Result<Self, ClientError> {
+        let version_attribute = self.version_attribute.as_deref();
+        gate_by_version(
+            version,
+            &mut self.refill_index_caches,
+            version_attribute,
+            "insert",
+        )?;
+        if version >= OVERWRITE_MODE_SINCE {
+            return Ok(self);
+        }
+        match self.overwrite_mode.take() {
+            None => {}
+            Some(OverwriteMode::Replace) => self.overwrite = Some(true),
+            Some(OverwriteMode::Conflict) => self.overwrite = Some(false),
+            Some(mode) => {
+                let mode = if mode == OverwriteMode::Ignore {
+                    "ignore"
+                } else {
+                    "update"
+                };
+                return Err(ClientError::NotSupported {
+                    operation: format!("insert with overwrite mode {} before ArangoDB 3.7", mode),
+                });
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Drop `refill_index_caches` for a server of `version` which does not know
+/// it, and refuse a `version_attribute` which it would ignore.
+fn gate_by_version(
+    version: ServerVersion,
+    refill_index_caches: &mut Option<bool>,
+    version_attribute: Option<&str>,
+    operation: &str,
+) -> Result<(), ClientError> {
+    if version < REFILL_INDEX_CACHES_SINCE {
+        *refill_index_caches = None;
+    }
+    if version_attribute.is_some() && version < VERSION_ATTRIBUTE_SINCE {
+        return Err(ClientError::NotSupported {
+            operation: format!(
+                "{} with a version attribute before ArangoDB 3.12",
+                operation
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Fail when documents are asked back from a silent operation, which the
+/// server would answer without them.
+fn check_silent(
+    silent: Option<bool>,
+    return_old: Option<bool>,
+    return_new: Option<bool>,
+) -> Result<(), ClientError> {
+    if silent != Some(true) {
+        return Ok(());
+    }
+    let option = match (return_old, return_new) {
+        (Some(true), _) => "return_old",
+        (_, Some(true)) => "return_new",
+        _ => return Ok(()),
+    };
+    Err(ClientError::InvalidOptions(format!(
+        "{} conflicts with silent, which returns no document",
+        option
+    )))
 }
 
 impl Default for InsertOptions {
@@ -67,8 +285,81 @@ impl Default for InsertOptions {
     }
 }
 
+/// Options for inserting a document or updating the existing one, see
+/// [`Collection::upsert_document`](crate::Collection::upsert_document)
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct UpsertOptions {
+    /// Wait until document has been synced to disk.
+    #[builder(default, setter(strip_option))]
+    wait_for_sync: Option<bool>,
+    /// Additionally return the complete new document under the attribute new in
+    /// the result.
+    #[builder(default, setter(strip_option))]
+    return_new: Option<bool>,
+    /// Additionally return the complete old document under the attribute old in
+    /// the result, when the document existed.
+    #[builder(default, setter(strip_option))]
+    return_old: Option<bool>,
+    /// If set to false, attributes of the existing document set to null in
+    /// the upserted document are removed. The default is true.
+    #[builder(default, setter(strip_option))]
+    keep_null: Option<bool>,
+    /// If set to false, objects of the upserted document replace those of the
+    /// existing document instead of being merged. The default is true.
+    #[builder(default, setter(strip_option))]
+    merge_objects: Option<bool>,
+}
+
+impl UpsertOptions {
+    /// Whether to wait until the document has been synced to disk.
+    pub fn wait_for_sync(&self) -> Option<bool> {
+        self.wait_for_sync
+    }
+
+    /// Whether to return the new document.
+    pub fn return_new(&self) -> Option<bool> {
+        self.return_new
+    }
+
+    /// Whether to return the old document.
+    pub fn return_old(&self) -> Option<bool> {
+        self.return_old
+    }
+
+    /// Whether attributes set to null are kept.
+    pub fn keep_null(&self) -> Option<bool> {
+        self.keep_null
+    }
+
+    /// Whether objects are merged into the existing ones.
+    pub fn merge_objects(&self) -> Option<bool> {
+        self.merge_objects
+    }
+
+    /// The options of an update-insert doing the upsert.
+    #[cfg(feature = "aql")]
+    pub(crate) fn insert_options(&self) -> InsertOptions {
+        InsertOptions {
+            wait_for_sync: self.wait_for_sync,
+            return_new: self.return_new,
+            return_old: self.return_old,
+            overwrite_mode: Some(OverwriteMode::Update),
+            keep_null: self.keep_null,
+            merge_objects: self.merge_objects,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for UpsertOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
 /// Options for document update,
-#[derive(Debug, Serialize, Deserialize, PartialEq, TypedBuilder)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
 #[builder(doc)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateOptions {
@@ -114,6 +405,129 @@ pub struct UpdateOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     silent: Option<bool>,
+    /// Revision the document must have, sent as the `If-Match` header.
+    /// Otherwise the update fails with `ClientError::PreconditionFailed`. Only
+    /// applies to a single document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    if_match: Option<String>,
+    /// Whether to refill the in-memory caches of the indexes with
+    /// `cacheEnabled` for the documents written. Dropped for a server older
+    /// than ArangoDB 3.10.2, which does not know it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    refill_index_caches: Option<bool>,
+    /// Attribute holding an external version of the documents: an update only
+    /// applies to an existing document whose version is lower than the one
+    /// given. Known since ArangoDB 3.12, an older server fails the update with
+    /// `ClientError::NotSupported` without any request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    version_attribute: Option<String>,
+}
+
+request_options!(UpdateOptions {
+    query: [
+        keep_null => "keepNull",
+        merge_objects => "mergeObjects",
+        wait_for_sync => "waitForSync",
+        ignore_revs => "ignoreRevs",
+        return_new => "returnNew",
+        return_old => "returnOld",
+        silent => "silent",
+        refill_index_caches => "refillIndexCaches",
+        version_attribute => "versionAttribute",
+    ],
+    headers: [if_match => "If-Match"],
+});
+
+impl UpdateOptions {
+    /// The options with `silent` set, for the writes returning nothing.
+    pub(crate) fn into_silent(mut self) -> Self {
+        self.silent = Some(true);
+        self
+    }
+
+    /// The options with the old and new document returned.
+    #[cfg(feature = "transactions")]
+    pub(crate) fn returning_old_and_new(mut self) -> Self {
+        self.return_old = Some(true);
+        self.return_new = Some(true);
+        self.silent = None;
+        self
+    }
+
+    /// Whether attributes set to null in the patch are kept.
+    pub fn keep_null(&self) -> Option<bool> {
+        self.keep_null
+    }
+
+    /// Whether objects are merged with those of the patch.
+    pub fn merge_objects(&self) -> Option<bool> {
+        self.merge_objects
+    }
+
+    /// Whether to wait until the document has been synced to disk.
+    pub fn wait_for_sync(&self) -> Option<bool> {
+        self.wait_for_sync
+    }
+
+    /// Whether the `_rev` of the patch is ignored rather than checked.
+    pub fn ignore_revs(&self) -> Option<bool> {
+        self.ignore_revs
+    }
+
+    /// Whether to return the new document.
+    pub fn return_new(&self) -> Option<bool> {
+        self.return_new
+    }
+
+    /// Whether to return the previous revision of the document.
+    pub fn return_old(&self) -> Option<bool> {
+        self.return_old
+    }
+
+    /// Whether to return no meta-data at all.
+    pub fn silent(&self) -> Option<bool> {
+        self.silent
+    }
+
+    /// Revision the document must have.
+    pub fn if_match(&self) -> Option<&str> {
+        self.if_match.as_deref()
+    }
+
+    /// Whether to refill the index caches for the documents written.
+    pub fn refill_index_caches(&self) -> Option<bool> {
+        self.refill_index_caches
+    }
+
+    /// The attribute holding the external version of the documents.
+    pub fn version_attribute(&self) -> Option<&str> {
+        self.version_attribute.as_deref()
+    }
+
+    /// Whether these options depend on the version of the server, see
+    /// [`for_version`](Self::for_version).
+    pub(crate) fn needs_version(&self) -> bool {
+        self.refill_index_caches.is_some() || self.version_attribute.is_some()
+    }
+
+    /// These options as understood by a server of `version`.
+    pub(crate) fn for_version(mut self, version: ServerVersion) -> Result<Self, ClientError> {
+        gate_by_version(
+            version,
+            &mut self.refill_index_caches,
+            self.version_attribute.as_deref(),
+            "update",
+        )?;
+        Ok(self)
+    }
+
+    /// Fail when documents are asked back which the server would not return.
+    pub(crate) fn check(&self) -> Result<(), ClientError> {
+        check_silent(self.silent, self.return_old, self.return_new)
+    }
 }
 
 impl Default for UpdateOptions {
@@ -122,7 +536,7 @@ impl Default for UpdateOptions {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub enum OverwriteMode {
     /// If a document with the specified _key value exists already,
@@ -139,32 +553,19 @@ pub enum OverwriteMode {
     Replace,
     /// If a document with the specified _key value exists already, it will be
     /// patched (partially updated) with the specified document value.
-    /// The overwrite mode can be further controlled via the keepNull and
-    /// mergeObjects parameters
+    /// The patch can be further controlled with
+    /// [`keep_null`](InsertOptions::keep_null) and
+    /// [`merge_objects`](InsertOptions::merge_objects).
     Update,
     /// if a document with the specified _key value exists already, return a
     /// unique constraint violation error so that the insert operation fails.
     /// This is also the default behavior in case the overwrite mode is not set,
     /// and the overwrite flag is false or not set either.
-    ///
-    /// keepNull (optional): If the intention is to delete existing attributes
-    /// with the update-insert command, the URL query parameter keepNull can be
-    /// used with a value of false. This will modify the behavior of the patch
-    /// command to remove any attributes from the existing document that are
-    /// contained in the patch document with an attribute value of null.
-    /// This option controls the update-insert behavior only.
-    ///
-    /// mergeObjects (optional): Controls whether objects (not arrays) will be
-    /// merged if present in both the existing and the update-insert document.
-    /// If set to false, the value in the patch document will overwrite the
-    /// existing document’s value. If set to true, objects will be merged. The
-    /// default is true. This option controls the update-insert behavior only.
-    /// TODO need to implement the two extra modes keepNull & mergeObjects
     Conflict,
 }
 
 /// Options for document replace,
-#[derive(Debug, Serialize, Deserialize, TypedBuilder)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
 #[builder(doc)]
 #[serde(rename_all = "camelCase")]
 pub struct ReplaceOptions {
@@ -195,6 +596,117 @@ pub struct ReplaceOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     silent: Option<bool>,
+    /// Revision the document must have, sent as the `If-Match` header.
+    /// Otherwise the replace fails with `ClientError::PreconditionFailed`. Only
+    /// applies to a single document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    if_match: Option<String>,
+    /// Whether to refill the in-memory caches of the indexes with
+    /// `cacheEnabled` for the documents written. Dropped for a server older
+    /// than ArangoDB 3.10.2, which does not know it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    refill_index_caches: Option<bool>,
+    /// Attribute holding an external version of the documents: a replacement
+    /// only applies to an existing document whose version is lower than the one
+    /// given. Known since ArangoDB 3.12, an older server fails the replacement
+    /// with `ClientError::NotSupported` without any request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    version_attribute: Option<String>,
+}
+
+request_options!(ReplaceOptions {
+    query: [
+        wait_for_sync => "waitForSync",
+        ignore_revs => "ignoreRevs",
+        return_new => "returnNew",
+        return_old => "returnOld",
+        silent => "silent",
+        refill_index_caches => "refillIndexCaches",
+        version_attribute => "versionAttribute",
+    ],
+    headers: [if_match => "If-Match"],
+});
+
+impl ReplaceOptions {
+    /// The options with `silent` set, for the writes returning nothing.
+    pub(crate) fn into_silent(mut self) -> Self {
+        self.silent = Some(true);
+        self
+    }
+
+    /// The options with the old and new document returned.
+    #[cfg(feature = "transactions")]
+    pub(crate) fn returning_old_and_new(mut self) -> Self {
+        self.return_old = Some(true);
+        self.return_new = Some(true);
+        self.silent = None;
+        self
+    }
+
+    /// Whether to wait until the document has been synced to disk.
+    pub fn wait_for_sync(&self) -> Option<bool> {
+        self.wait_for_sync
+    }
+
+    /// Whether the `_rev` of the new document is ignored rather than checked.
+    pub fn ignore_revs(&self) -> Option<bool> {
+        self.ignore_revs
+    }
+
+    /// Whether to return the new document.
+    pub fn return_new(&self) -> Option<bool> {
+        self.return_new
+    }
+
+    /// Whether to return the replaced document.
+    pub fn return_old(&self) -> Option<bool> {
+        self.return_old
+    }
+
+    /// Whether to return no meta-data at all.
+    pub fn silent(&self) -> Option<bool> {
+        self.silent
+    }
+
+    /// Revision the document must have.
+    pub fn if_match(&self) -> Option<&str> {
+        self.if_match.as_deref()
+    }
+
+    /// Whether to refill the index caches for the documents written.
+    pub fn refill_index_caches(&self) -> Option<bool> {
+        self.refill_index_caches
+    }
+
+    /// The attribute holding the external version of the documents.
+    pub fn version_attribute(&self) -> Option<&str> {
+        self.version_attribute.as_deref()
+    }
+
+    /// Whether these options depend on the version of the server, see
+    /// [`for_version`](Self::for_version).
+    pub(crate) fn needs_version(&self) -> bool {
+        self.refill_index_caches.is_some() || self.version_attribute.is_some()
+    }
+
+    /// These options as understood by a server of `version`.
+    pub(crate) fn for_version(mut self, version: ServerVersion) -> Result<Self, ClientError> {
+        gate_by_version(
+            version,
+            &mut self.refill_index_caches,
+            self.version_attribute.as_deref(),
+            "replace",
+        )?;
+        Ok(self)
+    }
+
+    /// Fail when documents are asked back which the server would not return.
+    pub(crate) fn check(&self) -> Result<(), ClientError> {
+        check_silent(self.silent, self.return_old, self.return_new)
+    }
 }
 
 impl Default for ReplaceOptions {
@@ -204,6 +716,75 @@ impl Default for ReplaceOptions {
 }
 
 /// Options for document reading.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentReadOptions {
+    /// Etag the document must have, as sent in the `If-Match` header.
+    /// Otherwise the server answers with a HTTP 412.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    if_match: Option<String>,
+    /// Etag the document must not have, as sent in the `If-None-Match` header.
+    /// Otherwise the server answers with a HTTP 304.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    if_none_match: Option<String>,
+    /// Whether a follower may answer the read, at the risk of returning an
+    /// outdated document (active failover, or Enterprise Edition cluster).
+    /// Cannot be combined with a transaction.
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    allow_dirty_read: Option<bool>,
+}
+
+request_options!(DocumentReadOptions {
+    query: [],
+    headers: [
+        if_match => "If-Match",
+        if_none_match => "If-None-Match",
+        allow_dirty_read => DIRTY_READ_HEADER,
+    ],
+});
+
+impl DocumentReadOptions {
+    /// Etag the document must have.
+    pub fn if_match(&self) -> Option<&str> {
+        self.if_match.as_deref()
+    }
+
+    /// Etag the document must not have.
+    pub fn if_none_match(&self) -> Option<&str> {
+        self.if_none_match.as_deref()
+    }
+
+    /// Whether a follower may answer the read.
+    pub fn allow_dirty_read(&self) -> Option<bool> {
+        self.allow_dirty_read
+    }
+
+    /// Fail when the read is both dirty and part of the transaction of the
+    /// handle, as tells `in_transaction`.
+    pub(crate) fn check(&self, in_transaction: bool) -> Result<(), ClientError> {
+        if self.allow_dirty_read == Some(true) && in_transaction {
+            return Err(ClientError::InvalidOptions(
+                "a document read cannot be both a dirty read and part of a stream transaction, \
+                 unset allow_dirty_read or read outside of the transaction"
+                    .to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for DocumentReadOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Condition of a document read, replaced by [`DocumentReadOptions`].
+#[deprecated(note = "use `DocumentReadOptions::builder()` instead")]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum ReadOptions {
@@ -218,14 +799,98 @@ pub enum ReadOptions {
     NoHeader,
 }
 
+#[allow(deprecated)]
 impl Default for ReadOptions {
     fn default() -> Self {
         Self::NoHeader
     }
 }
 
+#[allow(deprecated)]
+impl From<ReadOptions> for DocumentReadOptions {
+    fn from(options: ReadOptions) -> Self {
+        match options {
+            ReadOptions::IfNoneMatch(etag) => Self::builder().if_none_match(etag).build(),
+            ReadOptions::IfMatch(etag) => Self::builder().if_match(etag).build(),
+            ReadOptions::NoHeader => Self::default(),
+        }
+    }
+}
+
+/// Options for reading several documents at once.
+///
+/// A batch read happens either inside a stream transaction or as a dirty
+/// read, never both, as the server gives no guarantee about such a read.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReadOptions {
+    /// Id of the stream transaction to read within.
+    #[serde(skip)]
+    #[builder(default, setter(strip_option, into))]
+    pub(crate) transaction_id: Option<String>,
+    /// Whether a follower may answer the read, at the risk of returning
+    /// outdated documents (Enterprise Edition cluster). Cannot be combined
+    /// with a transaction.
+    #[serde(skip)]
+    #[builder(default, setter(strip_option))]
+    pub(crate) allow_dirty_read: Option<bool>,
+    /// If false, the `_rev` given along the `_key` of a document to read must
+    /// match its current revision. Default: true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    ignore_revs: Option<bool>,
+}
+
+request_options!(BatchReadOptions {
+    query: [ignore_revs => "ignoreRevs"],
+    headers: [
+        transaction_id => TRANSACTION_HEADER,
+        allow_dirty_read => DIRTY_READ_HEADER,
+    ],
+});
+
+impl BatchReadOptions {
+    /// Id of the stream transaction to read within.
+    pub fn transaction_id(&self) -> Option<&str> {
+        self.transaction_id.as_deref()
+    }
+
+    /// Whether a follower may answer the read.
+    pub fn allow_dirty_read(&self) -> Option<bool> {
+        self.allow_dirty_read
+    }
+
+    /// Whether the `_rev` given along the keys is ignored rather than checked.
+    pub fn ignore_revs(&self) -> Option<bool> {
+        self.ignore_revs
+    }
+
+    /// Fail when the read is both transactional and dirty.
+    ///
+    /// `in_transaction` tells whether the handle reading already belongs to a
+    /// transaction.
+    pub(crate) fn check(&self, in_transaction: bool) -> Result<(), ClientError> {
+        if self.allow_dirty_read == Some(true) && (in_transaction || self.transaction_id.is_some())
+        {
+            return Err(ClientError::InvalidOptions(
+                "a batch read cannot be both a dirty read and part of a stream transaction, unset \
+                 allow_dirty_read or read outside of the transaction"
+                    .to_owned(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for BatchReadOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
 /// Options for document removes,
-#[derive(Debug, Serialize, Deserialize, TypedBuilder)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TypedBuilder)]
 #[builder(doc)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveOptions {
@@ -233,6 +898,15 @@ pub struct RemoveOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     wait_for_sync: Option<bool>,
+    /// By default, or if this is set to true, the _rev attributes of the
+    /// selectors given to
+    /// [`Collection::remove_documents_by_header`](crate::Collection::remove_documents_by_header)
+    /// are ignored. If this is set to false, they are taken as a
+    /// precondition, and a document is only removed if its current revision
+    /// is the one specified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    ignore_revs: Option<bool>,
     /// Additionally return the complete old document under the attribute old in
     /// the result.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -244,6 +918,92 @@ pub struct RemoveOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default, setter(strip_option))]
     silent: Option<bool>,
+    /// Revision the document must have, sent as the `If-Match` header.
+    /// Otherwise the removal fails with `ClientError::PreconditionFailed`. Only
+    /// applies to a single document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option, into))]
+    if_match: Option<String>,
+    /// Whether to refill the in-memory caches of the indexes with
+    /// `cacheEnabled` for the documents written. Dropped for a server older
+    /// than ArangoDB 3.10.2, which does not know it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    refill_index_caches: Option<bool>,
+}
+
+request_options!(RemoveOptions {
+    query: [
+        wait_for_sync => "waitForSync",
+        ignore_revs => "ignoreRevs",
+        return_old => "returnOld",
+        silent => "silent",
+        refill_index_caches => "refillIndexCaches",
+    ],
+    headers: [if_match => "If-Match"],
+});
+
+impl RemoveOptions {
+    /// The options with `silent` set, for the writes returning nothing.
+    pub(crate) fn into_silent(mut self) -> Self {
+        self.silent = Some(true);
+        self
+    }
+
+    /// The options with the removed document returned.
+    #[cfg(feature = "transactions")]
+    pub(crate) fn returning_old(mut self) -> Self {
+        self.return_old = Some(true);
+        self.silent = None;
+        self
+    }
+
+    /// Whether to wait until the removal has been synced to disk.
+    pub fn wait_for_sync(&self) -> Option<bool> {
+        self.wait_for_sync
+    }
+
+    /// Whether the `_rev` of the selectors is ignored rather than checked.
+    pub fn ignore_revs(&self) -> Option<bool> {
+        self.ignore_revs
+    }
+
+    /// Whether to return the removed document.
+    pub fn return_old(&self) -> Option<bool> {
+        self.return_old
+    }
+
+    /// Whether to return no meta-data at all.
+    pub fn silent(&self) -> Option<bool> {
+        self.silent
+    }
+
+    /// Revision the document must have.
+    pub fn if_match(&self) -> Option<&str> {
+        self.if_match.as_deref()
+    }
+
+    /// Whether to refill the index caches for the documents written.
+    pub fn refill_index_caches(&self) -> Option<bool> {
+        self.refill_index_caches
+    }
+
+    /// Whether these options depend on the version of the server, see
+    /// [`for_version`](Self::for_version).
+    pub(crate) fn needs_version(&self) -> bool {
+        self.refill_index_caches.is_some()
+    }
+
+    /// These options as understood by a server of `version`.
+    pub(crate) fn for_version(mut self, version: ServerVersion) -> Result<Self, ClientError> {
+        gate_by_version(version, &mut self.refill_index_caches, None, "remove")?;
+        Ok(self)
+    }
+
+    /// Fail when documents are asked back which the server would not return.
+    pub(crate) fn check(&self) -> Result<(), ClientError> {
+        check_silent(self.silent, self.return_old, None)
+    }
 }
 
 impl Default for RemoveOptions {
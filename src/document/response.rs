@@ -1,7 +1,15 @@
 //! Types of response related to document
-use serde::{de::Error as DeError, Deserialize, Deserializer};
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 
-use super::Header;
+use serde::{
+    de::{Error as DeError, Unexpected},
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+use std::ops::Deref;
+
+use super::{Document, Header};
+use crate::{ArangoError, BulkErrorSummary};
 
 /// Standard Response when having CRUD operation on document
 ///
@@ -18,6 +26,10 @@ use super::Header;
 /// 412: is returned if an “If-Match” header is given and the found
 /// document has a different version. The response will also contain the found
 /// document’s current revision in the Etag header.
+///
+/// The errors are returned as `ClientError::Arango`, or per document by the
+/// bulk operations, whose error number tells them apart, e.g. with
+/// [`ArangoError::is_conflict`](crate::ArangoError::is_conflict).
 pub enum DocumentResponse<T> {
     /// Silent is when there is empty object returned by the server
     Silent,
@@ -74,6 +86,27 @@ impl<T> DocumentResponse<T> {
             None
         }
     }
+
+    /// Take the old document before changes, without cloning it
+    pub fn into_old(self) -> Option<T> {
+        self.into_parts().and_then(|(_, old, _)| old)
+    }
+    /// Take the new document, without cloning it
+    pub fn into_new(self) -> Option<T> {
+        self.into_parts().and_then(|(_, _, new)| new)
+    }
+    /// Split the response into its header, old and new documents, or `None`
+    /// when it is silent
+    pub fn into_parts(self) -> Option<(Header, Option<T>, Option<T>)> {
+        if let DocumentResponse::Response {
+            header, old, new, ..
+        } = self
+        {
+            Some((header, old, new))
+        } else {
+            None
+        }
+    }
 }
 
 impl<'de, T> Deserialize<'de> for DocumentResponse<T>
@@ -86,9 +119,25 @@ where
     {
         let mut obj = serde_json::Value::deserialize(deserializer)?;
 
-        let json = obj
-            .as_object_mut()
-            .ok_or_else(|| DeError::custom("should be a json object"))?;
+        let json = match obj.as_object_mut() {
+            Some(json) => json,
+            None => {
+                return Err(DeError::invalid_type(
+                    unexpected(&obj),
+                    &"the object of a document response",
+                ))
+            }
+        };
+
+        if json.get("error") == Some(&Value::Bool(true)) {
+            let error_num = json.get("errorNum").and_then(Value::as_u64);
+            let message = json.get("errorMessage").and_then(Value::as_str);
+            return Err(DeError::custom(format_args!(
+                "error response instead of a document: {}({})",
+                message.unwrap_or("unknown error"),
+                error_num.map_or_else(|| "?".to_owned(), |num| num.to_string())
+            )));
+        }
 
         if json.is_empty() {
             Ok(DocumentResponse::Silent)
@@ -103,22 +152,27 @@ where
                 .remove("_rev")
                 .ok_or_else(|| DeError::missing_field("_rev"))?;
             let header: Header = Header {
-                _id: serde_json::from_value(_id).map_err(DeError::custom)?,
+                _id: Some(serde_json::from_value(_id).map_err(DeError::custom)?),
                 _key: serde_json::from_value(_key).map_err(DeError::custom)?,
-                _rev: serde_json::from_value(_rev).map_err(DeError::custom)?,
+                _rev: Some(serde_json::from_value(_rev).map_err(DeError::custom)?),
             };
 
+            // a document not matching `T` must not pass for a missing one
             let old = json
                 .remove("old")
                 .map(T::deserialize)
                 .transpose()
-                .map_err(DeError::custom)?;
+                .map_err(|e| DeError::custom(format_args!("invalid `old` document: {}", e)))?;
             let new = json
                 .remove("new")
                 .map(T::deserialize)
                 .transpose()
+                .map_err(|e| DeError::custom(format_args!("invalid `new` document: {}", e)))?;
+            let _old_rev = json
+                .remove("_oldRev")
+                .map(serde_json::from_value)
+                .transpose()
                 .map_err(DeError::custom)?;
-            let _old_rev = json.remove("_old_rev").map(|v| v.to_string());
 
             Ok(DocumentResponse::Response {
                 header,
@@ -129,3 +183,311 @@ where
         }
     }
 }
+
+/// What a value is, for the error of a response of another type.
+fn unexpected(value: &Value) -> Unexpected<'_> {
+    match value {
+        Value::Null => Unexpected::Unit,
+        Value::Bool(b) => Unexpected::Bool(*b),
+        Value::Number(_) => Unexpected::Other("number"),
+        Value::String(s) => Unexpected::Str(s),
+        Value::Array(_) => Unexpected::Seq,
+        Value::Object(_) => Unexpected::Map,
+    }
+}
+
+/// Results of a bulk operation, one per document in the order of the input,
+/// as returned by
+/// [`Collection::create_documents`](crate::Collection::create_documents) and
+/// the other bulk writes.
+///
+/// It derefs to the slice of results, so the result of the `i`-th document
+/// is `results[i]`. A document which failed holds the error of the server,
+/// whose [`error_num`](ArangoError::error_num) tells why.
+pub struct BulkDocumentResult<T>(Vec<Result<DocumentResponse<T>, ArangoError>>);
+
+impl<T> BulkDocumentResult<T> {
+    /// The documents written, with their position in the input.
+    pub fn successes(&self) -> impl Iterator<Item = (usize, &DocumentResponse<T>)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(i, result)| result.as_ref().ok().map(|response| (i, response)))
+    }
+
+    /// The errors of the documents which failed, with their position in the
+    /// input.
+    pub fn errors(&self) -> impl Iterator<Item = (usize, &ArangoError)> {
+        self.0
+            .iter()
+            .enumerate()
+            .filter_map(|(i, result)| result.as_ref().err().map(|error| (i, error)))
+    }
+
+    /// Whether no document failed.
+    pub fn is_all_ok(&self) -> bool {
+        self.0.iter().all(Result::is_ok)
+    }
+
+    /// Numbers of documents which failed per error.
+    pub fn error_summary(&self) -> BulkErrorSummary {
+        BulkErrorSummary::from_errors(self.errors().map(|(_, error)| error))
+    }
+
+    /// Take the results, without cloning them.
+    pub fn into_results(self) -> Vec<Result<DocumentResponse<T>, ArangoError>> {
+        self.0
+    }
+}
+
+impl<T> From<Vec<Result<DocumentResponse<T>, ArangoError>>> for BulkDocumentResult<T> {
+    fn from(results: Vec<Result<DocumentResponse<T>, ArangoError>>) -> Self {
+        BulkDocumentResult(results)
+    }
+}
+
+impl<T> Deref for BulkDocumentResult<T> {
+    type Target = [Result<DocumentResponse<T>, ArangoError>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T> IntoIterator for BulkDocumentResult<T> {
+    type Item = Result<DocumentResponse<T>, ArangoError>;
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BulkDocumentResult<T> {
+    type Item = &'a Result<DocumentResponse<T>, ArangoError>;
+    type IntoIter = std::slice::Iter<'a, Result<DocumentResponse<T>, ArangoError>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+/// Outcome of [`Collection::insert_or_get`](crate::Collection::insert_or_get).
+#[derive(Debug)]
+pub enum InsertOrGet<T> {
+    /// The document was inserted.
+    Inserted(Header),
+    /// A document with the same key, or violating another unique index,
+    /// already existed and was left as it is.
+    Existing(Document<T>),
+}
+
+/// Outcome of
+/// [`Collection::read_document`](crate::Collection::read_document).
+#[derive(Debug)]
+pub enum DocumentLookup<T> {
+    /// The document, with another revision than the one given by
+    /// `if_none_match` if any.
+    Found(Document<T>),
+    /// The document still has the revision given by `if_none_match`, as
+    /// answered with a 304 without body.
+    NotModified,
+}
+
+/// Outcome of
+/// [`Collection::read_document_header`](crate::Collection::read_document_header).
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderLookup {
+    /// The document exists, with the revision given by `if_match` if any.
+    Found(Header),
+    /// The document still has the revision given by `if_none_match`.
+    NotModified,
+    /// The document has revision `current_rev` rather than the one given by
+    /// `if_match`.
+    PreconditionFailed { current_rev: String },
+    /// Neither the document nor possibly its collection exist, which a
+    /// response without body cannot tell apart.
+    NotFound,
+}
+
+/// Outcome of
+/// [`Collection::modify_document`](crate::Collection::modify_document).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModifiedDocument<T> {
+    /// The header of the document written, with its new revision.
+    pub header: Header,
+    /// The document as written.
+    pub document: T,
+    /// Number of attempts needed, 1 when the document was not changed by
+    /// another write meanwhile.
+    pub attempts: usize,
+}
+
+/// Outcome of
+/// [`Collection::compare_and_swap_field`](crate::Collection::compare_and_swap_field).
+#[derive(Debug, Clone, PartialEq)]
+pub enum CasOutcome {
+    /// The field held the expected value and was set to the new one.
+    Swapped { new_rev: String },
+    /// The field held `actual` instead, and the document was left as it is.
+    /// A missing field is `null`.
+    Mismatch { actual: Value },
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    fn parse(body: Value) -> Result<DocumentResponse<Value>, serde_json::Error> {
+        serde_json::from_value(body)
+    }
+
+    #[test]
+    fn malformed_responses() {
+        // the body of a bulk endpoint
+        let error = parse(json!([{ "_id": "users/a", "_key": "a", "_rev": "1" }]));
+        let error = error.err().unwrap().to_string();
+        assert!(error.contains("invalid type: sequence"), "{}", error);
+
+        let error = parse(json!({
+            "error": true,
+            "code": 404,
+            "errorNum": 1202,
+            "errorMessage": "document not found",
+        }));
+        let error = error.err().unwrap().to_string();
+        assert!(error.contains("document not found(1202)"), "{}", error);
+
+        let error = parse(json!({ "_id": "users/a", "_key": "a" }));
+        assert!(error
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("missing field `_rev`"));
+
+        for body in [
+            json!({ "_id": "users/a", "_key": 1, "_rev": "1" }),
+            json!({ "_id": "users/a", "_key": "a", "_rev": "1", "_oldRev": [] }),
+            json!("users/a"),
+            json!(null),
+            json!(42),
+        ] {
+            assert!(parse(body).is_err());
+        }
+    }
+
+    #[test]
+    fn mismatched_documents() {
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct User {
+            name: String,
+        }
+
+        let body = |old, new| json!({ "_id": "users/a", "_key": "a", "_rev": "2", "old": old, "new": new });
+        let parse = |body| serde_json::from_value::<DocumentResponse<User>>(body);
+
+        let error = parse(body(json!({ "name": "alice" }), json!({ "age": 30 })));
+        let error = error.err().unwrap().to_string();
+        assert!(error.contains("`new`"), "{}", error);
+        assert!(error.contains("missing field `name`"), "{}", error);
+
+        let error = parse(body(json!({ "name": 1 }), json!({ "name": "bob" })));
+        let error = error.err().unwrap().to_string();
+        assert!(error.contains("`old`"), "{}", error);
+        assert!(error.contains("invalid type: integer `1`"), "{}", error);
+
+        let response = parse(body(json!({ "name": "alice" }), json!({ "name": "bob" }))).unwrap();
+        assert_eq!(response.old_doc().unwrap().name, "alice");
+        assert_eq!(response.new_doc().unwrap().name, "bob");
+    }
+
+    #[test]
+    fn silent_and_full_responses() {
+        assert!(parse(json!({})).unwrap().is_silent());
+
+        let response = parse(json!({
+            "_id": "users/a",
+            "_key": "a",
+            "_rev": "2",
+            "_oldRev": "1",
+            "new": { "name": "alice" },
+        }))
+        .unwrap();
+        assert_eq!(response.header().unwrap()._key, "a");
+        assert_eq!(response.old_rev().map(String::as_str), Some("1"));
+        assert_eq!(response.new_doc(), Some(&json!({ "name": "alice" })));
+        assert_eq!(response.old_doc(), None);
+    }
+
+    #[test]
+    fn consuming_accessors() {
+        // not Clone, as documents holding large blobs may be
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Blob {
+            data: String,
+        }
+
+        let parse = |body| serde_json::from_value::<DocumentResponse<Blob>>(body).unwrap();
+        let full = || {
+            parse(json!({
+                "_id": "blobs/a",
+                "_key": "a",
+                "_rev": "2",
+                "old": { "data": "before" },
+                "new": { "data": "after" },
+            }))
+        };
+        let blob = |data: &str| Blob {
+            data: data.to_owned(),
+        };
+
+        assert_eq!(full().into_new(), Some(blob("after")));
+        assert_eq!(full().into_old(), Some(blob("before")));
+        let (header, old, new) = full().into_parts().unwrap();
+        assert_eq!(header._key, "a");
+        assert_eq!((old, new), (Some(blob("before")), Some(blob("after"))));
+
+        let without_old = parse(json!({ "_id": "blobs/a", "_key": "a", "_rev": "2" }));
+        assert_eq!(without_old.into_old(), None);
+
+        assert_eq!(parse(json!({})).into_new(), None);
+        assert_eq!(parse(json!({})).into_old(), None);
+        assert!(parse(json!({})).into_parts().is_none());
+    }
+
+    #[test]
+    fn bulk_results_in_input_order() {
+        let error = |num| -> ArangoError {
+            serde_json::from_value(json!({ "code": 409, "errorNum": num, "errorMessage": "" }))
+                .unwrap()
+        };
+        let results: BulkDocumentResult<Value> = vec![
+            Err(error(1210)),
+            Ok(parse(json!({ "_id": "users/b", "_key": "b", "_rev": "1" })).unwrap()),
+            Ok(DocumentResponse::Silent),
+            Err(error(1210)),
+            Err(error(1221)),
+        ]
+        .into();
+        assert!(!results.is_all_ok());
+        assert_eq!(results.len(), 5);
+        assert_eq!(
+            results[1].as_ref().ok().unwrap().header().unwrap()._key,
+            "b"
+        );
+        let successes: Vec<usize> = results.successes().map(|(i, _)| i).collect();
+        assert_eq!(successes, vec![1, 2]);
+        let errors: Vec<_> = results.errors().map(|(i, e)| (i, e.error_num())).collect();
+        assert_eq!(errors, vec![(0, 1210), (3, 1210), (4, 1221)]);
+        let summary = results.error_summary();
+        assert_eq!((summary.count(1210), summary.count(1221)), (2, 1));
+
+        let done: BulkDocumentResult<Value> = vec![Ok(DocumentResponse::Silent)].into();
+        assert!(done.is_all_ok());
+        assert!(done.error_summary().is_empty());
+        assert_eq!(done.into_iter().count(), 1);
+    }
+}
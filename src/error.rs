@@ -1,7 +1,8 @@
-use std::fmt;
+use std::{fmt, time::Duration};
 
 use serde::Deserialize;
 use thiserror::Error;
+use url::Url;
 
 use crate::connection::Permission;
 
@@ -20,13 +21,175 @@ pub enum ClientError {
     Serde(#[from] serde_json::error::Error),
     #[error("HTTP client error: {0}")]
     HttpClient(#[from] uclient::ClientError),
+    #[error(
+        "Edge between smart prefixes {from_prefix} and {to_prefix} is not allowed in a disjoint \
+         smart graph"
+    )]
+    SmartGraphConstraint {
+        from_prefix: String,
+        to_prefix: String,
+    },
+    #[error(
+        "Failed to write the audit record, change {}: {source}",
+        if *.rolled_back { "rolled back" } else { "kept" }
+    )]
+    AuditFailed {
+        source: Box<ClientError>,
+        /// Whether the change was rolled back with its transaction.
+        rolled_back: bool,
+    },
+    #[error("Cursor was cancelled")]
+    Cancelled,
+    #[error("Connection is shutting down")]
+    ShuttingDown,
+    #[error("Only available on a Coordinator of a cluster: {operation}")]
+    ClusterOnly { operation: String },
+    #[error("Not supported by the version or storage engine of the server: {operation}")]
+    NotSupported { operation: String },
+    #[error("Endpoint {endpoint} was removed in ArangoDB {removed_in}, use {alternative} instead")]
+    EndpointRemoved {
+        endpoint: &'static str,
+        removed_in: &'static str,
+        alternative: &'static str,
+    },
+    #[error("Requires access to the _system database: {operation}")]
+    RequiresSystemDatabase { operation: String },
+    #[error("Invalid options: {0}")]
+    InvalidOptions(String),
+    #[error("Invalid AQL name {name:?}: {reason}")]
+    InvalidName { name: String, reason: &'static str },
+    #[error("Invalid document key {key:?}: {reason}")]
+    InvalidKey { key: String, reason: &'static str },
+    #[error("Invalid document id {id:?}: {reason}")]
+    InvalidId { id: String, reason: &'static str },
+    #[error("Invalid patch: {0}")]
+    InvalidPatch(String),
+    #[error(
+        "Transaction size exceeded ({message}), consider setting intermediate_commit_count or \
+         intermediate_commit_size in the query options"
+    )]
+    TransactionSizeExceeded { message: String },
+    #[error("Query returned {got} results where at most one was expected")]
+    TooManyResults { got: usize },
+    #[error("Query returned no result where exactly one was expected")]
+    NoResult,
+    #[error("Read-only connection, refused to {operation}")]
+    ReadOnlyConnection { operation: String },
+    /// A write with an expected revision, given by `if_match` or by the
+    /// `_rev` of the document with `ignore_revs` unset, found the document
+    /// at another revision.
+    #[error(
+        "Precondition failed, the document is at revision {}: {source}",
+        .current_rev.as_deref().unwrap_or("unknown")
+    )]
+    PreconditionFailed {
+        /// The revision of the document on the server, to retry with.
+        current_rev: Option<String>,
+        source: ArangoError,
+    },
+    #[error("Collection not found: {name}")]
+    CollectionNotFound { name: String },
+    #[error("Service unavailable, retry after {retry_after:?}")]
+    ServiceUnavailable {
+        /// The delay asked for by the `Retry-After` header, if any.
+        retry_after: Option<Duration>,
+    },
+    #[error("{}", describe_failures(.failures))]
+    NoEndpointAvailable {
+        /// One failure per endpoint, in the order the endpoints were given.
+        failures: Vec<EndpointFailure>,
+    },
+    /// The server, or a proxy in front of it, sent data the client cannot
+    /// use, like an id which does not fit in a url. Such data must fail the
+    /// request with this error and never panic, which is why the modules
+    /// handling responses deny `unwrap` and `expect` outside of tests.
+    #[error("Malformed response from server: {0}")]
+    MalformedResponse(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A graph export which cannot be imported, at its `record`-th line
+    /// counting from 0 for the header.
+    #[error("Invalid graph export at record {record}: {reason}")]
+    InvalidGraphExport { record: u64, reason: String },
+    #[error("Server is in read-only mode")]
+    ServerReadOnly,
+    #[error("HTTP {status} not from ArangoDB ({content_type:?}): {body_snippet}")]
+    UpstreamHttp {
+        status: u16,
+        content_type: Option<String>,
+        /// The beginning of the body, as often sent by a proxy or load
+        /// balancer in front of ArangoDB.
+        body_snippet: String,
+    },
+}
+
+/// Why the handshake with an endpoint failed, see
+/// [`ClientError::NoEndpointAvailable`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointFailure {
+    /// The endpoint, without credentials.
+    pub endpoint: Url,
+    /// A short reason like `refused`, `timeout` or `TLS error`.
+    pub reason: String,
+}
+
+/// Format failures like `3 endpoints tried: refused, timeout, TLS error`.
+fn describe_failures(failures: &[EndpointFailure]) -> String {
+    let reasons: Vec<&str> = failures.iter().map(|f| f.reason.as_str()).collect();
+    format!(
+        "{} endpoint{} tried: {}",
+        failures.len(),
+        if failures.len() == 1 { "" } else { "s" },
+        reasons.join(", ")
+    )
+}
+
+impl ClientError {
+    /// Whether the request may succeed if sent again later, like while the
+    /// server is starting up or under maintenance, or when the request
+    /// failed or timed out in the HTTP client.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ClientError::ServiceUnavailable { .. } | ClientError::HttpClient(_)
+        )
+    }
 }
 
-#[derive(Deserialize, Debug, Error)]
+/// Refuse `operation` on a read-only handle.
+pub(crate) fn check_writable(read_only: bool, operation: &str) -> Result<(), ClientError> {
+    if read_only {
+        Err(ClientError::ReadOnlyConnection {
+            operation: operation.to_owned(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the server, or a proxy in front of it, refused the request to the
+/// user.
+pub(crate) fn is_forbidden(error: &ClientError) -> bool {
+    match error {
+        ClientError::Arango(e) => e.code() == 401 || e.code() == 403,
+        ClientError::UpstreamHttp { status, .. } => *status == 401 || *status == 403,
+        _ => false,
+    }
+}
+
+/// Number of an ArangoDB error, like 1202 for a document not found.
+pub type ErrorNum = u16;
+
+/// Error number of ArangoDB when two transactions write the same document.
+const ERROR_ARANGO_CONFLICT: ErrorNum = 1200;
+const ERROR_DOCUMENT_NOT_FOUND: ErrorNum = 1202;
+const ERROR_UNIQUE_CONSTRAINT_VIOLATED: ErrorNum = 1210;
+
+#[derive(Deserialize, Debug, Clone, Error)]
 pub struct ArangoError {
     pub(crate) code: u16,
     #[serde(rename = "errorNum")]
-    pub(crate) error_num: u16,
+    pub(crate) error_num: ErrorNum,
     #[serde(rename = "errorMessage")]
     pub(crate) message: String,
 }
@@ -43,11 +206,28 @@ impl ArangoError {
         self.code
     }
 
-    pub fn error_num(&self) -> u16 {
+    pub fn error_num(&self) -> ErrorNum {
         self.error_num
     }
 
     pub fn message(&self) -> &str {
         &self.message
     }
+
+    /// Whether a write lost against another one on the same document, or
+    /// the `_rev` given did not match the document (`1200`).
+    pub fn is_conflict(&self) -> bool {
+        self.error_num == ERROR_ARANGO_CONFLICT
+    }
+
+    /// Whether the document was not found (`1202`).
+    pub fn is_document_not_found(&self) -> bool {
+        self.error_num == ERROR_DOCUMENT_NOT_FOUND
+    }
+
+    /// Whether a write would have violated a unique index, like the primary
+    /// index on `_key` (`1210`).
+    pub fn is_unique_constraint_violated(&self) -> bool {
+        self.error_num == ERROR_UNIQUE_CONSTRAINT_VIOLATED
+    }
 }
@@ -0,0 +1,566 @@
+//! Reports on the use of indexes by AQL queries, see
+//! [`Database::index_usage_report`](crate::Database::index_usage_report).
+//!
+//! The execution plan of each query, as returned by `POST /_api/explain`, is
+//! walked for the nodes reading collections:
+//! - `IndexNode` and `JoinNode` read documents through indexes,
+//! - `EnumerateCollectionNode` reads all documents, which is a full scan when
+//!   a filter applies to them, either pushed into the node (ArangoDB 3.7 and
+//!   later) or in a `FilterNode` whose condition refers to the documents.
+//!
+//! The indexes found are resolved against the index listings of the
+//! collections, so that the indexes never used by any of the queries can be
+//! reported as well.
+//!
+//! Reports serialize to stable JSON, with sorted lists, so that they can be
+//! kept as a tuning baseline and compared.
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::aql::flatten_nodes;
+
+pub(crate) const EXPLAIN_API_PATH: &str = "_api/explain";
+
+/// Index types created by ArangoDB itself, never reported as unused.
+const SYSTEM_INDEX_TYPES: [&str; 2] = ["primary", "edge"];
+
+/// An index of a collection.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexRef {
+    pub collection: String,
+    /// Id of the index, like `users/12345`.
+    pub id: String,
+    /// Name of the index, known from ArangoDB 3.5 on.
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub index_type: String,
+    pub fields: Vec<String>,
+}
+
+/// A collection read document by document, to apply a filter no index
+/// covers.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullScan {
+    pub collection: String,
+    /// Name of the variable iterating over the documents in the query.
+    pub variable: String,
+}
+
+/// Use of indexes by a query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryIndexReport {
+    pub query: String,
+    /// Indexes used by the plan of the query, sorted.
+    pub indexes: Vec<IndexRef>,
+    /// Filtered collections scanned without index, sorted.
+    pub full_scans: Vec<FullScan>,
+    /// Warnings of the optimizer.
+    pub warnings: Vec<String>,
+}
+
+/// Use of indexes by a set of queries, see
+/// [`Database::index_usage_report`](crate::Database::index_usage_report).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexUsageReport {
+    /// One report per query, in the order of the queries.
+    pub queries: Vec<QueryIndexReport>,
+    /// Indexes of the collections read by the queries which none of them
+    /// uses, sorted. The primary and edge indexes are left out.
+    pub unused: Vec<IndexRef>,
+}
+
+/// An index as found in a plan, not resolved against the listing yet.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PlanIndex {
+    pub(crate) collection: String,
+    pub(crate) id: String,
+    pub(crate) name: Option<String>,
+    pub(crate) index_type: String,
+    pub(crate) fields: Vec<String>,
+}
+
+/// What a plan tells about the use of indexes.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct PlanAnalysis {
+    pub(crate) indexes: Vec<PlanIndex>,
+    pub(crate) full_scans: Vec<FullScan>,
+    /// Names of the collections the query reads or writes.
+    pub(crate) collections: BTreeSet<String>,
+}
+
+/// Names of the fields of an index, which are objects for some types, like
+/// the inverted indexes.
+fn field_names(index: &Value) -> Vec<String> {
+    index
+        .get("fields")
+        .and_then(Value::as_array)
+        .map(|fields| {
+            fields
+                .iter()
+                .filter_map(|field| match field {
+                    Value::String(name) => Some(name.clone()),
+                    other => other.get("name").and_then(Value::as_str).map(str::to_owned),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn as_string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(s) => Some(s.clone()),
+        // ids are numbers in the plans of some versions
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn plan_index(collection: &str, index: &Value) -> Option<PlanIndex> {
+    Some(PlanIndex {
+        collection: collection.to_owned(),
+        id: as_string(index.get("id"))?,
+        name: as_string(index.get("name")),
+        index_type: as_string(index.get("type")).unwrap_or_default(),
+        fields: field_names(index),
+    })
+}
+
+fn variable_id(node: &Value, key: &str) -> Option<u64> {
+    node.get(key)?.get("id")?.as_u64()
+}
+
+/// Ids of the variables an expression of a plan refers to.
+fn referenced_variables(expression: &Value, ids: &mut HashSet<u64>) {
+    if expression.get("type").and_then(Value::as_str) == Some("reference") {
+        if let Some(id) = expression.get("id").and_then(Value::as_u64) {
+            ids.insert(id);
+        }
+    }
+    if let Some(sub_nodes) = expression.get("subNodes").and_then(Value::as_array) {
+        for sub_node in sub_nodes {
+            referenced_variables(sub_node, ids);
+        }
+    }
+}
+
+/// Find the indexes used and the full scans in the plan of a query.
+pub(crate) fn analyze_plan(plan: &Value) -> PlanAnalysis {
+    let mut analysis = PlanAnalysis::default();
+    for collection in plan["collections"].as_array().into_iter().flatten() {
+        if let Some(name) = collection.get("name").and_then(Value::as_str) {
+            analysis.collections.insert(name.to_owned());
+        }
+    }
+
+    let mut nodes = Vec::new();
+    flatten_nodes(&plan["nodes"], &mut nodes);
+
+    // variables the condition of each filter refers to, through the
+    // calculation of the condition
+    let mut calculations: HashMap<u64, HashSet<u64>> = HashMap::new();
+    for node in &nodes {
+        if node["type"] == "CalculationNode" {
+            if let Some(out) = variable_id(node, "outVariable") {
+                let mut ids = HashSet::new();
+                referenced_variables(&node["expression"], &mut ids);
+                calculations.insert(out, ids);
+            }
+        }
+    }
+    let mut filtered = HashSet::new();
+    for node in &nodes {
+        if node["type"] == "FilterNode" {
+            if let Some(ids) = variable_id(node, "inVariable").and_then(|id| calculations.get(&id))
+            {
+                filtered.extend(ids.iter().copied());
+            }
+        }
+    }
+
+    for node in &nodes {
+        let collection = node.get("collection").and_then(Value::as_str);
+        match node["type"].as_str() {
+            Some("IndexNode") => {
+                let collection = match collection {
+                    Some(collection) => collection,
+                    None => continue,
+                };
+                // a single `index` in old versions
+                let indexes = match (node.get("indexes"), node.get("index")) {
+                    (Some(Value::Array(indexes)), _) => indexes.iter().collect(),
+                    (_, Some(index)) => vec![index],
+                    _ => vec![],
+                };
+                analysis.indexes.extend(
+                    indexes
+                        .into_iter()
+                        .filter_map(|index| plan_index(collection, index)),
+                );
+            }
+            Some("JoinNode") => {
+                for info in node["indexInfos"].as_array().into_iter().flatten() {
+                    if let Some(collection) = info.get("collection").and_then(Value::as_str) {
+                        analysis
+                            .indexes
+                            .extend(plan_index(collection, &info["index"]));
+                    }
+                }
+            }
+            Some("EnumerateCollectionNode") => {
+                let collection = match collection {
+                    Some(collection) => collection,
+                    None => continue,
+                };
+                let pushed_filter = !matches!(node.get("filter"), None | Some(Value::Null));
+                let filtered_later = matches!(
+                    variable_id(node, "outVariable"),
+                    Some(id) if filtered.contains(&id)
+                );
+                if pushed_filter || filtered_later {
+                    analysis.full_scans.push(FullScan {
+                        collection: collection.to_owned(),
+                        variable: node["outVariable"]["name"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_owned(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    analysis
+}
+
+/// An index of a listing of `GET /_api/index`.
+pub(crate) fn listed_index(collection: &str, index: &Value) -> Option<IndexRef> {
+    Some(IndexRef {
+        collection: collection.to_owned(),
+        id: as_string(index.get("id"))?,
+        name: as_string(index.get("name")),
+        index_type: as_string(index.get("type")).unwrap_or_default(),
+        fields: field_names(index),
+    })
+}
+
+/// Whether the id of an index in a plan, with or without the collection,
+/// is the one of `listed`.
+fn same_index(plan_id: &str, listed: &IndexRef) -> bool {
+    listed.id == plan_id || listed.id.rsplit('/').next() == Some(plan_id)
+}
+
+/// Build the report from the analysis of the plan of each query and the
+/// index listings of the collections.
+pub(crate) fn build_report(
+    queries: Vec<(String, PlanAnalysis, Vec<String>)>,
+    listings: &BTreeMap<String, Vec<IndexRef>>,
+) -> IndexUsageReport {
+    let mut used = HashSet::new();
+    let queries = queries
+        .into_iter()
+        .map(|(query, analysis, warnings)| {
+            let mut indexes: Vec<IndexRef> = analysis
+                .indexes
+                .into_iter()
+                .map(|index| {
+                    let listed = listings.get(&index.collection).and_then(|listing| {
+                        listing.iter().find(|listed| same_index(&index.id, listed))
+                    });
+                    match listed {
+                        Some(listed) => listed.clone(),
+                        None => IndexRef {
+                            id: format!("{}/{}", index.collection, index.id),
+                            collection: index.collection,
+                            name: index.name,
+                            index_type: index.index_type,
+                            fields: index.fields,
+                        },
+                    }
+                })
+                .collect();
+            indexes.sort();
+            indexes.dedup();
+            used.extend(indexes.iter().map(|index| index.id.clone()));
+            let mut full_scans = analysis.full_scans;
+            full_scans.sort();
+            full_scans.dedup();
+            QueryIndexReport {
+                query,
+                indexes,
+                full_scans,
+                warnings,
+            }
+        })
+        .collect();
+    let unused = listings
+        .values()
+        .flatten()
+        .filter(|index| {
+            !SYSTEM_INDEX_TYPES.contains(&index.index_type.as_str()) && !used.contains(&index.id)
+        })
+        .cloned()
+        .collect();
+    IndexUsageReport { queries, unused }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Plan of ArangoDB 3.6 for
+    /// `FOR u IN users FILTER u.email == @email FOR o IN orders FILTER
+    /// o.total > 10 RETURN o`, with the old style nested subqueries.
+    fn plan_3_6() -> Value {
+        json!({
+            "nodes": [
+                { "type": "SingletonNode", "id": 1 },
+                {
+                    "type": "IndexNode",
+                    "id": 9,
+                    "collection": "users",
+                    "outVariable": { "id": 0, "name": "u" },
+                    "indexes": [
+                        { "id": "1234", "type": "hash", "fields": ["email"], "unique": true }
+                    ]
+                },
+                {
+                    "type": "SubqueryNode",
+                    "id": 6,
+                    "subquery": {
+                        "nodes": [
+                            { "type": "SingletonNode", "id": 2 },
+                            {
+                                "type": "EnumerateCollectionNode",
+                                "id": 3,
+                                "collection": "orders",
+                                "random": false,
+                                "outVariable": { "id": 2, "name": "o" }
+                            },
+                            {
+                                "type": "CalculationNode",
+                                "id": 4,
+                                "expression": {
+                                    "type": "compare >",
+                                    "subNodes": [
+                                        {
+                                            "type": "attribute access",
+                                            "name": "total",
+                                            "subNodes": [
+                                                { "type": "reference", "name": "o", "id": 2 }
+                                            ]
+                                        },
+                                        { "type": "value", "value": 10 }
+                                    ]
+                                },
+                                "outVariable": { "id": 4, "name": "3" }
+                            },
+                            { "type": "FilterNode", "id": 5, "inVariable": { "id": 4, "name": "3" } },
+                            { "type": "ReturnNode", "id": 7, "inVariable": { "id": 2, "name": "o" } }
+                        ]
+                    },
+                    "outVariable": { "id": 5, "name": "5" }
+                },
+                { "type": "ReturnNode", "id": 8, "inVariable": { "id": 5, "name": "5" } }
+            ],
+            "collections": [
+                { "name": "orders", "type": "read" },
+                { "name": "users", "type": "read" }
+            ]
+        })
+    }
+
+    /// Plan of ArangoDB 3.11 for the same query, with the filter moved into
+    /// the enumeration, spliced subqueries and named indexes.
+    fn plan_3_11() -> Value {
+        json!({
+            "nodes": [
+                { "type": "SingletonNode", "id": 1 },
+                {
+                    "type": "IndexNode",
+                    "id": 9,
+                    "collection": "users",
+                    "outVariable": { "id": 0, "name": "u" },
+                    "indexes": [
+                        {
+                            "id": "1234",
+                            "name": "idx_email",
+                            "type": "persistent",
+                            "fields": ["email"],
+                            "unique": true
+                        }
+                    ]
+                },
+                { "type": "SubqueryStartNode", "id": 10 },
+                {
+                    "type": "EnumerateCollectionNode",
+                    "id": 3,
+                    "collection": "orders",
+                    "random": false,
+                    "filter": {
+                        "type": "compare >",
+                        "subNodes": [
+                            {
+                                "type": "attribute access",
+                                "name": "total",
+                                "subNodes": [ { "type": "reference", "name": "o", "id": 2 } ]
+                            },
+                            { "type": "value", "value": 10 }
+                        ]
+                    },
+                    "outVariable": { "id": 2, "name": "o" }
+                },
+                { "type": "SubqueryEndNode", "id": 11 },
+                { "type": "ReturnNode", "id": 8, "inVariable": { "id": 5, "name": "5" } }
+            ],
+            "collections": [
+                { "name": "orders", "type": "read" },
+                { "name": "users", "type": "read" }
+            ]
+        })
+    }
+
+    fn expected_analysis(name: Option<&str>, index_type: &str) -> PlanAnalysis {
+        PlanAnalysis {
+            indexes: vec![PlanIndex {
+                collection: "users".to_owned(),
+                id: "1234".to_owned(),
+                name: name.map(str::to_owned),
+                index_type: index_type.to_owned(),
+                fields: vec!["email".to_owned()],
+            }],
+            full_scans: vec![FullScan {
+                collection: "orders".to_owned(),
+                variable: "o".to_owned(),
+            }],
+            collections: vec!["orders".to_owned(), "users".to_owned()]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn plans_of_several_versions() {
+        assert_eq!(analyze_plan(&plan_3_6()), expected_analysis(None, "hash"));
+        assert_eq!(
+            analyze_plan(&plan_3_11()),
+            expected_analysis(Some("idx_email"), "persistent")
+        );
+    }
+
+    #[test]
+    fn unfiltered_enumeration_is_not_a_full_scan() {
+        let plan = json!({
+            "nodes": [
+                {
+                    "type": "EnumerateCollectionNode",
+                    "collection": "users",
+                    "outVariable": { "id": 0, "name": "u" },
+                    "filter": null
+                },
+                {
+                    "type": "CalculationNode",
+                    "expression": { "type": "value", "value": true },
+                    "outVariable": { "id": 1, "name": "1" }
+                },
+                // filters on something else
+                { "type": "FilterNode", "inVariable": { "id": 1, "name": "1" } },
+                {
+                    "type": "JoinNode",
+                    "indexInfos": [
+                        {
+                            "collection": "orders",
+                            "index": { "id": 77, "type": "persistent", "fields": ["user"] }
+                        }
+                    ]
+                }
+            ],
+            "collections": [{ "name": "users", "type": "read" }]
+        });
+        let analysis = analyze_plan(&plan);
+        assert!(analysis.full_scans.is_empty());
+        assert_eq!(analysis.indexes[0].id, "77");
+        assert_eq!(analysis.indexes[0].collection, "orders");
+
+        // garbage does not panic
+        assert_eq!(analyze_plan(&json!("plan")), PlanAnalysis::default());
+        assert_eq!(
+            analyze_plan(&json!({ "nodes": [{ "type": "IndexNode" }, 1, null] })),
+            PlanAnalysis::default()
+        );
+    }
+
+    #[test]
+    fn report() {
+        let listing = |collection: &str, indexes: Value| -> Vec<IndexRef> {
+            indexes
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|index| listed_index(collection, index))
+                .collect()
+        };
+        let mut listings = BTreeMap::new();
+        listings.insert(
+            "users".to_owned(),
+            listing(
+                "users",
+                json!([
+                    { "id": "users/0", "name": "primary", "type": "primary", "fields": ["_key"] },
+                    { "id": "users/1234", "name": "idx_email", "type": "persistent", "fields": ["email"] },
+                    { "id": "users/99", "name": "idx_age", "type": "persistent", "fields": ["age"] },
+                ]),
+            ),
+        );
+        listings.insert(
+            "orders".to_owned(),
+            listing(
+                "orders",
+                json!([
+                    { "id": "orders/0", "name": "primary", "type": "primary", "fields": ["_key"] },
+                    { "id": "orders/5", "name": "inv", "type": "inverted", "fields": [{ "name": "total" }] },
+                ]),
+            ),
+        );
+        let report = build_report(
+            vec![
+                ("q1".to_owned(), analyze_plan(&plan_3_6()), vec![]),
+                (
+                    "q2".to_owned(),
+                    analyze_plan(&plan_3_11()),
+                    vec!["warning".to_owned()],
+                ),
+            ],
+            &listings,
+        );
+        let idx_email = IndexRef {
+            collection: "users".to_owned(),
+            id: "users/1234".to_owned(),
+            name: Some("idx_email".to_owned()),
+            index_type: "persistent".to_owned(),
+            fields: vec!["email".to_owned()],
+        };
+        assert_eq!(report.queries[0].indexes, vec![idx_email.clone()]);
+        assert_eq!(report.queries[1].indexes, vec![idx_email]);
+        assert_eq!(report.queries[1].warnings, vec!["warning"]);
+        let unused: Vec<&str> = report.unused.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(unused, vec!["orders/5", "users/99"]);
+        assert_eq!(report.unused[0].fields, vec!["total"]);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["queries"][0]["fullScans"],
+            json!([{ "collection": "orders", "variable": "o" }])
+        );
+        assert_eq!(json["unused"][1]["type"], "persistent");
+        let back: IndexUsageReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back, report);
+    }
+}
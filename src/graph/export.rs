@@ -0,0 +1,758 @@
+//! Export of a named graph to a self-describing NDJSON stream, and import of
+//! such a stream into a database, see
+//! [`Database::export_graph`](crate::Database::export_graph) and
+//! [`Database::import_graph`](crate::Database::import_graph).
+//!
+//! The first line is a header with the definition of the graph and the type
+//! of each of its collections:
+//!
+//! ```json
+//! {"type":"graph","format":1,"graph":{"name":"social","edgeDefinitions":[...]},"collections":[{"name":"persons","type":"vertex"},{"name":"knows","type":"edge"}]}
+//! ```
+//!
+//! Then comes a line per document, every vertex before the first edge, each
+//! tagged with its collection:
+//!
+//! ```json
+//! {"type":"vertex","collection":"persons","document":{"_key":"alice","name":"Alice"}}
+//! {"type":"edge","collection":"knows","document":{"_key":"1","_from":"persons/alice","_to":"persons/bob"}}
+//! ```
+//!
+//! Documents keep their `_key`, and edges their `_from` and `_to`, while the
+//! `_id` and `_rev` assigned by the server are left out.
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{BufRead, Write},
+    time::{Duration, Instant},
+};
+
+use maybe_async::maybe_async;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use typed_builder::TypedBuilder;
+use uclient::ClientExt;
+
+use super::Graph;
+use crate::{
+    collection::{
+        options::{ImportOptions, OnDuplicate},
+        Collection, ERROR_COLLECTION_NOT_FOUND,
+    },
+    response::BulkErrorSummary,
+    AqlQuery, ClientError, Database,
+};
+
+/// Version of the format, written in the header.
+const FORMAT: u32 = 1;
+
+/// Error number of ArangoDB for a graph which already exists.
+const ERROR_GRAPH_DUPLICATE: u16 = 1925;
+
+/// Options for exporting a graph, see
+/// [`Database::export_graph`](crate::Database::export_graph)
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct ExportOptions {
+    /// Number of documents fetched per request. Default: 1000
+    #[builder(default = 1000)]
+    batch_size: u32,
+    /// Whether the orphan collections of the graph, whose vertices have no
+    /// edges, are exported with their documents. Default: true
+    #[builder(default = true)]
+    include_orphans: bool,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Options for importing a graph, see
+/// [`Database::import_graph`](crate::Database::import_graph)
+#[derive(Debug, Clone, PartialEq, TypedBuilder)]
+#[builder(doc)]
+pub struct ImportGraphOptions {
+    /// Name of the graph created instead of the exported one. Its
+    /// collections keep their names.
+    #[builder(default, setter(strip_option, into))]
+    rename_to: Option<String>,
+    /// What to do with documents whose key already exists. By default they
+    /// are rejected.
+    #[builder(default, setter(strip_option))]
+    on_duplicate: Option<OnDuplicate>,
+    /// Number of documents sent per request. Default: 1000
+    #[builder(default = 1000)]
+    batch_size: usize,
+    /// Number of documents of the export to skip, to resume an import from
+    /// the `offset` reported by its last [`GraphImportProgress`]. The graph
+    /// created by the import being resumed is kept.
+    #[builder(default, setter(strip_option))]
+    resume_from: Option<u64>,
+}
+
+impl Default for ImportGraphOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// What [`Database::export_graph`](crate::Database::export_graph) wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphExportReport {
+    /// Number of vertices written, of all vertex collections.
+    pub vertices: u64,
+    /// Number of edges written, of all edge collections.
+    pub edges: u64,
+}
+
+/// Progress of [`Database::import_graph`](crate::Database::import_graph),
+/// reported after each batch of documents.
+///
+/// Counters cover the whole import since it started or was resumed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphImportProgress {
+    /// Number of documents of the export consumed so far, including the
+    /// skipped ones when resuming. Pass it as `resume_from` to resume after
+    /// this batch.
+    pub offset: u64,
+    /// Collection of the latest batch.
+    pub collection: String,
+    /// Number of vertices sent.
+    pub vertices: u64,
+    /// Number of edges sent.
+    pub edges: u64,
+    /// Number of documents created.
+    pub created: u64,
+    /// Number of documents updated or replaced.
+    pub updated: u64,
+    /// Number of documents ignored as duplicates.
+    pub ignored: u64,
+    /// Number of documents rejected.
+    pub rejected: u64,
+    /// Numbers of documents rejected per error.
+    pub errors: BulkErrorSummary,
+    /// Time spent since the import started.
+    pub elapsed: Duration,
+}
+
+/// A line of an export.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Record {
+    Graph {
+        format: u32,
+        graph: Graph,
+        collections: Vec<CollectionRecord>,
+    },
+    Vertex {
+        collection: String,
+        document: Value,
+    },
+    Edge {
+        collection: String,
+        document: Value,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CollectionRecord {
+    name: String,
+    #[serde(rename = "type")]
+    kind: Kind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Kind {
+    Vertex,
+    Edge,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Vertex => "vertex",
+            Kind::Edge => "edge",
+        }
+    }
+}
+
+/// The collections of `graph`, the vertex collections first, each sorted by
+/// name.
+fn collections(graph: &Graph) -> Vec<CollectionRecord> {
+    let vertices: BTreeSet<&String> = graph
+        .edge_definitions
+        .iter()
+        .flat_map(|definition| definition.from.iter().chain(&definition.to))
+        .chain(&graph.orphan_collections)
+        .collect();
+    let edges: BTreeSet<&String> = graph
+        .edge_definitions
+        .iter()
+        .map(|definition| &definition.collection)
+        .collect();
+    let record = |kind| {
+        move |name: &String| CollectionRecord {
+            name: name.clone(),
+            kind,
+        }
+    };
+    vertices
+        .into_iter()
+        .map(record(Kind::Vertex))
+        .chain(edges.into_iter().map(record(Kind::Edge)))
+        .collect()
+}
+
+fn write_record<W: Write>(writer: &mut W, record: &Record) -> Result<(), ClientError> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn invalid(record: u64, reason: String) -> ClientError {
+    ClientError::InvalidGraphExport { record, reason }
+}
+
+#[maybe_async]
+pub(crate) async fn export_graph<C: ClientExt, W: Write>(
+    db: &Database<C>,
+    name: &str,
+    mut writer: W,
+    options: ExportOptions,
+) -> Result<GraphExportReport, ClientError> {
+    let mut graph = db.graph(name).await?;
+    if !options.include_orphans {
+        graph.orphan_collections.clear();
+    }
+    let collections = collections(&graph);
+    write_record(
+        &mut writer,
+        &Record::Graph {
+            format: FORMAT,
+            graph,
+            collections: collections.clone(),
+        },
+    )?;
+
+    let mut report = GraphExportReport::default();
+    for collection in collections {
+        let aql = AqlQuery::builder()
+            .query("FOR d IN @@collection SORT d._key RETURN UNSET(d, '_id', '_rev')")
+            .bind_var("@collection", collection.name.as_str())
+            .batch_size(options.batch_size.max(1))
+            .build();
+        let mut cursor = db.aql_query_batch::<Value>(aql).await?;
+        loop {
+            for document in std::mem::take(&mut cursor.result) {
+                let name = collection.name.clone();
+                let record = match collection.kind {
+                    Kind::Vertex => {
+                        report.vertices += 1;
+                        Record::Vertex {
+                            collection: name,
+                            document,
+                        }
+                    }
+                    Kind::Edge => {
+                        report.edges += 1;
+                        Record::Edge {
+                            collection: name,
+                            document,
+                        }
+                    }
+                };
+                write_record(&mut writer, &record)?;
+            }
+            if !cursor.more {
+                break;
+            }
+            let id = cursor.next_id()?.to_owned();
+            cursor = db.aql_next_batch(&id).await?;
+        }
+    }
+    writer.flush()?;
+    Ok(report)
+}
+
+#[maybe_async]
+pub(crate) async fn import_graph<C, R, F>(
+    db: &Database<C>,
+    reader: R,
+    options: ImportGraphOptions,
+    mut progress_callback: F,
+) -> Result<GraphImportProgress, ClientError>
+where
+    C: ClientExt,
+    R: BufRead,
+    F: FnMut(GraphImportProgress),
+{
+    let start = Instant::now();
+    let mut lines = reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()));
+    let header = match lines.next() {
+        Some(line) => parse(&line?, 0)?,
+        None => return Err(invalid(0, "no header".to_owned())),
+    };
+    let (mut graph, collections) = match header {
+        Record::Graph {
+            format: FORMAT,
+            graph,
+            collections,
+        } => (graph, collections),
+        Record::Graph { format, .. } => {
+            return Err(invalid(0, format!("unknown format {}", format)))
+        }
+        _ => return Err(invalid(0, "no header".to_owned())),
+    };
+    if let Some(name) = options.rename_to {
+        graph.name = name;
+    }
+
+    // the collections are created before the graph, with their type
+    let mut handles = HashMap::new();
+    for collection in collections {
+        let handle = match db.collection(&collection.name).await {
+            Ok(handle) => handle,
+            Err(ClientError::Arango(e)) if e.error_num() == ERROR_COLLECTION_NOT_FOUND => {
+                match collection.kind {
+                    Kind::Vertex => db.create_collection(&collection.name).await?,
+                    Kind::Edge => db.create_edge_collection(&collection.name).await?,
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        handles.insert(collection.name, (collection.kind, handle));
+    }
+    match db.create_graph(graph, false).await {
+        Ok(_) => {}
+        // created by the import being resumed
+        Err(ClientError::Arango(e))
+            if e.error_num() == ERROR_GRAPH_DUPLICATE && options.resume_from.is_some() => {}
+        Err(e) => return Err(e),
+    }
+
+    let skip = options.resume_from.unwrap_or(0);
+    let batch_size = options.batch_size.max(1);
+    let mut progress = GraphImportProgress {
+        offset: skip,
+        ..Default::default()
+    };
+    let mut batch: Option<(Kind, String, Vec<Value>)> = None;
+    let mut edges = false;
+    for (record, line) in (1..).zip(lines) {
+        let (kind, collection, document) = match parse(&line?, record)? {
+            Record::Vertex {
+                collection,
+                document,
+            } => (Kind::Vertex, collection, document),
+            Record::Edge {
+                collection,
+                document,
+            } => (Kind::Edge, collection, document),
+            Record::Graph { .. } => return Err(invalid(record, "second header".to_owned())),
+        };
+        // skipped documents are checked too, to fail at the same record
+        // whether resuming or not
+        match handles.get(&collection) {
+            Some((expected, _)) if *expected == kind => {}
+            _ => {
+                return Err(invalid(
+                    record,
+                    format!(
+                        "{} is no {} collection of the graph",
+                        collection,
+                        kind.name()
+                    ),
+                ))
+            }
+        }
+        match kind {
+            Kind::Edge => edges = true,
+            Kind::Vertex if edges => {
+                return Err(invalid(record, "vertex after the edges".to_owned()))
+            }
+            Kind::Vertex => {}
+        }
+        if record <= skip {
+            continue;
+        }
+
+        match &mut batch {
+            Some((_, name, documents)) if *name == collection && documents.len() < batch_size => {
+                documents.push(document)
+            }
+            _ => {
+                if let Some((kind, name, documents)) = batch.take() {
+                    let target = &handles[&name].1;
+                    send(
+                        target,
+                        kind,
+                        documents,
+                        &options.on_duplicate,
+                        &mut progress,
+                    )
+                    .await?;
+                    progress.elapsed = start.elapsed();
+                    progress_callback(progress.clone());
+                }
+                batch = Some((kind, collection, vec![document]));
+            }
+        }
+    }
+    if let Some((kind, name, documents)) = batch {
+        let target = &handles[&name].1;
+        send(
+            target,
+            kind,
+            documents,
+            &options.on_duplicate,
+            &mut progress,
+        )
+        .await?;
+        progress.elapsed = start.elapsed();
+        progress_callback(progress.clone());
+    }
+    progress.elapsed = start.elapsed();
+    Ok(progress)
+}
+
+fn parse(line: &str, record: u64) -> Result<Record, ClientError> {
+    serde_json::from_str(line).map_err(|e| invalid(record, e.to_string()))
+}
+
+/// Import a batch of `documents` of the same collection.
+#[maybe_async]
+async fn send<C: ClientExt>(
+    collection: &Collection<C>,
+    kind: Kind,
+    documents: Vec<Value>,
+    on_duplicate: &Option<OnDuplicate>,
+    progress: &mut GraphImportProgress,
+) -> Result<(), ClientError> {
+    let count = documents.len();
+    let options = match on_duplicate {
+        Some(on_duplicate) => ImportOptions::builder()
+            .batch_size(count)
+            .on_duplicate(*on_duplicate)
+            .build(),
+        None => ImportOptions::builder().batch_size(count).build(),
+    };
+    let imported = collection
+        .import_documents(documents, options, |_| {})
+        .await?;
+    progress.offset += count as u64;
+    match kind {
+        Kind::Vertex => progress.vertices += count as u64,
+        Kind::Edge => progress.edges += count as u64,
+    }
+    progress.collection = collection.name().to_owned();
+    progress.created += imported.created;
+    progress.updated += imported.updated;
+    progress.ignored += imported.ignored;
+    progress.rejected += imported.rejected;
+    progress.errors.merge(imported.errors);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use http::Method;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        collection::CollectionType,
+        test_support::{
+            diff::{diff_documents, MatchOptions},
+            fixtures, MockClient, MockResponse,
+        },
+    };
+
+    fn social() -> Value {
+        json!({
+            "name": "social",
+            "edgeDefinitions": [
+                { "collection": "knows", "from": ["persons"], "to": ["persons"] }
+            ],
+            "orphanCollections": ["places"],
+        })
+    }
+
+    fn graph_response(status: u16, graph: Value) -> MockResponse {
+        MockResponse::json(
+            status,
+            json!({ "error": false, "code": status, "graph": graph }),
+        )
+    }
+
+    fn imported(created: usize) -> MockResponse {
+        MockResponse::json(
+            201,
+            json!({
+                "error": false,
+                "created": created,
+                "errors": 0,
+                "empty": 0,
+                "updated": 0,
+                "ignored": 0,
+            }),
+        )
+    }
+
+    fn properties(name: &str, id: &str, kind: u8) -> MockResponse {
+        let mut body = fixtures::collection_properties(name, id);
+        body["type"] = json!(kind);
+        MockResponse::json(200, body)
+    }
+
+    fn not_found() -> MockResponse {
+        MockResponse::json(404, fixtures::error(404, 1203, "collection not found"))
+    }
+
+    /// The documents sent to the import API per collection.
+    fn imports(mock: &MockClient) -> HashMap<String, Vec<Value>> {
+        let mut imports: HashMap<String, Vec<Value>> = HashMap::new();
+        for request in mock.requests() {
+            if request.path.ends_with("/_api/import") {
+                let documents = request
+                    .body
+                    .lines()
+                    .map(|line| serde_json::from_str(line).unwrap());
+                imports
+                    .entry(request.query_param("collection").unwrap())
+                    .or_default()
+                    .extend(documents);
+            }
+        }
+        imports
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn export_and_import_round_trip() {
+        let persons = vec![
+            json!({ "_key": "alice", "name": "Alice" }),
+            json!({ "_key": "bob", "name": "Bob" }),
+            json!({ "_key": "carol", "name": "Carol", "tags": ["a", "b"] }),
+        ];
+        let places = vec![json!({ "_key": "paris", "location": [48.85, 2.35] })];
+        let knows = vec![
+            json!({ "_key": "1", "_from": "persons/alice", "_to": "persons/bob" }),
+            json!({ "_key": "2", "_from": "persons/bob", "_to": "persons/carol", "since": 2020 }),
+        ];
+
+        let source = MockClient::default();
+        source.on(
+            Method::GET,
+            "/_db/test_db/_api/gharial/social",
+            graph_response(200, social()),
+        );
+        // vertex collections first, each sorted by name
+        source.expect_cursor(
+            "test_db",
+            vec![persons[..2].to_vec(), persons[2..].to_vec()],
+        );
+        source.expect_cursor("test_db", vec![places.clone()]);
+        source.expect_cursor("test_db", vec![knows.clone()]);
+        let mut export = Vec::new();
+        let options = ExportOptions::builder().batch_size(2).build();
+        let report = source
+            .database("test_db")
+            .export_graph("social", &mut export, options)
+            .await
+            .unwrap();
+        assert_eq!((report.vertices, report.edges), (4, 2));
+        source.assert_no_pending();
+        let export = String::from_utf8(export).unwrap();
+        assert_eq!(export.lines().count(), 7);
+        let queries: Vec<Value> = source
+            .requests()
+            .iter()
+            .filter(|r| r.method == Method::POST)
+            .map(|r| r.json_body().unwrap()["bindVars"]["@collection"].clone())
+            .collect();
+        assert_eq!(queries, vec!["persons", "places", "knows"]);
+
+        let target = MockClient::default();
+        for (name, id, kind) in [("persons", "1", 2), ("places", "2", 2), ("knows", "3", 3)] {
+            let path = format!("/_db/copy/_api/collection/{}", name);
+            target.once(Method::GET, &path, not_found());
+            target.once(
+                Method::POST,
+                "/_db/copy/_api/collection",
+                properties(name, id, kind),
+            );
+            target.once(Method::GET, &path, properties(name, id, kind));
+        }
+        let mut copy = social();
+        copy["name"] = json!("social_copy");
+        target.once(
+            Method::POST,
+            "/_db/copy/_api/gharial",
+            graph_response(202, copy.clone()),
+        );
+        for created in [2, 1, 1, 2] {
+            target.once(Method::POST, "/_db/copy/_api/import", imported(created));
+        }
+        let mut reports = Vec::new();
+        let options = ImportGraphOptions::builder()
+            .rename_to("social_copy")
+            .batch_size(2)
+            .build();
+        let progress = target
+            .database("copy")
+            .import_graph(export.as_bytes(), options, |p| reports.push(p))
+            .await
+            .unwrap();
+        target.assert_no_pending();
+        assert_eq!(reports.len(), 4);
+        let offsets: Vec<u64> = reports.iter().map(|p| p.offset).collect();
+        assert_eq!(offsets, vec![2, 3, 4, 6]);
+        assert_eq!(reports[2].collection, "places");
+        assert_eq!(
+            (progress.vertices, progress.edges, progress.created),
+            (4, 2, 6)
+        );
+
+        let requests = target.requests();
+        let edges: Vec<bool> = requests
+            .iter()
+            .filter(|r| r.path == "/_db/copy/_api/collection" && r.method == Method::POST)
+            .map(|r| r.json_body().unwrap()["type"] == json!(CollectionType::Edge))
+            .collect();
+        assert_eq!(edges, vec![false, false, true]);
+        let graph = requests
+            .iter()
+            .find(|r| r.path == "/_db/copy/_api/gharial")
+            .unwrap()
+            .json_body()
+            .unwrap();
+        assert_eq!(graph, copy);
+
+        let sent = imports(&target);
+        let options = MatchOptions::default();
+        for (name, documents) in [("persons", persons), ("places", places), ("knows", knows)] {
+            let diff = diff_documents(documents, sent[name].clone(), &options);
+            assert!(diff.is_empty(), "{}: {:?}", name, diff);
+        }
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn export_fails_on_cursor_without_id() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_db/test_db/_api/gharial/social",
+            graph_response(200, social()),
+        );
+        let persons = vec![json!({ "_key": "alice" })];
+        let mut batch = fixtures::cursor_batches("lost", vec![persons, vec![]]).remove(0);
+        batch.as_object_mut().unwrap().remove("id");
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/cursor",
+            MockResponse::json(201, batch),
+        );
+
+        let mut export = Vec::new();
+        let result = mock
+            .database("test_db")
+            .export_graph("social", &mut export, Default::default())
+            .await;
+        assert!(matches!(result, Err(ClientError::MalformedResponse(_))));
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn resume_import() {
+        let header = json!({
+            "type": "graph",
+            "format": 1,
+            "graph": social(),
+            "collections": [
+                { "name": "persons", "type": "vertex" },
+                { "name": "places", "type": "vertex" },
+                { "name": "knows", "type": "edge" },
+            ],
+        });
+        let vertex = |key: &str| json!({ "type": "vertex", "collection": "persons", "document": { "_key": key } });
+        let edge = json!({
+            "type": "edge",
+            "collection": "knows",
+            "document": { "_key": "1", "_from": "persons/a", "_to": "persons/b" },
+        });
+        let lines = |records: &[Value]| {
+            records
+                .iter()
+                .map(|r| format!("{}\n", r))
+                .collect::<String>()
+        };
+
+        let mock = MockClient::default();
+        for (name, id) in [("persons", "1"), ("places", "2"), ("knows", "3")] {
+            let path = format!("/_db/copy/_api/collection/{}", name);
+            mock.on(
+                Method::GET,
+                &path,
+                MockResponse::json(200, fixtures::collection_info(name, id)),
+            );
+        }
+        mock.on(
+            Method::POST,
+            "/_db/copy/_api/gharial",
+            MockResponse::json(409, fixtures::error(409, 1925, "graph already exists")),
+        );
+        mock.on(Method::POST, "/_db/copy/_api/import", imported(1));
+        let db = mock.database("copy");
+        let export = lines(&[header.clone(), vertex("a"), vertex("b"), edge.clone()]);
+
+        // the graph exists and is only tolerated when resuming
+        let result = db
+            .import_graph(export.as_bytes(), Default::default(), |_| {})
+            .await;
+        assert!(matches!(result, Err(ClientError::Arango(e)) if e.error_num() == 1925));
+
+        mock.clear_requests();
+        let options = ImportGraphOptions::builder().resume_from(1).build();
+        let progress = db
+            .import_graph(export.as_bytes(), options, |_| {})
+            .await
+            .unwrap();
+        assert_eq!(
+            (progress.offset, progress.vertices, progress.edges),
+            (3, 1, 1)
+        );
+        let sent = imports(&mock);
+        assert_eq!(sent["persons"], vec![json!({ "_key": "b" })]);
+        assert_eq!(sent["knows"].len(), 1);
+
+        // a vertex after the edges fails once the documents before it are sent
+        mock.clear_requests();
+        let export = lines(&[header.clone(), vertex("a"), edge.clone(), vertex("b")]);
+        let options = ImportGraphOptions::builder().resume_from(0).build();
+        let result = db.import_graph(export.as_bytes(), options, |_| {}).await;
+        assert!(matches!(
+            result,
+            Err(ClientError::InvalidGraphExport { record: 3, .. })
+        ));
+        assert_eq!(imports(&mock)["persons"].len(), 1);
+
+        let result = db
+            .import_graph(&b"\n"[..], Default::default(), |_| {})
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClientError::InvalidGraphExport { record: 0, .. })
+        ));
+        let export = lines(&[vertex("a")]);
+        let result = db
+            .import_graph(export.as_bytes(), Default::default(), |_| {})
+            .await;
+        assert!(matches!(
+            result,
+            Err(ClientError::InvalidGraphExport { record: 0, .. })
+        ));
+    }
+}
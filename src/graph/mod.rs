@@ -0,0 +1,537 @@
+//! This module facilitates the building of new named graphs as well as the retrieval
+//! of existing indexes in ArangoDB.
+//!
+//! The various structures are following the HTTP specification as detailed in this ArangoDB
+//! [section](https://www.arangodb.com/docs/stable/http/gharial-management.html)
+//!
+//! For detailed information about ArangoDB named graphs, please check out the official
+//! ArangoDB [documentation](https://www.arangodb.com/docs/stable/http/gharial.html).
+
+use maybe_async::maybe_async;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+use uclient::ClientExt;
+use url::Url;
+
+use crate::{
+    connection::Session, document::Header, error::check_writable, response::deserialize_response,
+    ArangoError, ClientError,
+};
+
+pub mod export;
+
+pub(crate) const GHARIAL_API_PATH: &str = "_api/gharial";
+
+/// Represents a Named Graph in ArangoDB.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, TypedBuilder)]
+#[serde(rename_all = "camelCase")]
+pub struct Graph {
+    /// Name of the graph
+    #[builder(default)]
+    pub name: String,
+    /// An array of definitions for the relations of the graph.
+    #[builder(default)]
+    pub edge_definitions: Vec<EdgeDefinition>,
+    /// An array of additional vertex collections. Documents within these collections do not have edges within this graph.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty", default = "Vec::new")]
+    pub orphan_collections: Vec<String>,
+    /// Define if the created graph should be smart (Enterprise Edition only).
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_smart: Option<bool>,
+    /// Whether to create a Disjoint SmartGraph instead of a regular SmartGraph (Enterprise Edition only).
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_disjoint: Option<bool>,
+    /// a JSON object to define options for creating collections within this graph.
+    #[builder(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub options: Option<GraphOptions>,
+}
+
+/// Represents the available options for a [`Graph`] Creation
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphOptions {
+    /// Only has effect in Enterprise Edition and it is required if isSmart is true.
+    /// The attribute name that is used to smartly shard the vertices of a graph.
+    /// Every vertex in this SmartGraph has to have this attribute. Cannot be modified later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smart_graph_attribute: Option<String>,
+    /// The number of shards that is used for every collection within this graph. Cannot be modified later.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_of_shards: Option<u32>,
+    /// The replication factor used when initially creating collections for this graph.
+    /// Can be set to "satellite" to create a SatelliteGraph, which will ignore numberOfShards,
+    /// minReplicationFactor and writeConcern (Enterprise Edition only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replication_factor: Option<u32>,
+    /// Write concern for new collections in the graph.
+    /// It determines how many copies of each shard are required to be in sync on the different DB-Servers.
+    /// If there are less then these many copies in the cluster a shard will refuse to write.
+    /// Writes to shards with enough up-to-date copies will succeed at the same time however.
+    /// The value of writeConcern can not be larger than replicationFactor. (cluster only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub write_concern: Option<u32>,
+}
+
+/// Represents one Edge definition for a [`Graph`] Creation.
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EdgeDefinition {
+    /// Name of the edge collection
+    pub collection: String,
+    /// List of the `_from` collection names
+    pub from: Vec<String>,
+    /// List of the `_to` collection names
+    pub to: Vec<String>,
+}
+
+/// Represents a collection of [`Graphs`] on a database in ArangoDB.
+///
+/// [`Graphs`]: struct.Graph.html
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphCollection {
+    pub graphs: Vec<Graph>,
+}
+
+/// Represents a [`Graph`] as returned by ArangoDB after a HTTP retrieval
+///
+/// [`Graph`]: struct.Graph.html
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphResponse {
+    pub graph: Graph,
+}
+
+/// Options for writing vertices and edges through a named graph.
+#[derive(Debug, Clone, Serialize, PartialEq, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphWriteOptions {
+    /// Wait until the document has been synced to disk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    wait_for_sync: Option<bool>,
+    /// Return the document as stored in the `new` field of the response.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default, setter(strip_option))]
+    return_new: Option<bool>,
+    /// For bulk operations, stop sending documents once one of them was
+    /// refused by the server. Default: false
+    #[serde(skip)]
+    #[builder(default)]
+    fail_fast: bool,
+}
+
+impl Default for GraphWriteOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+/// Response of the creation of a vertex through a named graph.
+#[derive(Debug, Deserialize)]
+pub struct VertexResponse<T> {
+    pub vertex: Header,
+    /// The stored vertex, if `return_new` was set.
+    pub new: Option<T>,
+}
+
+/// Response of the creation of an edge through a named graph.
+#[derive(Debug, Deserialize)]
+pub struct EdgeResponse<T> {
+    pub edge: Header,
+    /// The stored edge, if `return_new` was set.
+    pub new: Option<T>,
+}
+
+/// A vertex collection of a named graph.
+///
+/// Unlike writes through a [`Collection`](crate::Collection), writes through
+/// the graph are checked against the graph definition by the server.
+#[derive(Debug, Clone)]
+pub struct GraphVertexCollection<C: ClientExt> {
+    url: Url,
+    session: Session<C>,
+    read_only: bool,
+}
+
+impl<C: ClientExt> GraphVertexCollection<C> {
+    pub(crate) fn new(
+        db_url: &Url,
+        graph: &str,
+        collection: &str,
+        session: Session<C>,
+        read_only: bool,
+    ) -> Self {
+        let path = format!("{}/{}/vertex/{}", GHARIAL_API_PATH, graph, collection);
+        GraphVertexCollection {
+            url: db_url.join(&path).unwrap(),
+            session,
+            read_only,
+        }
+    }
+
+    /// Create a vertex
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn create_vertex<T>(
+        &self,
+        vertex: T,
+        options: GraphWriteOptions,
+    ) -> Result<VertexResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        check_writable(self.read_only, "create a vertex")?;
+        create_one(&self.session, &self.url, &vertex, &options).await
+    }
+
+    /// Create vertices with up to `concurrency` requests in flight, as the
+    /// graph API has no batch endpoint.
+    ///
+    /// The results are in the order of `vertices`, with an error for each
+    /// vertex refused by the server, e.g. for violating a unique constraint.
+    /// With the `fail_fast` option, no vertex is sent after the first one
+    /// refused and the results only cover the vertices sent, which are the
+    /// first ones of `vertices`. Any other error, like a transport failure,
+    /// fails the whole call. In blocking mode vertices are sent one after
+    /// another.
+    ///
+    /// # Note
+    /// this function would make a request to arango server per vertex.
+    #[maybe_async]
+    pub async fn create_vertices<T>(
+        &self,
+        vertices: Vec<T>,
+        concurrency: usize,
+        options: GraphWriteOptions,
+    ) -> Result<Vec<Result<VertexResponse<T>, ArangoError>>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        check_writable(self.read_only, "create vertices")?;
+        create_many(&self.session, &self.url, vertices, concurrency, &options).await
+    }
+}
+
+/// An edge collection of a named graph.
+///
+/// Unlike writes through a [`Collection`](crate::Collection), writes through
+/// the graph are checked against the edge definitions by the server.
+#[derive(Debug, Clone)]
+pub struct GraphEdgeCollection<C: ClientExt> {
+    url: Url,
+    session: Session<C>,
+    read_only: bool,
+}
+
+impl<C: ClientExt> GraphEdgeCollection<C> {
+    pub(crate) fn new(
+        db_url: &Url,
+        graph: &str,
+        collection: &str,
+        session: Session<C>,
+        read_only: bool,
+    ) -> Self {
+        let path = format!("{}/{}/edge/{}", GHARIAL_API_PATH, graph, collection);
+        GraphEdgeCollection {
+            url: db_url.join(&path).unwrap(),
+            session,
+            read_only,
+        }
+    }
+
+    /// Create an edge
+    ///
+    /// # Note
+    /// this function would make a request to arango server.
+    #[maybe_async]
+    pub async fn create_edge<T>(
+        &self,
+        edge: T,
+        options: GraphWriteOptions,
+    ) -> Result<EdgeResponse<T>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        check_writable(self.read_only, "create an edge")?;
+        create_one(&self.session, &self.url, &edge, &options).await
+    }
+
+    /// Create edges with up to `concurrency` requests in flight, as the graph
+    /// API has no batch endpoint.
+    ///
+    /// The results are in the order of `edges`, with an error for each edge
+    /// refused by the server, e.g. for not matching an edge definition. With
+    /// the `fail_fast` option, no edge is sent after the first one refused and
+    /// the results only cover the edges sent, which are the first ones of
+    /// `edges`. Any other error, like a transport failure, fails the whole
+    /// call. In blocking mode edges are sent one after another.
+    ///
+    /// # Note
+    /// this function would make a request to arango server per edge.
+    #[maybe_async]
+    pub async fn create_edges<T>(
+        &self,
+        edges: Vec<T>,
+        concurrency: usize,
+        options: GraphWriteOptions,
+    ) -> Result<Vec<Result<EdgeResponse<T>, ArangoError>>, ClientError>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        check_writable(self.read_only, "create edges")?;
+        create_many(&self.session, &self.url, edges, concurrency, &options).await
+    }
+}
+
+#[maybe_async]
+async fn create_one<C, T, R>(
+    session: &Session<C>,
+    url: &Url,
+    doc: &T,
+    options: &GraphWriteOptions,
+) -> Result<R, ClientError>
+where
+    C: ClientExt,
+    T: Serialize,
+    R: DeserializeOwned,
+{
+    let mut url = url.clone();
+    let query = serde_qs::to_string(options).unwrap();
+    url.set_query(Some(query.as_str()));
+    let resp = session.post(url, &serde_json::to_string(doc)?).await?;
+    deserialize_response(&resp)
+}
+
+/// Keep the errors of the server about a single document in the results.
+fn item_result<R>(result: Result<R, ClientError>) -> Result<Result<R, ArangoError>, ClientError> {
+    match result {
+        Ok(response) => Ok(Ok(response)),
+        Err(ClientError::Arango(e)) => Ok(Err(e)),
+        Err(e) => Err(e),
+    }
+}
+
+#[maybe_async::async_impl]
+async fn create_many<C, T, R>(
+    session: &Session<C>,
+    url: &Url,
+    docs: Vec<T>,
+    concurrency: usize,
+    options: &GraphWriteOptions,
+) -> Result<Vec<Result<R, ArangoError>>, ClientError>
+where
+    C: ClientExt,
+    T: Serialize,
+    R: DeserializeOwned,
+{
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use futures_util::{
+        future,
+        stream::{self, StreamExt, TryStreamExt},
+    };
+
+    let failed = &AtomicBool::new(false);
+    // documents are taken from the source in order, and only while no
+    // refusal was seen, so the documents sent are always the first ones
+    stream::iter(docs)
+        .take_while(|_| future::ready(!failed.load(Ordering::SeqCst)))
+        .map(|doc| async move {
+            let result = item_result(create_one(session, url, &doc, options).await)?;
+            if options.fail_fast && result.is_err() {
+                failed.store(true, Ordering::SeqCst);
+            }
+            Ok(result)
+        })
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await
+}
+
+#[maybe_async::sync_impl]
+fn create_many<C, T, R>(
+    session: &Session<C>,
+    url: &Url,
+    docs: Vec<T>,
+    _concurrency: usize,
+    options: &GraphWriteOptions,
+) -> Result<Vec<Result<R, ArangoError>>, ClientError>
+where
+    C: ClientExt,
+    T: Serialize,
+    R: DeserializeOwned,
+{
+    let mut results = Vec::with_capacity(docs.len());
+    for doc in docs {
+        let result = item_result(create_one(session, url, &doc, options))?;
+        let refused = result.is_err();
+        results.push(result);
+        if options.fail_fast && refused {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod test {
+    use http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support::{fixtures, Matcher, MockClient, MockResponse};
+
+    const VERTEX_PATH: &str = "/_db/test_db/_api/gharial/social/vertex/persons";
+
+    fn vertex(key: &str) -> Value {
+        json!({ "_key": key })
+    }
+
+    fn created(kind: &str, collection: &str, key: &str) -> MockResponse {
+        MockResponse::json(
+            202,
+            json!({
+                "error": false,
+                "code": 202,
+                kind: { "_id": format!("{}/{}", collection, key), "_key": key, "_rev": "_r" },
+            }),
+        )
+    }
+
+    fn unique_violation() -> MockResponse {
+        MockResponse::json(
+            409,
+            fixtures::error(409, 1210, "unique constraint violated"),
+        )
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_vertices_in_order() {
+        let mock = MockClient::default();
+        for i in 0..4 {
+            let key = format!("v{}", i);
+            let response = match i {
+                2 => unique_violation(),
+                // the first vertex is answered last
+                0 => created("vertex", "persons", &key).with_yields(5),
+                _ => created("vertex", "persons", &key),
+            };
+            mock.once_match(
+                Method::POST,
+                VERTEX_PATH,
+                Matcher::Body(vertex(&key)),
+                response,
+            );
+        }
+        let persons = mock
+            .database("test_db")
+            .graph_vertex_collection("social", "persons");
+        let vertices = (0..4).map(|i| vertex(&format!("v{}", i))).collect();
+
+        let results = persons
+            .create_vertices::<Value>(vertices, 4, Default::default())
+            .await
+            .unwrap();
+        let keys: Vec<Option<&str>> = results
+            .iter()
+            .map(|r| r.as_ref().ok().map(|v| v.vertex._key.as_str()))
+            .collect();
+        assert_eq!(keys, vec![Some("v0"), Some("v1"), None, Some("v3")]);
+        assert_eq!(results[2].as_ref().err().map(|e| e.error_num()), Some(1210));
+        mock.assert_no_pending();
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_vertices_fail_fast() {
+        let mock = MockClient::default();
+        mock.once_match(
+            Method::POST,
+            VERTEX_PATH,
+            Matcher::Body(vertex("v0")),
+            created("vertex", "persons", "v0").with_yields(5),
+        );
+        mock.once_match(
+            Method::POST,
+            VERTEX_PATH,
+            Matcher::Body(vertex("v1")),
+            unique_violation(),
+        );
+        mock.on(
+            Method::POST,
+            VERTEX_PATH,
+            created("vertex", "persons", "other"),
+        );
+        let persons = mock
+            .database("test_db")
+            .graph_vertex_collection("social", "persons");
+        let vertices = (0..4).map(|i| vertex(&format!("v{}", i))).collect();
+        let options = GraphWriteOptions::builder().fail_fast(true).build();
+
+        let results = persons
+            .create_vertices::<Value>(vertices, 2, options)
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        // no vertex was sent after the refused one
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn create_edges_through_graph() {
+        let mock = MockClient::default();
+        let path = "/_db/test_db/_api/gharial/social/edge/knows";
+        mock.once(Method::POST, path, created("edge", "knows", "e0"));
+        mock.once(
+            Method::POST,
+            path,
+            MockResponse::json(
+                400,
+                fixtures::error(400, 1906, "edge does not conform to any edge definition"),
+            ),
+        );
+        let knows = mock
+            .database("test_db")
+            .graph_edge_collection("social", "knows");
+        let edges = vec![
+            json!({ "_from": "persons/v0", "_to": "persons/v1" }),
+            json!({ "_from": "persons/v0", "_to": "places/p0" }),
+        ];
+        let options = GraphWriteOptions::builder().wait_for_sync(true).build();
+
+        let results = knows
+            .create_edges::<Value>(edges, 1, options)
+            .await
+            .unwrap();
+        assert_eq!(
+            results[0].as_ref().unwrap().edge._id.as_deref(),
+            Some("knows/e0")
+        );
+        assert_eq!(results[1].as_ref().err().map(|e| e.error_num()), Some(1906));
+        let requests = mock.requests();
+        assert_eq!(
+            requests[0].query_param("waitForSync").as_deref(),
+            Some("true")
+        );
+
+        // other errors fail the whole call
+        let edges = vec![json!({ "_from": "persons/v0", "_to": "persons/v1" })];
+        let result = knows
+            .create_edges::<Value>(edges, 1, Default::default())
+            .await;
+        assert!(matches!(result, Err(ClientError::HttpClient(_))));
+    }
+}
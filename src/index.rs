@@ -19,8 +19,6 @@
 use serde::{Deserialize, Serialize};
 use typed_builder::TypedBuilder;
 
-pub(crate) const INDEX_API_PATH: &str = "_api/index";
-
 /// Represents an [`Index`] in ArangoDB. The following types are
 /// supported:
 /// * Fulltext
@@ -107,6 +107,77 @@
 //! Thanks to `maybe_async`, `arangors` can unify sync and async API and toggle
 //! with a feature gate. Arangors adopts async first policy.
 //!
+//! ### Faster JSON Parsing
+//!
+//! Response bodies are parsed with `serde_json`. When large cursor batches or
+//! bulk results make parsing a bottleneck, the `simd-json` feature parses
+//! them with `simd-json` instead, with the same results. Whether it is faster
+//! depends on the CPU and on the documents, so compare both with the
+//! `cursor_batch` benchmark first:
+//!
+//! ```toml
+//! [dependencies]
+//! arangors = { version = "0.4", features = ["simd-json"] }
+//! ```
+//!
+//! ### Client-Assigned Keys
+//!
+//! Imports can give the documents without `_key` a key chosen by the client,
+//! see `KeyStrategy` in `document::key`. The time sortable ULID and UUIDv7
+//! keys need the `ulid` and `uuid` features respectively.
+//!
+//! The keys given to the methods of a collection on a single document are
+//! checked against the key rules of ArangoDB, like `DocumentKey`, and fail
+//! with `ClientError::InvalidKey` without any request otherwise.
+//!
+//! ### Optional Modules
+//!
+//! Beside databases and the management of collections, which are always
+//! available, the APIs are split into features enabled by default:
+//!
+//! - `documents`: reads and writes of documents, with their options and
+//!   responses
+//! - `aql`: AQL queries and cursors, query explanations and the index usage
+//!   report of queries
+//! - `graph`: named graphs, and their vertex and edge collections, with their
+//!   export and import; needs `documents` and `aql`
+//! - `views`: ArangoSearch views and analyzers
+//! - `transactions`: stream transactions, and the audited collections built on
+//!   them along with `documents`
+//! - `admin`: indexes and the write-ahead log
+//!
+//! The helpers of a collection running an AQL query, like `range_scan` or
+//! `upsert_document`, need both `documents` and `aql`. Applications which only
+//! store documents can leave the others out, to compile less code. `sync` and
+//! `async` are short names of the `reqwest_blocking` and `reqwest_async`
+//! clients:
+//!
+//! ```toml
+//! [dependencies]
+//! arangors = { version = "0.4", features = ["rocksdb", "sync", "documents", "transactions"], default-features = false }
+//! ```
+//!
+//! ### Testing
+//!
+//! The `testing` feature, an alias of `test-support`, brings the mock HTTP
+//! client of `test_support`, and `assert_documents_match` reporting how the
+//! documents of a collection differ from the expected ones. It is meant for
+//! the tests of an application only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! arangors = { version = "0.4", features = ["testing"] }
+//! ```
+//!
+//! ### Prelude
+//!
+//! The types a typical application needs, like `Connection`, `Database`,
+//! `Collection`, `AqlQuery` and the document options, can be imported at once:
+//!
+//! ```rust, ignore
+//! use arangors::prelude::*;
+//! ```
+//!
 //! ### Connection
 //!
 //! There is three way to establish connections:
@@ -403,6 +474,8 @@ If what you want is "surf_async", please turn off default features by adding "de
 compile_error!(
     r#"only one of features "reqwest_async", "reqwest_blocking" and "surf_async" can be"#
 );
+#[cfg(feature = "aql")]
+pub use crate::aql::{AqlOptions, AqlQuery, Cursor};
 #[cfg(any(
     feature = "reqwest_async",
     feature = "reqwest_blocking",
@@ -410,25 +483,43 @@ compile_error!(
 ))]
 pub use crate::connection::Connection;
 pub use crate::{
-    aql::{AqlOptions, AqlQuery, Cursor},
     collection::Collection,
     connection::GenericConnection,
-    database::Database,
+    database::{Database, SystemDatabase},
     document::Document,
-    error::{ArangoError, ClientError},
+    error::{ArangoError, ClientError, EndpointFailure, ErrorNum},
+    response::{BulkErrorSummary, ResponseMeta},
 };
 pub use uclient;
 
+#[cfg(feature = "views")]
 pub mod analyzer;
+#[cfg(feature = "aql")]
 pub mod aql;
 pub mod collection;
 pub mod connection;
+#[cfg(feature = "aql")]
+pub mod cursor;
 pub mod database;
 pub mod document;
 pub mod error;
+#[cfg(feature = "aql")]
+pub mod explain;
+#[cfg(feature = "graph")]
 pub mod graph;
+#[cfg(feature = "admin")]
 pub mod index;
+mod options;
+pub mod prelude;
+#[cfg(feature = "aql")]
 mod query;
 mod response;
+mod send_sync;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+#[cfg(feature = "transactions")]
 pub mod transaction;
+#[cfg(feature = "views")]
 pub mod view;
+#[cfg(feature = "admin")]
+pub mod wal;
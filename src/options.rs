@@ -0,0 +1,249 @@
+//! Split of the options of a request between its query string and its
+//! headers.
+//!
+//! Option types are typed-builder structs with `Option` fields. Each of them
+//! declares with [`request_options!`] which fields are sent as query
+//! parameters and which as headers, instead of every operation building its
+//! request by hand. Unset fields are not sent at all.
+use http::request::Builder;
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+/// Header binding a request to a stream transaction.
+pub const TRANSACTION_HEADER: &str = "x-arango-trx-id";
+
+/// Options sent along a request, implemented with [`request_options!`].
+pub(crate) trait RequestOptions {
+    /// Names and values of the query parameters to set, in declaration order.
+    fn query_params(&self) -> Vec<(&'static str, String)>;
+
+    /// Names and values of the headers to set, in declaration order.
+    // only the document options have headers
+    #[cfg_attr(not(feature = "documents"), allow(dead_code))]
+    fn header_values(&self) -> Vec<(&'static str, String)>;
+
+    /// Append the query parameters to those already in `url`.
+    fn apply_query(&self, url: &mut Url) {
+        let params = self.query_params();
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+    }
+
+    /// Add the headers to the request being built.
+    #[cfg_attr(not(feature = "documents"), allow(dead_code))]
+    fn apply_headers(&self, mut build: Builder) -> Builder {
+        for (name, value) in self.header_values() {
+            build = build.header(name, value);
+        }
+        build
+    }
+}
+
+/// Text of an option as sent to the server: strings as they are, other
+/// values as JSON, e.g. `true` or the name of an enum variant.
+pub(crate) fn param_value<T: Serialize>(value: &Option<T>) -> Option<String> {
+    match serde_json::to_value(value.as_ref()?).ok()? {
+        Value::Null => None,
+        Value::String(s) => Some(s),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Implement [`RequestOptions`] for an options struct, e.g.
+///
+/// ```ignore
+/// request_options!(BatchReadOptions {
+///     query: [ignore_revs => "ignoreRevs"],
+///     headers: [transaction_id => TRANSACTION_HEADER],
+/// });
+/// ```
+///
+/// Every field listed must be an `Option` of a serializable type. Entries
+/// take attributes, like `#[cfg(feature = "cluster")]` for fields only
+/// present with a feature.
+macro_rules! request_options {
+    ($name:ident {
+        query: [$($(#[$qattr:meta])* $query:ident => $query_name:expr),* $(,)?],
+        headers: [$($(#[$hattr:meta])* $header:ident => $header_name:expr),* $(,)?] $(,)?
+    }) => {
+        impl $crate::options::RequestOptions for $name {
+            fn query_params(&self) -> Vec<(&'static str, String)> {
+                #[allow(unused_mut)]
+                let mut params = Vec::new();
+                $(
+                    $(#[$qattr])*
+                    if let Some(value) = $crate::options::param_value(&self.$query) {
+                        params.push(($query_name, value));
+                    }
+                )*
+                params
+            }
+
+            fn header_values(&self) -> Vec<(&'static str, String)> {
+                #[allow(unused_mut)]
+                let mut headers = Vec::new();
+                $(
+                    $(#[$hattr])*
+                    if let Some(value) = $crate::options::param_value(&self.$header) {
+                        headers.push(($header_name, value));
+                    }
+                )*
+                headers
+            }
+        }
+    };
+}
+
+pub(crate) use request_options;
+
+#[cfg(all(test, feature = "documents"))]
+mod test {
+    use http::Request;
+
+    use super::*;
+    use crate::collection::options::{
+        DropOptions, ExistenceOptions, FiguresOptions, ImportOptions, ImportType, OnDuplicate,
+    };
+    use crate::document::options::{
+        BatchReadOptions, DocumentReadOptions, InsertOptions, OverwriteMode, RemoveOptions,
+        ReplaceOptions, UpdateOptions,
+    };
+
+    #[test]
+    fn query_and_headers() {
+        let options = BatchReadOptions::builder()
+            .transaction_id("42")
+            .ignore_revs(false)
+            .build();
+        assert_eq!(
+            options.query_params(),
+            vec![("ignoreRevs", "false".to_owned())]
+        );
+        assert_eq!(
+            options.header_values(),
+            vec![("x-arango-trx-id", "42".to_owned())]
+        );
+
+        let mut url = Url::parse("http://localhost:8529/_api/document/users").unwrap();
+        url.set_query(Some("onlyget=true"));
+        options.apply_query(&mut url);
+        assert_eq!(url.query(), Some("onlyget=true&ignoreRevs=false"));
+        let req = options
+            .apply_headers(Request::put(url.as_str()))
+            .body(())
+            .unwrap();
+        assert_eq!(req.headers()["x-arango-trx-id"], "42");
+    }
+
+    #[test]
+    fn unset_options_are_not_sent() {
+        let mut url = Url::parse("http://localhost:8529/_api/document/users/").unwrap();
+        InsertOptions::default().apply_query(&mut url);
+        assert_eq!(url.query(), None);
+        assert!(DocumentReadOptions::default().header_values().is_empty());
+
+        let options = InsertOptions::builder()
+            .return_new(true)
+            .silent(false)
+            .build();
+        assert_eq!(options.return_new(), Some(true));
+        assert_eq!(options.overwrite(), None);
+        assert_eq!(
+            options.query_params(),
+            vec![
+                ("returnNew", "true".to_owned()),
+                ("silent", "false".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn read_options_compatibility() {
+        use crate::document::options::ReadOptions;
+
+        let options: DocumentReadOptions = ReadOptions::IfMatch("_rev1".to_owned()).into();
+        assert_eq!(options.if_match(), Some("_rev1"));
+        assert_eq!(
+            options.header_values(),
+            vec![("If-Match", "_rev1".to_owned())]
+        );
+        let options: DocumentReadOptions = ReadOptions::IfNoneMatch("_rev2".to_owned()).into();
+        assert_eq!(options.if_none_match(), Some("_rev2"));
+        assert_eq!(options.if_match(), None);
+        let options: DocumentReadOptions = ReadOptions::NoHeader.into();
+        assert_eq!(options, DocumentReadOptions::default());
+    }
+
+    #[test]
+    fn write_options_read_back() {
+        let insert = InsertOptions::builder()
+            .overwrite_mode(OverwriteMode::Update)
+            .keep_null(false)
+            .build();
+        let kept = insert.clone();
+        assert_eq!(kept, insert);
+        assert_eq!(kept.overwrite_mode(), Some(OverwriteMode::Update));
+        assert_eq!(kept.keep_null(), Some(false));
+        assert_eq!(kept.merge_objects(), None);
+
+        let update = UpdateOptions::builder()
+            .merge_objects(false)
+            .if_match("_rev1".to_owned())
+            .build();
+        assert_eq!(update.merge_objects(), Some(false));
+        assert_eq!(update.if_match(), Some("_rev1"));
+        assert_eq!(update.clone().ignore_revs(), None);
+
+        let replace = ReplaceOptions::builder()
+            .wait_for_sync(true)
+            .return_old(true)
+            .build();
+        assert_eq!(
+            (replace.wait_for_sync(), replace.return_old()),
+            (Some(true), Some(true))
+        );
+        assert_eq!(replace.return_new(), None);
+
+        let remove = RemoveOptions::builder()
+            .silent(true)
+            .ignore_revs(false)
+            .build();
+        assert_eq!(
+            (remove.silent(), remove.ignore_revs()),
+            (Some(true), Some(false))
+        );
+        assert_eq!(remove.if_match(), None);
+        assert!(format!("{:?}", remove).contains("silent: Some(true)"));
+    }
+
+    #[test]
+    fn collection_options() {
+        let import = ImportOptions::builder()
+            .batch_size(10)
+            .import_type(ImportType::List)
+            .from_prefix("users".to_owned())
+            .on_duplicate(OnDuplicate::Update)
+            .build();
+        assert_eq!(
+            import.query_params(),
+            vec![
+                ("type", "list".to_owned()),
+                ("fromPrefix", "users".to_owned()),
+                ("onDuplicate", "update".to_owned()),
+            ]
+        );
+        let kept = import.clone();
+        assert_eq!((kept.batch_size(), kept.retries()), (10, 0));
+        assert_eq!(kept.from_prefix(), Some("users"));
+        assert_eq!(kept.to_prefix(), None);
+
+        let drop = DropOptions::builder().is_system(true).build();
+        assert_eq!(drop.query_params(), vec![("isSystem", "true".to_owned())]);
+        assert_eq!(drop.clone().is_system(), Some(true));
+        assert!(FiguresOptions::default().query_params().is_empty());
+        assert_eq!(ExistenceOptions::default().batch_size(), 1000);
+    }
+}
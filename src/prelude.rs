@@ -0,0 +1,108 @@
+//! The types most applications need, to be imported at once with
+//!
+//! ```rust, ignore
+//! use arangors::prelude::*;
+//! ```
+//!
+//! Everything here is also available from its own module, e.g.
+//! [`InsertOptions`] from [`document::options`](crate::document::options).
+//! Items are only added to the prelude, never renamed or removed within a
+//! major version.
+#[cfg(feature = "aql")]
+pub use crate::aql::{AqlOptions, AqlQuery, Cursor};
+#[cfg(any(
+    feature = "reqwest_async",
+    feature = "reqwest_blocking",
+    feature = "surf_async"
+))]
+pub use crate::connection::Connection;
+#[cfg(feature = "documents")]
+pub use crate::document::{
+    options::{
+        BatchReadOptions, DocumentReadOptions, InsertOptions, OverwriteMode, RemoveOptions,
+        ReplaceOptions, UpdateOptions, UpsertOptions,
+    },
+    response::{
+        BulkDocumentResult, CasOutcome, DocumentLookup, DocumentResponse, HeaderLookup,
+        InsertOrGet, ModifiedDocument,
+    },
+};
+#[cfg(feature = "transactions")]
+pub use crate::transaction::{Transaction, TransactionCollections, TransactionSettings};
+pub use crate::{
+    collection::{
+        options::{CreateOptions, CreateParameters},
+        Collection, CollectionType,
+    },
+    connection::{GenericConnection, Permission},
+    database::Database,
+    document::{
+        key::DocumentId, Document, EdgeDocument, Header as DocumentHeader,
+        HeaderRef as DocumentHeaderRef,
+    },
+    error::{ArangoError, ClientError},
+    response::ResponseMeta,
+};
+pub use uclient::ClientExt;
+
+#[cfg(test)]
+mod test {
+    /// Fails to compile when an item of the prelude goes missing or changes
+    /// its kind.
+    #[test]
+    fn reexports() {
+        use super::*;
+
+        fn item<T>() {}
+        fn client<C: ClientExt>() {}
+        #[cfg(any(
+            feature = "reqwest_async",
+            feature = "reqwest_blocking",
+            feature = "surf_async"
+        ))]
+        item::<Connection>();
+        item::<GenericConnection<crate::test_support::MockClient>>();
+        item::<Database<crate::test_support::MockClient>>();
+        item::<Collection<crate::test_support::MockClient>>();
+        #[cfg(feature = "transactions")]
+        item::<Transaction<crate::test_support::MockClient>>();
+        client::<crate::test_support::MockClient>();
+        item::<Document<()>>();
+        item::<DocumentHeader>();
+        item::<DocumentHeaderRef<'static>>();
+        item::<DocumentId>();
+        item::<CreateOptions<'static>>();
+        item::<CreateParameters>();
+        item::<CollectionType>();
+        item::<Permission>();
+        item::<ArangoError>();
+        item::<ClientError>();
+        item::<ResponseMeta>();
+        #[cfg(feature = "documents")]
+        {
+            item::<DocumentResponse<()>>();
+            item::<InsertOrGet<()>>();
+            item::<CasOutcome>();
+            item::<HeaderLookup>();
+            item::<DocumentLookup<()>>();
+            item::<InsertOptions>();
+            item::<UpdateOptions>();
+            item::<ReplaceOptions>();
+            item::<RemoveOptions>();
+            item::<DocumentReadOptions>();
+            item::<BatchReadOptions>();
+            item::<OverwriteMode>();
+        }
+        #[cfg(feature = "aql")]
+        {
+            item::<AqlQuery<'static>>();
+            item::<AqlOptions>();
+            item::<Cursor<()>>();
+        }
+        #[cfg(feature = "transactions")]
+        {
+            item::<TransactionCollections>();
+            item::<TransactionSettings>();
+        }
+    }
+}
@@ -1 +1,261 @@
+//! Helpers shared by the handles to build request bodies: filling options
+//! with defaults, serializing deterministically, and computing query bounds
+//! and sample offsets.
+#[cfg(feature = "documents")]
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use serde_json::{Map, Value};
+
+/// Recursively insert the fields of `defaults` which are missing in
+/// `target`.
+///
+/// Fields already present in `target` win, so that options set on a single
+/// request take precedence over the defaults of a handle. Nested objects are
+/// merged field by field, `null` counts as present.
+pub(crate) fn merge_defaults(target: &mut Value, defaults: Value) {
+    if let (Value::Object(target), Value::Object(defaults)) = (target, defaults) {
+        for (key, default) in defaults {
+            match target.get_mut(&key) {
+                Some(value) => merge_defaults(value, default),
+                None => {
+                    target.insert(key, default);
+                }
+            }
+        }
+    }
+}
+
+/// Sort the keys of all objects in `value`, so that logically identical
+/// values serialize to identical bytes whatever the order of insertion.
+pub(crate) fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// 64-bit FNV-1a hash, which unlike the hasher of the standard library is
+/// stable across Rust versions and processes.
+pub(crate) fn fnv1a(bytes: &[u8], mut hash: u64) -> u64 {
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// Offset basis to start a [`fnv1a`] hash with.
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// The smallest string greater than all strings starting with `prefix`, so
+/// that `prefix <= s < bound` holds exactly for the strings `s` starting with
+/// `prefix` when compared by code point.
+///
+/// The code point order is also the byte order of the UTF-8 encoding, but
+/// not the ICU collation of ArangoDB, see
+/// [`Collection::prefix_scan`](crate::Collection::prefix_scan). The last
+/// character which has a successor is incremented and the characters after
+/// it dropped, skipping the surrogate code points which are not valid
+/// characters. There is no such string when `prefix` is empty or only made
+/// of `char::MAX`.
+#[cfg(feature = "documents")]
+pub(crate) fn prefix_upper_bound(prefix: &str) -> Option<String> {
+    let mut chars: Vec<char> = prefix.chars().collect();
+    while let Some(last) = chars.pop() {
+        if let Some(next) = next_char(last) {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
+#[cfg(feature = "documents")]
+fn next_char(c: char) -> Option<char> {
+    match c {
+        char::MAX => None,
+        '\u{D7FF}' => Some('\u{E000}'),
+        c => std::char::from_u32(c as u32 + 1),
+    }
+}
+
+/// Small seedable pseudo random generator (SplitMix64), good enough to pick
+/// sample offsets.
+#[cfg(any(test, feature = "documents"))]
+#[derive(Debug, Clone)]
+pub(crate) struct SplitMix64(u64);
+
+#[cfg(any(test, feature = "documents"))]
+impl SplitMix64 {
+    pub(crate) fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    /// Seeded from the clock.
+    #[cfg(feature = "documents")]
+    pub(crate) fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        SplitMix64(nanos)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in `[0, 1)`.
+    pub(crate) fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// One random offset in each of `n` strata of equal size covering
+/// `0..count`, in increasing order, or all offsets if `count <= n`.
+#[cfg(feature = "documents")]
+pub(crate) fn stratified_offsets(count: u64, n: u64, rng: &mut SplitMix64) -> Vec<u64> {
+    if count <= n {
+        return (0..count).collect();
+    }
+    let bound = |i: u64| (u128::from(i) * u128::from(count) / u128::from(n)) as u64;
+    (0..n)
+        .map(|i| {
+            // widths differ by at most one, and are at least one
+            let start = bound(i);
+            let width = bound(i + 1) - start;
+            start + ((rng.next_f64() * width as f64) as u64).min(width - 1)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{merge_defaults, sort_keys, SplitMix64};
+    #[cfg(feature = "documents")]
+    use super::{prefix_upper_bound, stratified_offsets};
+
+    #[test]
+    fn explicit_values_win() {
+        let mut target = json!({ "a": 1, "nested": { "b": null }, "scalar": "x" });
+        merge_defaults(
+            &mut target,
+            json!({ "a": 2, "c": 3, "nested": { "b": 4, "d": 5 }, "scalar": { "e": 6 } }),
+        );
+        assert_eq!(
+            target,
+            json!({ "a": 1, "c": 3, "nested": { "b": null, "d": 5 }, "scalar": "x" })
+        );
+    }
+
+    #[test]
+    fn sorted_keys() {
+        let value = sort_keys(json!({ "b": [{ "d": 1, "c": 2 }], "a": null }));
+        assert_eq!(value.to_string(), r#"{"a":null,"b":[{"c":2,"d":1}]}"#);
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn prefix_bounds() {
+        let bound = |prefix| prefix_upper_bound(prefix);
+        assert_eq!(bound("abc").as_deref(), Some("abd"));
+        assert_eq!(bound("path/").as_deref(), Some("path0"));
+        assert_eq!(bound("caf\u{e9}").as_deref(), Some("caf\u{ea}"));
+        assert_eq!(bound("日本").as_deref(), Some("日札"));
+        assert_eq!(bound("\u{1F600}").as_deref(), Some("\u{1F601}"));
+        // crossing to a longer UTF-8 encoding
+        assert_eq!(bound("a\u{7F}").as_deref(), Some("a\u{80}"));
+        assert_eq!(bound("a\u{FFFF}").as_deref(), Some("a\u{10000}"));
+        // surrogates are not characters
+        assert_eq!(bound("a\u{D7FF}").as_deref(), Some("a\u{E000}"));
+        // the maximal character has no successor
+        assert_eq!(bound("ab\u{10FFFF}").as_deref(), Some("ac"));
+        assert_eq!(bound("\u{10FFFF}\u{10FFFF}"), None);
+        assert_eq!(bound(""), None);
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn prefix_bound_orders_utf8_bytes() {
+        for prefix in &["a", "é", "日本", "\u{1F600}", "a\u{10FFFF}", "z\u{D7FF}"] {
+            let bound = prefix_upper_bound(prefix).unwrap();
+            assert!(bound.as_str() > *prefix);
+            assert!(bound.as_bytes() > prefix.as_bytes());
+            for suffix in &["", "a", "\u{7F}", "\u{FFFF}", "\u{10FFFF}\u{10FFFF}"] {
+                let s = format!("{}{}", prefix, suffix);
+                assert!(s.as_str() < bound.as_str(), "{:?} < {:?}", s, bound);
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_random() {
+        let mut a = SplitMix64::new(7);
+        let mut b = SplitMix64::new(7);
+        for _ in 0..100 {
+            let x = a.next_f64();
+            assert_eq!(x, b.next_f64());
+            assert!((0.0..1.0).contains(&x));
+        }
+        assert_ne!(SplitMix64::new(8).next_u64(), SplitMix64::new(7).next_u64());
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn stratified_offsets_are_spread() {
+        let (count, n, runs) = (1000, 10, 2000);
+        let mut positions = [0u32; 10];
+        let mut sum = 0.0;
+        for seed in 0..runs {
+            let offsets = stratified_offsets(count, n, &mut SplitMix64::new(seed));
+            assert_eq!(offsets.len(), n as usize);
+            for (i, offset) in offsets.iter().enumerate() {
+                // exactly one offset in each stratum
+                assert_eq!(*offset / 100, i as u64, "{:?}", offsets);
+                let position = offset % 100;
+                positions[(position / 10) as usize] += 1;
+                sum += position as f64;
+            }
+        }
+        // uniform within the strata
+        let samples = (runs * n) as f64;
+        let mean = sum / samples;
+        assert!((mean - 49.5).abs() < 1.5, "mean position {}", mean);
+        for count in positions.iter() {
+            let share = *count as f64 / samples;
+            assert!((share - 0.1).abs() < 0.01, "{:?}", positions);
+        }
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn stratified_offsets_bounds() {
+        let mut rng = SplitMix64::new(1);
+        assert_eq!(stratified_offsets(3, 5, &mut rng), vec![0, 1, 2]);
+        assert_eq!(stratified_offsets(5, 5, &mut rng), vec![0, 1, 2, 3, 4]);
+        assert!(stratified_offsets(0, 5, &mut rng).is_empty());
+        assert!(stratified_offsets(10, 0, &mut rng).is_empty());
+        // strata of uneven width
+        for seed in 0..100 {
+            let offsets = stratified_offsets(7, 3, &mut SplitMix64::new(seed));
+            assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(offsets.iter().all(|offset| *offset < 7));
+        }
+    }
+}
@@ -3,20 +3,23 @@
 //!
 //! For response with `error` and `code` fields indicating the whether the
 //! request is successful, use `deserialize_response` to abstract over request
-//! status and data of concerns.
+//! status and data of concerns, or `deserialize_body` when only the body is at
+//! hand.
 //!
 //! For response storing all information in `result` filed, use
 //! `ArangoResult`.
-use std::ops::Deref;
+#![cfg_attr(not(test), deny(clippy::unwrap_used, clippy::expect_used))]
 
-use log::trace;
+use std::{collections::HashMap, fmt, ops::Deref, time::Duration};
+
+use log::{debug, trace};
 use serde::{
     de::{self, DeserializeOwned, Deserializer},
     Deserialize,
 };
 use serde_json::value::Value;
 
-use crate::{ArangoError, ClientError};
+use crate::{error::ErrorNum, ArangoError, ClientError};
 
 /// Deserialize response from arango server
 ///
@@ -25,14 +28,156 @@ use crate::{ArangoError, ClientError};
 /// response of success and failure.
 ///
 /// When ArangoDB server response error code, then an error would be cast.
-pub(crate) fn deserialize_response<T>(text: &str) -> Result<T, ClientError>
+/// Error responses which are not from ArangoDB, like the HTML page of a proxy
+/// or an empty body, are cast to `ClientError::UpstreamHttp`. A 503 is cast to
+/// `ClientError::ServiceUnavailable` and a write refused in read-only mode to
+/// `ClientError::ServerReadOnly`.
+pub(crate) fn deserialize_response<T>(resp: &http::Response<String>) -> Result<T, ClientError>
 where
     T: DeserializeOwned,
 {
-    let response: Response<T> = serde_json::from_str(text)?;
+    if let Some(err) = unavailable_error(resp).or_else(|| upstream_error(resp)) {
+        return Err(err);
+    }
+    if resp.status().is_success() {
+        // skip the intermediate map of `Response`, which dominates the
+        // parsing of large cursor batches
+        if let Ok(data) = parse_body(resp.body()) {
+            return Ok(data);
+        }
+    }
+    match deserialize_body(resp.body()) {
+        Err(ClientError::Arango(ref e)) if is_server_read_only(e) => {
+            Err(ClientError::ServerReadOnly)
+        }
+        result => result,
+    }
+}
+
+/// Error number of ArangoDB for a write refused in read-only mode.
+const ERROR_ARANGO_READ_ONLY: u16 = 1004;
+/// Error number of ArangoDB for a forbidden operation.
+const ERROR_FORBIDDEN: u16 = 11;
+
+fn is_server_read_only(e: &ArangoError) -> bool {
+    e.error_num() == ERROR_ARANGO_READ_ONLY
+        || (e.code() == 403
+            && e.error_num() == ERROR_FORBIDDEN
+            && e.message().to_lowercase().contains("read-only"))
+}
+
+/// Map a 503 during server startup or maintenance, whatever its body, as it
+/// can be empty.
+fn unavailable_error(resp: &http::Response<String>) -> Option<ClientError> {
+    if resp.status() != http::StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    // only the delay in seconds is supported, not an HTTP date
+    let retry_after = resp
+        .headers()
+        .get(http::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+        .map(Duration::from_secs);
+    Some(ClientError::ServiceUnavailable { retry_after })
+}
+
+/// Deserialize the body of a response from arango server, see
+/// `deserialize_response`.
+pub(crate) fn deserialize_body<T>(text: &str) -> Result<T, ClientError>
+where
+    T: DeserializeOwned,
+{
+    let response: Response<T> = parse_body(text)?;
     Ok(Into::<Result<T, ArangoError>>::into(response)?)
 }
 
+/// Parse a JSON body, with `simd-json` when the feature of the same name is
+/// enabled and `serde_json` otherwise.
+///
+/// All response bodies of potentially large size, like cursor batches and
+/// bulk results, go through this function.
+pub(crate) fn parse_body<T>(text: &str) -> Result<T, ClientError>
+where
+    T: DeserializeOwned,
+{
+    #[cfg(feature = "simd-json")]
+    {
+        // simd-json parses in place
+        let mut bytes = text.as_bytes().to_vec();
+        simd_json::serde::from_slice(&mut bytes)
+            .map_err(|e| ClientError::Serde(de::Error::custom(e)))
+    }
+    #[cfg(not(feature = "simd-json"))]
+    {
+        Ok(serde_json::from_str(text)?)
+    }
+}
+
+/// Maximum length of an identifier received from the server.
+const MAX_ID_LEN: usize = 64;
+
+/// Check an identifier received from the server, like the id of a cursor or
+/// of a transaction, before putting it in the url or the headers of a
+/// request: a malformed id like `../_api/database` or with a line break must
+/// not change the request.
+pub(crate) fn checked_id<'a>(id: &'a str, what: &str) -> Result<&'a str, ClientError> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-');
+    if valid {
+        Ok(id)
+    } else {
+        let snippet: String = id.chars().take(MAX_ID_LEN).collect();
+        Err(ClientError::MalformedResponse(format!(
+            "invalid {} id {:?}",
+            what, snippet
+        )))
+    }
+}
+
+/// Header value of an identifier received from the server, see
+/// [`checked_id`].
+pub(crate) fn id_header(id: &str, what: &str) -> Result<http::HeaderValue, ClientError> {
+    http::HeaderValue::from_str(checked_id(id, what)?)
+        .map_err(|_| ClientError::MalformedResponse(format!("invalid {} id", what)))
+}
+
+/// Maximum length of the body kept in `ClientError::UpstreamHttp`.
+const BODY_SNIPPET_LEN: usize = 256;
+
+/// Detect an error response whose body is not an error of ArangoDB.
+fn upstream_error(resp: &http::Response<String>) -> Option<ClientError> {
+    let status = resp.status();
+    if !status.is_client_error() && !status.is_server_error() {
+        return None;
+    }
+    let is_arango_error = serde_json::from_str::<Value>(resp.body())
+        .ok()
+        .and_then(|body| body.get("error").and_then(Value::as_bool))
+        .unwrap_or(false);
+    if is_arango_error {
+        return None;
+    }
+
+    let content_type = resp
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+    let mut end = resp.body().len().min(BODY_SNIPPET_LEN);
+    while !resp.body().is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(ClientError::UpstreamHttp {
+        status: status.as_u16(),
+        content_type,
+        body_snippet: resp.body()[..end].to_owned(),
+    })
+}
+
 /// An helper enum to divide into successful and failed response
 ///
 /// Request to server can failed at application level, like insufficient
@@ -107,6 +252,214 @@ impl<T> Deref for ArangoResult<T> {
     }
 }
 
+/// Status and headers of a response, returned alongside the typed result by
+/// the `*_with_meta` methods, e.g.
+/// [`Collection::document_with_meta`](crate::Collection::document_with_meta).
+///
+/// Header names are looked up case-insensitively, so headers added by a proxy
+/// in front of the server can be read whatever their spelling.
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+    status: http::StatusCode,
+    headers: http::HeaderMap,
+}
+
+impl ResponseMeta {
+    /// Take the status and headers from `resp`, without copying them.
+    pub(crate) fn new<B>(resp: http::Response<B>) -> Self {
+        let (parts, _) = resp.into_parts();
+        ResponseMeta {
+            status: parts.status,
+            headers: parts.headers,
+        }
+    }
+
+    /// HTTP status code of the response.
+    pub fn status(&self) -> u16 {
+        self.status.as_u16()
+    }
+
+    /// All headers of the response.
+    pub fn headers(&self) -> &http::HeaderMap {
+        &self.headers
+    }
+
+    /// Value of header `name`, or `None` if it is missing or not visible
+    /// ASCII.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    /// `Etag` of the response without its quotes, which is the revision of
+    /// the document for document operations.
+    pub fn etag(&self) -> Option<&str> {
+        self.header("etag").map(|tag| tag.trim_matches('"'))
+    }
+
+    /// Whether the response may come from a follower, when a dirty read was
+    /// allowed, rather than from the leader. Reads from a follower may be
+    /// outdated.
+    pub fn potential_dirty_read(&self) -> bool {
+        self.header("x-arango-potential-dirty-read") == Some("true")
+    }
+
+    /// `x-arango-queue-time-seconds`, the time the last request spent in the
+    /// queue of the server before being processed.
+    pub fn queue_time(&self) -> Option<Duration> {
+        self.header("x-arango-queue-time-seconds")
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .filter(|s| s.is_finite() && *s >= 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// `x-arango-async-id` of a request queued with `x-arango-async: store`,
+    /// to fetch its result from `/_api/job/{id}` later.
+    pub fn async_id(&self) -> Option<&str> {
+        self.header("x-arango-async-id")
+    }
+
+    /// Errors of the single documents of a bulk operation, counted by the
+    /// server in `x-arango-error-codes`.
+    pub fn error_summary(&self) -> BulkErrorSummary {
+        BulkErrorSummary::from_headers(&self.headers)
+    }
+}
+
+/// Number of failed documents of a bulk operation per error number, as sent
+/// by the server in the `x-arango-error-codes` header, e.g.
+/// `{"1202":17,"1210":3}`.
+///
+/// It tells what went wrong without walking through the results of every
+/// document. The summary is empty when all documents succeeded, and as well
+/// when the header is missing or cannot be read, in which case the results
+/// of the documents remain the reference.
+///
+/// It displays as `20 errors: 17 x 1202 (document not found), 3 x 1210
+/// (unique constraint violated)`, with error numbers in ascending order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BulkErrorSummary(HashMap<ErrorNum, u64>);
+
+impl BulkErrorSummary {
+    const HEADER: &'static str = "x-arango-error-codes";
+
+    /// Read the summary from the `x-arango-error-codes` header of a response.
+    ///
+    /// Error numbers unknown to this crate are kept, entries which are not
+    /// a number of documents per error number are skipped.
+    pub(crate) fn from_headers(headers: &http::HeaderMap) -> Self {
+        let value = match headers.get(Self::HEADER).and_then(|v| v.to_str().ok()) {
+            Some(value) if !value.trim().is_empty() => value,
+            _ => return Self::default(),
+        };
+        let codes: HashMap<String, Value> = match serde_json::from_str(value) {
+            Ok(codes) => codes,
+            Err(e) => {
+                debug!(
+                    "ignore malformed {} header {:?}: {}",
+                    Self::HEADER,
+                    value,
+                    e
+                );
+                return Self::default();
+            }
+        };
+        let mut summary = Self::default();
+        for (num, count) in codes {
+            match (num.trim().parse::<ErrorNum>(), count.as_u64()) {
+                (Ok(num), Some(count)) if count > 0 => *summary.0.entry(num).or_default() += count,
+                (Ok(_), Some(_)) => {}
+                _ => debug!(
+                    "ignore entry {:?}: {} of {} header",
+                    num,
+                    count,
+                    Self::HEADER
+                ),
+            }
+        }
+        summary
+    }
+
+    /// Count the documents which failed with each of `errors`.
+    #[cfg(feature = "documents")]
+    pub(crate) fn from_errors<'a>(errors: impl IntoIterator<Item = &'a ArangoError>) -> Self {
+        let mut summary = Self::default();
+        for error in errors {
+            *summary.0.entry(error.error_num()).or_default() += 1;
+        }
+        summary
+    }
+
+    /// Add the counts of `other`, e.g. of the next batch of an import.
+    #[cfg(feature = "documents")]
+    pub(crate) fn merge(&mut self, other: BulkErrorSummary) {
+        for (num, count) in other.0 {
+            *self.0.entry(num).or_default() += count;
+        }
+    }
+
+    /// Number of failed documents, whatever their error.
+    pub fn total_errors(&self) -> u64 {
+        self.0.values().sum()
+    }
+
+    /// Whether no document failed.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether some documents failed, all of them with `error_num`, e.g.
+    /// to ignore the duplicates of an insert with 1210.
+    pub fn has_only(&self, error_num: ErrorNum) -> bool {
+        !self.0.is_empty() && self.0.keys().all(|num| *num == error_num)
+    }
+
+    /// Number of documents which failed with `error_num`.
+    pub fn count(&self, error_num: ErrorNum) -> u64 {
+        self.0.get(&error_num).copied().unwrap_or(0)
+    }
+
+    /// Error numbers and their number of documents, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (ErrorNum, u64)> + '_ {
+        self.0.iter().map(|(num, count)| (*num, *count))
+    }
+}
+
+/// Short description of the errors of single documents the most seen in
+/// bulk operations.
+fn describe(error_num: ErrorNum) -> Option<&'static str> {
+    Some(match error_num {
+        1200 => "conflict",
+        1202 => "document not found",
+        1205 => "illegal document identifier",
+        1210 => "unique constraint violated",
+        1216 => "document too large",
+        1221 => "illegal document key",
+        1227 => "invalid document type",
+        1233 => "edge attribute missing or invalid",
+        _ => return None,
+    })
+}
+
+impl fmt::Display for BulkErrorSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no errors");
+        }
+        let total = self.total_errors();
+        write!(f, "{} error{}:", total, if total == 1 { "" } else { "s" })?;
+        let mut codes: Vec<_> = self.iter().collect();
+        codes.sort_unstable();
+        for (i, (num, count)) in codes.into_iter().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            match describe(num) {
+                Some(desc) => write!(f, "{} {} x {} ({})", sep, count, num, desc)?,
+                None => write!(f, "{} {} x {}", sep, count, num)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -127,6 +480,13 @@ mod test {
                     false,\"globallyUniqueId\":\"hD260BE2A30F9/9947\"}";
         let result = serde_json::from_str::<Response<CollectionResponse>>(text);
         assert_eq!(result.is_ok(), true, "failed: {:?}", result);
+        match result.unwrap() {
+            Response::Ok(coll) => {
+                assert_eq!((coll.id.as_str(), coll.name.as_str()), ("9947", "relation"));
+                assert_eq!((coll.status, coll.r#type, coll.is_system), (2, 3, false));
+            }
+            Response::Err(e) => panic!("unexpected error {}", e),
+        }
 
         let text = "{\"error\":false,\"code\":412,\"id\":\"9947\",\"name\":\"relation\",\"status\"\
                     :2,\"type\":3,\"isSystem\": false,\"globallyUniqueId\":\"hD260BE2A30F9/9947\"}";
@@ -145,4 +505,295 @@ mod test {
             response
         );
     }
+
+    fn http_response(
+        status: u16,
+        content_type: Option<&str>,
+        body: &str,
+    ) -> http::Response<String> {
+        let mut builder = http::Response::builder().status(status);
+        if let Some(content_type) = content_type {
+            builder = builder.header(http::header::CONTENT_TYPE, content_type);
+        }
+        builder.body(body.to_owned()).unwrap()
+    }
+
+    #[test]
+    fn html_bad_gateway() {
+        let page = crate::test_support::fixtures::nginx_error_page(502, "Bad Gateway");
+        let resp = http_response(502, Some("text/html"), &page);
+        match deserialize_response::<CollectionResponse>(&resp) {
+            Err(ClientError::UpstreamHttp {
+                status,
+                content_type,
+                body_snippet,
+            }) => {
+                assert_eq!(status, 502);
+                assert_eq!(content_type.as_deref(), Some("text/html"));
+                assert!(body_snippet.contains("502 Bad Gateway"));
+            }
+            other => panic!("should be an upstream error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn empty_error_bodies() {
+        for status in [401, 504].iter() {
+            let resp = http_response(*status, None, "");
+            match deserialize_response::<CollectionResponse>(&resp) {
+                Err(ClientError::UpstreamHttp {
+                    status: actual,
+                    content_type: None,
+                    body_snippet,
+                }) => {
+                    assert_eq!(actual, *status);
+                    assert!(body_snippet.is_empty());
+                }
+                other => panic!("should be an upstream error: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn long_upstream_body_is_truncated() {
+        let body = "é".repeat(BODY_SNIPPET_LEN);
+        let resp = http_response(502, Some("text/plain"), &body);
+        match deserialize_response::<CollectionResponse>(&resp) {
+            Err(ClientError::UpstreamHttp { body_snippet, .. }) => {
+                assert!(body_snippet.len() <= BODY_SNIPPET_LEN);
+                assert!(body.starts_with(&body_snippet));
+            }
+            other => panic!("should be an upstream error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn arango_error_with_status() {
+        let resp = http_response(
+            404,
+            Some("application/json"),
+            r#"{"error":true,"code":404,"errorMessage":"collection not found","errorNum":1203}"#,
+        );
+        match deserialize_response::<CollectionResponse>(&resp) {
+            Err(ClientError::Arango(e)) => assert_eq!(e.error_num(), 1203),
+            other => panic!("should be an arango error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn service_unavailable() {
+        let body = crate::test_support::fixtures::service_unavailable().to_string();
+        let mut resp = http_response(503, Some("application/json"), &body);
+        resp.headers_mut()
+            .insert(http::header::RETRY_AFTER, "5".parse().unwrap());
+        let err = deserialize_response::<CollectionResponse>(&resp).unwrap_err();
+        match err {
+            ClientError::ServiceUnavailable { retry_after } => {
+                assert_eq!(retry_after, Some(Duration::from_secs(5)))
+            }
+            ref other => panic!("should be unavailable: {:?}", other),
+        }
+        assert!(err.is_retryable());
+
+        // during startup, without body nor delay
+        let resp = http_response(503, None, "");
+        match deserialize_response::<CollectionResponse>(&resp) {
+            Err(ClientError::ServiceUnavailable { retry_after: None }) => {}
+            other => panic!("should be unavailable: {:?}", other),
+        }
+        let mut resp = http_response(503, None, "");
+        resp.headers_mut().insert(
+            http::header::RETRY_AFTER,
+            "Wed, 21 Oct 2026 07:28:00 GMT".parse().unwrap(),
+        );
+        match deserialize_response::<CollectionResponse>(&resp) {
+            Err(ClientError::ServiceUnavailable { retry_after: None }) => {}
+            other => panic!("should be unavailable: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn server_read_only() {
+        for body in &[
+            r#"{"error":true,"code":403,"errorMessage":"forbidden: server is read-only","errorNum":11}"#,
+            r#"{"error":true,"code":403,"errorMessage":"read only","errorNum":1004}"#,
+        ] {
+            let resp = http_response(403, Some("application/json"), body);
+            let err = deserialize_response::<CollectionResponse>(&resp).unwrap_err();
+            assert!(matches!(err, ClientError::ServerReadOnly), "{:?}", err);
+            assert!(!err.is_retryable());
+        }
+
+        // missing permissions are not read-only mode
+        let resp = http_response(
+            403,
+            Some("application/json"),
+            r#"{"error":true,"code":403,"errorMessage":"forbidden","errorNum":11}"#,
+        );
+        match deserialize_response::<CollectionResponse>(&resp) {
+            Err(ClientError::Arango(e)) => assert_eq!(e.error_num(), 11),
+            other => panic!("should be an arango error: {:?}", other),
+        }
+    }
+
+    // the tests below run with both serde_json and simd-json
+
+    #[test]
+    fn unusual_escapes() {
+        #[derive(Debug, Deserialize)]
+        struct Doc {
+            text: String,
+            #[serde(rename = "k\"e\\y")]
+            key: String,
+        }
+        let text = r#"{"error":false,"text":"\"\\\/\b\f\n\r\t\u0000\u00e9\u20AC\ud83d\ude00 é€😀","k\"e\\y":"\u0041J"}"#;
+        let doc: Doc = deserialize_body(text).unwrap();
+        assert_eq!(doc.text, "\"\\/\u{8}\u{c}\n\r\t\0é€😀 é€😀");
+        assert_eq!(doc.key, "AJ");
+
+        let docs: Vec<Value> = parse_body(r#"[{"a":"𝄞"},{"é":"\\u00e9"}]"#).unwrap();
+        assert_eq!(docs[0]["a"], "𝄞");
+        assert_eq!(docs[1]["é"], "\\u00e9");
+    }
+
+    #[test]
+    fn large_numbers() {
+        #[derive(Debug, Deserialize)]
+        struct Doc {
+            above_f64: u64,
+            max: u64,
+            min: i64,
+        }
+        let text = r#"{"error":false,"above_f64":9007199254740993,"max":18446744073709551615,"min":-9223372036854775808}"#;
+        let doc: Doc = deserialize_body(text).unwrap();
+        assert_eq!(doc.above_f64, (1 << 53) + 1);
+        assert_eq!(doc.max, u64::MAX);
+        assert_eq!(doc.min, i64::MIN);
+
+        let docs: Vec<Value> = parse_body("[9007199254740993,18446744073709551615]").unwrap();
+        assert_eq!(docs[0].as_u64(), Some((1 << 53) + 1));
+        assert_eq!(docs[1].as_u64(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn invalid_json() {
+        let result = deserialize_body::<CollectionResponse>(r#"{"error":false"#);
+        assert!(matches!(result, Err(ClientError::Serde(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn adversarial_responses() {
+        let bodies = [
+            "",
+            "{",
+            "x",
+            "\0",
+            "null",
+            "[1,",
+            r#"{"error":"#,
+            r#"{"error":true}"#,
+            r#"{"error":"yes","code":200}"#,
+            "<html><body>It works!</body></html>",
+        ];
+        let content_types: [&[u8]; 4] = [b"application/json", b"text/html", b"", b"\xff\xfe"];
+        for status in [200, 201, 404, 500, 503].iter() {
+            for body in bodies.iter() {
+                for content_type in content_types.iter() {
+                    let mut resp = http_response(*status, None, body);
+                    let headers = resp.headers_mut();
+                    let value = http::HeaderValue::from_bytes(content_type).unwrap();
+                    headers.insert(http::header::CONTENT_TYPE, value);
+                    let value = http::HeaderValue::from_bytes(b"99999999999999999999\xff").unwrap();
+                    headers.insert(http::header::RETRY_AFTER, value.clone());
+                    headers.insert(BulkErrorSummary::HEADER, value);
+                    let result = deserialize_response::<CollectionResponse>(&resp);
+                    assert!(result.is_err(), "{} {:?}", status, body);
+                    assert!(ResponseMeta::new(resp).error_summary().is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn server_ids() {
+        assert_eq!(checked_id("1234", "cursor").unwrap(), "1234");
+        assert_eq!(checked_id("a_B-9", "cursor").unwrap(), "a_B-9");
+        let long = "1".repeat(MAX_ID_LEN + 1);
+        for id in &["", "../x", "1/2", "1?x=2", "1\r\nx", "é", long.as_str()] {
+            let result = checked_id(id, "cursor");
+            assert!(
+                matches!(result, Err(ClientError::MalformedResponse(_))),
+                "{:?}",
+                id
+            );
+            assert!(id_header(id, "transaction").is_err());
+        }
+    }
+
+    fn error_codes(value: Option<&str>) -> BulkErrorSummary {
+        let mut headers = http::HeaderMap::new();
+        if let Some(value) = value {
+            headers.insert("X-Arango-Error-Codes", value.parse().unwrap());
+        }
+        BulkErrorSummary::from_headers(&headers)
+    }
+
+    #[test]
+    fn error_summary() {
+        let summary = error_codes(Some(r#"{"1202":17,"1210":3}"#));
+        assert_eq!(summary.total_errors(), 20);
+        assert_eq!(summary.count(1202), 17);
+        assert_eq!(summary.count(1200), 0);
+        assert!(!summary.has_only(1202));
+        assert_eq!(
+            summary.to_string(),
+            "20 errors: 17 x 1202 (document not found), 3 x 1210 (unique constraint violated)"
+        );
+
+        let summary = error_codes(Some(r#"{"1210":1}"#));
+        assert!(summary.has_only(1210));
+        assert_eq!(
+            summary.to_string(),
+            "1 error: 1 x 1210 (unique constraint violated)"
+        );
+    }
+
+    #[test]
+    fn error_summary_without_errors() {
+        for value in [None, Some(""), Some(" "), Some("{}")].iter() {
+            let summary = error_codes(*value);
+            assert!(summary.is_empty(), "{:?}", value);
+            assert_eq!(summary.total_errors(), 0);
+            assert!(!summary.has_only(1210));
+            assert_eq!(summary.to_string(), "no errors");
+        }
+    }
+
+    #[test]
+    fn error_summary_unknown_codes() {
+        let summary = error_codes(Some(r#"{"4711":2,"1200":1}"#));
+        assert_eq!(summary.count(4711), 2);
+        assert_eq!(
+            summary.to_string(),
+            "3 errors: 1 x 1200 (conflict), 2 x 4711"
+        );
+    }
+
+    #[cfg(feature = "documents")]
+    #[test]
+    fn error_summary_malformed() {
+        assert!(error_codes(Some("1202:17")).is_empty());
+        assert!(error_codes(Some(r#"["1202"]"#)).is_empty());
+
+        // readable entries are kept
+        let summary = error_codes(Some(
+            r#"{"1202":17,"abc":1,"1210":"3","1221":-1,"99999":1}"#,
+        ));
+        assert_eq!(summary.iter().collect::<Vec<_>>(), vec![(1202, 17)]);
+
+        let mut summary = error_codes(Some(r#"{"1202":1}"#));
+        summary.merge(error_codes(Some(r#"{"1202":2,"1210":1}"#)));
+        assert_eq!(summary.count(1202), 3);
+        assert_eq!(summary.total_errors(), 4);
+    }
 }
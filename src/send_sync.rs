@@ -0,0 +1,264 @@
+//! Compile-time checks that the public types can be shared between threads
+//! and tasks, for instance moved into `tokio::spawn` or kept in the state of
+//! a web server.
+//!
+//! Handles generic over the HTTP client are `Send + Sync` as long as the
+//! client is. Types generic over documents are as long as the documents are,
+//! and the borrowed ones like [`HeaderRef`] as long as what they borrow.
+//! A new field breaking one of these bounds, like an `Rc` or a `RefCell`,
+//! fails the build of the crate itself rather than the builds downstream.
+use uclient::ClientExt;
+
+#[cfg(all(feature = "transactions", feature = "documents"))]
+use crate::collection::audit::*;
+#[cfg(feature = "documents")]
+use crate::document::{options::*, response::*};
+#[cfg(feature = "graph")]
+use crate::graph::{export::*, *};
+#[cfg(feature = "transactions")]
+use crate::transaction::{self, *};
+#[cfg(feature = "views")]
+use crate::{analyzer::*, view::*};
+#[cfg(feature = "aql")]
+use crate::{aql::*, cursor::*, explain::*};
+use crate::{
+    collection::{self, options::*, response::*, *},
+    connection::{options::*, role::*, *},
+    database::*,
+    document::{key::*, *},
+    error::*,
+    response::*,
+};
+#[cfg(feature = "admin")]
+use crate::{index::*, wal::*};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[allow(dead_code)]
+fn handles<C: ClientExt + Send + Sync + 'static, T: Send + Sync + 'static>() {
+    assert_send_sync::<GenericConnection<C, Normal>>();
+    assert_send_sync::<GenericConnection<C, Admin>>();
+    assert_send_sync::<Database<C>>();
+    assert_send_sync::<SystemDatabase<C>>();
+    assert_send_sync::<Collection<C>>();
+    #[cfg(feature = "aql")]
+    {
+        assert_send_sync::<QueryCursor<C, T>>();
+        assert_send_sync::<CursorHandle<C>>();
+        assert_send_sync::<Cursor<T>>();
+    }
+    #[cfg(feature = "transactions")]
+    assert_send_sync::<Transaction<C>>();
+    #[cfg(all(feature = "transactions", feature = "documents"))]
+    {
+        assert_send_sync::<AuditedCollection<C>>();
+        assert_send_sync::<AuditRecord<'static, T>>();
+    }
+    #[cfg(feature = "graph")]
+    {
+        assert_send_sync::<GraphVertexCollection<C>>();
+        assert_send_sync::<GraphEdgeCollection<C>>();
+        assert_send_sync::<VertexResponse<T>>();
+        assert_send_sync::<EdgeResponse<T>>();
+    }
+
+    assert_send_sync::<Document<T>>();
+    assert_send_sync::<EdgeDocument<T>>();
+    assert_send_sync::<KeyStrategy<T>>();
+    #[cfg(feature = "documents")]
+    {
+        assert_send_sync::<DocumentResponse<T>>();
+        assert_send_sync::<BulkDocumentResult<T>>();
+        assert_send_sync::<InsertOrGet<T>>();
+        assert_send_sync::<ModifiedDocument<T>>();
+        assert_send_sync::<DocumentLookup<T>>();
+    }
+}
+
+#[allow(dead_code)]
+fn values() {
+    // queries
+    #[cfg(feature = "aql")]
+    {
+        assert_send_sync::<AqlQuery<'static>>();
+        assert_send_sync::<BindVars<'static>>();
+        assert_send_sync::<AqlOptions>();
+        assert_send_sync::<QueryDefaults>();
+        assert_send_sync::<QueryBudget>();
+        assert_send_sync::<QueryRecord>();
+        assert_send_sync::<QueryStats>();
+        assert_send_sync::<QueryExtra>();
+        assert_send_sync::<Profile>();
+        assert_send_sync::<ExecutedPlan>();
+        assert_send_sync::<ExecutedNode>();
+        assert_send_sync::<IndexRef>();
+        assert_send_sync::<FullScan>();
+        assert_send_sync::<QueryIndexReport>();
+        assert_send_sync::<IndexUsageReport>();
+    }
+
+    // connections and databases
+    assert_send_sync::<Auth<'static>>();
+    assert_send_sync::<Credential<'static>>();
+    assert_send_sync::<Permission>();
+    assert_send_sync::<Version>();
+    assert_send_sync::<DatabaseAccess>();
+    assert_send_sync::<ConnectionStats>();
+    assert_send_sync::<OpenResource>();
+    assert_send_sync::<OpenResources>();
+    assert_send_sync::<ShutdownReport>();
+    assert_send_sync::<WarmUpOptions>();
+    assert_send_sync::<WarmUpReport>();
+    assert_send_sync::<WarmUpFailure>();
+    assert_send_sync::<Engine>();
+    assert_send_sync::<DatabaseDetails>();
+    #[cfg(feature = "cluster")]
+    {
+        assert_send_sync::<CreateDatabaseOptions>();
+        assert_send_sync::<ClusterRole>();
+        assert_send_sync::<ClusterStatus>();
+        assert_send_sync::<SyncStatus>();
+        assert_send_sync::<ServerHealth>();
+        assert_send_sync::<ClusterHealth>();
+        assert_send_sync::<ClusterScaleTargets>();
+        assert_send_sync::<ClusterServer>();
+    }
+    #[cfg(feature = "admin")]
+    assert_send_sync::<WalProperties>();
+
+    // collections
+    assert_send_sync::<CollectionType>();
+    assert_send_sync::<CreateParameters>();
+    assert_send_sync::<CreateOptions<'static>>();
+    assert_send_sync::<KeyOptions>();
+    assert_send_sync::<KeyGenerator>();
+    assert_send_sync::<DropOptions>();
+    assert_send_sync::<RangeOptions>();
+    assert_send_sync::<SampleOptions>();
+    assert_send_sync::<ExistenceOptions>();
+    assert_send_sync::<OnDuplicate>();
+    assert_send_sync::<ImportOptions>();
+    assert_send_sync::<ImportType>();
+    assert_send_sync::<Case>();
+    assert_send_sync::<ChecksumOptions>();
+    assert_send_sync::<FiguresOptions>();
+    assert_send_sync::<ComputedValue>();
+    assert_send_sync::<ComputeOn>();
+    assert_send_sync::<PropertiesOptions>();
+    assert_send_sync::<Info>();
+    assert_send_sync::<collection::response::Status>();
+    assert_send_sync::<collection::response::ReplicationFactor>();
+    #[cfg(feature = "cluster")]
+    assert_send_sync::<Shard>();
+    assert_send_sync::<DroppedCollection>();
+    assert_send_sync::<ImportResponse>();
+    assert_send_sync::<ImportProgress>();
+    assert_send_sync::<ChunkedInsertReport>();
+    assert_send_sync::<ExistenceReport>();
+    assert_send_sync::<CollectionHealth>();
+    assert_send_sync::<Properties>();
+    assert_send_sync::<Details>();
+    assert_send_sync::<ArangoIndex>();
+    assert_send_sync::<Figures>();
+    assert_send_sync::<Statistics>();
+    assert_send_sync::<Revision>();
+    assert_send_sync::<Checksum>();
+    assert_send_sync::<SortDirection>();
+
+    // documents
+    assert_send_sync::<DocumentKey>();
+    assert_send_sync::<DocumentId>();
+    assert_send_sync::<Header>();
+    assert_send_sync::<HeaderRef<'static>>();
+    #[cfg(feature = "documents")]
+    {
+        assert_send_sync::<InsertOptions>();
+        assert_send_sync::<UpdateOptions>();
+        assert_send_sync::<UpsertOptions>();
+        assert_send_sync::<OverwriteMode>();
+        assert_send_sync::<ReplaceOptions>();
+        assert_send_sync::<DocumentReadOptions>();
+        #[allow(deprecated)]
+        assert_send_sync::<ReadOptions>();
+        assert_send_sync::<BatchReadOptions>();
+        assert_send_sync::<RemoveOptions>();
+        assert_send_sync::<CasOutcome>();
+        assert_send_sync::<HeaderLookup>();
+    }
+
+    // transactions
+    #[cfg(feature = "transactions")]
+    {
+        assert_send_sync::<TransactionCollections>();
+        assert_send_sync::<TransactionSettings>();
+        assert_send_sync::<transaction::Status>();
+        assert_send_sync::<ArangoTransaction>();
+        assert_send_sync::<TransactionState>();
+        assert_send_sync::<TransactionList>();
+    }
+    #[cfg(all(feature = "transactions", feature = "documents"))]
+    {
+        assert_send_sync::<AuditOp>();
+        assert_send_sync::<AuditMode>();
+    }
+
+    // graphs
+    #[cfg(feature = "graph")]
+    {
+        assert_send_sync::<Graph>();
+        assert_send_sync::<GraphOptions>();
+        assert_send_sync::<EdgeDefinition>();
+        assert_send_sync::<GraphCollection>();
+        assert_send_sync::<GraphResponse>();
+        assert_send_sync::<GraphWriteOptions>();
+        assert_send_sync::<ExportOptions>();
+        assert_send_sync::<ImportGraphOptions>();
+        assert_send_sync::<GraphExportReport>();
+        assert_send_sync::<GraphImportProgress>();
+    }
+
+    // indexes
+    #[cfg(feature = "admin")]
+    {
+        assert_send_sync::<Index>();
+        assert_send_sync::<IndexSettings>();
+        assert_send_sync::<IndexCollection>();
+        assert_send_sync::<DeleteIndexResponse>();
+    }
+
+    // views and analyzers
+    #[cfg(feature = "views")]
+    {
+        assert_send_sync::<ViewType>();
+        assert_send_sync::<StoreValues>();
+        assert_send_sync::<PrimarySortCompression>();
+        assert_send_sync::<ViewDescription>();
+        assert_send_sync::<ArangoSearchViewLink>();
+        assert_send_sync::<ConsolidationPolicy>();
+        assert_send_sync::<PrimarySort>();
+        assert_send_sync::<StoredValues>();
+        assert_send_sync::<ArangoSearchViewProperties>();
+        assert_send_sync::<ArangoSearchViewPropertiesOptions>();
+        assert_send_sync::<ViewOptions>();
+        assert_send_sync::<View>();
+        assert_send_sync::<AnalyzerFeature>();
+        assert_send_sync::<AnalyzerCase>();
+        assert_send_sync::<NgramStreamType>();
+        assert_send_sync::<DelimiterAnalyzerProperties>();
+        assert_send_sync::<StemAnalyzerProperties>();
+        assert_send_sync::<NormAnalyzerProperties>();
+        assert_send_sync::<NgramAnalyzerProperties>();
+        assert_send_sync::<TextAnalyzerProperties>();
+        assert_send_sync::<AnalyzerInfo>();
+        assert_send_sync::<AnalyzerDescription>();
+    }
+
+    // errors and responses
+    assert_send_sync::<ClientError>();
+    assert_send_sync::<EndpointFailure>();
+    assert_send_sync::<ArangoError>();
+    assert_send_sync::<ResponseMeta>();
+    assert_send_sync::<BulkErrorSummary>();
+    #[cfg(any(test, feature = "test-support"))]
+    assert_send_sync::<crate::test_support::MockClient>();
+}
@@ -0,0 +1,577 @@
+//! Comparison of the documents of a collection with the expected ones, see
+//! [`assert_documents_match`].
+//!
+//! The engine, [`diff_documents`], works on plain `serde_json::Value`s, so it
+//! can also compare the results of a query, or documents exported from
+//! elsewhere.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use maybe_async::maybe_async;
+use serde_json::Value;
+use uclient::ClientExt;
+
+use crate::{AqlQuery, ClientError, Collection};
+
+/// How documents are compared, see [`diff_documents`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchOptions<'a> {
+    /// Fields left out of the comparison. A plain name is a top level
+    /// attribute, like `_rev`, a name starting with `/` is a JSON pointer to
+    /// a nested one, like `/meta/updatedAt`.
+    ///
+    /// Defaults to `_rev` and `_id`, which the server assigns.
+    pub ignore_fields: &'a [&'a str],
+    /// Whether the documents must come in the same order.
+    ///
+    /// Collections are read sorted by `_key`, so the expected documents must
+    /// be sorted by `_key` too. Otherwise, and by default, documents are
+    /// paired by `_key` whatever their order.
+    pub ordered: bool,
+    /// Largest difference between two numbers considered equal, 0 by
+    /// default.
+    pub epsilon: f64,
+}
+
+impl Default for MatchOptions<'_> {
+    fn default() -> Self {
+        MatchOptions {
+            ignore_fields: &["_rev", "_id"],
+            ordered: false,
+            epsilon: 0.0,
+        }
+    }
+}
+
+/// A field whose values differ. `None` is a field missing on that side.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /// JSON pointer to the field within the document, like `/tags/0`.
+    pub pointer: String,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+/// The differing fields of a document found on both sides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentDiff {
+    /// Document label, see [`DocumentsDiff`].
+    pub document: String,
+    pub fields: Vec<FieldDiff>,
+}
+
+/// Result of [`diff_documents`].
+///
+/// Documents are labelled by their `_key`, or by their position as in `#3`
+/// when they have none, e.g. when `_key` is ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocumentsDiff {
+    /// Expected documents not found.
+    pub missing: Vec<String>,
+    /// Documents found but not expected.
+    pub unexpected: Vec<String>,
+    /// Documents found on both sides whose fields differ.
+    pub changed: Vec<DocumentDiff>,
+}
+
+impl DocumentsDiff {
+    /// Whether both sides match.
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for DocumentsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.missing.is_empty() {
+            writeln!(f, "missing documents: {}", self.missing.join(", "))?;
+        }
+        if !self.unexpected.is_empty() {
+            writeln!(f, "unexpected documents: {}", self.unexpected.join(", "))?;
+        }
+        for document in &self.changed {
+            writeln!(f, "document {}:", document.document)?;
+            for field in &document.fields {
+                match (&field.expected, &field.actual) {
+                    (Some(expected), Some(actual)) => writeln!(
+                        f,
+                        "  {}: expected {}, found {}",
+                        field.pointer, expected, actual
+                    )?,
+                    (Some(expected), None) => {
+                        writeln!(f, "  {}: missing, expected {}", field.pointer, expected)?
+                    }
+                    (None, Some(actual)) => {
+                        writeln!(f, "  {}: unexpected {}", field.pointer, actual)?
+                    }
+                    (None, None) => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compare the documents of `collection` with `expected`, panicking with the
+/// differences if they do not match.
+///
+/// The collection is read batch by batch, sorted by `_key`, and every batch
+/// normalized as it arrives, dropping the ignored fields.
+///
+/// # Note
+/// this function would make a request to arango server.
+#[maybe_async]
+pub async fn assert_documents_match<C: ClientExt + Send + 'static>(
+    collection: &Collection<C>,
+    expected: Vec<Value>,
+    options: MatchOptions<'_>,
+) {
+    let actual = match read_documents(collection, &options).await {
+        Ok(actual) => actual,
+        Err(e) => panic!(
+            "failed to read the documents of collection `{}`: {}",
+            collection.name(),
+            e
+        ),
+    };
+    let diff = diff_documents(expected, actual, &options);
+    if !diff.is_empty() {
+        panic!(
+            "documents of collection `{}` do not match:\n{}",
+            collection.name(),
+            diff
+        );
+    }
+}
+
+#[maybe_async]
+async fn read_documents<C: ClientExt + Send + 'static>(
+    collection: &Collection<C>,
+    options: &MatchOptions<'_>,
+) -> Result<Vec<Value>, ClientError> {
+    let aql = AqlQuery::builder()
+        .query("FOR d IN @@collection SORT d._key RETURN d")
+        .bind_var("@collection", collection.name())
+        .build();
+    let mut cursor = collection.db().aql_cursor::<Value>(aql)?;
+    let mut documents = Vec::new();
+    while let Some(batch) = cursor.next_batch().await? {
+        documents.extend(batch.into_iter().map(|doc| strip(doc, options)));
+    }
+    Ok(documents)
+}
+
+/// Compare `expected` with `actual`, after dropping the ignored fields of
+/// both.
+pub fn diff_documents(
+    expected: Vec<Value>,
+    actual: Vec<Value>,
+    options: &MatchOptions<'_>,
+) -> DocumentsDiff {
+    let expected = normalize(expected, options);
+    let actual = normalize(actual, options);
+
+    // documents with a key present on one side only
+    let expected_keys: BTreeSet<&str> = expected.iter().filter_map(|d| key(d)).collect();
+    let actual_keys: BTreeSet<&str> = actual.iter().filter_map(|d| key(d)).collect();
+    let mut diff = DocumentsDiff {
+        missing: expected_keys
+            .difference(&actual_keys)
+            .map(|k| k.to_string())
+            .collect(),
+        unexpected: actual_keys
+            .difference(&expected_keys)
+            .map(|k| k.to_string())
+            .collect(),
+        changed: Vec::new(),
+    };
+    let expected: Vec<_> = expected
+        .iter()
+        .filter(|d| !matches!(key(d), Some(k) if !actual_keys.contains(k)))
+        .collect();
+    let actual: Vec<_> = actual
+        .iter()
+        .filter(|d| !matches!(key(d), Some(k) if !expected_keys.contains(k)))
+        .collect();
+
+    let mut pairs = Vec::new();
+    if options.ordered {
+        // documents out of order differ by their `_key`
+        pairs.extend(
+            expected
+                .iter()
+                .zip(actual.iter())
+                .enumerate()
+                .map(|(i, (expected, actual))| (label(expected, i), *expected, *actual)),
+        );
+        diff.missing
+            .extend((actual.len()..expected.len()).map(|i| label(expected[i], i)));
+        diff.unexpected
+            .extend((expected.len()..actual.len()).map(|i| label(actual[i], i)));
+    } else {
+        let by_key: BTreeMap<&str, &Value> = actual
+            .iter()
+            .filter_map(|d| key(d).map(|k| (k, *d)))
+            .collect();
+        for doc in &expected {
+            if let Some(k) = key(doc) {
+                pairs.push((k.to_owned(), *doc, by_key[k]));
+            }
+        }
+        // without a key, documents are paired after their content
+        let (expected, actual) = (keyless(&expected), keyless(&actual));
+        pairs.extend(
+            expected
+                .iter()
+                .zip(actual.iter())
+                .enumerate()
+                .map(|(i, (expected, actual))| (format!("#{}", i), *expected, *actual)),
+        );
+        diff.missing
+            .extend((actual.len()..expected.len()).map(|i| format!("#{}", i)));
+        diff.unexpected
+            .extend((expected.len()..actual.len()).map(|i| format!("#{}", i)));
+    }
+
+    for (document, expected, actual) in pairs {
+        let mut fields = Vec::new();
+        diff_values(
+            expected,
+            actual,
+            &mut String::new(),
+            options.epsilon,
+            &mut fields,
+        );
+        if !fields.is_empty() {
+            diff.changed.push(DocumentDiff { document, fields });
+        }
+    }
+    diff
+}
+
+/// Drop the ignored fields of `docs`, sorting them by `_key` unless their
+/// order matters.
+fn normalize(docs: Vec<Value>, options: &MatchOptions<'_>) -> Vec<Value> {
+    let mut docs: Vec<_> = docs.into_iter().map(|doc| strip(doc, options)).collect();
+    if !options.ordered {
+        docs.sort_by(|a, b| key(a).cmp(&key(b)));
+    }
+    docs
+}
+
+fn strip(mut doc: Value, options: &MatchOptions<'_>) -> Value {
+    for field in options.ignore_fields {
+        let (parent, name) = match field.rfind('/') {
+            Some(i) if field.starts_with('/') => {
+                (doc.pointer_mut(&field[..i]), unescape(&field[i + 1..]))
+            }
+            _ => (Some(&mut doc), field.to_string()),
+        };
+        if let Some(Value::Object(parent)) = parent {
+            parent.remove(&name);
+        }
+    }
+    doc
+}
+
+/// The documents of `docs` without a key, sorted by content.
+fn keyless<'a>(docs: &[&'a Value]) -> Vec<&'a Value> {
+    let mut docs: Vec<(String, &Value)> = docs
+        .iter()
+        .filter(|d| key(d).is_none())
+        .map(|d| (d.to_string(), *d))
+        .collect();
+    docs.sort_by(|a, b| a.0.cmp(&b.0));
+    docs.into_iter().map(|(_, d)| d).collect()
+}
+
+fn key(doc: &Value) -> Option<&str> {
+    doc.get("_key").and_then(Value::as_str)
+}
+
+fn label(doc: &Value, position: usize) -> String {
+    match key(doc) {
+        Some(k) => k.to_owned(),
+        None => format!("#{}", position),
+    }
+}
+
+/// Push the differences between `expected` and `actual`, found at `pointer`,
+/// to `diffs`.
+fn diff_values(
+    expected: &Value,
+    actual: &Value,
+    pointer: &mut String,
+    epsilon: f64,
+    diffs: &mut Vec<FieldDiff>,
+) {
+    let len = pointer.len();
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            let names: BTreeSet<&String> = expected.keys().chain(actual.keys()).collect();
+            for name in names {
+                push_token(pointer, name);
+                match (expected.get(name), actual.get(name)) {
+                    (Some(e), Some(a)) => diff_values(e, a, pointer, epsilon, diffs),
+                    (e, a) => diffs.push(FieldDiff {
+                        pointer: pointer.clone(),
+                        expected: e.cloned(),
+                        actual: a.cloned(),
+                    }),
+                }
+                pointer.truncate(len);
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            for i in 0..expected.len().max(actual.len()) {
+                push_token(pointer, &i.to_string());
+                match (expected.get(i), actual.get(i)) {
+                    (Some(e), Some(a)) => diff_values(e, a, pointer, epsilon, diffs),
+                    (e, a) => diffs.push(FieldDiff {
+                        pointer: pointer.clone(),
+                        expected: e.cloned(),
+                        actual: a.cloned(),
+                    }),
+                }
+                pointer.truncate(len);
+            }
+        }
+        (Value::Number(e), Value::Number(a)) if e == a => {}
+        // integers stay exact, others are compared as floats: the server
+        // returns `1.0` as `1`
+        (Value::Number(e), Value::Number(a)) if !(e.is_f64() || a.is_f64()) => {
+            diffs.push(FieldDiff {
+                pointer: pointer.clone(),
+                expected: Some(expected.clone()),
+                actual: Some(actual.clone()),
+            })
+        }
+        (Value::Number(e), Value::Number(a)) if matches!((e.as_f64(), a.as_f64()), (Some(e), Some(a)) if (e - a).abs() <= epsilon) =>
+            {}
+        _ if expected == actual => {}
+        _ => diffs.push(FieldDiff {
+            pointer: pointer.clone(),
+            expected: Some(expected.clone()),
+            actual: Some(actual.clone()),
+        }),
+    }
+}
+
+/// Append `token` to a JSON pointer, escaping `~` and `/`.
+fn push_token(pointer: &mut String, token: &str) {
+    pointer.push('/');
+    pointer.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::{fixtures, MockClient, MockResponse};
+    use http::Method;
+
+    fn field(pointer: &str, expected: Option<Value>, actual: Option<Value>) -> FieldDiff {
+        FieldDiff {
+            pointer: pointer.to_owned(),
+            expected,
+            actual,
+        }
+    }
+
+    #[test]
+    fn server_fields_are_ignored() {
+        let expected = vec![
+            json!({ "_key": "a", "n": 1 }),
+            json!({ "_key": "b", "n": 2 }),
+        ];
+        let actual = vec![
+            json!({ "_key": "b", "_id": "c/b", "_rev": "2", "n": 2 }),
+            json!({ "_key": "a", "_id": "c/a", "_rev": "1", "n": 1 }),
+        ];
+        let diff = diff_documents(expected.clone(), actual.clone(), &MatchOptions::default());
+        assert!(diff.is_empty(), "{}", diff);
+
+        // the order matters when asked
+        let options = MatchOptions {
+            ordered: true,
+            ..Default::default()
+        };
+        let diff = diff_documents(expected, actual, &options);
+        assert_eq!(diff.changed.len(), 2);
+        assert_eq!(
+            diff.changed[0].fields,
+            vec![
+                field("/_key", Some(json!("a")), Some(json!("b"))),
+                field("/n", Some(json!(1)), Some(json!(2))),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_and_unexpected_documents() {
+        let diff = diff_documents(
+            vec![json!({ "_key": "a" }), json!({ "_key": "b" })],
+            vec![json!({ "_key": "b" }), json!({ "_key": "c" })],
+            &MatchOptions::default(),
+        );
+        assert_eq!(diff.missing, vec!["a"]);
+        assert_eq!(diff.unexpected, vec!["c"]);
+        assert!(diff.changed.is_empty());
+        assert_eq!(
+            diff.to_string(),
+            "missing documents: a\nunexpected documents: c\n"
+        );
+    }
+
+    #[test]
+    fn nested_objects() {
+        let diff = diff_documents(
+            vec![json!({ "_key": "a", "meta": { "owner": { "name": "bob" }, "a/b": 1 } })],
+            vec![json!({ "_key": "a", "meta": { "owner": { "name": "eve", "age": 3 } } })],
+            &MatchOptions::default(),
+        );
+        assert_eq!(
+            diff.changed,
+            vec![DocumentDiff {
+                document: "a".to_owned(),
+                fields: vec![
+                    field("/meta/a~1b", Some(json!(1)), None),
+                    field("/meta/owner/age", None, Some(json!(3))),
+                    field("/meta/owner/name", Some(json!("bob")), Some(json!("eve"))),
+                ],
+            }]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "document a:\n  /meta/a~1b: missing, expected 1\n  /meta/owner/age: unexpected \
+             3\n  /meta/owner/name: expected \"bob\", found \"eve\"\n"
+        );
+
+        // nested fields can be ignored by pointer
+        let options = MatchOptions {
+            ignore_fields: &["/meta/owner", "/meta/a~1b"],
+            ..Default::default()
+        };
+        let diff = diff_documents(
+            vec![json!({ "_key": "a", "meta": { "owner": "bob", "a/b": 1 } })],
+            vec![json!({ "_key": "a", "meta": {} })],
+            &options,
+        );
+        assert!(diff.is_empty(), "{}", diff);
+    }
+
+    #[test]
+    fn arrays() {
+        let diff = diff_documents(
+            vec![json!({ "_key": "a", "tags": ["x", { "y": 1 }, "z"] })],
+            vec![json!({ "_key": "a", "tags": ["x", { "y": 2 }] })],
+            &MatchOptions::default(),
+        );
+        assert_eq!(
+            diff.changed[0].fields,
+            vec![
+                field("/tags/1/y", Some(json!(1)), Some(json!(2))),
+                field("/tags/2", Some(json!("z")), None),
+            ]
+        );
+
+        let diff = diff_documents(
+            vec![json!({ "_key": "a", "tags": ["x"] })],
+            vec![json!({ "_key": "a", "tags": "x" })],
+            &MatchOptions::default(),
+        );
+        assert_eq!(
+            diff.changed[0].fields,
+            vec![field("/tags", Some(json!(["x"])), Some(json!("x")))]
+        );
+    }
+
+    #[test]
+    fn floats() {
+        let expected = vec![json!({ "_key": "a", "x": 0.3, "n": 1.0, "big": 9007199254740993u64 })];
+        let actual =
+            vec![json!({ "_key": "a", "x": 0.1 + 0.2, "n": 1, "big": 9007199254740992u64 })];
+        let diff = diff_documents(expected.clone(), actual.clone(), &MatchOptions::default());
+        assert_eq!(
+            diff.changed[0].fields,
+            vec![
+                field(
+                    "/big",
+                    Some(json!(9007199254740993u64)),
+                    Some(json!(9007199254740992u64))
+                ),
+                field("/x", Some(json!(0.3)), Some(json!(0.1 + 0.2))),
+            ]
+        );
+
+        // integers are never compared as floats
+        let options = MatchOptions {
+            epsilon: 1e-9,
+            ..Default::default()
+        };
+        let diff = diff_documents(expected, actual, &options);
+        assert_eq!(diff.changed[0].fields.len(), 1);
+        assert_eq!(diff.changed[0].fields[0].pointer, "/big");
+    }
+
+    #[test]
+    fn documents_without_key() {
+        let options = MatchOptions {
+            ignore_fields: &["_key", "_id", "_rev"],
+            ..Default::default()
+        };
+        let diff = diff_documents(
+            vec![json!({ "n": 2 }), json!({ "n": 1 })],
+            vec![
+                json!({ "_key": "x", "n": 1 }),
+                json!({ "_key": "y", "n": 2 }),
+            ],
+            &options,
+        );
+        assert!(diff.is_empty(), "{}", diff);
+
+        let diff = diff_documents(
+            vec![json!({ "n": 1 })],
+            vec![json!({ "n": 1 }), json!({ "n": 2 })],
+            &options,
+        );
+        assert_eq!(diff.unexpected, vec!["#1"]);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn collection_is_read_by_batches() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_db/test_db/_api/collection/users",
+            MockResponse::json(200, fixtures::collection_info("users", "100")),
+        );
+        let collection = mock.database("test_db").collection("users").await.unwrap();
+        mock.expect_cursor(
+            "test_db",
+            vec![
+                vec![json!({ "_key": "a", "_id": "users/a", "_rev": "1", "name": "ann" })],
+                vec![json!({ "_key": "b", "_id": "users/b", "_rev": "2", "name": "bob" })],
+            ],
+        );
+        assert_documents_match(
+            &collection,
+            vec![
+                json!({ "_key": "b", "name": "bob" }),
+                json!({ "_key": "a", "name": "ann" }),
+            ],
+            MatchOptions::default(),
+        )
+        .await;
+        let query = mock.requests()[1].json_body().unwrap();
+        assert_eq!(query["bindVars"]["@collection"], "users");
+        mock.assert_no_pending();
+    }
+}
@@ -0,0 +1,188 @@
+//! Builders of realistic response bodies of the ArangoDB HTTP API.
+//!
+//! All builders return `serde_json::Value`, to be wrapped in a
+//! [`MockResponse`](super::MockResponse).
+use serde_json::{json, Map, Value};
+
+/// Error body as sent by ArangoDB, e.g. `error(404, 1202, "document not
+/// found")`.
+pub fn error(code: u16, error_num: u16, message: &str) -> Value {
+    json!({
+        "error": true,
+        "code": code,
+        "errorNum": error_num,
+        "errorMessage": message,
+    })
+}
+
+/// Error body sent by ArangoDB while starting up or under maintenance, with
+/// status 503. During startup the body may also be empty, see
+/// [`MockResponse::unavailable`](super::MockResponse::unavailable).
+pub fn service_unavailable() -> Value {
+    error(503, 503, "service unavailable")
+}
+
+/// Error page as served by nginx in front of the server, e.g.
+/// `nginx_error_page(502, "Bad Gateway")`, to be sent as `text/html`.
+pub fn nginx_error_page(status: u16, reason: &str) -> String {
+    format!(
+        "<html>\r\n<head><title>{status} {reason}</title></head>\r\n<body>\r\n\
+         <center><h1>{status} {reason}</h1></center>\r\n<hr><center>nginx/1.18.0</center>\r\n\
+         </body>\r\n</html>\r\n",
+        status = status,
+        reason = reason
+    )
+}
+
+/// Version of a community server as returned by `GET /_api/version`, e.g.
+/// `version("3.11.4")`.
+pub fn version(version: &str) -> Value {
+    json!({ "server": "arango", "version": version, "license": "community" })
+}
+
+/// Collection info as returned by `GET /_api/collection/{name}` for a
+/// document collection.
+pub fn collection_info(name: &str, id: &str) -> Value {
+    json!({
+        "error": false,
+        "code": 200,
+        "id": id,
+        "name": name,
+        "globallyUniqueId": format!("h0/{}", id),
+        "isSystem": name.starts_with('_'),
+        "status": 3,
+        "type": 2,
+    })
+}
+
+/// Collection properties as returned by
+/// `GET /_api/collection/{name}/properties` for a document collection, with
+/// the default key generator and no synchronous writes.
+pub fn collection_properties(name: &str, id: &str) -> Value {
+    let mut body = collection_info(name, id);
+    body["statusString"] = json!("loaded");
+    body["keyOptions"] = json!({ "type": "traditional", "allowUserKeys": true });
+    body["waitForSync"] = json!(false);
+    body["writeConcern"] = json!(1);
+    body
+}
+
+/// Responses of a cursor returning `batches` one after another.
+///
+/// All but the last batch have `hasMore` set and carry the cursor `id`.
+pub fn cursor_batches(id: &str, batches: Vec<Vec<Value>>) -> Vec<Value> {
+    let total: usize = batches.iter().map(Vec::len).sum();
+    let len = batches.len();
+    batches
+        .into_iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let more = i + 1 < len;
+            let mut batch = json!({
+                "error": false,
+                "code": 201,
+                "result": result,
+                "hasMore": more,
+                "cached": false,
+                "count": total,
+                "extra": {
+                    "stats": {
+                        "writesExecuted": 0,
+                        "writesIgnored": 0,
+                        "scannedFull": 0,
+                        "scannedIndex": 0,
+                        "filtered": 0,
+                        "httpRequests": 0,
+                        "executionTime": 0.0,
+                    },
+                    "warnings": [],
+                },
+            });
+            if more {
+                batch["id"] = Value::String(id.to_owned());
+            }
+            batch
+        })
+        .collect()
+}
+
+/// Builder of the body answering a document CRUD operation.
+#[derive(Debug, Clone)]
+pub struct DocumentFixture {
+    collection: String,
+    key: String,
+    rev: String,
+    old_rev: Option<String>,
+    old: Option<Value>,
+    new: Option<Value>,
+}
+
+impl DocumentFixture {
+    /// Response about document `key` in `collection`.
+    pub fn new(collection: &str, key: &str) -> Self {
+        DocumentFixture {
+            collection: collection.to_owned(),
+            key: key.to_owned(),
+            rev: "_mock_rev".to_owned(),
+            old_rev: None,
+            old: None,
+            new: None,
+        }
+    }
+
+    /// Set the revision of the document.
+    pub fn rev(mut self, rev: &str) -> Self {
+        self.rev = rev.to_owned();
+        self
+    }
+
+    /// Set the revision before the operation.
+    pub fn old_rev(mut self, rev: &str) -> Self {
+        self.old_rev = Some(rev.to_owned());
+        self
+    }
+
+    /// Set the document returned as `old`.
+    pub fn old_doc(mut self, doc: Value) -> Self {
+        self.old = Some(doc);
+        self
+    }
+
+    /// Set the document returned as `new`.
+    pub fn new_doc(mut self, doc: Value) -> Self {
+        self.new = Some(doc);
+        self
+    }
+
+    pub fn build(self) -> Value {
+        let mut obj = Map::new();
+        obj.insert(
+            "_id".to_owned(),
+            format!("{}/{}", self.collection, self.key).into(),
+        );
+        obj.insert("_key".to_owned(), self.key.into());
+        obj.insert("_rev".to_owned(), self.rev.into());
+        if let Some(old_rev) = self.old_rev {
+            obj.insert("_oldRev".to_owned(), old_rev.into());
+        }
+        if let Some(old) = self.old {
+            obj.insert("old".to_owned(), old);
+        }
+        if let Some(new) = self.new {
+            obj.insert("new".to_owned(), new);
+        }
+        Value::Object(obj)
+    }
+}
+
+/// A stored document, as returned by `GET /_api/document/{id}`.
+pub fn document(collection: &str, key: &str, rev: &str, data: Value) -> Value {
+    let mut doc = match data {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    };
+    doc.insert("_id".to_owned(), format!("{}/{}", collection, key).into());
+    doc.insert("_key".to_owned(), key.into());
+    doc.insert("_rev".to_owned(), rev.into());
+    Value::Object(doc)
+}
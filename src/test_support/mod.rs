@@ -0,0 +1,665 @@
+//! Utilities to test code built on top of arangors without a running ArangoDB.
+//!
+//! This mod is only available with the `test-support` feature, or its alias
+//! `testing`.
+//!
+//! The central piece is [`MockClient`], an implementation of
+//! `uclient::ClientExt` that answers requests with canned responses
+//! registered beforehand, and records every request it receives so that tests
+//! can assert what was sent to the server, and in which order.
+//!
+//! Canned responses mimicking the ArangoDB HTTP API can be built with the
+//! helpers in [`fixtures`].
+//!
+//! # Example
+//! ```rust, ignore
+//! use arangors::test_support::{fixtures, MockClient, MockResponse};
+//! use http::Method;
+//! use serde_json::json;
+//!
+//! let mock = MockClient::default();
+//! mock.expect_cursor("test_db", vec![vec![json!(1), json!(2)], vec![json!(3)]]);
+//!
+//! let db = mock.database("test_db");
+//! let result: Vec<u32> = db.aql_str("FOR i IN 1..3 RETURN i").await.unwrap();
+//! assert_eq!(result, vec![1, 2, 3]);
+//!
+//! mock.assert_requests(&[
+//!     (Method::POST, "/_db/test_db/_api/cursor"),
+//!     (Method::PUT, "/_db/test_db/_api/cursor/mock_cursor"),
+//! ]);
+//! ```
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use http::{header::HeaderName, HeaderMap, HeaderValue, Method, Request, Response, StatusCode};
+use serde_json::Value;
+use uclient::{ClientError, ClientExt};
+use url::Url;
+
+#[cfg(feature = "blocking")]
+use std::thread::sleep;
+
+#[cfg(not(feature = "blocking"))]
+use crate::connection::endpoints::sleep;
+use crate::{
+    connection::{GenericConnection, Session},
+    Database,
+};
+
+#[cfg(feature = "aql")]
+pub mod diff;
+pub mod fixtures;
+
+#[cfg(feature = "aql")]
+pub use self::diff::{assert_documents_match, MatchOptions};
+
+/// Base url used by connections created from a [`MockClient`].
+pub const MOCK_URL: &str = "http://localhost:8529/";
+
+/// Cursor id used by [`MockClient::expect_cursor`].
+pub const MOCK_CURSOR_ID: &str = "mock_cursor";
+
+/// Decide whether a registered response applies to a request, in addition to
+/// its method and path.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    /// Match any request.
+    Any,
+    /// The query string contains the given text, e.g. `returnNew=true`.
+    QueryContains(String),
+    /// The body is JSON equal to the given value.
+    Body(Value),
+    /// The raw body contains the given text.
+    BodyContains(String),
+    /// The request carries a header with the exact value.
+    Header(String, String),
+    /// All of the matchers match.
+    All(Vec<Matcher>),
+}
+
+impl Matcher {
+    fn matches(&self, request: &RecordedRequest) -> bool {
+        match self {
+            Matcher::Any => true,
+            Matcher::QueryContains(text) => {
+                matches!(request.query, Some(ref q) if q.contains(text.as_str()))
+            }
+            Matcher::Body(value) => request.json_body().as_ref() == Some(value),
+            Matcher::BodyContains(text) => request.body.contains(text.as_str()),
+            Matcher::Header(name, value) => request.header(name) == Some(value.as_str()),
+            Matcher::All(matchers) => matchers.iter().all(|m| m.matches(request)),
+        }
+    }
+}
+
+/// A canned HTTP response.
+#[derive(Debug, Clone)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    yields: usize,
+    delay: Option<Duration>,
+    transport_error: Option<String>,
+}
+
+impl MockResponse {
+    /// Response with the given status code and an empty body.
+    pub fn new(status: u16) -> Self {
+        MockResponse {
+            status,
+            headers: Vec::new(),
+            body: String::new(),
+            yields: 0,
+            delay: None,
+            transport_error: None,
+        }
+    }
+
+    /// Response with a JSON body, as ArangoDB would answer.
+    pub fn json(status: u16, body: Value) -> Self {
+        Self::new(status)
+            .with_header("content-type", "application/json; charset=utf-8")
+            .with_body(body.to_string())
+    }
+
+    /// Response with a plain text body, e.g. from a proxy in front of the
+    /// server.
+    pub fn text<T: Into<String>>(status: u16, body: T) -> Self {
+        Self::new(status)
+            .with_header("content-type", "text/plain")
+            .with_body(body)
+    }
+
+    /// Response 503 with an empty body, as sent while the server starts up,
+    /// with a `Retry-After` header if `retry_after` seconds are given.
+    pub fn unavailable(retry_after: Option<u64>) -> Self {
+        let response = Self::new(503);
+        match retry_after {
+            Some(seconds) => response.with_header("retry-after", seconds.to_string()),
+            None => response,
+        }
+    }
+
+    /// No response at all: the request fails with a HTTP client error with
+    /// `message`, like when the server cannot be reached.
+    pub fn transport_error<T: Into<String>>(message: T) -> Self {
+        let mut response = Self::new(0);
+        response.transport_error = Some(message.into());
+        response
+    }
+
+    /// Add a response header.
+    pub fn with_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Replace the response body.
+    pub fn with_body<T: Into<String>>(mut self, body: T) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Let other tasks make progress before answering, by yielding `yields`
+    /// times to the executor, so that responses of concurrent requests can
+    /// complete out of order. No effect in blocking mode.
+    pub fn with_yields(mut self, yields: usize) -> Self {
+        self.yields = yields;
+        self
+    }
+
+    /// Answer only after `delay`, like a slow or overloaded server. The
+    /// executor can run other tasks meanwhile.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    fn to_response(&self) -> Result<Response<String>, ClientError> {
+        if let Some(message) = &self.transport_error {
+            return Err(ClientError::HttpClient(message.clone()));
+        }
+        let status = StatusCode::from_u16(self.status)
+            .map_err(|e| ClientError::HttpClient(format!("{:?}", e)))?;
+        let mut build = Response::builder().status(status);
+        for (name, value) in self.headers.iter() {
+            build = build.header(name.as_str(), value.as_str());
+        }
+        build
+            .body(self.body.clone())
+            .map_err(|e| ClientError::HttpClient(format!("{:?}", e)))
+    }
+}
+
+/// A request received by a [`MockClient`].
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: Method,
+    /// Path of the request url, e.g. `/_db/test_db/_api/cursor`.
+    pub path: String,
+    /// Query string of the request url, if any.
+    pub query: Option<String>,
+    /// Request headers, including the ones set on the session.
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Parse the body as JSON.
+    pub fn json_body(&self) -> Option<Value> {
+        serde_json::from_str(&self.body).ok()
+    }
+
+    /// Get the value of a header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+
+    /// Get the value of a query parameter.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        let query = self.query.as_ref()?;
+        url::form_urlencoded::parse(query.as_bytes())
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.into_owned())
+    }
+}
+
+#[derive(Debug)]
+struct Route {
+    method: Method,
+    path: String,
+    matcher: Matcher,
+    response: MockResponse,
+    once: bool,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    routes: Vec<Route>,
+    requests: Vec<RecordedRequest>,
+}
+
+/// A HTTP client answering requests with canned responses.
+///
+/// Responses are registered with [`on`](MockClient::on),
+/// [`once`](MockClient::once) and their `_match` variants. Incoming requests
+/// are answered by the first registered response whose method, path and
+/// matcher fit. A response registered with `once` is discarded after use, so
+/// several of them can be registered for the same path to answer successive
+/// requests differently.
+///
+/// Clones share the registered responses and recorded requests, so the mock
+/// can be inspected after handing a clone to a connection.
+///
+/// A request without registered response fails with a HTTP client error.
+#[derive(Debug, Clone, Default)]
+pub struct MockClient {
+    headers: HeaderMap,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockClient {
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        // a panicking test should not break assertions of another one
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn register(
+        &self,
+        method: Method,
+        path: &str,
+        matcher: Matcher,
+        response: MockResponse,
+        once: bool,
+    ) {
+        self.state().routes.push(Route {
+            method,
+            path: path.to_owned(),
+            matcher,
+            response,
+            once,
+        });
+    }
+
+    /// Answer every request to `path` with `response`.
+    pub fn on(&self, method: Method, path: &str, response: MockResponse) -> &Self {
+        self.register(method, path, Matcher::Any, response, false);
+        self
+    }
+
+    /// Answer every request to `path` fitting `matcher` with `response`.
+    pub fn on_match(
+        &self,
+        method: Method,
+        path: &str,
+        matcher: Matcher,
+        response: MockResponse,
+    ) -> &Self {
+        self.register(method, path, matcher, response, false);
+        self
+    }
+
+    /// Answer the next request to `path` with `response`.
+    pub fn once(&self, method: Method, path: &str, response: MockResponse) -> &Self {
+        self.register(method, path, Matcher::Any, response, true);
+        self
+    }
+
+    /// Answer the next request to `path` fitting `matcher` with `response`.
+    pub fn once_match(
+        &self,
+        method: Method,
+        path: &str,
+        matcher: Matcher,
+        response: MockResponse,
+    ) -> &Self {
+        self.register(method, path, matcher, response, true);
+        self
+    }
+
+    /// Register the responses of an AQL query returning `batches` one after
+    /// another.
+    ///
+    /// The first batch answers the cursor creation in database `db`, the
+    /// following ones answer the cursor continuation with id
+    /// [`MOCK_CURSOR_ID`].
+    pub fn expect_cursor(&self, db: &str, batches: Vec<Vec<Value>>) -> &Self {
+        let create = format!("/_db/{}/_api/cursor", db);
+        let next = format!("{}/{}", create, MOCK_CURSOR_ID);
+        for (i, batch) in fixtures::cursor_batches(MOCK_CURSOR_ID, batches)
+            .into_iter()
+            .enumerate()
+        {
+            let (method, path) = if i == 0 {
+                (Method::POST, create.as_str())
+            } else {
+                (Method::PUT, next.as_str())
+            };
+            self.once(method, path, MockResponse::json(201, batch));
+        }
+        self
+    }
+
+    /// Refuse with a 403 the lookups the driver may make on its own, as for
+    /// an application user with access to a few collections only on a
+    /// server started with `--server.harden`: the version of the server, the
+    /// parsing of queries in database `db`, and the properties of
+    /// `collections`.
+    ///
+    /// A driver call needing no hidden privilege succeeds with the responses
+    /// registered for the call itself, whatever the refusals it meets.
+    pub fn forbid_introspection(&self, db: &str, collections: &[&str]) -> &Self {
+        let forbidden = || MockResponse::json(403, fixtures::error(403, 11, "forbidden"));
+        self.on(Method::GET, "/_api/version", forbidden());
+        self.on(
+            Method::POST,
+            &format!("/_db/{}/_api/query", db),
+            forbidden(),
+        );
+        for collection in collections {
+            let path = format!("/_db/{}/_api/collection/{}/properties", db, collection);
+            self.on(Method::GET, &path, forbidden());
+        }
+        self
+    }
+
+    /// All requests received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state().requests.clone()
+    }
+
+    /// Forget the requests received so far.
+    pub fn clear_requests(&self) {
+        self.state().requests.clear();
+    }
+
+    /// Assert that exactly the given requests were received, in order.
+    pub fn assert_requests(&self, expected: &[(Method, &str)]) {
+        let actual: Vec<(Method, String)> = self
+            .requests()
+            .into_iter()
+            .map(|r| (r.method, r.path))
+            .collect();
+        let expected: Vec<(Method, String)> = expected
+            .iter()
+            .map(|(m, p)| (m.clone(), p.to_string()))
+            .collect();
+        assert_eq!(actual, expected, "unexpected requests sent to mock server");
+    }
+
+    /// Assert that every response registered with `once` has been used.
+    pub fn assert_no_pending(&self) {
+        let pending: Vec<String> = self
+            .state()
+            .routes
+            .iter()
+            .filter(|r| r.once)
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert!(pending.is_empty(), "unused mock responses: {:?}", pending);
+    }
+
+    /// A connection using this client, authenticated as root.
+    ///
+    /// No request is made.
+    pub fn connection(&self) -> GenericConnection<MockClient> {
+        GenericConnection::new(
+            Url::parse(MOCK_URL).unwrap(),
+            "root".to_owned(),
+            Arc::new(self.clone()),
+        )
+    }
+
+    /// A database handle using this client.
+    ///
+    /// No request is made.
+    pub fn database(&self, name: &str) -> Database<MockClient> {
+        Database::new(
+            name,
+            &Url::parse(MOCK_URL).unwrap(),
+            Session::for_user(Arc::new(self.clone()), "root"),
+        )
+    }
+}
+
+#[maybe_async::maybe_async]
+impl ClientExt for MockClient {
+    fn new<U: Into<Option<HeaderMap>>>(headers: U) -> Result<Self, ClientError> {
+        Ok(MockClient {
+            headers: headers.into().unwrap_or_default(),
+            state: Default::default(),
+        })
+    }
+
+    fn headers(&mut self) -> &mut HeaderMap<HeaderValue> {
+        &mut self.headers
+    }
+
+    async fn request(&self, request: Request<String>) -> Result<Response<String>, ClientError> {
+        let (parts, body) = request.into_parts();
+        let mut headers = parts.headers;
+        for (name, value) in self.headers.iter() {
+            if !headers.contains_key(name) {
+                headers.insert(HeaderName::clone(name), value.clone());
+            }
+        }
+        let recorded = RecordedRequest {
+            method: parts.method,
+            path: parts.uri.path().to_owned(),
+            query: parts.uri.query().map(str::to_owned),
+            headers,
+            body,
+        };
+
+        let response = {
+            let mut state = self.state();
+            let position = state.routes.iter().position(|route| {
+                route.method == recorded.method
+                    && route.path == recorded.path
+                    && route.matcher.matches(&recorded)
+            });
+            let description = format!("{} {}", recorded.method, recorded.path);
+            state.requests.push(recorded);
+
+            match position {
+                Some(i) if state.routes[i].once => state.routes.remove(i).response,
+                Some(i) => state.routes[i].response.clone(),
+                None => {
+                    return Err(ClientError::HttpClient(format!(
+                        "no mock response registered for {}",
+                        description
+                    )))
+                }
+            }
+        };
+        yield_times(response.yields).await;
+        if let Some(delay) = response.delay {
+            sleep(delay).await;
+        }
+        response.to_response()
+    }
+}
+
+/// Yield `n` times to the executor.
+#[cfg(not(feature = "blocking"))]
+fn yield_times(n: usize) -> YieldNow {
+    YieldNow(n)
+}
+
+/// Nothing to yield to in blocking mode.
+#[cfg(feature = "blocking")]
+fn yield_times(_n: usize) {}
+
+/// Future ready after being polled the given number of times.
+#[cfg(not(feature = "blocking"))]
+struct YieldNow(usize);
+
+#[cfg(not(feature = "blocking"))]
+impl std::future::Future for YieldNow {
+    type Output = ();
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<()> {
+        if self.0 == 0 {
+            return std::task::Poll::Ready(());
+        }
+        self.0 -= 1;
+        cx.waker().wake_by_ref();
+        std::task::Poll::Pending
+    }
+}
+
+/// Index the recorded requests by path, handy to assert on requests whose
+/// order does not matter.
+pub fn requests_by_path(requests: &[RecordedRequest]) -> HashMap<String, Vec<&RecordedRequest>> {
+    let mut map: HashMap<String, Vec<&RecordedRequest>> = HashMap::new();
+    for request in requests {
+        map.entry(request.path.clone()).or_default().push(request);
+    }
+    map
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    #[cfg(feature = "documents")]
+    use serde::{Deserialize, Serialize};
+    #[cfg(any(feature = "documents", feature = "aql"))]
+    use serde_json::json;
+
+    use super::*;
+    use crate::ClientError as ArangoClientError;
+
+    #[cfg(feature = "documents")]
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct User {
+        username: String,
+    }
+
+    #[cfg(feature = "aql")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn mock_cursor_batches() {
+        let mock = MockClient::default();
+        mock.expect_cursor(
+            "test_db",
+            vec![vec![json!(1), json!(2)], vec![json!(3)], vec![json!(4)]],
+        );
+        let db = mock.database("test_db");
+
+        let result: Vec<u32> = db.aql_str("FOR i IN 1..4 RETURN i").await.unwrap();
+        assert_eq!(result, vec![1, 2, 3, 4]);
+
+        mock.assert_requests(&[
+            (Method::POST, "/_db/test_db/_api/cursor"),
+            (Method::PUT, "/_db/test_db/_api/cursor/mock_cursor"),
+            (Method::PUT, "/_db/test_db/_api/cursor/mock_cursor"),
+        ]);
+        mock.assert_no_pending();
+        let first = &mock.requests()[0];
+        assert_eq!(
+            first.json_body(),
+            Some(json!({ "query": "FOR i IN 1..4 RETURN i" }))
+        );
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn mock_document_crud() {
+        let mock = MockClient::default();
+        mock.on(
+            Method::GET,
+            "/_db/test_db/_api/collection/users",
+            MockResponse::json(200, fixtures::collection_info("users", "100")),
+        );
+        mock.on_match(
+            Method::POST,
+            "/_db/test_db/_api/document/users/",
+            Matcher::QueryContains("returnNew=true".to_owned()),
+            MockResponse::json(
+                202,
+                fixtures::DocumentFixture::new("users", "1")
+                    .rev("_rev1")
+                    .new_doc(json!({ "_key": "1", "username": "alice" }))
+                    .build(),
+            ),
+        );
+        let coll = mock.database("test_db").collection("users").await.unwrap();
+        let resp = coll
+            .create_document(
+                User {
+                    username: "alice".to_owned(),
+                },
+                crate::document::options::InsertOptions::builder()
+                    .return_new(true)
+                    .build(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.header().unwrap()._rev.as_deref(), Some("_rev1"));
+        assert_eq!(resp.new_doc().unwrap().username, "alice");
+
+        let request = &mock.requests()[1];
+        assert_eq!(request.query_param("returnNew"), Some("true".to_owned()));
+        assert_eq!(request.json_body(), Some(json!({ "username": "alice" })));
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn mock_error_and_unmatched() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/document/users/missing",
+            MockResponse::json(404, fixtures::error(404, 1202, "document not found")),
+        );
+        let coll = mock.database("test_db").collection("users").await;
+        assert!(matches!(coll, Err(ArangoClientError::HttpClient(_))));
+
+        let db = mock.database("test_db");
+        let coll = crate::Collection::new(
+            "users",
+            "100",
+            crate::collection::CollectionType::Document,
+            db.url(),
+            db.shared_session(),
+        );
+        let doc = coll.document::<User>("missing").await;
+        match doc {
+            Err(ArangoClientError::Arango(e)) => assert_eq!(e.error_num(), 1202),
+            _ => panic!("should be an arango error: {:?}", doc),
+        }
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn mock_server_starting_up() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users",
+            MockResponse::unavailable(Some(2)),
+        );
+        mock.once(
+            Method::GET,
+            "/_db/test_db/_api/collection/users",
+            MockResponse::json(503, fixtures::service_unavailable()),
+        );
+        let db = mock.database("test_db");
+        for expected in [Some(Duration::from_secs(2)), None].iter() {
+            match db.collection("users").await {
+                Err(ArangoClientError::ServiceUnavailable { retry_after }) => {
+                    assert_eq!(retry_after, *expected)
+                }
+                other => panic!("should be unavailable: {:?}", other.err()),
+            }
+        }
+    }
+}
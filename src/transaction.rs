@@ -1,21 +1,30 @@
 use maybe_async::maybe_async;
+#[cfg(feature = "aql")]
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "aql")]
 use serde_json::Value;
+#[cfg(feature = "aql")]
 use std::collections::HashMap;
 use std::sync::Arc;
 use typed_builder::TypedBuilder;
 use uclient::ClientExt;
 use url::Url;
 
+#[cfg(feature = "aql")]
+use crate::{
+    aql::{query_error, Cursor},
+    response::checked_id,
+    AqlQuery,
+};
 use crate::{
-    aql::Cursor,
     collection::response::Info,
+    connection::Session,
     response::{deserialize_response, ArangoResult},
-    AqlQuery, ClientError, Collection,
+    ClientError, Collection,
 };
 
-pub const TRANSACTION_HEADER: &str = "x-arango-trx-id";
+pub use crate::options::TRANSACTION_HEADER;
 
 #[derive(Debug, Serialize, Deserialize, TypedBuilder)]
 #[builder(doc)]
@@ -125,7 +134,7 @@ pub struct TransactionList {
 pub struct Transaction<C: ClientExt> {
     id: String,
     status: Status,
-    session: Arc<C>,
+    session: Session<C>,
     base_url: Url,
 }
 
@@ -133,7 +142,7 @@ impl<C> Transaction<C>
 where
     C: ClientExt,
 {
-    pub(crate) fn new(tx: ArangoTransaction, session: Arc<C>, base_url: Url) -> Self {
+    pub(crate) fn new(tx: ArangoTransaction, session: Session<C>, base_url: Url) -> Self {
         Transaction {
             id: tx.id,
             status: tx.status,
@@ -158,7 +167,32 @@ where
 
     /// The transaction session, contains the streaming transaction header value
     pub fn session(&self) -> Arc<C> {
-        Arc::clone(&self.session)
+        self.session.client()
+    }
+
+    /// The transaction session, sharing the registry of the connection.
+    pub(crate) fn shared_session(&self) -> Session<C> {
+        self.session.clone()
+    }
+
+    /// Stop tracking the transaction once committed or aborted.
+    fn ended(&self) {
+        self.session
+            .registry()
+            .transaction_ended(&self.base_url, &self.id);
+    }
+
+    /// Status of the transaction after a commit or an abort answered by
+    /// `resp`.
+    ///
+    /// The transaction has ended when the commit or abort succeeded, but also
+    /// when the server does not know it anymore, e.g. after its timeout.
+    fn finish(&self, resp: &http::Response<String>) -> Result<Status, ClientError> {
+        let result: Result<ArangoResult<ArangoTransaction>, _> = deserialize_response(resp);
+        if result.is_ok() || resp.status() == http::StatusCode::NOT_FOUND {
+            self.ended();
+        }
+        Ok(result?.unwrap().status)
     }
 
     /// Tries to commit the transaction, consuming the current object.
@@ -175,10 +209,7 @@ where
             .unwrap();
 
         let resp = self.session.put(url, "").await?;
-
-        let result: ArangoResult<ArangoTransaction> = deserialize_response(resp.body())?;
-
-        Ok(result.unwrap().status)
+        self.finish(&resp)
     }
 
     /// Tries to commit the transaction.
@@ -196,10 +227,7 @@ where
             .unwrap();
 
         let resp = self.session.put(url, "").await?;
-
-        let result: ArangoResult<ArangoTransaction> = deserialize_response(resp.body())?;
-
-        Ok(result.unwrap().status)
+        self.finish(&resp)
     }
 
     /// Tries to abort the transaction.
@@ -222,10 +250,7 @@ where
             .unwrap();
 
         let resp = self.session.delete(url, "").await?;
-
-        let result: ArangoResult<ArangoTransaction> = deserialize_response(resp.body())?;
-
-        Ok(result.unwrap().status)
+        self.finish(&resp)
     }
 
     /// Get collection object with name.
@@ -234,6 +259,11 @@ where
     /// operations using the colleciton will be transactional and require a transaction commit to be writen
     /// in ArangoDB.
     ///
+    /// Every request of the collection, like the document reads and writes,
+    /// carries the `x-arango-trx-id` header of the transaction. A collection
+    /// obtained from the database joins the transaction with
+    /// [`Collection::clone_with_transaction`].
+    ///
     /// # Note
     /// this function would make a request to arango server.
     #[maybe_async]
@@ -242,21 +272,33 @@ where
             .base_url
             .join(&format!("_api/collection/{}", name))
             .unwrap();
-        let resp: Info = deserialize_response(self.session.get(url, "").await?.body())?;
+        let resp: Info = deserialize_response(&self.session.get(url, "").await?)?;
         Ok(Collection::from_transaction_response(self, &resp))
     }
+}
 
+/// AQL queries run in this transaction.
+#[cfg(feature = "aql")]
+impl<C> Transaction<C>
+where
+    C: ClientExt,
+{
     #[maybe_async]
     pub async fn aql_query_batch<R>(&self, aql: AqlQuery<'_>) -> Result<Cursor<R>, ClientError>
     where
         R: DeserializeOwned,
     {
+        aql.validate_in_transaction()?;
         let url = self.base_url.join("_api/cursor").unwrap();
         let resp = self
             .session
             .post(url, &serde_json::to_string(&aql)?)
             .await?;
-        deserialize_response(resp.body())
+        let cursor: Cursor<R> = deserialize_response(&resp).map_err(query_error)?;
+        self.session
+            .registry()
+            .cursor_batch(&self.base_url, cursor.id.as_deref(), cursor.more);
+        Ok(cursor)
     }
 
     #[maybe_async]
@@ -264,13 +306,18 @@ where
     where
         R: DeserializeOwned,
     {
+        let cursor_id = checked_id(cursor_id, "cursor")?;
         let url = self
             .base_url
             .join(&format!("_api/cursor/{}", cursor_id))
             .unwrap();
         let resp = self.session.put(url, "").await?;
 
-        deserialize_response(resp.body())
+        let cursor: Cursor<R> = deserialize_response(&resp).map_err(query_error)?;
+        self.session
+            .registry()
+            .cursor_batch(&self.base_url, Some(cursor_id), cursor.more);
+        Ok(cursor)
     }
 
     #[maybe_async]
@@ -282,7 +329,7 @@ where
         let mut results: Vec<R> = Vec::new();
         loop {
             if response_cursor.more {
-                let id = response_cursor.id.unwrap().clone();
+                let id = response_cursor.next_id()?.to_owned();
                 results.extend(response_cursor.result.into_iter());
                 response_cursor = self.aql_next_batch(id.as_str()).await?;
             } else {
@@ -350,3 +397,154 @@ where
         self.aql_query(aql).await
     }
 }
+
+#[cfg(all(test, any(feature = "documents", feature = "aql")))]
+mod test {
+    use http::Method;
+    #[cfg(any(feature = "documents", feature = "rocksdb"))]
+    use serde_json::json;
+    use serde_json::Value;
+
+    use super::*;
+    use crate::test_support::{fixtures, MockClient, MockResponse};
+
+    #[cfg(all(feature = "rocksdb", feature = "aql"))]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn reject_intermediate_commits_in_transaction() {
+        use crate::aql::AqlOptions;
+
+        let mock = MockClient::default();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/transaction/begin",
+            MockResponse::json(
+                201,
+                json!({ "error": false, "code": 201, "result": { "id": "42", "status": "running" } }),
+            ),
+        );
+        let settings = TransactionSettings::builder()
+            .collections(
+                TransactionCollections::builder()
+                    .write(vec!["users".to_owned()])
+                    .build(),
+            )
+            .build();
+        let tx = mock
+            .database("test_db")
+            .begin_transaction(settings)
+            .await
+            .unwrap();
+
+        let aql = AqlQuery::builder()
+            .query("FOR u IN users UPDATE u WITH { seen: true } IN users")
+            .options(
+                AqlOptions::builder()
+                    .intermediate_commit_count(1000)
+                    .build(),
+            )
+            .build();
+        let result = tx.aql_query_batch::<Value>(aql).await;
+        assert!(matches!(result, Err(ClientError::InvalidOptions(_))));
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[cfg(feature = "documents")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn document_writes_in_transaction() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/transaction/begin",
+            MockResponse::json(
+                201,
+                json!({ "error": false, "code": 201, "result": { "id": "42", "status": "running" } }),
+            ),
+        );
+        mock.on(
+            Method::GET,
+            "/_db/test_db/_api/collection/users",
+            MockResponse::json(200, fixtures::collection_info("users", "100")),
+        );
+        let written =
+            || MockResponse::json(202, fixtures::DocumentFixture::new("users", "a").build());
+        mock.once(Method::POST, "/_db/test_db/_api/document/users/", written());
+        mock.once(
+            Method::PATCH,
+            "/_db/test_db/_api/document/users/a",
+            written(),
+        );
+        mock.once(
+            Method::DELETE,
+            "/_db/test_db/_api/document/users/a",
+            written(),
+        );
+        mock.once(Method::POST, "/_db/test_db/_api/document/users/", written());
+        let db = mock.database("test_db");
+        let settings = TransactionSettings::builder()
+            .collections(
+                TransactionCollections::builder()
+                    .write(vec!["users".to_owned()])
+                    .build(),
+            )
+            .build();
+        let tx = db.begin_transaction(settings).await.unwrap();
+        let users = tx.collection("users").await.unwrap();
+
+        let doc = json!({ "_key": "a" });
+        users
+            .create_document(doc.clone(), Default::default())
+            .await
+            .unwrap();
+        users
+            .update_document("a", json!({ "seen": true }), Default::default())
+            .await
+            .unwrap();
+        users
+            .remove_document::<Value>("a", Default::default(), None)
+            .await
+            .unwrap();
+        // the handles of the database stay out of the transaction
+        let outside = db.collection("users").await.unwrap();
+        outside
+            .create_document(doc, Default::default())
+            .await
+            .unwrap();
+
+        let requests = mock.requests();
+        let headers: Vec<_> = requests
+            .iter()
+            .filter(|r| r.path.contains("_api/document"))
+            .map(|r| r.header(TRANSACTION_HEADER))
+            .collect();
+        assert_eq!(headers, vec![Some("42"), Some("42"), Some("42"), None]);
+        mock.assert_no_pending();
+    }
+
+    #[cfg(feature = "aql")]
+    #[maybe_async::test(feature = "blocking", async(not(feature = "blocking"), tokio::test))]
+    async fn map_transaction_size_exceeded() {
+        let mock = MockClient::default();
+        mock.once(
+            Method::POST,
+            "/_db/test_db/_api/cursor",
+            MockResponse::json(
+                500,
+                fixtures::error(
+                    500,
+                    32,
+                    "maximal transaction size limit of 134217728 bytes reached",
+                ),
+            ),
+        );
+        let result = mock
+            .database("test_db")
+            .aql_str::<Value>("FOR u IN users UPDATE u WITH { seen: true } IN users")
+            .await;
+        match result {
+            Err(e @ ClientError::TransactionSizeExceeded { .. }) => {
+                assert!(e.to_string().contains("intermediate_commit_count"))
+            }
+            other => panic!("should be a typed error: {:?}", other.err()),
+        }
+    }
+}
@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use typed_builder::TypedBuilder;
 
+pub use crate::collection::options::SortDirection;
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub enum ViewType {
     #[serde(rename = "arangosearch")]
@@ -75,13 +77,6 @@ pub struct ArangoSearchViewLink {
     pub store_values: Option<StoreValues>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-#[serde(rename_all = "lowercase")]
-pub enum SortDirection {
-    Asc,
-    Desc,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
 pub enum ConsolidationPolicy {
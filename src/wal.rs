@@ -0,0 +1,60 @@
+//! Types of the write-ahead log (WAL) administration, see
+//! [`Database::flush_wal`](crate::Database::flush_wal) and
+//! [`Database::wal_properties`](crate::Database::wal_properties).
+//!
+//! The properties of the write-ahead log only exist with the MMFiles storage
+//! engine. Operations the storage engine does not implement, or whose
+//! endpoint the server removed, fail with
+//! [`ClientError::NotSupported`](crate::ClientError::NotSupported).
+use serde::{Deserialize, Serialize};
+use typed_builder::TypedBuilder;
+
+pub(crate) const WAL_API_PATH: &str = "_admin/wal";
+
+/// Properties of the write-ahead log of the MMFiles storage engine.
+///
+/// All fields are optional: the unset ones are left unchanged by
+/// [`Database::set_wal_properties`](crate::Database::set_wal_properties).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TypedBuilder)]
+#[builder(doc)]
+#[serde(rename_all = "camelCase")]
+pub struct WalProperties {
+    /// Whether operations bigger than a logfile can be written, in a
+    /// separate logfile.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_oversize_entries: Option<bool>,
+    /// Size of each logfile in bytes.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logfile_size: Option<u64>,
+    /// Maximum number of historic logfiles kept after their data was
+    /// collected.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub historic_logfiles: Option<u64>,
+    /// Maximum number of reserve logfiles allocated in the background.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reserve_logfiles: Option<u64>,
+    /// Interval in milliseconds of the automatic syncing of the logfiles.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_interval: Option<u64>,
+    /// Maximum time in milliseconds to wait for the collector when
+    /// throttled.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle_wait: Option<u64>,
+    /// Number of operations pending collection above which writes are
+    /// throttled, 0 to never throttle.
+    #[builder(default, setter(strip_option))]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle_when_pending: Option<u64>,
+}
+
+impl Default for WalProperties {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
@@ -1,3 +1,4 @@
+#![cfg(feature = "views")]
 #![allow(unused_imports)]
 #![allow(unused_parens)]
 use crate::common::{collection, connection};
@@ -1,10 +1,11 @@
+#![cfg(feature = "aql")]
 #![allow(unused_imports)]
 #![allow(unused_parens)]
 
 use pretty_assertions::assert_eq;
 use serde::{Deserialize, Serialize};
 
-use arangors::{AqlQuery, Connection, Document};
+use arangors::{AqlOptions, AqlQuery, Connection, Document};
 use common::{connection, test_setup};
 
 use crate::common::{get_arangodb_host, get_root_password, get_root_user};
@@ -93,3 +94,31 @@ async fn test_aql_try_bind() {
     assert_eq!(result.len(), 1);
     assert_eq!(result[0].document.password, "test2_pwd");
 }
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_aql_executed_plan() {
+    test_setup();
+    let conn = connection().await;
+    let db = conn.db("test_db").await.unwrap();
+    let aql = AqlQuery::builder()
+        .query(r#"FOR i in test_collection FILTER i._key IN @keys return i"#)
+        .bind_var("keys", vec!["a", "b"])
+        .options(AqlOptions::builder().profile(2).build())
+        .build();
+    let cursor = db.aql_query_batch::<serde_json::Value>(aql).await.unwrap();
+    let plan = cursor.extra.unwrap().executed_plan().unwrap();
+    let index = plan
+        .nodes
+        .iter()
+        .find(|node| node.node_type == "IndexNode")
+        .unwrap();
+    assert!(index.calls >= 1);
+    assert!(plan
+        .nodes
+        .windows(2)
+        .all(|pair| pair[0].runtime >= pair[1].runtime));
+}
@@ -6,9 +6,14 @@ use pretty_assertions::assert_eq;
 use serde_json::{json, Value};
 
 use crate::common::{collection, connection};
+#[cfg(feature = "documents")]
+use arangors::document::options::UpdateOptions;
 use arangors::{
     collection::{
-        options::{ChecksumOptions, PropertiesOptions},
+        options::{
+            ChecksumOptions, ComputeOn, ComputedValue, CreateOptions, FiguresOptions, KeyGenerator,
+            KeyOptions, PropertiesOptions,
+        },
         response::Status,
         CollectionType,
     },
@@ -36,6 +41,27 @@ async fn test_get_collection() {
     assert_eq!(coll.is_err(), true);
 }
 
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_list_collections_without_system_ones() {
+    test_setup();
+    let conn = connection().await;
+
+    let database = conn.db("test_db").await.unwrap();
+    let all = database.collections(false).await.unwrap();
+    assert!(all.iter().any(|info| info.is_system));
+    let user_ones = database.collections(true).await.unwrap();
+    assert!(user_ones.iter().all(|info| !info.is_system));
+    assert!(user_ones.iter().any(|info| info.name == "test_collection"));
+
+    let names = database.collection_names().await.unwrap();
+    assert_eq!(names.len(), user_ones.len());
+    assert!(names.iter().any(|name| name == "test_collection"));
+}
+
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -87,7 +113,7 @@ async fn test_create_and_drop_collection() {
     let coll = database.create_collection(collection_name).await;
     assert_eq!(coll.is_err(), false, "Fail to create the collection");
 
-    let res = coll.unwrap().drop().await;
+    let res = coll.unwrap().drop(Default::default()).await;
     assert_eq!(res.is_err(), false, "Fail to drop the collection");
 }
 
@@ -123,7 +149,90 @@ async fn test_create_and_drop_edge_collection() {
     let coll = database.create_edge_collection(collection_name).await;
     assert_eq!(coll.is_err(), false, "Fail to create the collection");
 
-    let res = coll.unwrap().drop().await;
+    let res = coll.unwrap().drop(Default::default()).await;
+    assert_eq!(res.is_err(), false, "Fail to drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_create_collection_with_properties() {
+    test_setup();
+    let collection_name = "test_collection_create_with_properties";
+    let conn = connection().await;
+
+    let database = conn.db("test_db").await.unwrap();
+    let options = CreateOptions::builder()
+        .name(collection_name)
+        .wait_for_sync(true)
+        .key_options(
+            KeyOptions::builder()
+                .generator(KeyGenerator::Autoincrement {
+                    increment: None,
+                    offset: None,
+                })
+                .build(),
+        )
+        .build();
+    let (coll, properties) = database
+        .create_collection_with_properties(options, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(coll.id(), properties.info.id);
+    assert_eq!(properties.info.name, collection_name);
+    assert_eq!(properties.info.status, Status::Loaded);
+    assert_eq!(properties.detail.wait_for_sync, true);
+    assert!(matches!(
+        properties.detail.key_options.generator,
+        KeyGenerator::Autoincrement { .. }
+    ));
+
+    let res = coll.drop(Default::default()).await;
+    assert_eq!(res.is_err(), false, "Fail to drop the collection");
+}
+
+#[cfg(feature = "documents")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_insert_with_autoincrement_keys() {
+    test_setup();
+    let collection_name = "test_collection_autoincrement_keys";
+    let conn = connection().await;
+
+    let database = conn.db("test_db").await.unwrap();
+    let _ = database.drop_collection(collection_name).await;
+    let generator = KeyGenerator::Autoincrement {
+        increment: Some(5),
+        offset: Some(100),
+    };
+    let options = CreateOptions::builder()
+        .name(collection_name)
+        .key_options(KeyOptions::builder().generator(generator.clone()).build())
+        .build();
+    let (coll, properties) = database
+        .create_collection_with_properties(options, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(properties.detail.key_options.generator, generator);
+
+    let first = coll
+        .create_document(json!({ "name": "first" }), Default::default())
+        .await
+        .unwrap();
+    let second = coll
+        .create_document(json!({ "name": "second" }), Default::default())
+        .await
+        .unwrap();
+    let first: u64 = first.header().unwrap()._key.parse().unwrap();
+    let second: u64 = second.header().unwrap()._key.parse().unwrap();
+    assert_eq!(second, first + 5);
+
+    let res = coll.drop(Default::default()).await;
     assert_eq!(res.is_err(), false, "Fail to drop the collection");
 }
 
@@ -146,7 +255,9 @@ async fn test_truncate_collection() {
     assert_eq!(res.name, collection_name);
     assert_eq!(res.collection_type, CollectionType::Document);
 
-    coll.drop().await.expect("Fail to drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Fail to drop the collection");
 }
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
@@ -178,16 +289,19 @@ async fn test_get_properties() {
     assert_eq!(result.detail.wait_for_sync, false);
     assert_eq!(result.detail.key_options.allow_user_keys, true);
     assert_eq!(
-        result.detail.key_options.key_type,
-        Some("traditional".to_string())
+        result.detail.key_options.generator,
+        KeyGenerator::Traditional
     );
     assert_eq!(result.detail.key_options.last_value, Some(0));
     assert_eq!(result.info.status, Status::Loaded);
     assert_eq!(result.detail.write_concern, 1);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
+#[cfg(feature = "aql")]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -211,8 +325,8 @@ async fn test_get_document_count() {
     assert_eq!(result.detail.wait_for_sync, false);
     assert_eq!(result.detail.key_options.allow_user_keys, true);
     assert_eq!(
-        result.detail.key_options.key_type,
-        Some("traditional".to_string())
+        result.detail.key_options.generator,
+        KeyGenerator::Traditional
     );
     assert_eq!(result.detail.key_options.last_value, Some(0));
     assert_eq!(result.info.status, Status::Loaded);
@@ -226,8 +340,12 @@ async fn test_get_document_count() {
     let updated_count = coll.document_count().await;
     let updated_result = updated_count.unwrap();
     assert_eq!(updated_result.info.count, Some(1));
+    let count = coll.count().await.unwrap();
+    assert_eq!(count, 1);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -255,8 +373,8 @@ async fn test_get_statistics() {
         "allow user keys"
     );
     assert_eq!(
-        result.detail.key_options.key_type,
-        Some("traditional".to_string())
+        result.detail.key_options.generator,
+        KeyGenerator::Traditional
     );
     assert_eq!(result.detail.key_options.last_value, Some(0), "last value");
     assert_eq!(result.info.status, Status::Loaded);
@@ -266,9 +384,35 @@ async fn test_get_statistics() {
     #[cfg(not(feature = "mmfiles"))]
     assert_eq!(result.figures.indexes.size, Some(0), "indexes size");
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_get_statistics_with_details() {
+    test_setup();
+    let collection_name = "test_collection_statistics_details";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let options = FiguresOptions::builder().details(true).build();
+    let result = coll.statistics_with_options(options).await.unwrap();
+    assert_eq!(result.count, Some(0), "count");
+    assert_eq!(result.figures.indexes.count, Some(1));
+    #[cfg(feature = "rocksdb")]
+    assert!(result.figures.extra.contains_key("engine"));
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[cfg(feature = "documents")]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -291,16 +435,25 @@ async fn test_get_revision_id() {
     assert_eq!(result.detail.wait_for_sync, false);
     assert_eq!(result.detail.key_options.allow_user_keys, true);
     assert_eq!(
-        result.detail.key_options.key_type,
-        Some("traditional".to_string())
+        result.detail.key_options.generator,
+        KeyGenerator::Traditional
     );
     assert_eq!(result.detail.key_options.last_value, Some(0));
     assert_eq!(result.info.status, Status::Loaded);
     assert_eq!(result.detail.write_concern, 1);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.create_document(json!({ "name": "test_user" }), Default::default())
+        .await
+        .unwrap();
+    let revision = coll.revision().await.unwrap();
+    assert_ne!(revision, result.revision);
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
+#[cfg(feature = "aql")]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -366,9 +519,54 @@ async fn test_get_checksum() {
     );
     assert_eq!(updated_result.checksum.is_empty(), false);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[cfg(feature = "documents")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_checksum_of_data_only() {
+    test_setup();
+    let conn = connection().await;
+    let first = collection(&conn, "test_collection_checksum_first").await;
+    let second = collection(&conn, "test_collection_checksum_second").await;
+
+    let doc = json!({ "_key": "alice", "name": "Alice" });
+    first
+        .create_document(doc.clone(), Default::default())
+        .await
+        .unwrap();
+    second
+        .create_document(doc, Default::default())
+        .await
+        .unwrap();
+    let data_only = || {
+        ChecksumOptions::builder()
+            .with_revision(false)
+            .with_data(true)
+            .build()
+    };
+    let checksum = first.checksum_with_options(data_only()).await.unwrap();
+    let other = second.checksum_with_options(data_only()).await.unwrap();
+    assert_eq!(checksum.checksum, other.checksum);
+
+    second
+        .update_document("alice", json!({ "name": "Bob" }), Default::default())
+        .await
+        .unwrap();
+    let other = second.checksum_with_options(data_only()).await.unwrap();
+    assert_ne!(checksum.checksum, other.checksum);
+
+    first.drop(Default::default()).await.unwrap();
+    second.drop(Default::default()).await.unwrap();
 }
 
+#[cfg(feature = "aql")]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -414,7 +612,9 @@ async fn test_put_load() {
     assert_eq!(updated_result.status, Status::Loaded);
     assert_eq!(updated_result.collection_type, CollectionType::Document);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -443,7 +643,9 @@ async fn test_put_unload() {
     );
     assert_eq!(result.collection_type, CollectionType::Document);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -462,7 +664,9 @@ async fn test_put_load_indexes_into_memory() {
     let result = load_index.unwrap();
     assert_eq!(result, true);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -487,14 +691,92 @@ async fn test_put_changes_properties() {
     assert_eq!(result.detail.wait_for_sync, true);
     assert_eq!(result.detail.key_options.allow_user_keys, true);
     assert_eq!(
-        result.detail.key_options.key_type,
-        Some("traditional".to_string())
+        result.detail.key_options.generator,
+        KeyGenerator::Traditional
     );
     assert_eq!(result.detail.key_options.last_value, Some(0));
     assert_eq!(result.info.status, Status::Loaded);
     assert_eq!(result.detail.write_concern, 1);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[cfg(feature = "documents")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_put_changes_schema() {
+    test_setup();
+    let collection_name = "test_collection_changes_schema";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let schema = json!({
+        "rule": { "properties": { "name": { "type": "string" } }, "required": ["name"] },
+        "level": "moderate",
+        "message": "a name is required",
+    });
+    let options = PropertiesOptions::builder().schema(schema).build();
+    coll.change_properties(options).await.unwrap();
+    let result = coll.properties().await.unwrap();
+    let schema = result.detail.schema.unwrap();
+    assert_eq!(schema["message"], "a name is required");
+    let refused = coll.create_document(json!({}), Default::default()).await;
+    assert!(matches!(refused, Err(ClientError::Arango(_))));
+
+    let options = PropertiesOptions::builder().schema(Value::Null).build();
+    let result = coll.change_properties(options).await.unwrap();
+    assert_eq!(result.detail.schema, None);
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[cfg(feature = "documents")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_put_changes_computed_values() {
+    test_setup();
+    let collection_name = "test_collection_computed_values";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let updated_at = ComputedValue::builder()
+        .name("updatedAt")
+        .expression("RETURN DATE_ISO8601(DATE_NOW())")
+        .overwrite(true)
+        .compute_on(vec![ComputeOn::Update])
+        .build();
+    let options = PropertiesOptions::builder()
+        .computed_values(vec![updated_at])
+        .build();
+    let result = coll.change_properties(options).await.unwrap();
+    let computed = result.detail.computed_values.unwrap();
+    assert_eq!(computed[0].name, "updatedAt");
+    assert_eq!(computed[0].compute_on, Some(vec![ComputeOn::Update]));
+
+    coll.create_document(json!({ "_key": "alice" }), Default::default())
+        .await
+        .unwrap();
+    let options = UpdateOptions::builder().return_new(true).build();
+    let update = coll
+        .update_document("alice", json!({ "name": "Alice" }), options)
+        .await
+        .unwrap();
+    let new_doc: &Value = update.new_doc().unwrap();
+    assert!(new_doc["updatedAt"].is_string());
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -518,10 +800,12 @@ async fn test_put_rename() {
     assert_eq!(result.status, Status::Loaded);
     assert_eq!(result.collection_type, CollectionType::Document);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
-#[cfg(feature = "rocksdb")]
+#[cfg(all(feature = "rocksdb", feature = "documents"))]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -533,12 +817,37 @@ async fn test_put_recalculate() {
     let conn = connection().await;
     let coll = collection(&conn, collection_name).await;
 
+    coll.create_document(json!({ "name": "test_user" }), Default::default())
+        .await
+        .unwrap();
     let recalculate = coll.recalculate_count().await;
 
     let result = recalculate.unwrap();
-    assert_eq!(result, true);
+    assert_eq!(result, 1);
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[cfg(feature = "rocksdb")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_put_compact() {
+    test_setup();
+    let collection_name = "test_collection_compact";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let compact = coll.compact().await;
+    assert_eq!(compact.is_err(), false, "Fail to compact the collection");
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[cfg(any(feature = "mmfiles"))]
@@ -573,5 +882,7 @@ async fn test_put_rotate_journal() {
     // let result = rotate.unwrap();
     // assert_eq!(result, true, "rotate result should be true");
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
@@ -1,3 +1,4 @@
+#![cfg(feature = "documents")]
 #![allow(unused_imports)]
 #![allow(unused_parens)]
 
@@ -8,9 +9,11 @@ use serde_json::{json, Value};
 use arangors::{
     document::{
         options::{
-            InsertOptions, OverwriteMode, ReadOptions, RemoveOptions, ReplaceOptions, UpdateOptions,
+            BatchReadOptions, DocumentReadOptions, InsertOptions, OverwriteMode, RemoveOptions,
+            ReplaceOptions, UpdateOptions, UpsertOptions,
         },
-        response::DocumentResponse,
+        response::{CasOutcome, DocumentLookup, DocumentResponse, HeaderLookup},
+        EdgeDocument, Header,
     },
     ClientError, Connection, Document,
 };
@@ -21,7 +24,6 @@ use std::{convert::TryInto, ptr::null};
 
 pub mod common;
 
-#[cfg(not(feature = "arango3_7"))]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -48,12 +50,12 @@ async fn test_post_create_document() {
 
     let header = result.header().unwrap();
     assert_eq!(
-        header._id.is_empty(),
+        header._id.is_none(),
         false,
         "We should get the id of the document"
     );
     assert_eq!(
-        header._rev.is_empty(),
+        header._rev.is_none(),
         false,
         "We should get the revision of the document"
     );
@@ -85,8 +87,8 @@ async fn test_post_create_document() {
     assert_eq!(doc.document["testDescription"], "Test with new");
 
     let header = result.header().unwrap();
-    assert_eq!(header._id.is_empty(), false);
-    assert_eq!(header._rev.is_empty(), false);
+    assert_eq!(header._id.is_none(), false);
+    assert_eq!(header._rev.is_none(), false);
     assert_eq!(header._key.is_empty(), false);
 
     let key = &header._key;
@@ -119,8 +121,8 @@ async fn test_post_create_document() {
 
     let header = result.header().unwrap();
 
-    assert_eq!(header._id.is_empty(), false,);
-    assert_eq!(header._rev.is_empty(), false,);
+    assert_eq!(header._id.is_none(), false,);
+    assert_eq!(header._rev.is_none(), false,);
     assert_eq!(header._key.is_empty(), false,);
 
     // Fourth testis about the silent option
@@ -137,11 +139,11 @@ async fn test_post_create_document() {
 
     assert_eq!(result.is_silent(), true);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
-/// TODO need to use CI to validate this test
-#[cfg(any(feature = "arango3_7"))]
 #[maybe_async::test(
     any(feature = "reqwest_blocking"),
     async(any(feature = "reqwest_async"), tokio::test),
@@ -166,12 +168,12 @@ async fn test_post_create_document_3_7() {
 
     let header = result.header().unwrap();
     assert_eq!(
-        header._id.is_empty(),
+        header._id.is_none(),
         false,
         "We should get the id of the document"
     );
     assert_eq!(
-        header._rev.is_empty(),
+        header._rev.is_none(),
         false,
         "We should get the revision of the document"
     );
@@ -193,7 +195,7 @@ async fn test_post_create_document_3_7() {
     let result = create.unwrap();
 
     assert_eq!(
-        result.return_new().is_some(),
+        result.new_doc().is_some(),
         true,
         "we should get the new document under 'new' property"
     );
@@ -203,11 +205,11 @@ async fn test_post_create_document_3_7() {
     assert_eq!(doc.document["testDescription"], "Test with new");
 
     let header = result.header().unwrap();
-    assert_eq!(header._id.is_empty(), false);
-    assert_eq!(header._rev.is_empty(), false);
+    assert_eq!(header._id.is_none(), false);
+    assert_eq!(header._rev.is_none(), false);
     assert_eq!(header._key.is_empty(), false);
 
-    let key = header._key;
+    let key = header._key.clone();
     // Third test is to update a simple document with option return old
     // Should not return  anything according to doc if overWriteMode is not used for
     // now TODO update this test with overwriteMode later
@@ -236,8 +238,8 @@ async fn test_post_create_document_3_7() {
     );
 
     let header = result.header().unwrap();
-    assert_eq!(header._id.is_empty(), false);
-    assert_eq!(header._rev.is_empty(), false);
+    assert_eq!(header._id.is_none(), false);
+    assert_eq!(header._rev.is_none(), false);
     assert_eq!(header._key.is_empty(), false);
 
     // Fourth testis about the silent option
@@ -265,7 +267,8 @@ async fn test_post_create_document_3_7() {
             test_doc,
             InsertOptions::builder()
                 .return_new(true)
-                .overwrite_mode(OverwriteMode::Ignore),
+                .overwrite_mode(OverwriteMode::Ignore)
+                .build(),
         )
         .await;
 
@@ -283,7 +286,9 @@ async fn test_post_create_document_3_7() {
     let update = coll
         .create_document(
             test_doc,
-            InsertOptions::builder().overwrite_mode(OverwriteMode::Replace),
+            InsertOptions::builder()
+                .overwrite_mode(OverwriteMode::Replace)
+                .build(),
         )
         .await;
 
@@ -307,7 +312,9 @@ async fn test_post_create_document_3_7() {
     let update = coll
         .create_document(
             test_doc,
-            InsertOptions::builder().overwrite_mode(OverwriteMode::Update),
+            InsertOptions::builder()
+                .overwrite_mode(OverwriteMode::Update)
+                .build(),
         )
         .await;
 
@@ -321,7 +328,9 @@ async fn test_post_create_document_3_7() {
 
     assert_eq!(result.header().is_none(), false);
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -345,7 +354,7 @@ async fn test_get_read_document() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
     let read = coll.document(_key.as_str()).await;
 
     let result: Document<Value> = read.unwrap();
@@ -354,12 +363,22 @@ async fn test_get_read_document() {
     assert_eq!(result.document["testDescription"], "read a document");
     // Test if we get the right doc when it does match
     let read: Result<Document<Value>, ClientError> = coll
-        .document_with_options(_key.as_str(), ReadOptions::IfMatch(_rev.clone()))
+        .document_with_options(
+            _key.as_str(),
+            DocumentReadOptions::builder()
+                .if_match(_rev.clone())
+                .build(),
+        )
         .await;
     assert_eq!(read.is_err(), false, "got the right document");
     // Test if we get the 412 code response when there is no match
     let read: Result<Document<Value>, ClientError> = coll
-        .document_with_options(_key.as_str(), ReadOptions::IfMatch("_dsdsds_d".to_string()))
+        .document_with_options(
+            _key.as_str(),
+            DocumentReadOptions::builder()
+                .if_match("_dsdsds_d".to_string())
+                .build(),
+        )
         .await;
     // We should get a 412, for now for some reason the error is parsed as a
     // document todo fix how the reponse/error is built
@@ -372,7 +391,211 @@ async fn test_get_read_document() {
 
     // todo need to test with with IfNoneMatch and 304
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_read_document_header_with_head() {
+    test_setup();
+    let conn = connection().await;
+    let coll = collection(&conn, "test_collection_head_document").await;
+
+    let created = coll
+        .create_document(json!({ "_key": "a" }), Default::default())
+        .await
+        .unwrap();
+    let rev = created.header().unwrap()._rev.clone().unwrap();
+
+    let found = coll.read_document_header("a", Default::default()).await;
+    match found.unwrap() {
+        HeaderLookup::Found(header) => assert_eq!(header._rev, Some(rev.clone())),
+        other => panic!("expected the header, got {:?}", other),
+    }
+    let options = DocumentReadOptions::builder()
+        .if_none_match(rev.clone())
+        .build();
+    let not_modified = coll.read_document_header("a", options).await.unwrap();
+    assert_eq!(not_modified, HeaderLookup::NotModified);
+    let options = DocumentReadOptions::builder()
+        .if_match("_other".to_owned())
+        .build();
+    let mismatch = coll.read_document_header("a", options).await.unwrap();
+    assert_eq!(
+        mismatch,
+        HeaderLookup::PreconditionFailed { current_rev: rev }
+    );
+    let missing = coll
+        .read_document_header("b", Default::default())
+        .await
+        .unwrap();
+    assert_eq!(missing, HeaderLookup::NotFound);
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_read_document_if_none_match() {
+    test_setup();
+    let conn = connection().await;
+    let coll = collection(&conn, "test_collection_read_document_lookup").await;
+
+    let created = coll
+        .create_document(json!({ "_key": "a", "n": 1 }), Default::default())
+        .await
+        .unwrap();
+    let rev = created.header().unwrap()._rev.clone().unwrap();
+
+    let options = DocumentReadOptions::builder()
+        .if_none_match(rev.clone())
+        .build();
+    let unchanged = coll.read_document::<Value>("a", options).await.unwrap();
+    assert!(matches!(unchanged, DocumentLookup::NotModified));
+    let options = DocumentReadOptions::builder()
+        .if_none_match("_other".to_owned())
+        .build();
+    match coll.read_document::<Value>("a", options).await.unwrap() {
+        DocumentLookup::Found(doc) => assert_eq!(doc.document["n"], 1),
+        DocumentLookup::NotModified => panic!("expected the document"),
+    }
+    let options = DocumentReadOptions::builder()
+        .if_match("_other".to_owned())
+        .build();
+    let mismatch = coll.read_document::<Value>("a", options).await;
+    match mismatch {
+        Err(ClientError::PreconditionFailed { current_rev, .. }) => {
+            assert_eq!(current_rev, Some(rev))
+        }
+        other => panic!("expected a failed precondition, got {:?}", other),
+    }
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_replace_document_with_version_attribute() {
+    test_setup();
+    let conn = connection().await;
+    let coll = collection(&conn, "test_collection_version_attribute").await;
+    let db = conn.db("test_db").await.unwrap();
+    let version = db.arango_version().await.unwrap().version;
+    let mut numbers = version.split('.').map(|n| n.parse::<u32>().unwrap_or(0));
+    let since_3_12 = (numbers.next(), numbers.next()) >= (Some(3), Some(12));
+
+    coll.create_document(json!({ "_key": "a", "v": 2 }), Default::default())
+        .await
+        .unwrap();
+    let options = || ReplaceOptions::builder().version_attribute("v").build();
+    let stale = coll
+        .replace_document("a", json!({ "v": 1 }), options(), None)
+        .await;
+    if !since_3_12 {
+        assert!(matches!(stale, Err(ClientError::NotSupported { .. })));
+        coll.drop(Default::default())
+            .await
+            .expect("Should drop the collection");
+        return;
+    }
+    stale.unwrap();
+    let doc: Document<Value> = coll.document("a").await.unwrap();
+    assert_eq!(doc.document["v"], 2, "a lower version must not be written");
+
+    coll.replace_document("a", json!({ "v": 3 }), options(), None)
+        .await
+        .unwrap();
+    let doc: Document<Value> = coll.document("a").await.unwrap();
+    assert_eq!(doc.document["v"], 3);
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[cfg(feature = "aql")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_upsert_document() {
+    test_setup();
+    let conn = connection().await;
+    let coll = collection(&conn, "test_collection_upsert_document").await;
+
+    let created = coll
+        .upsert_document(
+            "a",
+            json!({ "n": 1, "tags": { "x": 1 } }),
+            Default::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.old_rev(), None, "the document did not exist");
+    let options = UpsertOptions::builder()
+        .merge_objects(false)
+        .return_new(true)
+        .build();
+    let updated = coll
+        .upsert_document("a", json!({ "tags": { "y": 2 } }), options)
+        .await
+        .unwrap();
+    assert_eq!(updated.old_rev(), created.header().unwrap()._rev.as_ref());
+    let new = updated.new_doc().unwrap();
+    assert_eq!(new["n"], 1);
+    assert_eq!(new["tags"], json!({ "y": 2 }));
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_document_exists_and_has_collection() {
+    test_setup();
+    let conn = connection().await;
+    let name = "test_collection_document_exists";
+    let coll = collection(&conn, name).await;
+    let db = conn.db("test_db").await.unwrap();
+
+    coll.create_document(json!({ "_key": "a" }), Default::default())
+        .await
+        .unwrap();
+    let found = coll.document_exists("a").await.unwrap();
+    assert_eq!(found, true);
+    let missing = coll.document_exists("b").await.unwrap();
+    assert_eq!(missing, false);
+    let exists = db.has_collection(name).await.unwrap();
+    assert_eq!(exists, true);
+
+    let dropped = coll.clone();
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+    let exists = db.has_collection(name).await.unwrap();
+    assert_eq!(exists, false);
+    let found = dropped.document_exists("a").await.unwrap();
+    assert_eq!(found, false, "the collection is gone");
 }
 
 #[maybe_async::test(
@@ -397,7 +620,7 @@ async fn test_get_read_document_header() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
 
     let read = coll.document_header(_key.as_str()).await;
 
@@ -417,7 +640,12 @@ async fn test_get_read_document_header() {
     );
 
     let read = coll
-        .document_header_with_options(_key.as_str(), ReadOptions::IfMatch(_rev.clone()))
+        .document_header_with_options(
+            _key.as_str(),
+            DocumentReadOptions::builder()
+                .if_match(_rev.clone())
+                .build(),
+        )
         .await;
 
     assert_eq!(read.is_ok(), true, "We should have the right header");
@@ -431,7 +659,12 @@ async fn test_get_read_document_header() {
     );
 
     let read = coll
-        .document_header_with_options(_key.as_str(), ReadOptions::IfMatch("_dsdsds".to_string()))
+        .document_header_with_options(
+            _key.as_str(),
+            DocumentReadOptions::builder()
+                .if_match("_dsdsds".to_string())
+                .build(),
+        )
         .await;
 
     assert_eq!(
@@ -440,7 +673,12 @@ async fn test_get_read_document_header() {
         "We should have an error and the right doc returned"
     );
     let read = coll
-        .document_header_with_options(_key.as_str(), ReadOptions::IfNoneMatch(_rev.clone()))
+        .document_header_with_options(
+            _key.as_str(),
+            DocumentReadOptions::builder()
+                .if_none_match(_rev.clone())
+                .build(),
+        )
         .await;
 
     assert_eq!(
@@ -449,7 +687,9 @@ async fn test_get_read_document_header() {
         "the If-None-Match header is given and the document has the same version"
     );
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 }
 
 #[maybe_async::test(
@@ -497,14 +737,14 @@ async fn test_patch_update_document() {
     assert_eq!(old_doc["no"], 1);
     assert_eq!(old_doc["testDescription"], "update document");
     let header = result.header().unwrap();
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
     let update = coll
         .update_document(_key.as_str(), json!({ "no":3}), Default::default())
         .await;
 
     let result = update.unwrap();
     assert_eq!(
-        result.header().unwrap()._rev != _rev.to_string(),
+        result.header().unwrap()._rev.as_ref() != Some(_rev),
         true,
         "We should get a different revision after update"
     );
@@ -525,7 +765,9 @@ async fn test_patch_update_document() {
          specified _rev in body"
     );
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
     // todo do more test for merge objects and stuff
 }
 
@@ -551,7 +793,7 @@ async fn test_post_replace_document() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
 
     let replace = coll
         .replace_document(
@@ -636,7 +878,9 @@ async fn test_post_replace_document() {
          specified _rev in body"
     );
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
 
     // todo do more test
 }
@@ -664,7 +908,7 @@ async fn test_delete_remove_document() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
 
     let remove: Result<DocumentResponse<Value>, ClientError> = coll
         .remove_document(
@@ -701,7 +945,7 @@ async fn test_delete_remove_document() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
     let remove: Result<DocumentResponse<Value>, ClientError> = coll
         .remove_document(
             _key.as_str(),
@@ -722,7 +966,7 @@ async fn test_delete_remove_document() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
     let remove: Result<DocumentResponse<Value>, ClientError> = coll
         .remove_document(
             _key.as_str(),
@@ -755,7 +999,9 @@ async fn test_delete_remove_document() {
         "We should get 404 because we just have removed the doc before"
     );
 
-    coll.drop().await.expect("Should drop the collection");
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
     // todo do more test
 }
 
@@ -796,7 +1042,7 @@ async fn test_document_deserialization() {
     let result = create.unwrap();
     let header = result.header().unwrap();
     let _key = &header._key;
-    let _rev = &header._rev;
+    let _rev = header._rev.as_ref().unwrap();
 
     let read = coll.document(_key.as_str()).await;
     let result: Document<Item> = read.unwrap();
@@ -813,6 +1059,660 @@ async fn test_document_deserialization() {
     assert_eq!(result.header._id, header._id);
 
     assert_eq!(result.key, header._key);
-    assert_eq!(result.rev, header._rev);
-    assert_eq!(result.id, header._id);
+    assert_eq!(Some(&result.rev), header._rev.as_ref());
+    assert_eq!(Some(&result.id), header._id.as_ref());
+}
+
+#[cfg(feature = "aql")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_compare_and_swap_field() {
+    test_setup();
+    let collection_name = "test_compare_and_swap_field";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+    coll.create_document(
+        Document::new(json!({ "_key": "counter", "stats": { "count": 0 } })),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+
+    let swapped = coll
+        .compare_and_swap_field("counter", "stats.count", json!(0), json!(1))
+        .await
+        .unwrap();
+    let header = coll.document_header("counter").await.unwrap();
+    assert_eq!(
+        swapped,
+        CasOutcome::Swapped {
+            new_rev: header._rev.unwrap()
+        }
+    );
+    let mismatch = coll
+        .compare_and_swap_field("counter", "stats.count", json!(0), json!(1))
+        .await
+        .unwrap();
+    assert_eq!(mismatch, CasOutcome::Mismatch { actual: json!(1) });
+
+    // two clients race from the same value, one wins
+    let other_conn = connection().await;
+    let other = other_conn
+        .db("test_db")
+        .await
+        .unwrap()
+        .collection(collection_name)
+        .await
+        .unwrap();
+    #[cfg(not(feature = "blocking"))]
+    let (first, second) = futures_util::join!(
+        coll.compare_and_swap_field("counter", "stats.count", json!(1), json!(2)),
+        other.compare_and_swap_field("counter", "stats.count", json!(1), json!(3)),
+    );
+    #[cfg(feature = "blocking")]
+    let (first, second) = std::thread::scope(|s| {
+        let first =
+            s.spawn(|| coll.compare_and_swap_field("counter", "stats.count", json!(1), json!(2)));
+        let second =
+            s.spawn(|| other.compare_and_swap_field("counter", "stats.count", json!(1), json!(3)));
+        (first.join().unwrap(), second.join().unwrap())
+    });
+    let outcomes = [first.unwrap(), second.unwrap()];
+    let winners = outcomes
+        .iter()
+        .filter(|o| matches!(o, CasOutcome::Swapped { .. }))
+        .count();
+    assert_eq!(winners, 1, "{:?}", outcomes);
+    let written = if matches!(outcomes[0], CasOutcome::Swapped { .. }) {
+        2
+    } else {
+        3
+    };
+    let loser = outcomes
+        .iter()
+        .find(|o| matches!(o, CasOutcome::Mismatch { .. }))
+        .unwrap();
+    assert_eq!(
+        loser,
+        &CasOutcome::Mismatch {
+            actual: json!(written)
+        }
+    );
+    let doc: Document<Value> = coll.document("counter").await.unwrap();
+    assert_eq!(doc.document["stats"]["count"], json!(written));
+
+    let missing = coll
+        .compare_and_swap_field("nobody", "stats.count", json!(0), json!(1))
+        .await;
+    assert!(matches!(missing, Err(ClientError::Arango(ref e)) if e.error_num() == 1202));
+
+    // an object is swapped whole, keeping the other attributes on the path
+    coll.create_document(
+        Document::new(json!({
+            "_key": "object",
+            "outer": { "value": { "a": 1, "b": 2 }, "kept": true },
+        })),
+        Default::default(),
+    )
+    .await
+    .unwrap();
+    let swapped = coll
+        .compare_and_swap_field(
+            "object",
+            "outer.value",
+            json!({ "a": 1, "b": 2 }),
+            json!({ "c": 3 }),
+        )
+        .await
+        .unwrap();
+    assert!(
+        matches!(swapped, CasOutcome::Swapped { .. }),
+        "{:?}",
+        swapped
+    );
+    let doc: Document<Value> = coll.document("object").await.unwrap();
+    assert_eq!(
+        doc.document["outer"],
+        json!({ "value": { "c": 3 }, "kept": true })
+    );
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_create_documents() {
+    test_setup();
+    let collection_name = "test_collection_create_documents";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let docs: Vec<Value> = (0..3)
+        .map(|i| json!({ "_key": format!("bulk{}", i), "no": i }))
+        .collect();
+    let results = coll
+        .create_documents(
+            docs.clone(),
+            InsertOptions::builder().return_new(true).build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 3);
+    for (result, doc) in results.iter().zip(&docs) {
+        let result = result.as_ref().ok().unwrap();
+        assert_eq!(result.header().unwrap()._key, doc["_key"]);
+        assert_eq!(result.new_doc().unwrap()["no"], doc["no"]);
+    }
+
+    // the duplicate fails alone, in its position
+    let docs = vec![json!({ "_key": "bulk3" }), json!({ "_key": "bulk0" })];
+    let (results, meta) = coll
+        .create_documents_with_meta(docs, Default::default())
+        .await
+        .unwrap();
+    assert!(results[0].is_ok());
+    assert!(matches!(&results[1], Err(e) if e.error_num() == 1210));
+    assert!(meta.error_summary().has_only(1210));
+
+    let silent = InsertOptions::builder().silent(true).build();
+    let results = coll
+        .create_documents(vec![json!({ "_key": "bulk4" })], silent)
+        .await
+        .unwrap();
+    assert!(results[0].as_ref().ok().unwrap().is_silent());
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_create_documents_chunked() {
+    test_setup();
+    let collection_name = "test_collection_create_documents_chunked";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    // the last chunk repeats the key of the fourth document
+    let docs = (0..10)
+        .chain(Some(3))
+        .map(|i| json!({ "_key": format!("chunk{}", i), "no": i }));
+    let report = coll
+        .create_documents_chunked(docs, 4, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(report.sent, 11);
+    assert_eq!(report.created, 10);
+    assert_eq!(report.rejected, 1);
+    assert!(matches!(&report.failures[..], [(Some(10), e)] if e.error_num() == 1210));
+
+    let count = coll.document_count().await.unwrap().info.count;
+    assert_eq!(count, Some(10));
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_create_document_with_key() {
+    test_setup();
+    let collection_name = "test_collection_create_document_with_key";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let doc = Document::with_key("explicit", json!({ "no": 1 }));
+    let created = coll.create_document(doc, Default::default()).await.unwrap();
+    assert_eq!(created.header().unwrap()._key, "explicit");
+
+    let read: Document<Value> = coll.document("explicit").await.unwrap();
+    assert_eq!(read.header._key, "explicit");
+    assert_eq!(
+        read.header._id,
+        Some(format!("{}/explicit", collection_name))
+    );
+    assert_eq!(read.document["no"], 1);
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_patch_document() {
+    test_setup();
+    let collection_name = "test_collection_patch_document";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let doc =
+        json!({ "_key": "p", "status": "open", "note": "x", "meta": { "by": "alice", "at": 1 } });
+    coll.create_document(doc, Default::default()).await.unwrap();
+
+    // nested objects are merged by default, nulls are kept
+    let patch = json!({ "note": null, "meta": { "by": "bob" } });
+    let returning_new = || UpdateOptions::builder().return_new(true);
+    let patched = coll
+        .patch_document("p", patch.clone(), returning_new().build())
+        .await
+        .unwrap();
+    let new = patched.new_doc().unwrap();
+    assert_eq!(new["note"], Value::Null);
+    assert_eq!(new["meta"], json!({ "by": "bob", "at": 1 }));
+
+    // without merging, the nested object is replaced, and nulls removed
+    let options = returning_new()
+        .keep_null(false)
+        .merge_objects(false)
+        .build();
+    let patched = coll.patch_document("p", patch, options).await.unwrap();
+    let new = patched.new_doc().unwrap();
+    assert!(new.get("note").is_none());
+    assert_eq!(new["meta"], json!({ "by": "bob" }));
+    assert_eq!(new["status"], "open");
+
+    let refused = coll
+        .patch_document("p", json!(["status"]), Default::default())
+        .await;
+    assert!(matches!(refused, Err(ClientError::InvalidPatch(_))));
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_modify_document() {
+    test_setup();
+    let collection_name = "test_collection_modify_document";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let doc = json!({ "_key": "counter", "count": 0 });
+    coll.create_document(doc, Default::default()).await.unwrap();
+    let increment = |doc: &mut Value| doc["count"] = json!(doc["count"].as_i64().unwrap() + 1);
+    for count in 1..=3 {
+        let modified = coll.modify_document("counter", increment, 3).await.unwrap();
+        assert_eq!(modified.attempts, 1);
+        assert_eq!(modified.document["count"], count);
+    }
+    let read: Document<Value> = coll.document("counter").await.unwrap();
+    assert_eq!(read.document["count"], 3);
+
+    let missing = coll.modify_document("nobody", increment, 3).await;
+    assert!(matches!(missing, Err(ClientError::Arango(ref e)) if e.is_document_not_found()));
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_silent_writes() {
+    test_setup();
+    let collection_name = "test_collection_silent_writes";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let doc = json!({ "_key": "quiet", "no": 1 });
+    coll.create_document_silent(&doc, Default::default())
+        .await
+        .unwrap();
+    coll.update_document_silent("quiet", json!({ "no": 2 }), Default::default())
+        .await
+        .unwrap();
+    coll.replace_document_silent("quiet", &doc, Default::default(), None)
+        .await
+        .unwrap();
+    let read: Document<Value> = coll.document("quiet").await.unwrap();
+    assert_eq!(read.document["no"], 1);
+
+    coll.remove_document_silent("quiet", Default::default(), None)
+        .await
+        .unwrap();
+    let again = coll
+        .remove_document_silent("quiet", Default::default(), None)
+        .await;
+    assert!(matches!(again, Err(ClientError::Arango(ref e)) if e.is_document_not_found()));
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_create_edge() {
+    test_setup();
+    let collection_name = "test_collection_create_edge";
+    let conn = connection().await;
+    let database = conn.db("test_db").await.unwrap();
+    let _ = database.drop_collection(collection_name).await;
+    let coll = database
+        .create_edge_collection(collection_name)
+        .await
+        .unwrap();
+
+    let edge = EdgeDocument::try_new("users/alice", "users/bob", json!({ "since": 2020 }))
+        .unwrap()
+        .with_key("ab");
+    let created = coll
+        .create_edge(
+            edge.clone(),
+            InsertOptions::builder().return_new(true).build(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.new_doc().unwrap()._to, edge._to);
+
+    let read: EdgeDocument<Value> = coll.edge("ab").await.unwrap();
+    assert_eq!((read._from, read._to), (edge._from, edge._to));
+    assert_eq!(read.document, json!({ "since": 2020 }));
+
+    coll.drop(Default::default()).await.unwrap();
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_update_documents() {
+    test_setup();
+    let collection_name = "test_collection_update_documents";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let docs: Vec<Value> = (0..3)
+        .map(|i| json!({ "_key": format!("patch{}", i), "no": i, "tags": { "a": 1 } }))
+        .collect();
+    let created = coll
+        .create_documents(docs, Default::default())
+        .await
+        .unwrap();
+    let rev = created[2]
+        .as_ref()
+        .ok()
+        .unwrap()
+        .header()
+        .unwrap()
+        ._rev
+        .clone();
+
+    let patches = vec![
+        json!({ "_key": "patch0", "tags": { "b": 2 }, "no": null }),
+        json!({ "_key": "patch1", "_rev": "stale", "no": 10 }),
+        json!({ "no": 20 }),
+        json!({ "_key": "patch2", "_rev": rev, "no": 30 }),
+    ];
+    let options = UpdateOptions::builder()
+        .keep_null(false)
+        .merge_objects(false)
+        .ignore_revs(false)
+        .return_new(true)
+        .return_old(true)
+        .build();
+    let (results, meta) = coll
+        .update_documents_with_meta::<Value, _>(patches, options)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 4);
+    let updated = results[0].as_ref().ok().unwrap();
+    assert_eq!(updated.old_doc().unwrap()["no"], json!(0));
+    assert_eq!(updated.new_doc().unwrap()["tags"], json!({ "b": 2 }));
+    assert!(updated.new_doc().unwrap().get("no").is_none());
+    // a stale revision and a missing key fail alone, in their position
+    assert!(matches!(&results[1], Err(e) if e.error_num() == 1200));
+    assert!(results[2].is_err());
+    assert_eq!(
+        results[3].as_ref().ok().unwrap().new_doc().unwrap()["no"],
+        json!(30)
+    );
+    assert_eq!(meta.error_summary().total_errors(), 2);
+
+    let doc: Document<Value> = coll.document("patch1").await.unwrap();
+    assert_eq!(doc.document["no"], json!(1));
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_replace_documents() {
+    test_setup();
+    let collection_name = "test_collection_replace_documents";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let docs: Vec<Value> = (0..2)
+        .map(|i| json!({ "_key": format!("swap{}", i), "no": i }))
+        .collect();
+    let created = coll
+        .create_documents(docs, Default::default())
+        .await
+        .unwrap();
+    let revs: Vec<String> = created
+        .iter()
+        .map(|r| {
+            r.as_ref()
+                .ok()
+                .unwrap()
+                .header()
+                .unwrap()
+                ._rev
+                .clone()
+                .unwrap()
+        })
+        .collect();
+
+    let replacements = vec![
+        json!({ "_key": "swap0", "_rev": revs[0], "name": "first" }),
+        json!({ "_key": "swap1", "_rev": "stale", "name": "second" }),
+    ];
+    let options = ReplaceOptions::builder()
+        .ignore_revs(false)
+        .return_old(true)
+        .build();
+    let results = coll.replace_documents(replacements, options).await.unwrap();
+    assert_eq!(results.len(), 2);
+    let replaced = results[0].as_ref().ok().unwrap();
+    assert_eq!(replaced.old_rev(), Some(&revs[0]));
+    assert_eq!(replaced.old_doc().unwrap()["no"], json!(0));
+    assert!(matches!(&results[1], Err(e) if e.error_num() == 1200));
+
+    let doc: Document<Value> = coll.document("swap0").await.unwrap();
+    assert_eq!(doc.document["name"], json!("first"));
+    assert!(doc.document.get("no").is_none());
+    let doc: Document<Value> = coll.document("swap1").await.unwrap();
+    assert_eq!(doc.document["no"], json!(1));
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_read_documents() {
+    test_setup();
+    let conn = connection().await;
+    let coll = collection(&conn, "test_collection_read_documents").await;
+
+    let created = coll
+        .create_documents(
+            vec![
+                json!({ "_key": "a", "no": 1 }),
+                json!({ "_key": "b", "no": 2 }),
+            ],
+            Default::default(),
+        )
+        .await
+        .unwrap();
+    assert!(created.iter().all(Result::is_ok));
+
+    let docs = coll
+        .read_documents::<_, Value>(&["b", "missing", "a"], Default::default())
+        .await
+        .unwrap();
+    assert_eq!(docs.len(), 3);
+    assert_eq!(docs[0].as_ref().unwrap().document["no"], 2);
+    assert_eq!(docs[1].as_ref().err().map(|e| e.error_num()), Some(1202));
+    assert_eq!(docs[2].as_ref().unwrap().document["no"], 1);
+
+    // the revisions of the selectors are only checked without ignore_revs
+    let selectors = vec![json!({ "_key": "a", "_rev": "_stale" })];
+    let options = BatchReadOptions::builder().ignore_revs(false).build();
+    let docs = coll
+        .read_documents::<_, Value>(&selectors, options)
+        .await
+        .unwrap();
+    assert!(docs[0].as_ref().err().unwrap().is_conflict());
+    let docs = coll
+        .read_documents::<_, Value>(&selectors, Default::default())
+        .await
+        .unwrap();
+    assert!(docs[0].is_ok());
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_remove_document_if_match() {
+    test_setup();
+    let conn = connection().await;
+    let coll = collection(&conn, "test_collection_remove_if_match").await;
+
+    let created = coll
+        .create_document(json!({ "_key": "a" }), Default::default())
+        .await
+        .unwrap();
+    let rev = created.header().unwrap()._rev.clone().unwrap();
+
+    let options = RemoveOptions::builder().if_match("_stale").build();
+    let removed = coll.remove_document::<Value>("a", options, None).await;
+    let current = match removed {
+        Err(ClientError::PreconditionFailed { current_rev, .. }) => current_rev,
+        other => panic!("expected a failed precondition, got {:?}", other.is_ok()),
+    };
+    assert_eq!(current, Some(rev.clone()));
+
+    let options = RemoveOptions::builder().if_match(rev).build();
+    coll.remove_document::<Value>("a", options, None)
+        .await
+        .unwrap();
+
+    coll.drop(Default::default())
+        .await
+        .expect("Should drop the collection");
+}
+
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_remove_documents() {
+    test_setup();
+    let collection_name = "test_collection_remove_documents";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let docs: Vec<Value> = (0..4)
+        .map(|i| json!({ "_key": format!("gone{}", i), "no": i }))
+        .collect();
+    let created = coll
+        .create_documents(docs, Default::default())
+        .await
+        .unwrap();
+    let rev = |i: usize| {
+        created[i]
+            .as_ref()
+            .ok()
+            .unwrap()
+            .header()
+            .unwrap()
+            ._rev
+            .clone()
+    };
+
+    let keys = vec!["gone0".to_owned(), "missing".to_owned()];
+    let options = RemoveOptions::builder().return_old(true).build();
+    let (results, meta) = coll
+        .remove_documents_with_meta::<Value>(keys, options)
+        .await
+        .unwrap();
+    assert_eq!(
+        results[0].as_ref().ok().unwrap().old_doc().unwrap()["no"],
+        json!(0)
+    );
+    assert!(matches!(&results[1], Err(e) if e.error_num() == 1202));
+    assert_eq!(meta.error_summary().total_errors(), 1);
+
+    let headers = vec![
+        Header {
+            _rev: rev(1),
+            ..Header::with_key("gone1")
+        },
+        Header {
+            _rev: Some("stale".to_owned()),
+            ..Header::with_key("gone2")
+        },
+    ];
+    let options = RemoveOptions::builder().ignore_revs(false).build();
+    let results = coll
+        .remove_documents_by_header::<Value>(headers, options)
+        .await
+        .unwrap();
+    assert!(results[0].is_ok());
+    assert!(matches!(&results[1], Err(e) if e.error_num() == 1200));
+    let count = coll.document_count().await.unwrap();
+    assert_eq!(count.info.count, Some(2));
+}
+
+#[cfg(feature = "aql")]
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_all_documents() {
+    test_setup();
+    let collection_name = "test_collection_all_documents";
+    let conn = connection().await;
+    let coll = collection(&conn, collection_name).await;
+
+    let docs: Vec<Value> = (0..5).map(|i| json!({ "_key": i.to_string() })).collect();
+    coll.create_documents(docs, Default::default())
+        .await
+        .unwrap();
+    let all = coll.all_documents::<Value>(2).unwrap();
+    #[cfg(not(feature = "blocking"))]
+    let all: Vec<_> = futures_util::StreamExt::collect(all).await;
+    #[cfg(feature = "blocking")]
+    let all: Vec<_> = all.collect();
+    let mut keys: Vec<String> = all
+        .into_iter()
+        .map(|doc| doc.unwrap().header._key)
+        .collect();
+    keys.sort();
+    assert_eq!(keys, ["0", "1", "2", "3", "4"]);
+
+    // dropped after the first batch
+    let all = coll.all_documents::<Value>(2).unwrap();
+    #[cfg(not(feature = "blocking"))]
+    let first = futures_util::StreamExt::next(&mut Box::pin(all)).await;
+    #[cfg(feature = "blocking")]
+    let first = { all }.next();
+    assert!(first.unwrap().is_ok());
+    coll.document_count().await.unwrap();
+    assert!(conn.open_resources().cursors.is_empty());
 }
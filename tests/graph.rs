@@ -1,3 +1,4 @@
+#![cfg(feature = "graph")]
 #![allow(unused_imports)]
 #![allow(unused_parens)]
 
@@ -12,7 +13,7 @@ use arangors::{
         response::Status,
         CollectionType,
     },
-    graph::*,
+    graph::{export::*, *},
     ClientError, Connection, Database, Document,
 };
 use common::{get_arangodb_host, get_normal_password, get_normal_user, test_setup};
@@ -159,6 +160,71 @@ async fn test_graph_retrieval() {
     assert_eq!(result.name, "test_graph2");
 }
 
+#[maybe_async::test(
+    any(feature = "reqwest_blocking"),
+    async(any(feature = "reqwest_async"), tokio::test),
+    async(any(feature = "surf_async"), async_std::test)
+)]
+async fn test_graph_export_round_trip() {
+    test_setup();
+    let conn = connection().await;
+
+    let database = conn.db("test_db").await.unwrap();
+    // Cleanup
+    drop_all_graphs(&database, vec!["test_export", "test_export_copy"]).await;
+    for name in ["export_persons", "export_knows"] {
+        let _ = database.drop_collection(name).await;
+    }
+
+    let graph = Graph::builder()
+        .name("test_export".to_string())
+        .edge_definitions(vec![EdgeDefinition {
+            collection: "export_knows".to_string(),
+            from: vec!["export_persons".to_string()],
+            to: vec!["export_persons".to_string()],
+        }])
+        .build();
+    database.create_graph(graph, true).await.unwrap();
+    let persons = database.collection("export_persons").await.unwrap();
+    let knows = database.collection("export_knows").await.unwrap();
+    for key in ["alice", "bob"] {
+        persons
+            .create_document(json!({ "_key": key }), Default::default())
+            .await
+            .unwrap();
+    }
+    let edge = json!({ "_key": "1", "_from": "export_persons/alice", "_to": "export_persons/bob" });
+    knows
+        .create_document(edge, Default::default())
+        .await
+        .unwrap();
+
+    let mut export = Vec::new();
+    let report = database
+        .export_graph("test_export", &mut export, Default::default())
+        .await
+        .unwrap();
+    assert_eq!((report.vertices, report.edges), (2, 1));
+
+    database.drop_graph("test_export", true).await.unwrap();
+    let options = ImportGraphOptions::builder()
+        .rename_to("test_export_copy")
+        .build();
+    let progress = database
+        .import_graph(export.as_slice(), options, |_| {})
+        .await
+        .unwrap();
+    assert_eq!(progress.created, 3);
+    let result = database.graph("test_export_copy").await.unwrap();
+    assert_eq!(result.edge_definitions.len(), 1);
+    let knows = database.collection("export_knows").await.unwrap();
+    assert_eq!(knows.collection_type(), CollectionType::Edge);
+    let edge: Document<Value> = knows.document("1").await.unwrap();
+    assert_eq!(edge.document["_from"], "export_persons/alice");
+
+    database.drop_graph("test_export_copy", true).await.unwrap();
+}
+
 // This tests the default value of `orphanCollections` which can't be optional but can be empty
 #[test]
 fn minimal_serialization_works() {
@@ -1,3 +1,4 @@
+#![cfg(feature = "admin")]
 #![allow(unused_imports)]
 #![allow(unused_parens)]
 
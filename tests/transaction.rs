@@ -1,3 +1,4 @@
+#![cfg(all(feature = "transactions", feature = "documents"))]
 #![allow(unused_imports)]
 #![allow(unused_parens)]
 use log::trace;